@@ -3,14 +3,24 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use std::sync::Mutex;
 
-use crate::channels::permissions::channel_profile;
+use crate::channels::outcomes::{InboundOutcome, InboundOutcomeCounts, InboundOutcomes};
+use crate::channels::permissions::{channel_profile, channel_sandbox};
+use crate::channels::turn::{ChannelTurn, TurnInput, load_or_create_session};
+use crate::channels::whatsapp::{MediaAttachment, MediaType};
 use crate::providers::error::ProviderError;
-use crate::providers::factory::{DEFAULT_PROVIDER_RETRIES, ProviderAgentBuilder};
+use crate::providers::factory::ProviderAgentBuilder;
 use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use chrono::Offset;
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{ConnectInfo, DefaultBodyLimit, Path, Request, State},
     http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse, Response,
+        sse::{Event as SseEvent, KeepAlive, Sse},
+    },
     routing::post,
 };
 use serde::{Deserialize, Serialize};
@@ -18,12 +28,30 @@ use tower_http::limit::RequestBodyLimitLayer;
 
 use crate::config::Config;
 use crate::kernel::core::Kernel;
-use crate::kernel::permissions::{CapabilitySet, Permission};
-use crate::scheduler::job::{CreateJobRequest, Principal, PrincipalType, ScheduleType};
+use crate::kernel::events::{SessionEvent, SessionEventSink, SessionEvents};
+use crate::kernel::permissions::{
+    CapabilitySet, ChannelPermissionProfile, PathPattern, Permission,
+    parse_permission_with_base,
+};
+use crate::scheduler::job::{
+    CreateJobRequest, Principal, PrincipalType, ScheduleType, SchedulerHealth,
+};
+use crate::scheduler::error::SchedulerError;
+use crate::scheduler::service::{normalize_cron_expr, normalize_interval_expr};
+use crate::session::db::SqliteStore;
+use crate::session::error::SessionDbError;
 use crate::session::manager::SessionManager;
 use crate::session::memory::MemoryRetriever;
-use crate::session::types::{MessageType, StoredMessage};
-use crate::tools::traits::ExecutionMode;
+use crate::tools::traits::{ArtifactKind, ExecutionMode};
+
+const MAX_SESSION_TITLE_CHARS: usize = 200;
+/// Prefix of forked session ids, `api-fork:<uuid>`. It is outside the `api:<user>` space
+/// of default sessions, so a fork can never take another user's default session id.
+const FORK_SESSION_PREFIX: &str = "api-fork:";
+/// Session metadata key holding the model chosen through `/v1/chat`.
+const SESSION_MODEL_KEY: &str = "model";
+const SCHEDULER_DISABLED: &str =
+    "scheduler is disabled; set [scheduler].enabled = true to create schedules";
 
 #[derive(Debug, Deserialize)]
 struct PromptRequest {
@@ -34,21 +62,155 @@ struct PromptRequest {
 #[derive(Debug, Serialize)]
 struct PromptResponse {
     response: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    turn_limit_reached: bool,
+    /// Model that answered, after routing (see [`build_agent_for_kernel`]).
+    model_id: String,
+    /// Files tools produced during the turn; omitted when there are none.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<ArtifactResponse>,
+    /// Progress updates the agent sent with `say` during the turn, oldest first; omitted
+    /// when there are none.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    messages: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A turn artifact as the API reports it. The path is relative to the jail root (or the
+/// working directory without one), so responses do not expose the host's layout.
+#[derive(Debug, Serialize, Deserialize)]
+struct ArtifactResponse {
+    path: String,
+    mime: String,
+    kind: ArtifactKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct PromptMessageRequest {
     message: String,
     session_id: Option<String>,
+    grant_permissions: Option<Vec<String>>,
+    /// `[[models]]` id to use for this and later turns on the session.
+    model: Option<String>,
+    /// Files for the turn, stored for the duration of the request and readable by tools.
+    #[serde(default)]
+    attachments: Vec<PromptAttachment>,
+    /// IANA timezone for this request's dates and schedules; defaults to the server's.
+    timezone: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
+struct PromptAttachment {
+    /// Base64-encoded file contents.
+    data: String,
+    mime_type: Option<String>,
+    file_name: Option<String>,
+}
+
+/// Per-request directory holding `/v1/chat` attachments; removed when the turn ends.
+struct AttachmentDir(std::path::PathBuf);
+
+impl Drop for AttachmentDir {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.0) {
+            tracing::warn!(
+                error = %err,
+                path = %self.0.display(),
+                "failed to remove api attachment dir"
+            );
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct PromptMessageResponse {
     response: String,
     session_id: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    turn_limit_reached: bool,
+    #[serde(default)]
+    model_id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<ArtifactResponse>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    messages: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize)]
+struct PermissionRequiredResponse {
+    error: &'static str,
+    permissions: Vec<String>,
+    /// What the agent answered without the permissions; omitted if the prompt failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<String>,
+}
+
+/// Errors from the chat endpoint. Besides plain status errors, a tool call may have
+/// been denied for permissions the channel allows granting; the client can retry the
+/// request with those listed in `grant_permissions`.
+#[derive(Debug)]
+enum PromptMessageError {
+    Status(StatusCode, String),
+    PermissionRequired {
+        permissions: Vec<String>,
+        response: Option<String>,
+    },
+}
+
+impl From<(StatusCode, String)> for PromptMessageError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        PromptMessageError::Status(status, message)
+    }
+}
+
+impl IntoResponse for PromptMessageError {
+    fn into_response(self) -> Response {
+        match self {
+            PromptMessageError::Status(status, message) => (status, message).into_response(),
+            PromptMessageError::PermissionRequired {
+                permissions,
+                response,
+            } => (
+                StatusCode::CONFLICT,
+                Json(PermissionRequiredResponse {
+                    error: "permission_required",
+                    permissions,
+                    response,
+                }),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Errors from schedule creation. A busy scheduler answers 503 with `Retry-After`, unlike
+/// a disabled one (501), which retrying will not fix.
+#[derive(Debug)]
+enum ScheduleCreateError {
+    Status(StatusCode, String),
+    SchedulerBusy { retry_after_secs: u64 },
+}
+
+impl From<(StatusCode, String)> for ScheduleCreateError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        ScheduleCreateError::Status(status, message)
+    }
+}
+
+impl IntoResponse for ScheduleCreateError {
+    fn into_response(self) -> Response {
+        match self {
+            ScheduleCreateError::Status(status, message) => (status, message).into_response(),
+            ScheduleCreateError::SchedulerBusy { retry_after_secs } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(axum::http::header::RETRY_AFTER, retry_after_secs.to_string())],
+                "scheduler is at capacity; retry later".to_string(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct ScheduleCreateRequest {
     name: Option<String>,
     schedule_type: String,
@@ -60,9 +222,11 @@ struct ScheduleCreateRequest {
     max_executions: Option<u32>,
     capabilities: Option<Vec<String>>,
     metadata: Option<serde_json::Value>,
+    /// IANA timezone for the request; cron expressions without a `tz|` prefix run in it.
+    timezone: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct ScheduleCreateResponse {
     status: String,
     job_id: String,
@@ -87,16 +251,241 @@ struct ScheduleListResponse {
     schedules: Vec<ScheduleItemResponse>,
 }
 
+#[derive(Debug, Serialize)]
+struct ToolItemResponse {
+    name: String,
+    description: String,
+    schema: serde_json::Value,
+    permissions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolListResponse {
+    tools: Vec<ToolItemResponse>,
+}
+
+#[derive(Debug, Serialize)]
+struct PermissionsResponse {
+    channel_id: String,
+    /// Usable now without a grant.
+    permissions: Vec<String>,
+    /// Can be added for one request with `grant_permissions`.
+    grantable: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionItemResponse {
+    id: String,
+    channel: String,
+    channel_id: String,
+    title: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_activity: chrono::DateTime<chrono::Utc>,
+    message_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionListResponse {
+    sessions: Vec<SessionItemResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionUpdateRequest {
+    /// Absent leaves the title alone; `null` or an empty string clears it.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    title: Option<Option<String>>,
+    /// Merged into the stored metadata as a JSON merge patch.
+    metadata: Option<serde_json::Value>,
+}
+
+/// Wraps a field that was present, even as `null`, so it differs from a missing one.
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SessionForkRequest {
+    /// Last message to copy; all of them when unset.
+    seq_order: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionForkResponse {
+    session_id: String,
+    forked_from: String,
+    message_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionUpdateResponse {
+    id: String,
+    title: Option<String>,
+    metadata: Option<serde_json::Value>,
+}
+
 #[derive(Clone)]
 pub struct AppState {
     agent_builder: ProviderAgentBuilder,
     max_turns: usize,
     kernel: Arc<Kernel>,
     config: Config,
+    /// Resolves relative permission paths; fixed when the router is built.
+    base_dir: std::path::PathBuf,
     rate_limiter: Arc<RateLimiter>,
     auth_identities: HashMap<String, String>,
     session_manager: Arc<SessionManager>,
     memory_retriever: Arc<MemoryRetriever>,
+    inbound_outcomes: Arc<InboundOutcomes>,
+    idempotency: IdempotencyCache,
+    session_events: SessionEvents,
+}
+
+/// Most `Idempotency-Key`s remembered at once; the oldest finished ones are evicted first.
+const IDEMPOTENCY_MAX_ENTRIES: usize = 10_000;
+
+/// Requests sent with an `Idempotency-Key` header. A key is reserved while its first
+/// request runs and then holds that request's successful response, which is replayed
+/// when the same identity retries the same route with the same body within
+/// `api.idempotency_ttl_secs`.
+#[derive(Clone, Default)]
+struct IdempotencyCache {
+    inner: Arc<Mutex<HashMap<String, IdempotencyEntry>>>,
+}
+
+struct IdempotencyEntry {
+    body_hash: [u8; 32],
+    /// Set once the request succeeds; `None` while it is still running.
+    response: Option<(std::time::Instant, serde_json::Value)>,
+}
+
+/// What to do with a request after checking its `Idempotency-Key`.
+enum IdempotencyCheck<T> {
+    /// Run the request. The key, if any, stays reserved until the guard completes or drops.
+    Proceed(Option<IdempotencyGuard>),
+    /// The key already succeeded with this body; reply with its response.
+    Replay(T),
+}
+
+impl IdempotencyCache {
+    /// Replays a finished key, or reserves a new one. A key still in flight is a 409 and a
+    /// key reused with a different body is a 422.
+    fn begin<T: serde::de::DeserializeOwned>(
+        &self,
+        key: Option<String>,
+        body_hash: [u8; 32],
+        ttl: std::time::Duration,
+    ) -> Result<IdempotencyCheck<T>, (StatusCode, String)> {
+        let Some(key) = key else {
+            return Ok(IdempotencyCheck::Proceed(None));
+        };
+        let mut guard = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        guard.retain(|_, entry| {
+            entry
+                .response
+                .as_ref()
+                .is_none_or(|(stored_at, _)| stored_at.elapsed() <= ttl)
+        });
+        if let Some(entry) = guard.get(&key) {
+            if entry.body_hash != body_hash {
+                return Err((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Idempotency-Key was already used with a different request body".to_string(),
+                ));
+            }
+            let Some((_, response)) = &entry.response else {
+                return Err((
+                    StatusCode::CONFLICT,
+                    "a request with this Idempotency-Key is still in progress".to_string(),
+                ));
+            };
+            if let Ok(response) = serde_json::from_value(response.clone()) {
+                return Ok(IdempotencyCheck::Replay(response));
+            }
+        }
+        if guard.len() >= IDEMPOTENCY_MAX_ENTRIES {
+            let oldest = guard
+                .iter()
+                .filter_map(|(key, entry)| entry.response.as_ref().map(|(at, _)| (*at, key)))
+                .min()
+                .map(|(_, key)| key.clone());
+            let Some(oldest) = oldest else {
+                return Err((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "too many idempotent requests in progress".to_string(),
+                ));
+            };
+            guard.remove(&oldest);
+        }
+        guard.insert(
+            key.clone(),
+            IdempotencyEntry {
+                body_hash,
+                response: None,
+            },
+        );
+        Ok(IdempotencyCheck::Proceed(Some(IdempotencyGuard {
+            cache: self.clone(),
+            key,
+            completed: false,
+        })))
+    }
+}
+
+/// A reserved `Idempotency-Key`. Completing it stores the response; dropping it without
+/// completing releases the key, so a failed request can be retried.
+struct IdempotencyGuard {
+    cache: IdempotencyCache,
+    key: String,
+    completed: bool,
+}
+
+impl IdempotencyGuard {
+    fn complete<T: Serialize>(mut self, response: &T) {
+        let Ok(value) = serde_json::to_value(response) else {
+            return;
+        };
+        let mut guard = self.cache.inner.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(entry) = guard.get_mut(&self.key) {
+            entry.response = Some((std::time::Instant::now(), value));
+            self.completed = true;
+        }
+    }
+}
+
+impl Drop for IdempotencyGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut guard = self.cache.inner.lock().unwrap_or_else(|err| err.into_inner());
+        if guard
+            .get(&self.key)
+            .is_some_and(|entry| entry.response.is_none())
+        {
+            guard.remove(&self.key);
+        }
+    }
+}
+
+/// SHA-256 of the parsed request, so a key reused with a different body is caught.
+fn request_hash<T: Serialize>(request: &T) -> [u8; 32] {
+    use sha2::Digest;
+    let body = serde_json::to_vec(request).unwrap_or_default();
+    sha2::Sha256::digest(body).into()
+}
+
+/// Cache key for the request's `Idempotency-Key`, scoped to the caller and route so keys
+/// never collide across identities or endpoints.
+fn idempotency_key(headers: &HeaderMap, user_id: &str, route: &str) -> Option<String> {
+    let key = headers.get("idempotency-key")?.to_str().ok()?.trim();
+    if key.is_empty() {
+        return None;
+    }
+    Some(format!("{user_id}\n{route}\n{key}"))
 }
 
 #[derive(Clone, Default)]
@@ -109,7 +498,15 @@ impl RateLimiter {
         if limit == 0 {
             return true;
         }
-        let mut guard = self.inner.lock().expect("rate limiter mutex poisoned");
+        let mut guard = self.inner.lock().unwrap_or_else(|err| {
+            tracing::warn!(
+                event = "lock_poisoned",
+                lock = "rate_limiter",
+                "recovering poisoned lock"
+            );
+            self.inner.clear_poison();
+            err.into_inner()
+        });
         let now = std::time::Instant::now();
         let window = std::time::Duration::from_secs(60);
         let entries = guard.entry(key.to_string()).or_default();
@@ -128,25 +525,31 @@ async fn prompt_handler(
     Json(payload): Json<PromptRequest>,
 ) -> Result<Json<PromptResponse>, (StatusCode, String)> {
     let user_id = authenticate(&state, &headers)?;
+    ensure_can_prompt(&state, &user_id)?;
     enforce_rate_limit(&state, &user_id)?;
     let session_id = payload
         .session_id
         .unwrap_or_else(|| default_session_id(&user_id));
-    validate_session_id(&session_id, &user_id)?;
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let profile = channel_profile(&state.config.channels(), "api", &base_dir);
+    authorize_session(&state.session_manager, &session_id, &user_id)?;
+    let profile = channel_profile(&state.config.channels(), "api", &state.base_dir);
+    let say_sink = Arc::new(SessionEventSink::new(
+        state.session_events.clone(),
+        session_id.clone(),
+    ));
     let scoped_kernel = Arc::new(
         state
             .kernel
             .clone_with_context(Some(user_id.clone()), Some(session_id.clone()))
             .with_channel_id(Some("api".to_string()))
-            .with_prompt_profile(profile),
+            .with_prompt_profile(profile)
+            .with_message_sink(Some(say_sink.clone())),
     );
-    let agent = build_agent_for_kernel(
+    let (agent, effective_model) = build_agent_for_kernel(
         &state.config,
         &state.agent_builder,
-        scoped_kernel,
+        Arc::clone(&scoped_kernel),
         state.max_turns,
+        None,
     )
     .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
     tracing::info!(
@@ -161,7 +564,7 @@ async fn prompt_handler(
         .prompt_with_turns_retry_usage(
             payload.prompt.clone(),
             state.max_turns,
-            DEFAULT_PROVIDER_RETRIES,
+            state.config.provider_max_retries(),
         )
         .await
         .map_err(map_provider_error)?;
@@ -175,10 +578,10 @@ async fn prompt_handler(
         user_id: Some(user_id.clone()),
         provider: Some(agent.provider_name().to_string()),
         model: agent.model_name(),
-        input_tokens: response.1.input_tokens,
-        output_tokens: response.1.output_tokens,
-        total_tokens: response.1.total_tokens,
-        cached_input_tokens: response.1.cached_input_tokens,
+        input_tokens: response.usage.input_tokens,
+        output_tokens: response.usage.output_tokens,
+        total_tokens: response.usage.total_tokens,
+        cached_input_tokens: response.usage.cached_input_tokens,
     };
     if let Err(err) = state.session_manager.record_usage(&usage_event) {
         tracing::warn!(error = %err, "failed to record usage");
@@ -187,106 +590,109 @@ async fn prompt_handler(
         event = "channel_prompt_complete",
         channel_id = "api",
         user_id = %user_id,
-        response_len = response.0.len(),
-        input_tokens = response.1.input_tokens,
-        output_tokens = response.1.output_tokens,
-        total_tokens = response.1.total_tokens,
+        response_len = response.output.len(),
+        input_tokens = response.usage.input_tokens,
+        output_tokens = response.usage.output_tokens,
+        total_tokens = response.usage.total_tokens,
         "api prompt completed"
     );
-    Ok(Json(PromptResponse { response: response.0 }))
+    state.session_events.publish(SessionEvent::Done {
+        session_id,
+        response: response.output.clone(),
+        model_id: effective_model.clone(),
+    });
+    Ok(Json(PromptResponse {
+        response: response.output,
+        turn_limit_reached: response.turn_limit_reached,
+        model_id: effective_model,
+        artifacts: artifact_responses(&scoped_kernel),
+        messages: say_sink.sent(),
+    }))
 }
 
 async fn prompt_message_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<PromptMessageRequest>,
-) -> Result<Json<PromptMessageResponse>, (StatusCode, String)> {
+) -> Result<Json<PromptMessageResponse>, PromptMessageError> {
     let user_id = authenticate(&state, &headers)?;
+    ensure_can_prompt(&state, &user_id)?;
+    let idempotency = match state.idempotency.begin(
+        idempotency_key(&headers, &user_id, "/v1/chat"),
+        request_hash(&payload),
+        state.config.api().idempotency_ttl(),
+    )? {
+        IdempotencyCheck::Replay(response) => return Ok(Json(response)),
+        IdempotencyCheck::Proceed(guard) => guard,
+    };
     enforce_rate_limit(&state, &user_id)?;
     let session_id = payload
         .session_id
         .unwrap_or_else(|| default_session_id(&user_id));
-    validate_session_id(&session_id, &user_id)?;
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let profile = channel_profile(&state.config.channels(), "api", &base_dir);
+    authorize_session(&state.session_manager, &session_id, &user_id)?;
+    let profile = channel_profile(&state.config.channels(), "api", &state.base_dir);
+    let grants = parse_grant_permissions(
+        payload.grant_permissions.as_deref().unwrap_or_default(),
+        &profile,
+        &state.base_dir,
+    )?;
+    let base_capabilities = state.kernel.context().capabilities.as_ref().clone();
+    let mut capabilities = base_capabilities.clone();
+    for permission in grants {
+        capabilities.insert(permission);
+    }
+    let (_attachment_dir, attachments) = if payload.attachments.is_empty() {
+        (None, Vec::new())
+    } else {
+        let (dir, attachments) =
+            store_attachments(&state.config.data_dir(), &payload.attachments)?;
+        (Some(dir), attachments)
+    };
+    let profile = with_attachment_permissions(profile, &attachments);
+    let (tz_offset, tz_name) = request_timezone(&state.kernel, payload.timezone.as_deref())?;
+    let say_sink = Arc::new(SessionEventSink::new(
+        state.session_events.clone(),
+        session_id.clone(),
+    ));
     let scoped_kernel = Arc::new(
         state
             .kernel
             .clone_with_context(Some(user_id.clone()), Some(session_id.clone()))
             .with_channel_id(Some("api".to_string()))
-            .with_prompt_profile(profile),
+            .with_prompt_profile(profile)
+            .with_capabilities(capabilities)
+            .with_timezone(tz_offset, tz_name, payload.timezone.is_some())
+            .with_message_sink(Some(say_sink.clone())),
     );
 
-    let session = match state.session_manager.get_session(&session_id) {
-        Ok(Some(session)) => session,
-        Ok(None) => state
-            .session_manager
-            .create_session(
-                session_id.clone(),
-                "api".to_string(),
-                "api".to_string(),
-                user_id.clone(),
-                scoped_kernel.context().capabilities.as_ref().clone(),
-            )
-            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?,
-        Err(err) => return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string())),
-    };
-
-    let memory_config = state.config.memory();
-    let existing_messages = state
-        .session_manager
-        .get_messages(
-            &session.id,
-            memory_config.max_session_messages.unwrap_or(50),
-        )
-        .unwrap_or_default();
-    let filtered_messages = if memory_config.include_tool_messages() {
-        existing_messages
-    } else {
-        existing_messages
-            .into_iter()
-            .filter(|message| message.message_type != MessageType::Tool)
-            .collect::<Vec<_>>()
-    };
-    let context_messages = state.memory_retriever.build_context(
-        scoped_kernel.context().user_id.as_deref(),
-        scoped_kernel.context().session_id.as_deref(),
-        &filtered_messages,
+    let session = load_or_create_session(
+        &state.session_manager,
+        &session_id,
+        "api",
+        &user_id,
+        &base_capabilities,
+    )
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    let model_id = select_session_model(&state, &session, payload.model.as_deref())?;
+    let turn = ChannelTurn::new(
+        &state.session_manager,
+        &state.memory_retriever,
+        "api",
+        &user_id,
+        session,
+        TurnInput {
+            text: &payload.message,
+            attachments: &attachments,
+            ..Default::default()
+        },
     );
-    let context_snippet = MemoryRetriever::to_prompt_snippet(&context_messages);
-    let prompt_to_send = if let Some(context) = context_snippet {
-        format!("Context:\n{context}\n\nUser: {}", payload.message)
-    } else {
-        payload.message.clone()
-    };
-
-    let mut seq_order = match state.session_manager.get_messages(&session.id, 1) {
-        Ok(messages) => messages
-            .last()
-            .map(|message| message.seq_order + 1)
-            .unwrap_or(0),
-        Err(_) => 0,
-    };
-    let user_message = StoredMessage {
-        message_type: MessageType::User,
-        content: payload.message.clone(),
-        tool_call_id: None,
-        seq_order,
-        token_estimate: None,
-    };
-    if state
-        .session_manager
-        .append_message(&session.id, &user_message)
-        .is_ok()
-    {
-        seq_order += 1;
-    }
 
-    let agent = build_agent_for_kernel(
+    let (agent, effective_model) = build_agent_for_kernel(
         &state.config,
         &state.agent_builder,
-        scoped_kernel,
+        Arc::clone(&scoped_kernel),
         state.max_turns,
+        model_id.as_deref(),
     )
     .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
     tracing::info!(
@@ -294,87 +700,112 @@ async fn prompt_message_handler(
         channel_id = "api",
         user_id = %user_id,
         session_id = %session_id,
-        prompt_len = prompt_to_send.len(),
+        prompt_len = turn.prompt().len(),
         max_turns = state.max_turns,
         "api prompt received"
     );
     let response = agent
-        .prompt_with_turns_retry_usage(prompt_to_send, state.max_turns, DEFAULT_PROVIDER_RETRIES)
-        .await
-        .map_err(map_provider_error)?;
-    let usage_event = crate::session::types::UsageEvent {
-        session_id: Some(session.id.clone()),
-        channel_id: Some("api".to_string()),
-        user_id: Some(user_id.clone()),
-        provider: Some(agent.provider_name().to_string()),
-        model: agent.model_name(),
-        input_tokens: response.1.input_tokens,
-        output_tokens: response.1.output_tokens,
-        total_tokens: response.1.total_tokens,
-        cached_input_tokens: response.1.cached_input_tokens,
-    };
-    if let Err(err) = state.session_manager.record_usage(&usage_event) {
-        tracing::warn!(error = %err, "failed to record usage");
+        .prompt_with_turns_retry_usage(
+            turn.prompt(),
+            state.max_turns,
+            state.config.provider_max_retries(),
+        )
+        .await;
+
+    // The turn is not persisted when grantable permissions were denied, so the
+    // client can resend the same message with `grant_permissions` set. The agent's
+    // answer without them is still returned alongside the conflict.
+    let denied = scoped_kernel.unprompted_denials();
+    if !denied.is_empty() {
+        let permissions = denied
+            .iter()
+            .map(|permission| permission.to_string())
+            .collect::<Vec<_>>();
+        tracing::info!(
+            event = "channel_permission_required",
+            channel_id = "api",
+            user_id = %user_id,
+            session_id = %session_id,
+            permissions = ?permissions,
+            "api prompt requires permission grants"
+        );
+        return Err(PromptMessageError::PermissionRequired {
+            permissions,
+            response: response.ok().map(|response| response.output),
+        });
     }
+
+    turn.record_user_message();
+    turn.record_tool_calls(scoped_kernel.take_tool_calls());
+    let response = response.map_err(map_provider_error)?;
+    turn.record_usage(&agent, &response.usage);
     tracing::info!(
         event = "channel_prompt_complete",
         channel_id = "api",
         user_id = %user_id,
         session_id = %session_id,
-        response_len = response.0.len(),
-        input_tokens = response.1.input_tokens,
-        output_tokens = response.1.output_tokens,
-        total_tokens = response.1.total_tokens,
+        response_len = response.output.len(),
+        input_tokens = response.usage.input_tokens,
+        output_tokens = response.usage.output_tokens,
+        total_tokens = response.usage.total_tokens,
         "api prompt completed"
     );
 
-    let assistant_message = StoredMessage {
-        message_type: MessageType::Assistant,
-        content: response.0.clone(),
-        tool_call_id: None,
-        seq_order,
-        token_estimate: None,
-    };
-    if let Err(err) = state
-        .session_manager
-        .append_message(&session.id, &assistant_message)
-    {
-        tracing::warn!(error = %err, "failed to store assistant message");
-    }
-    if let Err(err) = state.session_manager.touch(&session.id) {
-        tracing::warn!(error = %err, "failed to update session activity");
-    }
+    turn.record_response(&response.output);
+    state.session_events.publish(SessionEvent::Done {
+        session_id: session_id.clone(),
+        response: response.output.clone(),
+        model_id: effective_model.clone(),
+    });
 
-    Ok(Json(PromptMessageResponse {
-        response: response.0,
+    let response = PromptMessageResponse {
+        response: response.output,
         session_id,
-    }))
+        turn_limit_reached: response.turn_limit_reached,
+        model_id: effective_model,
+        artifacts: artifact_responses(&scoped_kernel),
+        messages: say_sink.sent(),
+    };
+    if let Some(idempotency) = idempotency {
+        idempotency.complete(&response);
+    }
+    Ok(Json(response))
 }
 
 async fn schedule_create_handler(
     State(state): State<AppState>,
     headers: HeaderMap,
     Json(payload): Json<ScheduleCreateRequest>,
-) -> Result<Json<ScheduleCreateResponse>, (StatusCode, String)> {
+) -> Result<Json<ScheduleCreateResponse>, ScheduleCreateError> {
     let user_id = authenticate(&state, &headers)?;
+    ensure_can_prompt(&state, &user_id)?;
+    let idempotency = match state.idempotency.begin(
+        idempotency_key(&headers, &user_id, "/v1/schedules"),
+        request_hash(&payload),
+        state.config.api().idempotency_ttl(),
+    )? {
+        IdempotencyCheck::Replay(response) => return Ok(Json(response)),
+        IdempotencyCheck::Proceed(guard) => guard,
+    };
     enforce_rate_limit(&state, &user_id)?;
     let session_id = payload
         .session_id
         .unwrap_or_else(|| default_session_id(&user_id));
-    validate_session_id(&session_id, &user_id)?;
+    authorize_session(&state.session_manager, &session_id, &user_id)?;
     if let Some(channel_id) = payload.channel_id.as_deref()
         && channel_id != "api"
     {
-        return Err((StatusCode::BAD_REQUEST, "invalid channel_id".to_string()));
+        return Err((StatusCode::BAD_REQUEST, "invalid channel_id".to_string()).into());
     }
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let profile = channel_profile(&state.config.channels(), "api", &base_dir);
+    let profile = channel_profile(&state.config.channels(), "api", &state.base_dir);
+    let (tz_offset, tz_name) = request_timezone(&state.kernel, payload.timezone.as_deref())?;
     let scoped_kernel = state
         .kernel
         .clone_with_context(Some(user_id.clone()), Some(session_id))
         .with_channel_id(Some("api".to_string()))
         .with_prompt_profile(profile)
-        .with_execution_mode(ExecutionMode::User);
+        .with_execution_mode(ExecutionMode::User)
+        .with_timezone(tz_offset, tz_name, payload.timezone.is_some());
 
     ensure_schedule_permission(
         scoped_kernel.context().capabilities.as_ref(),
@@ -382,15 +813,26 @@ async fn schedule_create_handler(
         "create",
     )?;
     let scheduler = scoped_kernel.context().scheduler.clone().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "scheduler not available".to_string(),
+        ScheduleCreateError::Status(
+            StatusCode::NOT_IMPLEMENTED,
+            SCHEDULER_DISABLED.to_string(),
         )
     })?;
     let schedule_type = parse_schedule_type(&payload.schedule_type)?;
     let mut schedule_expr = payload.schedule_expr.clone();
-    if matches!(schedule_type, ScheduleType::Cron) {
-        schedule_expr = normalize_cron_expr(&schedule_expr)?;
+    match schedule_type {
+        ScheduleType::Cron => {
+            schedule_expr = normalize_cron_expr(
+                &schedule_expr,
+                scoped_kernel.context().cron_default_timezone(),
+            )
+            .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+        }
+        ScheduleType::Interval => {
+            schedule_expr = normalize_interval_expr(&schedule_expr)
+                .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+        }
+        ScheduleType::Once => {}
     }
     let task_prompt = payload.task_prompt.clone();
     let name = payload
@@ -427,14 +869,25 @@ async fn schedule_create_handler(
         created_by_system: false,
         metadata: payload.metadata,
     };
-    let job = scheduler
-        .create_job(request)
-        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
-    Ok(Json(ScheduleCreateResponse {
+    let job = scheduler.create_job(request).map_err(|err| match err {
+        SchedulerError::AtCapacity { retry_after_secs } => {
+            ScheduleCreateError::SchedulerBusy { retry_after_secs }
+        }
+        SchedulerError::Disabled => ScheduleCreateError::Status(
+            StatusCode::NOT_IMPLEMENTED,
+            SCHEDULER_DISABLED.to_string(),
+        ),
+        err => ScheduleCreateError::Status(StatusCode::BAD_REQUEST, err.to_string()),
+    })?;
+    let response = ScheduleCreateResponse {
         status: "created".to_string(),
         job_id: job.id,
         next_run_at: job.next_run_at,
-    }))
+    };
+    if let Some(idempotency) = idempotency {
+        idempotency.complete(&response);
+    }
+    Ok(Json(response))
 }
 
 async fn schedule_list_handler(
@@ -443,8 +896,7 @@ async fn schedule_list_handler(
 ) -> Result<Json<ScheduleListResponse>, (StatusCode, String)> {
     let user_id = authenticate(&state, &headers)?;
     enforce_rate_limit(&state, &user_id)?;
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let profile = channel_profile(&state.config.channels(), "api", &base_dir);
+    let profile = channel_profile(&state.config.channels(), "api", &state.base_dir);
     let scoped_kernel = state
         .kernel
         .clone_with_context(Some(user_id.clone()), Some(default_session_id(&user_id)))
@@ -487,9 +939,9 @@ async fn schedule_cancel_handler(
     Path(job_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let user_id = authenticate(&state, &headers)?;
+    ensure_can_prompt(&state, &user_id)?;
     enforce_rate_limit(&state, &user_id)?;
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let profile = channel_profile(&state.config.channels(), "api", &base_dir);
+    let profile = channel_profile(&state.config.channels(), "api", &state.base_dir);
     let scoped_kernel = state
         .kernel
         .clone_with_context(Some(user_id.clone()), Some(default_session_id(&user_id)))
@@ -520,84 +972,653 @@ async fn schedule_cancel_handler(
     Ok(StatusCode::NO_CONTENT)
 }
 
+async fn tool_list_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ToolListResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    let tools = state
+        .kernel
+        .tool_registry()
+        .tools()
+        .iter()
+        .map(|tool| {
+            let spec = tool.spec();
+            ToolItemResponse {
+                name: spec.name.clone(),
+                description: spec.description.clone(),
+                schema: spec.schema.clone(),
+                permissions: tool.permission_hints(),
+            }
+        })
+        .collect();
+    Ok(Json(ToolListResponse { tools }))
+}
+
+/// The caller's effective permissions on the API channel.
+async fn permissions_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<PermissionsResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    let profile = channel_profile(&state.config.channels(), "api", &state.base_dir);
+    let labels = |set: &CapabilitySet| {
+        let mut labels = set.permissions().map(ToString::to_string).collect::<Vec<_>>();
+        labels.sort();
+        labels
+    };
+    let grantable = labels(&profile.max_allowed);
+    let kernel = state
+        .kernel
+        .clone_with_context(Some(user_id.clone()), Some(default_session_id(&user_id)))
+        .with_channel_id(Some("api".to_string()))
+        .with_prompt_profile(profile);
+    Ok(Json(PermissionsResponse {
+        channel_id: "api".to_string(),
+        permissions: labels(&kernel.effective_capabilities()),
+        grantable,
+    }))
+}
+
+async fn scheduler_health_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SchedulerHealth>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    ensure_admin(&state, &user_id)?;
+    let scheduler = state.kernel.context().scheduler.clone().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "scheduler not available".to_string(),
+        )
+    })?;
+    let health = scheduler
+        .health()
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(health))
+}
+
+async fn inbound_outcomes_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<InboundOutcomeCounts>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    ensure_admin(&state, &user_id)?;
+    Ok(Json(state.inbound_outcomes.counts()))
+}
+
+/// Streams tool and completion events for `session_id` as server-sent events, for
+/// dashboards. Limited to admin and observer identities.
+async fn session_events_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<
+    Sse<impl futures::Stream<Item = Result<SseEvent, std::convert::Infallible>>>,
+    (StatusCode, String),
+> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    ensure_observer(&state, &user_id)?;
+    tracing::info!(
+        event = "session_observer_connected",
+        user_id = %user_id,
+        session_id = %session_id,
+        "session observer connected"
+    );
+    let receiver = state.session_events.subscribe();
+    let stream = futures::stream::unfold(
+        (receiver, session_id),
+        |(mut receiver, session_id)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.session_id() == session_id => {
+                        let frame = SseEvent::default()
+                            .event(event.name())
+                            .json_data(&event)
+                            .unwrap_or_else(|_| SseEvent::default().comment("unserializable"));
+                        return Some((Ok(frame), (receiver, session_id)));
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            event = "session_observer_lagged",
+                            session_id = %session_id,
+                            skipped,
+                            "session observer missed events"
+                        );
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn session_list_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SessionListResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    let sessions = state
+        .session_manager
+        .list_sessions_for_user(&user_id)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .into_iter()
+        .map(|session| SessionItemResponse {
+            id: session.id,
+            channel: session.channel_type,
+            channel_id: session.channel_id,
+            title: session.title,
+            created_at: session.created_at,
+            last_activity: session.last_activity,
+            message_count: session.message_count,
+        })
+        .collect();
+    Ok(Json(SessionListResponse { sessions }))
+}
+
+/// Copies a session (up to an optional `seq_order`) into a new `api-fork:<uuid>` session
+/// that later prompts can continue independently.
+async fn session_fork_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    payload: Option<Json<SessionForkRequest>>,
+) -> Result<Json<SessionForkResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    ensure_can_prompt(&state, &user_id)?;
+    enforce_rate_limit(&state, &user_id)?;
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let session = state
+        .session_manager
+        .get_session(&session_id)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "session not found".to_string()))?;
+    if session.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "session not owned by user".to_string()));
+    }
+    let fork_id = format!("{FORK_SESSION_PREFIX}{}", uuid::Uuid::new_v4().simple());
+    let message_count = state
+        .session_manager
+        .fork_session(&session_id, &fork_id, payload.seq_order)
+        .map_err(|err| match err {
+            SessionDbError::CompactedHistory(_) => (StatusCode::BAD_REQUEST, err.to_string()),
+            err => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        })?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "session not found".to_string()))?;
+    tracing::info!(
+        event = "session_forked",
+        user_id = %user_id,
+        session_id = %session_id,
+        fork_id = %fork_id,
+        seq_order = ?payload.seq_order,
+        message_count,
+        "session forked"
+    );
+    Ok(Json(SessionForkResponse {
+        session_id: fork_id,
+        forked_from: session_id,
+        message_count,
+    }))
+}
+
+async fn session_update_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(payload): Json<SessionUpdateRequest>,
+) -> Result<Json<SessionUpdateResponse>, (StatusCode, String)> {
+    let user_id = authenticate(&state, &headers)?;
+    enforce_rate_limit(&state, &user_id)?;
+    let session = state
+        .session_manager
+        .get_session(&session_id)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "session not found".to_string()))?;
+    if session.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "session not owned by user".to_string()));
+    }
+    if let Some(title) = &payload.title {
+        let title = title.as_deref().unwrap_or_default().trim();
+        if title.chars().count() > MAX_SESSION_TITLE_CHARS {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("title exceeds {MAX_SESSION_TITLE_CHARS} characters"),
+            ));
+        }
+        let title = (!title.is_empty()).then_some(title);
+        state
+            .session_manager
+            .set_title(&session_id, title)
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    }
+    if let Some(metadata) = &payload.metadata {
+        if !metadata.is_object() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "metadata must be a JSON object".to_string(),
+            ));
+        }
+        state
+            .session_manager
+            .merge_metadata(&session_id, metadata)
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    }
+    let session = state
+        .session_manager
+        .get_session(&session_id)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "session not found".to_string()))?;
+    Ok(Json(SessionUpdateResponse {
+        id: session.id,
+        title: session.title,
+        metadata: session.metadata,
+    }))
+}
+
 pub async fn serve(
     config: Config,
     kernel: Kernel,
     agent_builder: ProviderAgentBuilder,
+    session_store: SqliteStore,
 ) -> Result<()> {
-    let (addr, router) = router(config, kernel, agent_builder)?;
+    let (addr, router) = router(config, kernel, agent_builder, session_store)?;
     let listener = tokio::net::TcpListener::bind(addr.clone())
         .await
         .with_context(|| format!("failed to bind {addr}"))?;
-    axum::serve(listener, router)
-        .await
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
         .context("server failed")?;
     Ok(())
 }
 
+/// Whether `bind` only accepts connections from this machine.
+fn is_localhost_bind(bind: &str) -> bool {
+    if let Ok(addr) = bind.parse::<std::net::SocketAddr>() {
+        return addr.ip().is_loopback();
+    }
+    let host = bind.rsplit_once(':').map_or(bind, |(host, _)| host);
+    host.eq_ignore_ascii_case("localhost")
+}
+
+/// Refuses to serve an unauthenticated API on a non-loopback address unless
+/// `api.allow_insecure_external` opts in.
+fn check_bind_security(config: &Config) -> Result<()> {
+    let bind = config.bind();
+    if is_localhost_bind(bind) {
+        return Ok(());
+    }
+    let api_config = config.api();
+    if !api_config.auth().api_keys().is_empty() {
+        tracing::warn!(event = "api_external_bind", bind = %bind, "API is exposed externally");
+        return Ok(());
+    }
+    if api_config.allow_insecure_external() {
+        tracing::warn!(
+            event = "api_external_bind",
+            bind = %bind,
+            "API is exposed externally without authentication"
+        );
+        return Ok(());
+    }
+    anyhow::bail!(
+        "refusing to bind API to non-localhost address '{bind}' without api.auth.api_keys; \
+         configure API keys or set api.allow_insecure_external = true"
+    )
+}
+
+/// Builds the API routes; `session_store` is the sessions database shared with the rest
+/// of the process.
 pub fn router(
     config: Config,
     kernel: Kernel,
     agent_builder: ProviderAgentBuilder,
+    session_store: SqliteStore,
 ) -> Result<(String, Router)> {
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    check_bind_security(&config)?;
+    let base_dir = config.base_dir();
     let profile = channel_profile(&config.channels(), "api", &base_dir);
-    let kernel = kernel
+    let session_events = SessionEvents::default();
+    let kernel = channel_sandbox(kernel, &config.channels(), "api", &base_dir)
         .with_prompt_profile(profile)
-        .with_channel_id(Some("api".to_string()));
+        .with_channel_id(Some("api".to_string()))
+        .with_session_events(Some(session_events.clone()));
     let api_config = config.api();
-    let session_store = crate::session::db::SqliteStore::new(
-        config
-            .data_dir()
-            .join("sessions.db")
-            .to_string_lossy()
-            .to_string(),
+    let session_manager = Arc::new(
+        SessionManager::new(session_store.clone())
+            .with_max_stored_messages(config.memory().max_stored_messages()),
+    );
+    let memory_retriever = Arc::new(
+        MemoryRetriever::new(config.memory(), session_store)
+            .with_context_window(config.context_window_tokens()),
     );
-    session_store.touch()?;
-    let session_manager = Arc::new(SessionManager::new(session_store.clone()));
-    let memory_retriever = Arc::new(MemoryRetriever::new(config.memory(), session_store));
     let state = AppState {
         agent_builder: agent_builder.clone(),
-        max_turns: config.max_turns(),
+        max_turns: config.channel_max_turns("api"),
         kernel: Arc::new(kernel),
         config: config.clone(),
+        base_dir,
         rate_limiter: Arc::new(RateLimiter::default()),
         auth_identities: api_auth_map(&api_config.auth().api_keys()),
         session_manager,
         memory_retriever,
+        inbound_outcomes: Arc::new(InboundOutcomes::default()),
+        idempotency: IdempotencyCache::default(),
+        session_events,
     };
 
     let max_body = api_config.max_body_bytes();
+    let record_outcome = axum::middleware::from_fn_with_state(
+        Arc::clone(&state.inbound_outcomes),
+        record_inbound_outcome,
+    );
+    // `RequestBodyLimitLayer` is a hard cap for every route, so it takes the largest
+    // configured limit; `DefaultBodyLimit` then applies each route's own cap when the
+    // JSON body is read.
+    let body_limits = api_config.body_limits();
+    let route_limit = |route: &str| body_limits.limit(route, max_body);
+    let largest = ["prompt", "chat", "schedules"]
+        .into_iter()
+        .map(route_limit)
+        .fold(max_body, usize::max);
+    let limited = |route: &str| DefaultBodyLimit::max(route_limit(route));
     let app = Router::new()
-        .route("/v1/prompt", post(prompt_handler))
-        .route("/v1/chat", post(prompt_message_handler))
-        .route("/v1/schedules", post(schedule_create_handler))
-        .route("/v1/schedules", axum::routing::get(schedule_list_handler))
+        .route(
+            "/v1/prompt",
+            post(prompt_handler).layer(
+                tower::ServiceBuilder::new()
+                    .layer(limited("prompt"))
+                    .layer(record_outcome.clone()),
+            ),
+        )
+        .route(
+            "/v1/chat",
+            post(prompt_message_handler).layer(
+                tower::ServiceBuilder::new()
+                    .layer(limited("chat"))
+                    .layer(record_outcome),
+            ),
+        )
+        .route(
+            "/v1/schedules",
+            post(schedule_create_handler)
+                .layer(limited("schedules"))
+                .get(schedule_list_handler),
+        );
+    let defaults = Router::new()
+        .route("/v1/tools", axum::routing::get(tool_list_handler))
+        .route("/v1/permissions", axum::routing::get(permissions_handler))
+        .route("/v1/sessions", axum::routing::get(session_list_handler))
+        .route(
+            "/v1/sessions/{session_id}",
+            axum::routing::patch(session_update_handler),
+        )
+        .route(
+            "/v1/sessions/{session_id}/fork",
+            post(session_fork_handler),
+        )
+        .route(
+            "/v1/admin/scheduler/health",
+            axum::routing::get(scheduler_health_handler),
+        )
+        .route(
+            "/v1/admin/inbound",
+            axum::routing::get(inbound_outcomes_handler),
+        )
+        .route(
+            "/v1/admin/sessions/{session_id}/events",
+            axum::routing::get(session_events_handler),
+        )
         .route(
             "/v1/schedules/{job_id}/cancel",
             post(schedule_cancel_handler),
         )
-        .layer(RequestBodyLimitLayer::new(max_body))
+        .layer(DefaultBodyLimit::max(max_body));
+    let app = app
+        .merge(defaults)
+        .layer(RequestBodyLimitLayer::new(largest))
         .with_state(state);
+    let allowed_ips = api_config.allowed_ips();
+    let app = if allowed_ips.is_empty() {
+        app
+    } else {
+        app.layer(axum::middleware::from_fn_with_state(
+            Arc::new(allowed_ips),
+            ip_allowlist,
+        ))
+    };
 
     Ok((config.bind().to_string(), app))
 }
 
+/// Counts each prompt request by how it was answered. A 429 covers both `api.rate_limit`
+/// and provider rate limits; auth rejections count as filtered.
+async fn record_inbound_outcome(
+    State(outcomes): State<Arc<InboundOutcomes>>,
+    request: Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    let outcome = if status.is_success() {
+        InboundOutcome::Processed
+    } else if status == StatusCode::TOO_MANY_REQUESTS {
+        InboundOutcome::RateLimited
+    } else if status == StatusCode::UNAUTHORIZED {
+        InboundOutcome::Filtered
+    } else {
+        InboundOutcome::Failed
+    };
+    outcomes.record("api", None, outcome);
+    response
+}
+
+/// Rejects requests from outside `api.allowed_ips` before any handler or auth runs. Requests
+/// without a known peer address are rejected too.
+async fn ip_allowlist(
+    State(allowed): State<Arc<Vec<ipnet::IpNet>>>,
+    request: Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<std::net::SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical());
+    match peer {
+        Some(ip) if allowed.iter().any(|net| net.contains(&ip)) => next.run(request).await,
+        _ => {
+            tracing::warn!(event = "api_ip_rejected", peer = ?peer, "source IP not allowed");
+            (StatusCode::FORBIDDEN, "source IP not allowed").into_response()
+        }
+    }
+}
+
+/// Decodes `/v1/chat` attachments into a fresh dir under `data_dir`, next to WhatsApp
+/// media, so tools such as `multimodal_looker` can read them inside a jail root.
+fn store_attachments(
+    data_dir: &std::path::Path,
+    payload: &[PromptAttachment],
+) -> Result<(AttachmentDir, Vec<MediaAttachment>), (StatusCode, String)> {
+    let internal = |err: std::io::Error| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string());
+    let path = data_dir
+        .join("api-attachments")
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&path).map_err(internal)?;
+    let dir = AttachmentDir(path.canonicalize().unwrap_or(path));
+    let mut attachments = Vec::with_capacity(payload.len());
+    for (idx, attachment) in payload.iter().enumerate() {
+        let bytes = BASE64_STANDARD.decode(attachment.data.trim()).map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("attachment {} is not valid base64: {err}", idx + 1),
+            )
+        })?;
+        let mime_type = attachment.mime_type.as_deref();
+        let name = attachment
+            .file_name
+            .as_deref()
+            .and_then(|name| std::path::Path::new(name).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| {
+                let extension = mime_type
+                    .and_then(|mime| mime.split('/').nth(1))
+                    .unwrap_or("bin");
+                format!("attachment.{extension}")
+            });
+        let local_path = dir.0.join(format!("{}-{name}", idx + 1));
+        std::fs::write(&local_path, &bytes).map_err(internal)?;
+        let media_type = match mime_type.and_then(|mime| mime.split('/').next()) {
+            Some("image") => MediaType::Image,
+            Some("audio") => MediaType::Audio,
+            Some("video") => MediaType::Video,
+            _ => MediaType::Document,
+        };
+        attachments.push(MediaAttachment {
+            media_type,
+            mime_type: attachment.mime_type.clone(),
+            file_name: attachment.file_name.clone(),
+            local_path,
+            caption: None,
+            size_bytes: Some(bytes.len() as u64),
+            thumbnail_path: None,
+            thumbnail_mime_type: None,
+            thumbnail_size_bytes: None,
+        });
+    }
+    Ok((dir, attachments))
+}
+
+/// Lists the turn's artifacts with paths relative to the jail root, or the working
+/// directory when unjailed, so responses don't expose the host layout.
+fn artifact_responses(kernel: &Kernel) -> Vec<ArtifactResponse> {
+    let context = kernel.context();
+    let base = context
+        .jail_root
+        .as_deref()
+        .unwrap_or(&context.working_dir)
+        .canonicalize()
+        .ok();
+    kernel
+        .artifacts()
+        .into_iter()
+        .map(|artifact| {
+            let relative = base
+                .as_deref()
+                .and_then(|base| artifact.path.strip_prefix(base).ok())
+                .map(std::path::Path::to_path_buf)
+                .or_else(|| artifact.path.file_name().map(std::path::PathBuf::from))
+                .unwrap_or_default();
+            ArtifactResponse {
+                path: relative.to_string_lossy().into_owned(),
+                mime: artifact.mime,
+                kind: artifact.kind,
+            }
+        })
+        .collect()
+}
+
+/// Pre-authorizes reading each stored attachment, as WhatsApp does for downloaded media.
+fn with_attachment_permissions(
+    mut profile: ChannelPermissionProfile,
+    attachments: &[MediaAttachment],
+) -> ChannelPermissionProfile {
+    for attachment in attachments {
+        let path = PathPattern(attachment.local_path.to_string_lossy().to_string());
+        profile
+            .pre_authorized
+            .insert(Permission::FileRead { path: path.clone() });
+        profile.max_allowed.insert(Permission::FileRead { path });
+    }
+    profile
+}
+
+/// Resolves the model for a chat turn. An explicit `model` must name a `[[models]]` entry
+/// and is stored in the session metadata so later turns stay on it; otherwise the
+/// session's stored model is used while it is still configured.
+fn select_session_model(
+    state: &AppState,
+    session: &crate::session::types::Session,
+    requested: Option<&str>,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let router = crate::providers::factory::ProviderFactory::build_agent_router(&state.config)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    let stored = session
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(SESSION_MODEL_KEY))
+        .and_then(|value| value.as_str());
+    let Some(requested) = requested else {
+        return Ok(match stored {
+            Some(model_id) if router.contains(model_id) => Some(model_id.to_string()),
+            Some(model_id) => {
+                tracing::warn!(
+                    event = "session_model_unavailable",
+                    session_id = %session.id,
+                    model_id = %model_id,
+                    "stored session model is no longer configured; using default"
+                );
+                None
+            }
+            None => None,
+        });
+    };
+    if !router.contains(requested) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("unknown model '{requested}'"),
+        ));
+    }
+    if stored != Some(requested) {
+        state
+            .session_manager
+            .merge_metadata(&session.id, &serde_json::json!({ SESSION_MODEL_KEY: requested }))
+            .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    }
+    Ok(Some(requested.to_string()))
+}
+
+/// Builds the agent for a request and names the model it uses: the `[[models]]` id picked
+/// by routing (including the fallback to the first model when `routing.default_model` is
+/// missing), or the top-level provider model when no `[[models]]` are configured.
 fn build_agent_for_kernel(
     config: &Config,
     agent_builder: &ProviderAgentBuilder,
     kernel: Arc<Kernel>,
     max_turns: usize,
-) -> Result<crate::providers::factory::ProviderAgent> {
+    model_id: Option<&str>,
+) -> Result<(crate::providers::factory::ProviderAgent, String)> {
     let registry = kernel.tool_registry();
     if let Ok(router) = crate::providers::factory::ProviderFactory::build_agent_router(config)
         && !router.is_empty()
     {
-        router.build_default(config, registry, Arc::clone(&kernel), max_turns)
+        let agent = match model_id {
+            Some(model_id) => {
+                router.build_model(model_id, config, registry, Arc::clone(&kernel), max_turns)
+            }
+            None => router.build_default(config, registry, Arc::clone(&kernel), max_turns),
+        }?;
+        let effective = model_id
+            .or(router.default_id())
+            .map(str::to_string)
+            .or_else(|| agent.model_name())
+            .unwrap_or_default();
+        Ok((agent, effective))
     } else {
-        agent_builder
+        let agent = agent_builder
             .clone()
-            .build(registry, Arc::clone(&kernel), max_turns)
+            .build(registry, Arc::clone(&kernel), max_turns)?;
+        let effective = agent.model_name().unwrap_or_default();
+        Ok((agent, effective))
     }
 }
 
@@ -619,6 +1640,52 @@ fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<String, (Status
     Err((StatusCode::UNAUTHORIZED, "invalid api key".to_string()))
 }
 
+fn ensure_admin(state: &AppState, user_id: &str) -> Result<(), (StatusCode, String)> {
+    if state
+        .config
+        .api()
+        .admin_identities()
+        .iter()
+        .any(|identity| identity == user_id)
+    {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "admin access required".to_string()))
+    }
+}
+
+/// Observer identities can watch sessions but never drive the agent.
+fn ensure_can_prompt(state: &AppState, user_id: &str) -> Result<(), (StatusCode, String)> {
+    if state
+        .config
+        .api()
+        .observer_identities()
+        .iter()
+        .any(|identity| identity == user_id)
+    {
+        Err((
+            StatusCode::FORBIDDEN,
+            "observer identities are read-only".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn ensure_observer(state: &AppState, user_id: &str) -> Result<(), (StatusCode, String)> {
+    let api = state.config.api();
+    if api
+        .admin_identities()
+        .iter()
+        .chain(api.observer_identities().iter())
+        .any(|identity| identity == user_id)
+    {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "observer access required".to_string()))
+    }
+}
+
 fn enforce_rate_limit(state: &AppState, user_id: &str) -> Result<(), (StatusCode, String)> {
     let limit = state.config.api().rate_limit().requests_per_minute();
     if let Some(limit) = limit
@@ -655,19 +1722,29 @@ fn default_session_id(user_id: &str) -> String {
     format!("api:{}", session_user_segment(user_id))
 }
 
-fn validate_session_id(session_id: &str, user_id: &str) -> Result<(), (StatusCode, String)> {
-    if !session_id.starts_with("api:") {
+/// Lets `user_id` use `session_id`: an existing session only if they own it, as for forks,
+/// and otherwise only their default session, which is created on first use.
+fn authorize_session(
+    session_manager: &SessionManager,
+    session_id: &str,
+    user_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    if !session_id.starts_with("api:") && !session_id.starts_with(FORK_SESSION_PREFIX) {
         return Err((StatusCode::BAD_REQUEST, "invalid session_id".to_string()));
     }
-    let expected = session_user_segment(user_id);
-    let actual = session_id.trim_start_matches("api:");
-    if actual != expected {
-        return Err((
+    let session = session_manager
+        .get_session(session_id)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    match session {
+        Some(session) if session.user_id != user_id => {
+            Err((StatusCode::FORBIDDEN, "session not owned by user".to_string()))
+        }
+        None if session_id != default_session_id(user_id) => Err((
             StatusCode::FORBIDDEN,
             "session_id does not match user".to_string(),
-        ));
+        )),
+        _ => Ok(()),
     }
-    Ok(())
 }
 
 fn session_user_segment(user_id: &str) -> &str {
@@ -699,6 +1776,32 @@ fn ensure_schedule_permission(
     }
 }
 
+/// Parses per-request permission grants; each must fall within the channel's `max_allowed`.
+fn parse_grant_permissions(
+    values: &[String],
+    profile: &ChannelPermissionProfile,
+    base_dir: &std::path::Path,
+) -> Result<Vec<Permission>, (StatusCode, String)> {
+    values
+        .iter()
+        .map(|value| {
+            let permission = parse_permission_with_base(value, base_dir).map_err(|err| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("invalid permission '{value}': {err}"),
+                )
+            })?;
+            if !profile.max_allowed.allows(&permission) {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    format!("permission '{value}' is not grantable on this channel"),
+                ));
+            }
+            Ok(permission)
+        })
+        .collect()
+}
+
 fn parse_schedule_type(value: &str) -> Result<ScheduleType, (StatusCode, String)> {
     match value {
         "interval" => Ok(ScheduleType::Interval),
@@ -759,32 +1862,22 @@ fn default_job_name(task_prompt: &str) -> String {
     }
 }
 
-fn normalize_cron_expr(value: &str) -> Result<String, (StatusCode, String)> {
-    let trimmed = value.trim();
-    let (tz, raw) = if let Some((prefix, rest)) = trimmed.split_once('|') {
-        let tz = prefix.trim();
-        if tz.is_empty() {
-            return Err((StatusCode::BAD_REQUEST, "cron timezone missing".to_string()));
-        }
-        (Some(tz), rest.trim())
-    } else {
-        (None, trimmed)
-    };
-    let field_count = raw.split_whitespace().count();
-    let normalized = match field_count {
-        5 => format!("0 {raw}"),
-        6 | 7 => raw.to_string(),
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                "cron expression must have 5 or 6 fields".to_string(),
-            ));
-        }
+/// Timezone offset and name for a request: the IANA `timezone` it asked for (offset as of
+/// now), else the kernel's.
+fn request_timezone(
+    kernel: &Kernel,
+    timezone: Option<&str>,
+) -> Result<(String, String), (StatusCode, String)> {
+    let Some(timezone) = timezone else {
+        let context = kernel.context();
+        return Ok((context.timezone_offset.clone(), context.timezone_name.clone()));
     };
-    Ok(match tz {
-        Some(tz) => format!("{tz}|{normalized}"),
-        None => normalized,
-    })
+    let tz = timezone
+        .trim()
+        .parse::<chrono_tz::Tz>()
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("invalid timezone '{timezone}'")))?;
+    let offset = chrono::Utc::now().with_timezone(&tz).offset().fix();
+    Ok((offset.to_string(), tz.name().to_string()))
 }
 
 fn map_provider_error(err: ProviderError) -> (StatusCode, String) {
@@ -796,3 +1889,1099 @@ fn map_provider_error(err: ProviderError) -> (StatusCode, String) {
     tracing::error!(error = %err, status = ?status, "prompt failed");
     (status, err.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::response::IntoResponse;
+    use tower::ServiceExt;
+
+    use super::{
+        FORK_SESSION_PREFIX, IdempotencyCache, IdempotencyCheck, PromptAttachment,
+        PromptMessageError, RateLimiter, default_session_id, map_provider_error, parse_grant_permissions, request_hash, router,
+        store_attachments, with_attachment_permissions,
+    };
+    use crate::config::{
+        AgentConfig, ApiAuthConfig, ApiBodyLimitsConfig, ApiConfig, ChannelConfig, ChannelsConfig,
+        Config, ModelConfig, RoutingConfig,
+    };
+    use crate::kernel::core::Kernel;
+    use crate::kernel::permissions::{
+        ChannelPermissionProfile, DomainPattern, PathPattern, Permission,
+    };
+    use crate::session::db::SqliteStore;
+    use crate::providers::error::ProviderError;
+    use crate::providers::factory::ProviderAgentBuilder;
+    use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+    use crate::session::manager::SessionManager;
+    use crate::session::types::MessageType;
+    use crate::tools::echo::EchoTool;
+    use crate::tools::filesystem::FilesystemTool;
+    use crate::tools::registry::ToolRegistry;
+
+    /// Data dir of a test config, removed when dropped.
+    struct TestDataDir(std::path::PathBuf);
+
+    impl Drop for TestDataDir {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    /// OpenAI-style config talking to `provider_url` (a [`ScriptedProvider`]), with a fresh
+    /// data dir that lives as long as the returned guard.
+    fn test_config(provider_url: Option<String>) -> (Config, TestDataDir) {
+        let data_dir =
+            std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        let config = Config {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4o-mini".to_string()),
+            base_url: provider_url,
+            data_dir: Some(data_dir.to_string_lossy().to_string()),
+            api_key_env: Some("PATH".to_string()),
+            ..Default::default()
+        };
+        (config, TestDataDir(data_dir))
+    }
+
+    fn test_router(
+        config: Config,
+        kernel: Kernel,
+        agent_builder: ProviderAgentBuilder,
+    ) -> anyhow::Result<(String, axum::Router)> {
+        let store =
+            SqliteStore::open_migrated(&config.sessions_db_path(), config.sqlite_pragmas())?;
+        router(config, kernel, agent_builder, store)
+    }
+
+    #[test]
+    fn rate_limiter_recovers_from_poisoned_lock() {
+        let limiter = RateLimiter::default();
+        let inner = limiter.inner.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = inner.lock().unwrap();
+            panic!("handler panicked while holding the rate limiter");
+        })
+        .join();
+        assert!(limiter.inner.is_poisoned());
+
+        assert!(limiter.allow("api:alice", 2));
+        assert!(limiter.allow("api:alice", 2));
+        assert!(!limiter.allow("api:alice", 2));
+        assert!(!limiter.inner.is_poisoned());
+    }
+
+    #[test]
+    fn idempotency_keys_are_reserved_and_tied_to_the_body() {
+        let cache = IdempotencyCache::default();
+        let ttl = std::time::Duration::from_secs(60);
+        let key = || Some("api:alice\n/v1/chat\nretry-1".to_string());
+        let body = request_hash(&serde_json::json!({ "message": "hi" }));
+        let other = request_hash(&serde_json::json!({ "message": "bye" }));
+        let begin = |hash| cache.begin::<serde_json::Value>(key(), hash, ttl);
+
+        let Ok(IdempotencyCheck::Proceed(Some(guard))) = begin(body) else {
+            panic!("first request should reserve the key");
+        };
+        assert_eq!(begin(body).err().unwrap().0, StatusCode::CONFLICT);
+        drop(guard);
+
+        let Ok(IdempotencyCheck::Proceed(Some(guard))) = begin(body) else {
+            panic!("a released key can be reserved again");
+        };
+        guard.complete(&serde_json::json!({ "response": "hello" }));
+        let Ok(IdempotencyCheck::Replay(response)) = begin(body) else {
+            panic!("a finished key should replay");
+        };
+        assert_eq!(response["response"], "hello");
+        assert_eq!(
+            begin(other).err().unwrap().0,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn idempotency_cache_evicts_the_oldest_finished_key() {
+        let cache = IdempotencyCache::default();
+        let ttl = std::time::Duration::from_secs(60);
+        let body = request_hash(&serde_json::json!({}));
+        for index in 0..super::IDEMPOTENCY_MAX_ENTRIES {
+            let Ok(IdempotencyCheck::Proceed(Some(guard))) =
+                cache.begin::<serde_json::Value>(Some(format!("key-{index}")), body, ttl)
+            else {
+                panic!("key-{index} should be reserved");
+            };
+            guard.complete(&serde_json::json!({ "index": index }));
+        }
+
+        let Ok(IdempotencyCheck::Proceed(Some(_guard))) =
+            cache.begin::<serde_json::Value>(Some("new".to_string()), body, ttl)
+        else {
+            panic!("a full cache should make room");
+        };
+        let entries = cache.inner.lock().unwrap();
+        assert_eq!(entries.len(), super::IDEMPOTENCY_MAX_ENTRIES);
+        assert!(!entries.contains_key("key-0"));
+    }
+
+    #[test]
+    fn provider_errors_map_to_status_codes() {
+        let cases = [
+            (429, StatusCode::TOO_MANY_REQUESTS),
+            (500, StatusCode::SERVICE_UNAVAILABLE),
+            (502, StatusCode::SERVICE_UNAVAILABLE),
+            (503, StatusCode::SERVICE_UNAVAILABLE),
+            (504, StatusCode::SERVICE_UNAVAILABLE),
+            (400, StatusCode::BAD_REQUEST),
+            (401, StatusCode::BAD_REQUEST),
+            (404, StatusCode::BAD_REQUEST),
+        ];
+        for (upstream, expected) in cases {
+            let err = ProviderError::from_status(upstream, format!("upstream {upstream}"));
+            let (status, _) = map_provider_error(err);
+            assert_eq!(status, expected, "upstream status {upstream}");
+        }
+    }
+
+    #[tokio::test]
+    async fn permission_required_is_a_structured_conflict() {
+        let response = PromptMessageError::PermissionRequired {
+            permissions: vec!["net:api.github.com".to_string()],
+            response: None,
+        }
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "error": "permission_required",
+                "permissions": ["net:api.github.com"]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_succeeds_when_retried_with_the_required_grant() {
+        let (config, data_dir) = test_config(None);
+        std::fs::create_dir_all(&data_dir.0).unwrap();
+        let dir = data_dir.0.canonicalize().unwrap();
+        let note = dir.join("note.txt");
+        std::fs::write(&note, "hi").unwrap();
+        let read_note = ScriptedReply::tool_call(
+            "filesystem",
+            serde_json::json!({ "operation": "read", "path": note.to_string_lossy() }),
+        );
+        let provider = ScriptedProvider::start(vec![
+            read_note.clone(),
+            ScriptedReply::text("I need permission to read the note."),
+            read_note,
+            ScriptedReply::text("The note says hi."),
+        ])
+        .await;
+        let max_allowed = format!("filesystem:read:{}/**", dir.display());
+        let config = Config {
+            base_url: Some(provider.base_url()),
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["owner:alice".to_string()],
+                }),
+                ..Default::default()
+            }),
+            channels: Some(ChannelsConfig {
+                profiles: [(
+                    "api".to_string(),
+                    ChannelConfig {
+                        max_allowed: Some(vec![max_allowed]),
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+            }),
+            ..config
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(FilesystemTool::new())).unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config.clone(), kernel, agent_builder).unwrap();
+        let chat = |body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat")
+                .header("content-type", "application/json")
+                .header("x-api-key", "owner")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let denied = app
+            .clone()
+            .oneshot(chat(serde_json::json!({ "message": "what does my note say?" })))
+            .await
+            .unwrap();
+        assert_eq!(denied.status(), StatusCode::CONFLICT);
+        let denied = json(denied).await;
+        let required = format!("filesystem:read:{}", note.display());
+        assert_eq!(denied["permissions"], serde_json::json!([required]));
+        assert_eq!(denied["response"], "I need permission to read the note.");
+        let sessions = SessionManager::new(crate::session::db::SqliteStore::new(
+            config.sessions_db_path().to_string_lossy().to_string(),
+        ));
+        let session_id = default_session_id("alice");
+        assert!(
+            sessions
+                .get_messages(&session_id, 10)
+                .map(|messages| messages.is_empty())
+                .unwrap_or(true)
+        );
+
+        let granted = app
+            .oneshot(chat(serde_json::json!({
+                "message": "what does my note say?",
+                "grant_permissions": [required],
+            })))
+            .await
+            .unwrap();
+        assert_eq!(granted.status(), StatusCode::OK);
+        assert_eq!(json(granted).await["response"], "The note says hi.");
+        let stored = sessions.get_messages(&session_id, 10).unwrap();
+        assert_eq!(stored.first().unwrap().content, "what does my note say?");
+        assert_eq!(stored.last().unwrap().content, "The note says hi.");
+    }
+
+    #[tokio::test]
+    async fn say_messages_come_back_in_the_chat_response() {
+        let (config, _data_dir) = test_config(None);
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::tool_call(
+                "say",
+                serde_json::json!({ "message": "Crunching the numbers..." }),
+            ),
+            ScriptedReply::text("Done: 42."),
+        ])
+        .await;
+        let config = Config {
+            base_url: Some(provider.base_url()),
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["owner:alice".to_string()],
+                }),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(crate::tools::say::SayTool::new()))
+            .unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/chat")
+                    .header("content-type", "application/json")
+                    .header("x-api-key", "owner")
+                    .body(Body::from(
+                        serde_json::json!({ "message": "add it up" }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+        assert_eq!(body["response"], "Done: 42.");
+        assert_eq!(body["messages"], serde_json::json!(["Crunching the numbers..."]));
+    }
+
+    #[test]
+    fn grant_permissions_must_fit_channel_max_allowed() {
+        let mut profile = ChannelPermissionProfile::default();
+        profile.max_allowed.insert(Permission::NetAccess {
+            domain: DomainPattern("api.github.com".to_string()),
+        });
+        let base_dir = std::path::Path::new("/tmp");
+
+        let grants =
+            parse_grant_permissions(&["net:api.github.com".to_string()], &profile, base_dir)
+                .unwrap();
+        assert_eq!(grants.len(), 1);
+
+        let (status, _) =
+            parse_grant_permissions(&["net:example.com".to_string()], &profile, base_dir)
+                .unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _) =
+            parse_grant_permissions(&["bogus".to_string()], &profile, base_dir).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn chat_model_choice_sticks_to_the_session() {
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::text("first"),
+            ScriptedReply::text("second"),
+        ])
+        .await;
+        let base_url = Some(provider.base_url());
+        let model = |id: &str, name: &str| ModelConfig {
+            id: id.to_string(),
+            provider: Some("openai".to_string()),
+            model: name.to_string(),
+            base_url: base_url.clone(),
+            api_key_env: Some("PATH".to_string()),
+            ..Default::default()
+        };
+        let (config, _data_dir) = test_config(None);
+        let config = Config {
+            models: Some(vec![model("fast", "fast-model"), model("smart", "smart-model")]),
+            ..config
+        };
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let chat = |payload: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat")
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(chat(serde_json::json!({ "message": "hi", "model": "unknown" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(chat(serde_json::json!({ "message": "hi", "model": "smart" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let session_id = body["session_id"].as_str().unwrap().to_string();
+
+        // Tagging the session merges into its metadata and keeps the chosen model.
+        let patch = Request::builder()
+            .method("PATCH")
+            .uri(format!("/v1/sessions/{session_id}"))
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "title": null, "metadata": { "tags": ["work"] } })
+                    .to_string(),
+            ))
+            .unwrap();
+        let response = app.clone().oneshot(patch).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["title"], serde_json::Value::Null);
+        assert_eq!(
+            body["metadata"],
+            serde_json::json!({ "model": "smart", "tags": ["work"] })
+        );
+
+        let response = app
+            .oneshot(chat(serde_json::json!({ "message": "again" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["model"], "smart-model");
+        assert_eq!(requests[1]["model"], "smart-model");
+    }
+
+    #[test]
+    fn chat_attachments_stay_inside_a_jailed_data_dir() {
+        let (config, _data_dir) = test_config(None);
+        let jail_root = config.data_dir();
+        std::fs::create_dir_all(&jail_root).unwrap();
+        let image = PromptAttachment {
+            data: "iVBORw0KGgo=".to_string(),
+            mime_type: Some("image/png".to_string()),
+            file_name: None,
+        };
+        let (_dir, attachments) =
+            store_attachments(&config.data_dir(), std::slice::from_ref(&image)).unwrap();
+        let stored = attachments[0].local_path.to_string_lossy().to_string();
+
+        let resolved = crate::tools::path_utils::resolve_path(
+            &jail_root,
+            Some(jail_root.as_path()),
+            &stored,
+        )
+        .unwrap();
+        assert!(resolved.canonical.starts_with(jail_root.canonicalize().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn chat_attachments_are_readable_during_the_turn_and_removed_after() {
+        let image = PromptAttachment {
+            data: "iVBORw0KGgo=".to_string(),
+            mime_type: Some("image/png".to_string()),
+            file_name: Some("../photo.png".to_string()),
+        };
+        let (config, _data_dir) = test_config(None);
+        let (dir, attachments) =
+            store_attachments(&config.data_dir(), std::slice::from_ref(&image)).unwrap();
+        let stored = attachments[0].local_path.clone();
+        assert_eq!(stored.parent(), Some(dir.0.as_path()));
+        assert!(stored.ends_with("1-photo.png"));
+        assert_eq!(std::fs::read(&stored).unwrap(), b"\x89PNG\r\n\x1a\n");
+        let profile =
+            with_attachment_permissions(ChannelPermissionProfile::default(), &attachments);
+        let read = Permission::FileRead {
+            path: PathPattern(stored.to_string_lossy().to_string()),
+        };
+        assert!(profile.pre_authorized.allows(&read));
+        assert!(profile.max_allowed.allows(&read));
+        drop(dir);
+        assert!(!stored.exists());
+        assert!(config.data_dir().join("api-attachments").is_dir());
+
+        let provider = ScriptedProvider::start(vec![ScriptedReply::text("a cat")]).await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let chat = |attachment: serde_json::Value| {
+            let payload = serde_json::json!({
+                "message": "what is this?",
+                "attachments": [attachment],
+            });
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat")
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(chat(serde_json::json!({ "data": "not base64!" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(chat(serde_json::json!({
+                "data": image.data,
+                "mime_type": "image/png",
+                "file_name": "photo.png",
+            })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = provider.requests()[0].to_string();
+        let listed = request
+            .split("type=image path=")
+            .nth(1)
+            .and_then(|rest| rest.split(' ').next())
+            .expect("attachment listed in prompt");
+        assert!(listed.ends_with("1-photo.png"), "{listed}");
+        assert!(!std::path::Path::new(listed).exists());
+    }
+
+    #[tokio::test]
+    async fn responses_report_the_fallback_model_that_answered() {
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::text("from chat"),
+            ScriptedReply::text("from prompt"),
+        ])
+        .await;
+        let (config, _data_dir) = test_config(None);
+        let config = Config {
+            // `primary` is no longer configured, so routing falls back to `backup`.
+            routing: Some(RoutingConfig {
+                default_model: Some("primary".to_string()),
+            }),
+            models: Some(vec![ModelConfig {
+                id: "backup".to_string(),
+                provider: Some("openai".to_string()),
+                model: "backup-model".to_string(),
+                base_url: Some(provider.base_url()),
+                api_key_env: Some("PATH".to_string()),
+                ..Default::default()
+            }]),
+            ..config
+        };
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+
+        for (uri, payload) in [
+            ("/v1/chat", serde_json::json!({ "message": "hi" })),
+            ("/v1/prompt", serde_json::json!({ "prompt": "hi" })),
+        ] {
+            let request = Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["model_id"], "backup", "{uri}: {body}");
+        }
+        assert_eq!(provider.requests()[0]["model"], "backup-model");
+    }
+
+    #[tokio::test]
+    async fn configured_provider_retries_are_used_for_prompts() {
+        // An empty script answers every request with HTTP 500, which is retryable.
+        let provider = ScriptedProvider::start(Vec::new()).await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            provider_max_retries: Some(1),
+            ..config
+        };
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"message":"hi"}"#))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn inbound_outcomes_count_filtered_and_processed_prompts() {
+        let provider = ScriptedProvider::start(vec![ScriptedReply::text("hello")]).await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["secret:alice".to_string()],
+                }),
+                admin_identities: Some(vec!["alice".to_string()]),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let chat = |key: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat")
+                .header("content-type", "application/json")
+                .header("x-api-key", key)
+                .body(Body::from(r#"{"message":"hi"}"#))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(chat("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(chat("secret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .uri("/v1/admin/inbound")
+            .header("x-api-key", "secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let counts: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            counts,
+            serde_json::json!({ "processed": 1, "failed": 0, "rate_limited": 0, "filtered": 1 })
+        );
+    }
+
+    #[tokio::test]
+    async fn observers_stream_session_events_but_cannot_prompt() {
+        use futures::StreamExt;
+
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::tool_call("echo", serde_json::json!({ "message": "ping" })),
+            ScriptedReply::text("pong"),
+        ])
+        .await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["owner:alice".to_string(), "watch:dashboard".to_string()],
+                }),
+                observer_identities: Some(vec!["dashboard".to_string()]),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new())).unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let chat = |key: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat")
+                .header("content-type", "application/json")
+                .header("x-api-key", key)
+                .body(Body::from(r#"{"message":"hi"}"#))
+                .unwrap()
+        };
+        let events = |key: &str| {
+            Request::builder()
+                .uri(format!("/v1/admin/sessions/{}/events", default_session_id("alice")))
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(events("owner")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(events("watch")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut stream = response.into_body().into_data_stream();
+
+        let response = app.clone().oneshot(chat("owner")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut received = String::new();
+        while !received.contains("event: done") {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+                .await
+                .expect("session event")
+                .unwrap()
+                .unwrap();
+            received.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert!(received.contains("event: tool_event"), "{received}");
+        assert!(received.contains(r#""tool":"echo""#), "{received}");
+        assert!(received.contains(r#""response":"pong""#), "{received}");
+
+        let response = app.clone().oneshot(chat("watch")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let cancel = Request::builder()
+            .method("POST")
+            .uri("/v1/schedules/some-job/cancel")
+            .header("x-api-key", "watch")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(cancel).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn forked_sessions_continue_from_the_fork_point() {
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::text("Paris it is"),
+            ScriptedReply::text("Booked the Louvre"),
+            ScriptedReply::text("Rome instead"),
+            ScriptedReply::text("Hello"),
+        ])
+        .await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec![
+                        "owner:alice".to_string(),
+                        "other:bob".to_string(),
+                        "lookalike:alice:fork-1".to_string(),
+                    ],
+                }),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let post = |uri: &str, key: &str, body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header("x-api-key", key)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        for message in ["plan a trip", "book a museum"] {
+            let response = app
+                .clone()
+                .oneshot(post("/v1/chat", "owner", serde_json::json!({ "message": message })))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let fork_uri = format!("/v1/sessions/{}/fork", default_session_id("alice"));
+        let body = serde_json::json!({ "seq_order": 1 });
+        let response = app
+            .clone()
+            .oneshot(post(&fork_uri, "other", body.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(post(&fork_uri, "owner", body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let fork: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(fork["forked_from"], "api:alice");
+        assert_eq!(fork["message_count"], 2);
+        let fork_id = fork["session_id"].as_str().unwrap();
+        assert!(fork_id.starts_with(FORK_SESSION_PREFIX), "{fork_id}");
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/v1/chat",
+                "owner",
+                serde_json::json!({ "message": "what about Rome?", "session_id": fork_id }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = provider.requests()[2].to_string();
+        assert!(request.contains("Paris it is"), "{request}");
+        assert!(!request.contains("Booked the Louvre"), "{request}");
+
+        // Ownership comes from the stored session, not from the id naming the user:
+        // `api:alice:fork-1` is the default session of the user `alice:fork-1`.
+        let response = app
+            .clone()
+            .oneshot(post("/v1/chat", "lookalike", serde_json::json!({ "message": "hi" })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        for (uri, session_id) in [
+            ("/v1/chat", "api:alice:fork-1"),
+            ("/v1/prompt", "api:alice:fork-1"),
+            ("/v1/chat", "api:alice:fork-unknown"),
+            ("/v1/chat", "api-fork:unknown"),
+        ] {
+            let body = serde_json::json!({
+                "message": "peek",
+                "prompt": "peek",
+                "session_id": session_id,
+            });
+            let response = app.clone().oneshot(post(uri, "owner", body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{uri} {session_id}");
+        }
+        assert_eq!(provider.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn permissions_endpoint_lists_effective_and_grantable_permissions() {
+        let profile = ChannelConfig {
+            pre_authorized: Some(vec!["net:example.com".to_string()]),
+            max_allowed: Some(vec![
+                "net:example.com".to_string(),
+                "net:api.github.com".to_string(),
+                "filesystem:read:notes/**".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let (config, _data_dir) = test_config(None);
+        let mut config = Config {
+            channels: Some(ChannelsConfig {
+                profiles: [("api".to_string(), profile)].into(),
+            }),
+            ..config
+        };
+        // Relative paths resolve against the base the config was loaded with, not the
+        // process cwd at request time.
+        config.resolve_paths(std::path::Path::new("/srv/picobot"));
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/permissions")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["channel_id"], "api");
+        let permissions = body["permissions"].as_array().unwrap();
+        assert!(permissions.contains(&serde_json::json!("net:example.com")), "{body}");
+        assert!(!permissions.contains(&serde_json::json!("net:api.github.com")), "{body}");
+        assert_eq!(
+            body["grantable"],
+            serde_json::json!([
+                "filesystem:read:/srv/picobot/notes/**",
+                "net:api.github.com",
+                "net:example.com"
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_turns_store_tool_calls_between_user_and_assistant_messages() {
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::tool_call(
+                "echo",
+                serde_json::json!({ "message": "ping", "api_token": "hunter2" }),
+            ),
+            ScriptedReply::text("pong"),
+        ])
+        .await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["owner:alice".to_string()],
+                }),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let sessions_db = config.sessions_db_path();
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new())).unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/chat")
+                    .header("content-type", "application/json")
+                    .header("x-api-key", "owner")
+                    .body(Body::from(r#"{"message":"hi"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let manager = SessionManager::new(crate::session::db::SqliteStore::new(
+            sessions_db.to_string_lossy().to_string(),
+        ));
+        let stored = manager.get_messages(&default_session_id("alice"), 10).unwrap();
+        let types = stored.iter().map(|message| message.message_type.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![MessageType::User, MessageType::Tool, MessageType::Assistant]
+        );
+        assert!(stored.windows(2).all(|pair| pair[0].seq_order < pair[1].seq_order));
+        let call: serde_json::Value = serde_json::from_str(&stored[1].content).unwrap();
+        assert_eq!(
+            call,
+            serde_json::json!({
+                "tool": "echo",
+                "input": { "message": "ping", "api_token": "[redacted]" },
+                "outcome": "success",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_uses_the_api_channel_turn_budget() {
+        let mut script = vec![ScriptedReply::tool_call("echo", serde_json::json!({})); 10];
+        script.push(ScriptedReply::text("partial summary"));
+        let provider = ScriptedProvider::start(script).await;
+        let budget = |max_turns| ChannelConfig {
+            max_turns: Some(max_turns),
+            ..Default::default()
+        };
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            max_turns: Some(6),
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["owner:alice".to_string()],
+                }),
+                ..Default::default()
+            }),
+            channels: Some(ChannelsConfig {
+                profiles: [
+                    ("api".to_string(), budget(1)),
+                    ("whatsapp".to_string(), budget(4)),
+                ]
+                .into(),
+            }),
+            ..config
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new())).unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/chat")
+                    .header("content-type", "application/json")
+                    .header("x-api-key", "owner")
+                    .body(Body::from(r#"{"message":"keep going"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["turn_limit_reached"], true);
+        let tool_rounds = provider
+            .requests()
+            .iter()
+            .filter(|request| request["tools"].as_array().is_some_and(|tools| !tools.is_empty()))
+            .count();
+        // rig makes max_turns + 2 requests with tools before giving up: 3 for the api
+        // channel's budget of 1, where the global budget of 6 would allow 8.
+        assert_eq!(tool_rounds, 3);
+    }
+
+    #[tokio::test]
+    async fn chat_stops_after_agent_max_tool_rounds() {
+        let mut script = vec![ScriptedReply::tool_call("echo", serde_json::json!({})); 10];
+        script.push(ScriptedReply::text("partial summary"));
+        let provider = ScriptedProvider::start(script).await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            max_turns: Some(6),
+            agent: Some(AgentConfig {
+                max_tool_rounds: Some(2),
+            }),
+            api: Some(ApiConfig {
+                auth: Some(ApiAuthConfig {
+                    api_keys: vec!["owner:alice".to_string()],
+                }),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new())).unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/chat")
+                    .header("content-type", "application/json")
+                    .header("x-api-key", "owner")
+                    .body(Body::from(r#"{"message":"keep going"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["turn_limit_reached"], true);
+        let tool_rounds = provider
+            .requests()
+            .iter()
+            .filter(|request| request["tools"].as_array().is_some_and(|tools| !tools.is_empty()))
+            .count();
+        // max_tool_rounds + 2 requests with tools; the legacy max_turns of 6 is ignored.
+        assert_eq!(tool_rounds, 4);
+    }
+
+    #[tokio::test]
+    async fn body_limits_apply_per_route() {
+        let provider = ScriptedProvider::start(vec![ScriptedReply::text("ok")]).await;
+        let (config, _data_dir) = test_config(Some(provider.base_url()));
+        let config = Config {
+            api: Some(ApiConfig {
+                // Above axum's 2 MiB default for JSON bodies, to check the route cap wins.
+                body_limits: Some(ApiBodyLimitsConfig {
+                    chat: Some(3 * 1024 * 1024),
+                    schedules: Some(1024),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..config
+        };
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+        let padding = "x".repeat(5 * 1024 * 1024 / 2);
+        let post = |uri: &str, payload: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap()
+        };
+
+        let schedule = serde_json::json!({
+            "schedule_type": "interval",
+            "schedule_expr": "60",
+            "task_prompt": padding,
+        });
+        let response = app
+            .clone()
+            .oneshot(post("/v1/schedules", schedule))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let chat = serde_json::json!({ "message": padding });
+        let response = app.oneshot(post("/v1/chat", chat)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod api;
+pub mod outcomes;
 pub mod permissions;
 pub mod repl;
+pub mod turn;
 pub mod whatsapp;
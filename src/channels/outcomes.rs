@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+/// How a channel disposed of one inbound message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboundOutcome {
+    /// The agent answered and the turn was recorded.
+    Processed,
+    /// The message was accepted but handling failed (session, agent, or provider error).
+    Failed,
+    /// The message was refused because the channel was at capacity or over its rate limit.
+    RateLimited,
+    /// The sender was not allowed to use the channel.
+    Filtered,
+}
+
+impl InboundOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Processed => "processed",
+            Self::Failed => "failed",
+            Self::RateLimited => "rate_limited",
+            Self::Filtered => "filtered",
+        }
+    }
+}
+
+/// Counters of inbound message outcomes since startup, one set for all messages recorded.
+///
+/// Counts are not split by `channel_id`, which only labels the log event; a channel that
+/// wants its own totals keeps its own `InboundOutcomes`, as the API and WhatsApp channels do.
+#[derive(Debug, Default)]
+pub struct InboundOutcomes {
+    processed: AtomicU64,
+    failed: AtomicU64,
+    rate_limited: AtomicU64,
+    filtered: AtomicU64,
+}
+
+/// Point-in-time copy of [`InboundOutcomes`], as served by `/v1/admin/inbound`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct InboundOutcomeCounts {
+    pub processed: u64,
+    pub failed: u64,
+    pub rate_limited: u64,
+    pub filtered: u64,
+}
+
+impl InboundOutcomes {
+    pub fn record(&self, channel_id: &str, user_id: Option<&str>, outcome: InboundOutcome) {
+        let total = self.counter(outcome).fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::info!(
+            event = "inbound_outcome",
+            channel_id = %channel_id,
+            user_id = ?user_id,
+            outcome = outcome.as_str(),
+            total,
+            "inbound message handled"
+        );
+    }
+
+    pub fn counts(&self) -> InboundOutcomeCounts {
+        InboundOutcomeCounts {
+            processed: self.processed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            filtered: self.filtered.load(Ordering::Relaxed),
+        }
+    }
+
+    fn counter(&self, outcome: InboundOutcome) -> &AtomicU64 {
+        match outcome {
+            InboundOutcome::Processed => &self.processed,
+            InboundOutcome::Failed => &self.failed,
+            InboundOutcome::RateLimited => &self.rate_limited,
+            InboundOutcome::Filtered => &self.filtered,
+        }
+    }
+}
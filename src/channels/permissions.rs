@@ -1,23 +1,36 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::config::ChannelsConfig;
+use crate::kernel::core::Kernel;
 use crate::kernel::permissions::{
     CapabilitySet, ChannelPermissionProfile, MemoryScope, Permission, parse_permission_with_base,
+    resolve_permission_path,
 };
 
 pub fn channel_profile(
     config: &ChannelsConfig,
     channel_id: &str,
     base_dir: &Path,
+) -> ChannelPermissionProfile {
+    named_channel_profile(config, channel_id, channel_id, base_dir)
+}
+
+/// Like [`channel_profile`], for a channel that reads `[channels.profiles.<profile_name>]`;
+/// default grants still name `channel_id`, the channel the session lives on.
+pub fn named_channel_profile(
+    config: &ChannelsConfig,
+    profile_name: &str,
+    channel_id: &str,
+    base_dir: &Path,
 ) -> ChannelPermissionProfile {
     let mut profile = ChannelPermissionProfile::default();
-    let Some(channel) = config.profiles.get(channel_id) else {
-        profile.pre_authorized = default_pre_authorized();
+    let Some(channel) = config.profiles.get(profile_name) else {
+        profile.pre_authorized = default_pre_authorized(channel_id);
         profile.max_allowed = profile.pre_authorized.clone();
         return profile;
     };
     if channel.pre_authorized.is_none() && channel.max_allowed.is_none() {
-        profile.pre_authorized = default_pre_authorized();
+        profile.pre_authorized = default_pre_authorized(channel_id);
         profile.max_allowed = profile.pre_authorized.clone();
     } else {
         profile.pre_authorized = parse_permissions(channel.pre_authorized.as_ref(), base_dir);
@@ -25,12 +38,66 @@ pub fn channel_profile(
         if profile.max_allowed.permissions().next().is_none() {
             profile.max_allowed = profile.pre_authorized.clone();
         }
+        profile.pre_authorized =
+            clamp_to_max_allowed(profile_name, &profile.pre_authorized, &profile.max_allowed);
+    }
+    if channel.auto_grant_within_max_allowed() {
+        for permission in profile.max_allowed.permissions() {
+            profile.pre_authorized.insert(permission.clone());
+        }
     }
     profile.allow_user_prompts = channel.allow_user_prompts();
     profile.prompt_timeout_secs = channel.prompt_timeout_secs();
+    profile.prompt_timeout_action = channel.prompt_timeout_action();
     profile
 }
 
+/// Applies the channel's `jail_root` and `working_dir` overrides to `kernel`.
+///
+/// Relative paths resolve against `base_dir`; unset fields keep the kernel's global values.
+pub fn channel_sandbox(
+    kernel: Kernel,
+    config: &ChannelsConfig,
+    channel_id: &str,
+    base_dir: &Path,
+) -> Kernel {
+    let Some(channel) = config.profiles.get(channel_id) else {
+        return kernel;
+    };
+    let mut kernel = kernel;
+    if let Some(jail_root) = &channel.jail_root {
+        let jail_root = PathBuf::from(resolve_permission_path(base_dir, jail_root));
+        kernel = kernel.with_jail_root(Some(jail_root));
+    }
+    if let Some(working_dir) = &channel.working_dir {
+        let working_dir = PathBuf::from(resolve_permission_path(base_dir, working_dir));
+        kernel = kernel.with_working_dir(working_dir);
+    }
+    kernel
+}
+
+/// Drops pre-authorized permissions that `max_allowed` does not cover, so the ceiling holds
+/// even when validation warnings about the channel were ignored.
+fn clamp_to_max_allowed(
+    channel_id: &str,
+    pre_authorized: &CapabilitySet,
+    max_allowed: &CapabilitySet,
+) -> CapabilitySet {
+    let (kept, dropped): (Vec<Permission>, Vec<Permission>) = pre_authorized
+        .permissions()
+        .cloned()
+        .partition(|permission| max_allowed.allows(permission));
+    for permission in &dropped {
+        tracing::warn!(
+            event = "pre_authorized_exceeds_max_allowed",
+            channel_id = %channel_id,
+            permission = %permission,
+            "dropping pre-authorized permission outside max_allowed"
+        );
+    }
+    CapabilitySet::from_permissions(&kept)
+}
+
 fn parse_permissions(entries: Option<&Vec<String>>, base_dir: &Path) -> CapabilitySet {
     let mut set = CapabilitySet::empty();
     let Some(entries) = entries else {
@@ -54,7 +121,7 @@ fn parse_permissions(entries: Option<&Vec<String>>, base_dir: &Path) -> Capabili
     set
 }
 
-fn default_pre_authorized() -> CapabilitySet {
+fn default_pre_authorized(channel_id: &str) -> CapabilitySet {
     let mut set = CapabilitySet::empty();
     set.insert(Permission::MemoryRead {
         scope: MemoryScope::Session,
@@ -62,15 +129,16 @@ fn default_pre_authorized() -> CapabilitySet {
     set.insert(Permission::MemoryWrite {
         scope: MemoryScope::Session,
     });
+    // Only the channel the session lives on; other channels must be granted explicitly.
     set.insert(Permission::Notify {
-        channel: "*".to_string(),
+        channel: channel_id.to_string(),
     });
     set
 }
 
 #[cfg(test)]
 mod tests {
-    use super::channel_profile;
+    use super::{channel_profile, named_channel_profile};
     use crate::config::{ChannelConfig, ChannelsConfig};
     use crate::kernel::permissions::{MemoryScope, Permission};
     use std::collections::HashMap;
@@ -99,6 +167,37 @@ mod tests {
         assert!(profile.max_allowed.allows(&required));
     }
 
+    #[test]
+    fn channel_profile_default_notify_is_scoped_to_channel() {
+        let config = ChannelsConfig::default();
+        let profile = channel_profile(&config, "whatsapp", PathBuf::from("/tmp").as_path());
+        assert!(profile.pre_authorized.allows(&Permission::Notify {
+            channel: "whatsapp".to_string(),
+        }));
+        assert!(!profile.pre_authorized.allows(&Permission::Notify {
+            channel: "email".to_string(),
+        }));
+    }
+
+    #[test]
+    fn named_profile_default_notify_uses_the_channel_not_the_profile() {
+        let mut profiles = HashMap::new();
+        profiles.insert("whatsapp-sales".to_string(), ChannelConfig::default());
+        let config = ChannelsConfig { profiles };
+        let profile = named_channel_profile(
+            &config,
+            "whatsapp-sales",
+            "whatsapp",
+            PathBuf::from("/tmp").as_path(),
+        );
+        assert!(profile.pre_authorized.allows(&Permission::Notify {
+            channel: "whatsapp".to_string(),
+        }));
+        assert!(!profile.pre_authorized.allows(&Permission::Notify {
+            channel: "whatsapp-sales".to_string(),
+        }));
+    }
+
     #[test]
     fn channel_profile_parses_configured_permissions() {
         let mut channel = ChannelConfig::default();
@@ -115,6 +214,28 @@ mod tests {
         assert!(profile.pre_authorized.allows(&required));
     }
 
+    #[test]
+    fn channel_profile_drops_pre_authorized_outside_max_allowed() {
+        let mut channel = ChannelConfig::default();
+        channel.pre_authorized = Some(vec![
+            "filesystem:read:/tmp/**".to_string(),
+            "shell:*".to_string(),
+        ]);
+        channel.max_allowed = Some(vec!["filesystem:read:/tmp/**".to_string()]);
+        let mut profiles = HashMap::new();
+        profiles.insert("api".to_string(), channel);
+        let config = ChannelsConfig { profiles };
+
+        let profile = channel_profile(&config, "api", PathBuf::from("/tmp").as_path());
+        assert!(profile.pre_authorized.allows(&Permission::FileRead {
+            path: crate::kernel::permissions::PathPattern("/tmp/**".to_string()),
+        }));
+        assert!(!profile.pre_authorized.allows(&Permission::ShellExec {
+            allowed_commands: None,
+        }));
+        assert_eq!(profile.pre_authorized.permissions().count(), 1);
+    }
+
     #[test]
     fn channel_profile_max_allowed_inherits_pre_authorized() {
         let mut channel = ChannelConfig::default();
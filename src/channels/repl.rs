@@ -1,21 +1,25 @@
 use std::io::{self, Write};
 use std::sync::Arc;
 
-use crate::channels::permissions::channel_profile;
+use crate::channels::permissions::{channel_profile, channel_sandbox};
+use crate::channels::turn::{ChannelTurn, TurnInput, load_or_create_session};
 use crate::config::Config;
 use crate::kernel::core::Kernel;
 use crate::kernel::permissions::{Permission, PermissionPrompter, PromptDecision};
-use crate::providers::factory::ProviderAgentBuilder;
+use crate::providers::factory::{ProviderAgent, ProviderAgentBuilder};
 use anyhow::{Context, Result};
 use futures::StreamExt;
 use rig::agent::{Agent, MultiTurnStreamItem, Text};
 use rig::completion::{CompletionModel, GetTokenUsage, Usage};
 use rig::streaming::{StreamedAssistantContent, StreamedUserContent, StreamingPrompt};
 use rig::wasm_compat::WasmCompatSend;
+use tokio_util::sync::CancellationToken;
 
+use crate::session::db::SqliteStore;
 use crate::session::manager::SessionManager;
 use crate::session::memory::MemoryRetriever;
-use crate::session::types::{MessageType, StoredMessage};
+use crate::session::types::{MessageType, NewMessage};
+use crate::tools::say::MessageSink;
 use async_trait::async_trait;
 
 struct StreamedPromptResult {
@@ -82,7 +86,83 @@ where
     })
 }
 
-struct ReplPrompter;
+/// What a Ctrl+C press does in the interactive REPL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InterruptEvent {
+    Cancel,
+    Quit,
+}
+
+/// Tracks the in-flight prompt so Ctrl+C cancels it instead of killing the REPL. A press
+/// while idle, or a second press while a cancel is already pending, quits.
+#[derive(Default)]
+struct Interrupts {
+    in_flight: std::sync::Mutex<Option<CancellationToken>>,
+}
+
+impl Interrupts {
+    fn begin(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+        *self.lock() = Some(token.clone());
+        token
+    }
+
+    fn end(&self) {
+        *self.lock() = None;
+    }
+
+    fn on_ctrl_c(&self) -> InterruptEvent {
+        match self.lock().as_ref() {
+            Some(token) if !token.is_cancelled() => {
+                token.cancel();
+                InterruptEvent::Cancel
+            }
+            _ => InterruptEvent::Quit,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<CancellationToken>> {
+        self.in_flight.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Prints `say` messages as they arrive, between the streamed response text. In plain
+/// mode they go to stderr so stdout carries only responses.
+struct ReplMessageSink {
+    plain: bool,
+}
+
+#[async_trait]
+impl MessageSink for ReplMessageSink {
+    async fn send(&self, message: &str) -> Result<(), anyhow::Error> {
+        if self.plain {
+            let mut stderr = io::stderr();
+            writeln!(stderr, "\n{message}").context("failed to write message")?;
+            return stderr.flush().context("failed to flush stderr");
+        }
+        let mut stdout = io::stdout();
+        writeln!(stdout, "\n{message}").context("failed to write message")?;
+        stdout.flush().context("failed to flush stdout")
+    }
+}
+
+/// Reads permission decisions from stdin. In plain mode the questions go to stderr so
+/// stdout carries only responses.
+struct ReplPrompter {
+    plain: bool,
+}
+
+impl ReplPrompter {
+    fn say(&self, text: &str) {
+        if self.plain {
+            eprint!("{text}");
+            let _ = io::stderr().flush();
+        } else {
+            print!("{text}");
+            let _ = io::stdout().flush();
+        }
+    }
+}
 
 #[async_trait]
 impl PermissionPrompter for ReplPrompter {
@@ -92,9 +172,9 @@ impl PermissionPrompter for ReplPrompter {
         permissions: &[Permission],
         timeout_secs: u64,
     ) -> Option<PromptDecision> {
-        println!("\nPermission required for tool '{tool_name}':");
+        self.say(&format!("\nPermission required for tool '{tool_name}':\n"));
         for permission in permissions {
-            println!("- {permission}");
+            self.say(&format!("- {permission}\n"));
         }
         tracing::info!(
             event = "prompt_requested",
@@ -103,23 +183,24 @@ impl PermissionPrompter for ReplPrompter {
             timeout_secs,
             "permission prompt requested"
         );
-        print!("Allow? [o]nce / [s]ession / [n]o (timeout {timeout_secs}s): ");
-        let _ = io::stdout().flush();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
+        self.say(&format!(
+            "Allow? [o]nce / [s]ession / [n]o (timeout {timeout_secs}s): "
+        ));
+        let Ok(input) = read_stdin_line().await else {
             tracing::warn!(
                 event = "prompt_decision",
                 tool = %tool_name,
                 decision = "input_error",
                 "failed to read prompt input"
             );
-            return None;
-        }
+            return Some(PromptDecision::Deny);
+        };
+        // Anything but an explicit allow is a denial; `None` is reserved for timeouts so
+        // the channel's `prompt_timeout_action` never applies to unreadable answers.
         let decision = match input.trim().to_ascii_lowercase().as_str() {
-            "o" | "once" => Some(PromptDecision::AllowOnce),
+            "o" | "once" | "y" | "yes" => Some(PromptDecision::AllowOnce),
             "s" | "session" => Some(PromptDecision::AllowSession),
-            "n" | "no" => Some(PromptDecision::Deny),
-            _ => None,
+            _ => Some(PromptDecision::Deny),
         };
         tracing::info!(
             event = "prompt_decision",
@@ -137,17 +218,15 @@ impl PermissionPrompter for ReplPrompter {
         extension: std::time::Duration,
         timeout_secs: u64,
     ) -> Option<bool> {
-        println!(
-            "\nTool '{tool_name}' hit a soft timeout at {:.0}s.",
+        self.say(&format!(
+            "\nTool '{tool_name}' hit a soft timeout at {:.0}s.\n",
             timeout.as_secs_f64()
-        );
-        print!(
+        ));
+        self.say(&format!(
             "Extend by {:.0}s? [y]es / [n]o (timeout {timeout_secs}s): ",
             extension.as_secs_f64()
-        );
-        let _ = io::stdout().flush();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
+        ));
+        let Ok(input) = read_stdin_line().await else {
             tracing::warn!(
                 event = "prompt_decision",
                 tool = %tool_name,
@@ -155,7 +234,7 @@ impl PermissionPrompter for ReplPrompter {
                 "failed to read timeout extension input"
             );
             return None;
-        }
+        };
         let approved = matches!(input.trim().to_ascii_lowercase().as_str(), "y" | "yes");
         tracing::info!(
             event = "prompt_decision",
@@ -167,10 +246,26 @@ impl PermissionPrompter for ReplPrompter {
     }
 }
 
+/// Reads one line from stdin on a blocking thread, so the kernel can give up on a prompt
+/// at its timeout instead of waiting on the terminal forever.
+async fn read_stdin_line() -> io::Result<String> {
+    tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line).map(|_| line)
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Runs the REPL. `plain` drops the banner, input prompt, and streamed tool notices so
+/// stdin/stdout can be scripted (`echo "hi" | picobot repl --plain`); each response is
+/// written to stdout as one block once the turn completes.
 pub async fn run(
     config: Config,
     kernel: Kernel,
     agent_builder: ProviderAgentBuilder,
+    session_store: SqliteStore,
+    plain: bool,
 ) -> Result<()> {
     let user_id = std::env::var("PICOBOT_USER_ID")
         .ok()
@@ -178,42 +273,67 @@ pub async fn run(
     let session_id = std::env::var("PICOBOT_SESSION_ID")
         .ok()
         .unwrap_or_else(|| "repl:local".to_string());
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let base_dir = config.base_dir();
     let channel_id = "repl".to_string();
+    let max_turns = config.channel_max_turns(&channel_id);
     let profile = channel_profile(&config.channels(), &channel_id, &base_dir);
+    let kernel = channel_sandbox(kernel, &config.channels(), &channel_id, &base_dir);
     let kernel = Arc::new(
         kernel
             .clone_with_context(Some(user_id), Some(session_id))
             .with_channel_id(Some(channel_id))
             .with_prompt_profile(profile)
-            .with_prompter(Some(Arc::new(ReplPrompter))),
-    );
-    let session_store = crate::session::db::SqliteStore::new(
-        config
-            .data_dir()
-            .join("sessions.db")
-            .to_string_lossy()
-            .to_string(),
+            .with_prompter(Some(Arc::new(ReplPrompter { plain })))
+            .with_message_sink(Some(Arc::new(ReplMessageSink { plain }))),
     );
-    session_store.touch()?;
     let memory_config = config.memory();
-    let session_manager = SessionManager::new(session_store.clone());
-    let memory_retriever = MemoryRetriever::new(memory_config.clone(), session_store);
-    let agent = if let Ok(router) =
-        crate::providers::factory::ProviderFactory::build_agent_router(&config)
-        && !router.is_empty()
-    {
-        router.build_default(
-            &config,
-            kernel.tool_registry(),
-            kernel.clone(),
-            config.max_turns(),
-        )?
-    } else {
-        agent_builder.build(kernel.tool_registry(), kernel.clone(), config.max_turns())?
+    let session_manager = SessionManager::new(session_store.clone())
+        .with_max_stored_messages(memory_config.max_stored_messages());
+    let memory_retriever = MemoryRetriever::new(memory_config.clone(), session_store)
+        .with_context_window(config.context_window_tokens());
+    let agent_router = crate::providers::factory::ProviderFactory::build_agent_router(&config)
+        .ok()
+        .filter(|router| !router.is_empty());
+    // Built per turn, like the API and WhatsApp, so `{date}` in the preamble stays current.
+    let build_agent = || match &agent_router {
+        Some(router) => {
+            router.build_default(&config, kernel.tool_registry(), kernel.clone(), max_turns)
+        }
+        None => agent_builder
+            .clone()
+            .build(kernel.tool_registry(), kernel.clone(), max_turns),
     };
 
-    println!("picobot repl (type 'exit' to quit)");
+    if plain {
+        return run_plain(
+            build_agent,
+            &kernel,
+            &session_manager,
+            &memory_retriever,
+            &config,
+            |line| io::stdin().read_line(line),
+            &mut io::stdout(),
+        )
+        .await;
+    }
+
+    let interrupts = Arc::new(Interrupts::default());
+    {
+        let interrupts = Arc::clone(&interrupts);
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                match interrupts.on_ctrl_c() {
+                    InterruptEvent::Cancel => eprintln!("\ncancelling... (Ctrl+C again to quit)"),
+                    InterruptEvent::Quit => {
+                        println!();
+                        std::process::exit(130);
+                    }
+                }
+            }
+        });
+    }
+
+    println!("picobot repl (type 'exit' to quit, Ctrl+C cancels a running prompt)");
 
     let stdin = io::stdin();
     let mut stdout = io::stdout();
@@ -223,9 +343,13 @@ pub async fn run(
         stdout.flush().context("failed to flush stdout")?;
 
         let mut input = String::new();
-        stdin
+        let read = stdin
             .read_line(&mut input)
             .context("failed to read stdin")?;
+        if read == 0 {
+            println!();
+            break;
+        }
         let prompt = input.trim();
         if prompt.is_empty() {
             continue;
@@ -233,6 +357,10 @@ pub async fn run(
         if prompt == "exit" {
             break;
         }
+        if prompt == "/permissions" {
+            println!("{}", format_permissions(&kernel));
+            continue;
+        }
 
         let session_id = kernel
             .context()
@@ -284,26 +412,9 @@ pub async fn run(
             prompt.to_string()
         };
 
-        let mut seq_order = match session_manager.get_messages(&session.id, 1) {
-            Ok(messages) => messages
-                .last()
-                .map(|message| message.seq_order + 1)
-                .unwrap_or(0),
-            Err(_) => 0,
-        };
-
-        let user_message = StoredMessage {
-            message_type: MessageType::User,
-            content: prompt.to_string(),
-            tool_call_id: None,
-            seq_order,
-            token_estimate: None,
-        };
-        match session_manager.append_message(&session.id, &user_message) {
-            Ok(()) => seq_order += 1,
-            Err(err) => {
-                tracing::warn!(error = %err, "failed to store user message");
-            }
+        let user_message = NewMessage::new(MessageType::User, prompt);
+        if let Err(err) = session_manager.append_message(&session.id, &user_message) {
+            tracing::warn!(error = %err, "failed to store user message");
         }
 
         tracing::info!(
@@ -314,16 +425,48 @@ pub async fn run(
             prompt_len = prompt_to_send.len(),
             "repl prompt received"
         );
-        let response = match &agent {
-            crate::providers::factory::ProviderAgent::OpenAI(inner) => {
-                stream_prompt_to_stdout(inner, &prompt_to_send, config.max_turns()).await
+        let agent = match build_agent() {
+            Ok(agent) => agent,
+            Err(err) => {
+                tracing::error!(error = %err, "failed to build agent");
+                println!("Sorry, something went wrong: {err}");
+                continue;
             }
-            crate::providers::factory::ProviderAgent::OpenRouter(inner) => {
-                stream_prompt_to_stdout(inner, &prompt_to_send, config.max_turns()).await
+        };
+        let streamed = async {
+            match &agent {
+                ProviderAgent::OpenAI(inner) => {
+                    stream_prompt_to_stdout(inner, &prompt_to_send, max_turns).await
+                }
+                ProviderAgent::OpenRouter(inner) => {
+                    stream_prompt_to_stdout(inner, &prompt_to_send, max_turns).await
+                }
+                ProviderAgent::Gemini(inner) => {
+                    stream_prompt_to_stdout(inner, &prompt_to_send, max_turns).await
+                }
             }
-            crate::providers::factory::ProviderAgent::Gemini(inner) => {
-                stream_prompt_to_stdout(inner, &prompt_to_send, config.max_turns()).await
+        };
+        let cancel = interrupts.begin();
+        let response = tokio::select! {
+            response = streamed => Some(response),
+            _ = cancel.cancelled() => None,
+        };
+        interrupts.end();
+        for call in kernel.take_tool_calls() {
+            let tool_message = NewMessage::new(MessageType::Tool, call.to_content());
+            if let Err(err) = session_manager.append_message(&session.id, &tool_message) {
+                tracing::warn!(error = %err, "failed to store tool message");
             }
+        }
+        let Some(response) = response else {
+            tracing::info!(
+                event = "channel_prompt_cancelled",
+                channel_id = "repl",
+                session_id = %session.id,
+                "repl prompt cancelled"
+            );
+            println!("\n[cancelled]");
+            continue;
         };
         let response = match response {
             Ok(response) => response,
@@ -359,13 +502,7 @@ pub async fn run(
             "repl prompt completed"
         );
 
-        let assistant_message = StoredMessage {
-            message_type: MessageType::Assistant,
-            content: response.response,
-            tool_call_id: None,
-            seq_order,
-            token_estimate: None,
-        };
+        let assistant_message = NewMessage::new(MessageType::Assistant, response.response);
         if let Err(err) = session_manager.append_message(&session.id, &assistant_message) {
             tracing::warn!(error = %err, "failed to store assistant message");
         }
@@ -376,3 +513,191 @@ pub async fn run(
 
     Ok(())
 }
+
+/// The session's effective permissions, one per line, for the `/permissions` command.
+fn format_permissions(kernel: &Kernel) -> String {
+    let mut lines = kernel
+        .effective_capabilities()
+        .permissions()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return "No permissions granted.".to_string();
+    }
+    lines.sort();
+    lines.join("\n")
+}
+
+/// Line-in, response-out loop for scripted use. Reads go through `read_line` on each call
+/// rather than a held lock, so permission prompts can read their answers from the same
+/// stdin. Ends at EOF or `exit`.
+async fn run_plain(
+    build_agent: impl Fn() -> Result<ProviderAgent>,
+    kernel: &Kernel,
+    session_manager: &SessionManager,
+    memory_retriever: &MemoryRetriever,
+    config: &Config,
+    mut read_line: impl FnMut(&mut String) -> io::Result<usize>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let context = kernel.context();
+    let user_id = context
+        .user_id
+        .clone()
+        .unwrap_or_else(|| "local-user".to_string());
+    let session_id = context
+        .session_id
+        .clone()
+        .unwrap_or_else(|| "repl:local".to_string());
+    loop {
+        let mut input = String::new();
+        if read_line(&mut input).context("failed to read stdin")? == 0 {
+            break;
+        }
+        let prompt = input.trim();
+        if prompt.is_empty() {
+            continue;
+        }
+        if prompt == "exit" {
+            break;
+        }
+        if prompt == "/permissions" {
+            writeln!(out, "{}", format_permissions(kernel)).context("failed to write response")?;
+            continue;
+        }
+        let session = load_or_create_session(
+            session_manager,
+            &session_id,
+            "repl",
+            &user_id,
+            context.capabilities.as_ref(),
+        )?;
+        let turn = ChannelTurn::new(
+            session_manager,
+            memory_retriever,
+            "repl",
+            &user_id,
+            session,
+            TurnInput::text(prompt),
+        );
+        turn.record_user_message();
+        tracing::info!(
+            event = "channel_prompt",
+            channel_id = "repl",
+            user_id = %user_id,
+            session_id = %session_id,
+            prompt_len = turn.prompt().len(),
+            "repl prompt received"
+        );
+        let agent = match build_agent() {
+            Ok(agent) => agent,
+            Err(err) => {
+                tracing::error!(error = %err, "failed to build agent");
+                eprintln!("Sorry, something went wrong: {err}");
+                continue;
+            }
+        };
+        let reply = agent
+            .prompt_with_turns_retry_usage(
+                turn.prompt(),
+                config.channel_max_turns("repl"),
+                config.provider_max_retries(),
+            )
+            .await;
+        turn.record_tool_calls(kernel.take_tool_calls());
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(err) => {
+                tracing::error!(error = %err, "prompt failed");
+                eprintln!("Sorry, something went wrong: {err}");
+                continue;
+            }
+        };
+        turn.record_usage(&agent, &reply.usage);
+        let response = reply.text_with_note();
+        turn.record_response(&response);
+        writeln!(out, "{response}").context("failed to write response")?;
+        out.flush().context("failed to flush stdout")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use std::sync::Arc;
+
+    use super::{InterruptEvent, Interrupts, run_plain};
+    use crate::config::{Config, MemoryConfig};
+    use crate::kernel::core::Kernel;
+    use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+    use crate::session::db::SqliteStore;
+    use crate::session::manager::SessionManager;
+    use crate::session::memory::MemoryRetriever;
+    use crate::tools::registry::ToolRegistry;
+
+    #[test]
+    fn ctrl_c_cancels_a_busy_prompt_and_quits_when_idle() {
+        let interrupts = Interrupts::default();
+        assert_eq!(interrupts.on_ctrl_c(), InterruptEvent::Quit);
+
+        let token = interrupts.begin();
+        assert_eq!(interrupts.on_ctrl_c(), InterruptEvent::Cancel);
+        assert!(token.is_cancelled());
+        assert_eq!(interrupts.on_ctrl_c(), InterruptEvent::Quit);
+
+        interrupts.end();
+        let token = interrupts.begin();
+        assert_eq!(interrupts.on_ctrl_c(), InterruptEvent::Cancel);
+        assert!(token.is_cancelled());
+        interrupts.end();
+        assert_eq!(interrupts.on_ctrl_c(), InterruptEvent::Quit);
+    }
+
+    #[tokio::test]
+    async fn plain_mode_answers_each_stdin_line_on_stdout() {
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::text("pong"),
+            ScriptedReply::text("second answer"),
+        ])
+        .await;
+        let registry = Arc::new(ToolRegistry::new());
+        let kernel = Arc::new(
+            Kernel::new(Arc::clone(&registry))
+                .clone_with_context(Some("local-user".to_string()), Some("repl:test".to_string())),
+        );
+        let builds = std::sync::atomic::AtomicUsize::new(0);
+        let build_agent = || {
+            builds.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            provider.builder().build_with_env(&registry, Arc::clone(&kernel), 5, |_| {
+                Some("test-key".to_string())
+            })
+        };
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let session_manager = SessionManager::new(store.clone());
+        let memory_retriever = MemoryRetriever::new(MemoryConfig::default(), store);
+        let mut stdin = std::io::Cursor::new("ping\n\nand again\n");
+        let mut stdout = Vec::new();
+
+        run_plain(
+            build_agent,
+            &kernel,
+            &session_manager,
+            &memory_retriever,
+            &Config::default(),
+            |line| stdin.read_line(line),
+            &mut stdout,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(String::from_utf8(stdout).unwrap(), "pong\nsecond answer\n");
+        // One agent per turn, so the preamble's date is rendered fresh each time.
+        assert_eq!(builds.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(session_manager.get_messages("repl:test", 10).unwrap().len(), 4);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
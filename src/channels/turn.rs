@@ -0,0 +1,468 @@
+use rig::completion::Usage;
+
+use crate::channels::whatsapp::{MediaAttachment, MediaType, SharedContent};
+use crate::kernel::permissions::CapabilitySet;
+use crate::kernel::transcript::ToolCallRecord;
+use crate::providers::factory::ProviderAgent;
+use crate::session::error::SessionDbResult;
+use crate::session::manager::SessionManager;
+use crate::session::memory::MemoryRetriever;
+use crate::session::types::{MessageType, NewMessage, Session, UsageEvent};
+
+/// Loads `session_id`, creating it for `user_id` on `channel_id` when missing.
+pub fn load_or_create_session(
+    session_manager: &SessionManager,
+    session_id: &str,
+    channel_id: &str,
+    user_id: &str,
+    capabilities: &CapabilitySet,
+) -> SessionDbResult<Session> {
+    match session_manager.get_session(session_id)? {
+        Some(session) => Ok(session),
+        None => session_manager.create_session(
+            session_id.to_string(),
+            channel_id.to_string(),
+            channel_id.to_string(),
+            user_id.to_string(),
+            capabilities.clone(),
+        ),
+    }
+}
+
+/// What the user sent: text plus any downloaded media and shared locations or contacts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TurnInput<'m> {
+    pub text: &'m str,
+    pub attachments: &'m [MediaAttachment],
+    /// Attachments on the message that were not downloaded because of a per-message cap.
+    pub omitted_attachments: usize,
+    pub shared: &'m [SharedContent],
+}
+
+impl<'m> TurnInput<'m> {
+    pub fn text(text: &'m str) -> Self {
+        Self {
+            text,
+            ..Default::default()
+        }
+    }
+}
+
+/// One inbound message on a conversational channel.
+///
+/// Builds the prompt (session context, attachment listing, user text) and persists the
+/// user and assistant messages, so every channel stores and replays turns the same way.
+/// Persistence failures are logged rather than failing the turn.
+pub struct ChannelTurn<'a> {
+    session_manager: &'a SessionManager,
+    channel_id: &'a str,
+    user_id: String,
+    session: Session,
+    user_text: String,
+    prompt: String,
+}
+
+impl<'a> ChannelTurn<'a> {
+    pub fn new(
+        session_manager: &'a SessionManager,
+        memory_retriever: &MemoryRetriever,
+        channel_id: &'a str,
+        user_id: &str,
+        session: Session,
+        input: TurnInput<'_>,
+    ) -> Self {
+        let text = input.text;
+        let memory_config = &memory_retriever.config;
+        let existing_messages = session_manager
+            .get_messages(
+                &session.id,
+                memory_config.max_session_messages.unwrap_or(50),
+            )
+            .unwrap_or_default();
+        let filtered_messages = if memory_config.include_tool_messages() {
+            existing_messages
+        } else {
+            existing_messages
+                .into_iter()
+                .filter(|message| message.message_type != MessageType::Tool)
+                .collect::<Vec<_>>()
+        };
+        let context_messages =
+            memory_retriever.build_context(Some(user_id), Some(&session.id), &filtered_messages);
+        let context_snippet = MemoryRetriever::to_prompt_snippet(&context_messages);
+
+        let attachment_prompt = format_attachments_prompt(&input);
+        let user_text = if attachment_prompt.is_empty() {
+            text.to_string()
+        } else if text.trim().is_empty() {
+            attachment_prompt
+        } else {
+            format!("{attachment_prompt}\n\n{text}")
+        };
+        let prompt = if let Some(context) = context_snippet {
+            format!("Context:\n{context}\n\nUser: {user_text}")
+        } else {
+            user_text.clone()
+        };
+
+        Self {
+            session_manager,
+            channel_id,
+            user_id: user_id.to_string(),
+            session,
+            user_text,
+            prompt,
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// The prompt to send to the agent, including any session context.
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    pub fn record_user_message(&self) {
+        self.append(MessageType::User, self.user_text.clone());
+    }
+
+    pub fn record_usage(&self, agent: &ProviderAgent, usage: &Usage) {
+        let event = UsageEvent {
+            session_id: Some(self.session.id.clone()),
+            channel_id: Some(self.channel_id.to_string()),
+            user_id: Some(self.user_id.clone()),
+            provider: Some(agent.provider_name().to_string()),
+            model: agent.model_name(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            total_tokens: usage.total_tokens,
+            cached_input_tokens: usage.cached_input_tokens,
+        };
+        if let Err(err) = self.session_manager.record_usage(&event) {
+            tracing::warn!(error = %err, "failed to record usage");
+        }
+    }
+
+    /// Stores the tool calls the agent made this turn, between the user and assistant
+    /// messages, so history shows what the agent did.
+    pub fn record_tool_calls(&self, calls: Vec<ToolCallRecord>) {
+        for call in calls {
+            self.append(MessageType::Tool, call.to_content());
+        }
+    }
+
+    /// Stores the assistant reply and marks the session active.
+    pub fn record_response(&self, response: &str) {
+        self.append(MessageType::Assistant, response.to_string());
+        if let Err(err) = self.session_manager.touch(&self.session.id) {
+            tracing::warn!(error = %err, "failed to update session activity");
+        }
+    }
+
+    fn append(&self, message_type: MessageType, content: String) {
+        let label = match message_type {
+            MessageType::User => "user",
+            MessageType::Tool => "tool",
+            _ => "assistant",
+        };
+        let message = NewMessage::new(message_type, content);
+        if let Err(err) = self.session_manager.append_message(&self.session.id, &message) {
+            tracing::warn!(error = %err, "failed to store {label} message");
+        }
+    }
+}
+
+fn format_attachments_prompt(input: &TurnInput<'_>) -> String {
+    let attachments = input.attachments;
+    let mut lines = Vec::new();
+    if !attachments.is_empty() {
+        lines.push(
+            "User sent attachments (use multimodal_looker for images, documents, audio, or video if needed):"
+                .to_string(),
+        );
+        for (idx, attachment) in attachments.iter().enumerate() {
+            let label = format!("{}. {}", idx + 1, attachment_label(attachment));
+            lines.push(label);
+        }
+    }
+    if input.omitted_attachments > 0 {
+        lines.push(format!(
+            "{} more attachment(s) on this message were not downloaded (per-message attachment \
+             limit reached).",
+            input.omitted_attachments
+        ));
+    }
+    lines.extend(input.shared.iter().map(shared_content_label));
+    lines.join("\n")
+}
+
+fn shared_content_label(content: &SharedContent) -> String {
+    match content {
+        SharedContent::Location {
+            latitude,
+            longitude,
+            name,
+            address,
+            live,
+        } => {
+            let kind = if *live { "live location" } else { "location" };
+            let mut parts = vec![format!(
+                "User shared a {kind}: latitude={latitude} longitude={longitude}"
+            )];
+            if let Some(name) = name {
+                parts.push(format!("name={name}"));
+            }
+            if let Some(address) = address {
+                parts.push(format!("address={address}"));
+            }
+            parts.join(" ")
+        }
+        SharedContent::Contact {
+            display_name,
+            vcard,
+        } => {
+            let name = display_name.as_deref().unwrap_or("unnamed");
+            match vcard {
+                Some(vcard) => format!("User shared a contact: name={name}\n{}", vcard.trim()),
+                None => format!("User shared a contact: name={name}"),
+            }
+        }
+    }
+}
+
+fn attachment_label(attachment: &MediaAttachment) -> String {
+    let kind = match attachment.media_type {
+        MediaType::Image => "image",
+        MediaType::Document => "document",
+        MediaType::Audio => "audio",
+        MediaType::Video => "video",
+        MediaType::Sticker => "sticker",
+    };
+    let mut parts = Vec::new();
+    parts.push(format!("type={kind}"));
+    parts.push(format!("path={}", attachment.local_path.display()));
+    if let Some(name) = &attachment.file_name {
+        parts.push(format!("name={name}"));
+    }
+    if let Some(mime) = &attachment.mime_type {
+        parts.push(format!("mime={mime}"));
+    }
+    if let Some(size) = attachment.size_bytes {
+        parts.push(format!("bytes={size}"));
+    }
+    if let Some(caption) = &attachment.caption {
+        parts.push(format!("caption={caption}"));
+    }
+    if let Some(thumbnail_path) = &attachment.thumbnail_path {
+        parts.push(format!("thumbnail_path={}", thumbnail_path.display()));
+    }
+    if let Some(thumbnail_mime) = &attachment.thumbnail_mime_type {
+        parts.push(format!("thumbnail_mime={thumbnail_mime}"));
+    }
+    if let Some(thumbnail_size) = attachment.thumbnail_size_bytes {
+        parts.push(format!("thumbnail_bytes={thumbnail_size}"));
+    }
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{ChannelTurn, TurnInput, load_or_create_session};
+    use crate::channels::whatsapp::{MediaAttachment, MediaType, SharedContent};
+    use crate::config::MemoryConfig;
+    use crate::kernel::permissions::CapabilitySet;
+    use crate::session::db::SqliteStore;
+    use crate::session::manager::SessionManager;
+    use crate::session::memory::MemoryRetriever;
+    use crate::session::types::MessageType;
+
+    fn temp_channel() -> (SessionManager, MemoryRetriever, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let retriever = MemoryRetriever::new(MemoryConfig::default(), store.clone());
+        (SessionManager::new(store), retriever, dir)
+    }
+
+    #[test]
+    fn text_turns_persist_and_feed_the_next_prompt() {
+        let (manager, retriever, dir) = temp_channel();
+        let session = load_or_create_session(
+            &manager,
+            "api:alice",
+            "api",
+            "api:alice",
+            &CapabilitySet::empty(),
+        )
+        .unwrap();
+        let turn = ChannelTurn::new(
+            &manager,
+            &retriever,
+            "api",
+            "api:alice",
+            session,
+            TurnInput::text("hi"),
+        );
+        assert_eq!(turn.prompt(), "hi");
+        turn.record_user_message();
+        turn.record_response("hello there");
+
+        let session = load_or_create_session(
+            &manager,
+            "api:alice",
+            "api",
+            "api:alice",
+            &CapabilitySet::empty(),
+        )
+        .unwrap();
+        let turn = ChannelTurn::new(
+            &manager,
+            &retriever,
+            "api",
+            "api:alice",
+            session,
+            TurnInput::text("and again"),
+        );
+        assert!(turn.prompt().starts_with("Context:\n"));
+        assert!(turn.prompt().contains("[user] hi"));
+        assert!(turn.prompt().contains("[assistant] hello there"));
+        assert!(turn.prompt().ends_with("User: and again"));
+
+        let stored = manager.get_messages("api:alice", 10).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].message_type, MessageType::User);
+        assert_eq!(stored[1].message_type, MessageType::Assistant);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn attachment_turns_list_media_in_prompt_and_history() {
+        let (manager, retriever, dir) = temp_channel();
+        let session = load_or_create_session(
+            &manager,
+            "whatsapp:15551234567",
+            "whatsapp",
+            "15551234567",
+            &CapabilitySet::empty(),
+        )
+        .unwrap();
+        let attachment = MediaAttachment {
+            media_type: MediaType::Image,
+            mime_type: Some("image/jpeg".to_string()),
+            file_name: None,
+            local_path: PathBuf::from("/media/photo.jpg"),
+            caption: Some("sunset".to_string()),
+            size_bytes: Some(2048),
+            thumbnail_path: None,
+            thumbnail_mime_type: None,
+            thumbnail_size_bytes: None,
+        };
+        let turn = ChannelTurn::new(
+            &manager,
+            &retriever,
+            "whatsapp",
+            "15551234567",
+            session,
+            TurnInput {
+                text: "what is this?",
+                attachments: &[attachment],
+                ..Default::default()
+            },
+        );
+        assert!(turn.prompt().starts_with("User sent attachments"));
+        assert!(turn.prompt().contains(
+            "1. type=image path=/media/photo.jpg mime=image/jpeg bytes=2048 caption=sunset"
+        ));
+        assert!(turn.prompt().ends_with("\n\nwhat is this?"));
+        turn.record_user_message();
+
+        let stored = manager.get_messages("whatsapp:15551234567", 10).unwrap();
+        assert_eq!(stored.len(), 1);
+        // Without prior history the prompt is exactly the stored user text.
+        assert_eq!(stored[0].content, turn.prompt());
+        assert_eq!(turn.session().channel_id, "whatsapp");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn omitted_attachments_are_noted_in_prompt() {
+        let (manager, retriever, dir) = temp_channel();
+        let session = load_or_create_session(
+            &manager,
+            "whatsapp:15551234567",
+            "whatsapp",
+            "15551234567",
+            &CapabilitySet::empty(),
+        )
+        .unwrap();
+        let turn = ChannelTurn::new(
+            &manager,
+            &retriever,
+            "whatsapp",
+            "15551234567",
+            session,
+            TurnInput {
+                text: "summarize these",
+                omitted_attachments: 2,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            turn.prompt(),
+            "2 more attachment(s) on this message were not downloaded (per-message attachment \
+             limit reached).\n\nsummarize these"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn shared_location_and_contact_are_described_in_prompt() {
+        let (manager, retriever, dir) = temp_channel();
+        let session = load_or_create_session(
+            &manager,
+            "whatsapp:15551234567",
+            "whatsapp",
+            "15551234567",
+            &CapabilitySet::empty(),
+        )
+        .unwrap();
+        let shared = [
+            SharedContent::Location {
+                latitude: 37.7955,
+                longitude: -122.3937,
+                name: Some("Ferry Building".to_string()),
+                address: None,
+                live: false,
+            },
+            SharedContent::Contact {
+                display_name: Some("Jane Doe".to_string()),
+                vcard: Some("BEGIN:VCARD\nFN:Jane Doe\nEND:VCARD".to_string()),
+            },
+        ];
+        let turn = ChannelTurn::new(
+            &manager,
+            &retriever,
+            "whatsapp",
+            "15551234567",
+            session,
+            TurnInput {
+                text: "",
+                shared: &shared,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            turn.prompt(),
+            "User shared a location: latitude=37.7955 longitude=-122.3937 name=Ferry Building\n\
+             User shared a contact: name=Jane Doe\nBEGIN:VCARD\nFN:Jane Doe\nEND:VCARD"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -9,26 +9,34 @@ use dashmap::DashMap;
 use futures::{Stream, StreamExt};
 use qrcode::QrCode;
 use qrcode::render::unicode;
-use tokio::sync::{Mutex as AsyncMutex, Semaphore, mpsc, watch};
+use tokio::sync::{Mutex as AsyncMutex, OwnedSemaphorePermit, Semaphore, mpsc, watch};
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 use wacore::proto_helpers::MessageExt;
 
-use crate::channels::permissions::channel_profile;
+use crate::channels::outcomes::{InboundOutcome, InboundOutcomes};
+use crate::channels::permissions::{channel_sandbox, named_channel_profile};
+use crate::channels::turn::{ChannelTurn, TurnInput, load_or_create_session};
 use crate::config::{Config, WhatsappConfig};
 use crate::kernel::core::Kernel;
 use crate::kernel::permissions::{PathPattern, Permission};
-use crate::providers::factory::{
-    DEFAULT_PROVIDER_RETRIES, ProviderAgent, ProviderAgentBuilder, ProviderFactory,
-};
+use crate::providers::factory::{ProviderAgent, ProviderAgentBuilder, ProviderFactory};
+use crate::scheduler::job::JobAccount;
 use crate::session::manager::SessionManager;
 use crate::session::memory::MemoryRetriever;
-use crate::session::types::{MessageType, StoredMessage};
+use crate::tools::say::MessageSink;
+use crate::tools::traits::{Artifact, ArtifactKind};
+
+const USER_LOCK_PRUNE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const MEDIA_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[async_trait]
 pub trait WhatsAppBackend: Send + Sync {
     async fn start(&self) -> Result<()>;
     async fn send_text(&self, to: &str, body: &str) -> Result<String>;
+    /// Uploads a tool artifact and sends it as an image, audio, video, or document message.
+    async fn send_media(&self, to: &str, artifact: &Artifact) -> Result<String>;
     fn inbound_stream(&self) -> Pin<Box<dyn Stream<Item = InboundMessage> + Send>>;
 }
 
@@ -39,16 +47,21 @@ pub struct WhatsappRustBackend {
 
 struct WhatsappOutbound {
     to: String,
-    text: String,
+    content: OutboundContent,
     reply: tokio::sync::oneshot::Sender<Result<String>>,
 }
 
+enum OutboundContent {
+    Text(String),
+    Media(Artifact),
+}
+
 impl WhatsappRustBackend {
     pub fn new(
         store_path: String,
         media_root: PathBuf,
-        max_media_size_bytes: u64,
-        allowed_senders: Option<Vec<String>>,
+        media_limits: MediaLimits,
+        sender_filter: SenderFilter,
         qr_cache: watch::Sender<Option<String>>,
     ) -> Self {
         let (inbound_tx, inbound_rx) = mpsc::unbounded_channel();
@@ -56,8 +69,8 @@ impl WhatsappRustBackend {
         tokio::spawn(run_whatsapp_loop(
             store_path,
             media_root,
-            max_media_size_bytes,
-            allowed_senders,
+            media_limits,
+            sender_filter,
             inbound_tx,
             outbound_rx,
             qr_cache,
@@ -69,29 +82,44 @@ impl WhatsappRustBackend {
     }
 }
 
-#[async_trait]
-impl WhatsAppBackend for WhatsappRustBackend {
-    async fn start(&self) -> Result<()> {
-        Ok(())
-    }
-
-    async fn send_text(&self, to: &str, body: &str) -> Result<String> {
+impl WhatsappRustBackend {
+    async fn send(&self, to: &str, content: OutboundContent) -> Result<String> {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.outbound_tx
             .send(WhatsappOutbound {
                 to: to.to_string(),
-                text: body.to_string(),
+                content,
                 reply: tx,
             })
             .context("whatsapp outbound channel closed")?;
         rx.await.context("whatsapp outbound response closed")?
     }
+}
+
+#[async_trait]
+impl WhatsAppBackend for WhatsappRustBackend {
+    async fn start(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn send_text(&self, to: &str, body: &str) -> Result<String> {
+        self.send(to, OutboundContent::Text(body.to_string())).await
+    }
+
+    async fn send_media(&self, to: &str, artifact: &Artifact) -> Result<String> {
+        self.send(to, OutboundContent::Media(artifact.clone())).await
+    }
 
     fn inbound_stream(&self) -> Pin<Box<dyn Stream<Item = InboundMessage> + Send>> {
-        let mut guard = self
-            .inbound_rx
-            .lock()
-            .expect("inbound stream mutex poisoned");
+        let mut guard = self.inbound_rx.lock().unwrap_or_else(|err| {
+            tracing::warn!(
+                event = "lock_poisoned",
+                lock = "inbound_rx",
+                "recovering poisoned lock"
+            );
+            self.inbound_rx.clear_poison();
+            err.into_inner()
+        });
         let receiver = guard.take().expect("inbound stream already taken");
         Box::pin(UnboundedReceiverStream::new(receiver))
     }
@@ -106,6 +134,32 @@ pub struct InboundMessage {
     #[allow(dead_code)]
     pub message_id: Option<String>,
     pub attachments: Vec<MediaAttachment>,
+    /// Attachments dropped by `whatsapp.max_attachments_per_message`.
+    pub omitted_attachments: usize,
+    pub shared: Vec<SharedContent>,
+}
+
+/// Per-message media download limits from `[whatsapp]`.
+#[derive(Debug, Clone, Copy)]
+pub struct MediaLimits {
+    pub max_size_bytes: u64,
+    pub max_per_message: usize,
+}
+
+/// Non-media content a user shared: location pins and contact cards.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedContent {
+    Location {
+        latitude: f64,
+        longitude: f64,
+        name: Option<String>,
+        address: Option<String>,
+        live: bool,
+    },
+    Contact {
+        display_name: Option<String>,
+        vcard: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -162,12 +216,59 @@ impl WhatsAppOutboundSender {
             }
         }
     }
+
+    pub async fn send_artifact(&self, user_id: &str, artifact: &Artifact) -> Result<String> {
+        match self.backend.send_media(user_id, artifact).await {
+            Ok(delivery_id) => Ok(delivery_id),
+            Err(err) => {
+                tracing::error!(
+                    user = %user_id,
+                    path = %artifact.path.display(),
+                    error = %err,
+                    "WhatsApp media send failed"
+                );
+                Err(err)
+            }
+        }
+    }
+
+    /// Sends the reply text, then each artifact the turn's tools produced as media.
+    pub async fn send_reply(&self, user_id: &str, text: &str, artifacts: &[Artifact]) {
+        let _ = self.send(user_id, text).await;
+        for artifact in artifacts {
+            let _ = self.send_artifact(user_id, artifact).await;
+        }
+    }
+}
+
+/// Sends `say` messages to the sender of the message being handled, as separate messages
+/// ahead of the reply.
+pub struct WhatsAppMessageSink {
+    outbound: Arc<WhatsAppOutboundSender>,
+    user_id: String,
+}
+
+impl WhatsAppMessageSink {
+    pub fn new(outbound: Arc<WhatsAppOutboundSender>, user_id: impl Into<String>) -> Self {
+        Self {
+            outbound,
+            user_id: user_id.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl MessageSink for WhatsAppMessageSink {
+    async fn send(&self, message: &str) -> Result<()> {
+        self.outbound.send(&self.user_id, message).await.map(|_| ())
+    }
 }
 
 pub async fn run(
     config: Config,
     kernel: Kernel,
     agent_builder: ProviderAgentBuilder,
+    session_store: crate::session::db::SqliteStore,
 ) -> Result<()> {
     let whatsapp_config = config.whatsapp();
     if whatsapp_config.enabled == Some(false) {
@@ -175,44 +276,160 @@ pub async fn run(
         return Ok(());
     }
 
-    let store_path = whatsapp_store_path(&config, &whatsapp_config);
-    let allowed_senders = whatsapp_allowed_senders(&whatsapp_config);
     let media_root = whatsapp_media_root(&config, &whatsapp_config);
+    ensure_media_dir(&media_root)?;
+    // Background tasks stop once the channel returns, fails, or is dropped.
+    let shutdown = CancellationToken::new();
+    let _shutdown_guard = shutdown.clone().drop_guard();
+    spawn_media_cleanup(
+        media_root.clone(),
+        whatsapp_config.media_retention_hours(),
+        MEDIA_CLEANUP_INTERVAL,
+        shutdown.child_token(),
+    );
 
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let profile = channel_profile(&config.channels(), "whatsapp", &base_dir);
-    let base_kernel = kernel
+    let accounts = whatsapp_accounts(&config, &whatsapp_config);
+    let runs = accounts.into_iter().map(|account| {
+        let account_id = account.id.clone();
+        let run = run_account(
+            config.clone(),
+            kernel.clone(),
+            agent_builder.clone(),
+            AccountRun {
+                account,
+                session_store: session_store.clone(),
+                media_root: media_root.clone(),
+                shutdown: shutdown.child_token(),
+            },
+        );
+        async move { (account_id, run.await) }
+    });
+    // One failing account is logged and leaves the others serving.
+    let mut runs = runs.collect::<tokio::task::JoinSet<_>>();
+    let total = runs.len();
+    let failed = async {
+        let mut failed = 0;
+        while let Some(joined) = runs.join_next().await {
+            let (account_id, err) = match joined {
+                Ok((_, Ok(()))) => continue,
+                Ok((account_id, Err(err))) => (account_id, err),
+                Err(err) => ("unknown".to_string(), anyhow::Error::new(err)),
+            };
+            failed += 1;
+            tracing::error!(
+                event = "whatsapp_account_failed",
+                account_id = %account_id,
+                error = %err,
+                "WhatsApp account stopped with an error"
+            );
+        }
+        failed
+    };
+    tokio::select! {
+        failed = failed => {
+            if failed == total {
+                anyhow::bail!("every WhatsApp account failed");
+            }
+        }
+        Ok(()) = tokio::signal::ctrl_c() => {
+            tracing::info!(event = "whatsapp_shutdown", "shutting down WhatsApp channel");
+        }
+    }
+    Ok(())
+}
+
+/// Sweeps expired media now and every `interval` after, until `shutdown` is cancelled.
+fn spawn_media_cleanup(
+    root: PathBuf,
+    retention_hours: u64,
+    interval: Duration,
+    shutdown: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            cleanup_expired_media(&root, retention_hours).await;
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+        tracing::debug!(event = "whatsapp_media_cleanup_stopped", "media cleanup stopped");
+    })
+}
+
+/// Per-account inputs of [`run_account`], next to the components every account shares.
+struct AccountRun {
+    account: WhatsappAccount,
+    session_store: crate::session::db::SqliteStore,
+    media_root: PathBuf,
+    shutdown: CancellationToken,
+}
+
+/// Serves one WhatsApp account: its own backend, outbound sender, notification queue and
+/// handler slots, with sessions kept under the account's prefix.
+async fn run_account(
+    config: Config,
+    kernel: Kernel,
+    agent_builder: ProviderAgentBuilder,
+    run: AccountRun,
+) -> Result<()> {
+    let AccountRun {
+        account,
+        session_store,
+        media_root,
+        shutdown,
+    } = run;
+    // Stops this account's background tasks on every return path.
+    let _shutdown_guard = shutdown.clone().drop_guard();
+    let whatsapp_config = config.whatsapp();
+    let base_dir = config.base_dir();
+    let profile =
+        named_channel_profile(&config.channels(), &account.profile, "whatsapp", &base_dir);
+    let max_turns = config.channel_max_turns(&account.profile);
+    let base_kernel = channel_sandbox(kernel, &config.channels(), &account.profile, &base_dir)
         .with_prompt_profile(profile)
         .with_channel_id(Some("whatsapp".to_string()));
 
-    let session_store = crate::session::db::SqliteStore::new(
-        config
-            .data_dir()
-            .join("sessions.db")
-            .to_string_lossy()
-            .to_string(),
-    );
-    session_store.touch()?;
-    let memory_config = config.memory();
-    let session_manager = SessionManager::new(session_store.clone());
-    let memory_retriever = MemoryRetriever::new(memory_config.clone(), session_store);
+    let session_manager = SessionManager::new(session_store.clone())
+        .with_max_stored_messages(config.memory().max_stored_messages());
+    let memory_retriever = MemoryRetriever::new(config.memory(), session_store.clone())
+        .with_context_window(config.context_window_tokens());
     let agent_router = ProviderFactory::build_agent_router(&config)
         .ok()
         .filter(|router| !router.is_empty());
 
-    ensure_media_dir(&media_root)?;
+    let inbound_outcomes = Arc::new(InboundOutcomes::default());
     let (qr_cache_tx, mut qr_cache_rx) = watch::channel(None);
     let backend: Arc<dyn WhatsAppBackend> = Arc::new(WhatsappRustBackend::new(
-        store_path,
+        account.store_path.clone(),
         media_root.clone(),
-        whatsapp_config.max_media_size_bytes(),
-        allowed_senders.clone(),
+        MediaLimits {
+            max_size_bytes: whatsapp_config.max_media_size_bytes(),
+            max_per_message: whatsapp_config.max_attachments_per_message(),
+        },
+        SenderFilter {
+            allowed: account.allowed_senders.clone(),
+            outcomes: Arc::clone(&inbound_outcomes),
+        },
         qr_cache_tx,
     ));
+    let account_id = account.id.clone();
+    let qr_shutdown = shutdown.clone();
     tokio::spawn(async move {
-        while qr_cache_rx.changed().await.is_ok() {
+        loop {
+            tokio::select! {
+                _ = qr_shutdown.cancelled() => break,
+                changed = qr_cache_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
             if let Some(code) = qr_cache_rx.borrow().clone() {
-                tracing::info!("WhatsApp QR Code:\n{}", render_qr_code(&code));
+                tracing::info!(
+                    "WhatsApp QR Code for account '{account_id}':\n{}",
+                    render_qr_code(&code)
+                );
             }
         }
     });
@@ -220,6 +437,7 @@ pub async fn run(
     let inbound = WhatsAppInboundAdapter::new(Arc::clone(&backend));
     let outbound = Arc::new(WhatsAppOutboundSender::new(Arc::clone(&backend)));
     let mut base_kernel = base_kernel;
+    let mut notification_worker = None;
     if config.notifications().enabled() {
         let queue_config = crate::notifications::queue::NotificationQueueConfig {
             max_attempts: config.notifications().max_attempts(),
@@ -227,41 +445,84 @@ pub async fn run(
             max_backoff: Duration::from_millis(config.notifications().max_backoff_ms()),
             max_records: config.notifications().max_records(),
         };
-        let queue = crate::notifications::queue::NotificationQueue::new(queue_config);
+        let queue = crate::notifications::queue::NotificationQueue::new(queue_config)
+            .with_store(session_store.clone(), account.session_prefix.clone());
         let channel = Arc::new(
             crate::notifications::whatsapp::WhatsAppNotificationChannel::new(outbound.clone()),
         );
-        let notifications = crate::notifications::service::NotificationService::new(queue, channel);
-        let worker = notifications.clone();
-        tokio::spawn(async move {
-            worker.worker_loop().await;
-        });
+        let notifications = crate::notifications::service::NotificationService::new(queue, channel)
+            .with_dedup_window(Duration::from_secs(config.notifications().dedup_window_secs()))
+            .with_quiet_hours(config.notifications().quiet_hours())
+            .with_workers(config.notifications().workers());
+        notification_worker = Some(spawn_notification_worker(
+            notifications.clone(),
+            shutdown.clone(),
+        ));
         let notification_arc = Arc::new(notifications);
         base_kernel = base_kernel.with_notifications(Some(notification_arc.clone()));
+        // Jobs created on this account notify through it (see `JobExecutor`) until the
+        // account stops, however `run_account` returns.
         if let Some(scheduler) = base_kernel.context().scheduler.clone() {
-            scheduler.set_notifications(Some(notification_arc)).await;
+            scheduler
+                .set_notifications(&account.id, Some(notification_arc))
+                .await;
+            let account_id = account.id.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                shutdown.cancelled().await;
+                scheduler.set_notifications(&account_id, None).await;
+            });
         }
     }
     backend.start().await?;
 
     let max_concurrent = whatsapp_config.max_concurrent_messages();
-    let global_semaphore = Arc::new(Semaphore::new(max_concurrent));
+    let busy_timeout = whatsapp_config.busy_timeout();
+    let admission = AdmissionSlots::new(max_concurrent, busy_timeout.is_some());
     let per_user_locks: Arc<DashMap<String, Arc<AsyncMutex<()>>>> = Arc::new(DashMap::new());
-
-    let cleanup_root = media_root.clone();
-    let retention_hours = whatsapp_config.media_retention_hours();
-    tokio::spawn(async move {
-        loop {
-            cleanup_expired_media(&cleanup_root, retention_hours).await;
-            tokio::time::sleep(Duration::from_secs(60 * 60)).await;
-        }
-    });
+    {
+        let per_user_locks = Arc::clone(&per_user_locks);
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(USER_LOCK_PRUNE_INTERVAL) => {}
+                }
+                let removed = prune_idle_user_locks(&per_user_locks);
+                if removed > 0 {
+                    tracing::debug!(
+                        event = "whatsapp_user_locks_pruned",
+                        removed,
+                        remaining = per_user_locks.len(),
+                        "pruned idle whatsapp user locks"
+                    );
+                }
+            }
+        });
+    }
 
     let mut inbound_stream = inbound.subscribe().await;
     while let Some(message) = inbound_stream.next().await {
-        let permit = match global_semaphore.clone().acquire_owned().await {
-            Ok(permit) => permit,
-            Err(_) => continue,
+        let Some(admitted) = admission.try_admit() else {
+            tracing::warn!(
+                event = "whatsapp_message_shed",
+                user_id = %message.user_id,
+                waiting = max_concurrent,
+                "too many messages waiting for a handler slot; dropping message"
+            );
+            let outbound = outbound.clone();
+            let busy_reply = whatsapp_config.messages().busy().to_string();
+            let user_id = message.user_id.clone();
+            tokio::spawn(async move {
+                let _ = outbound.send(&user_id, &busy_reply).await;
+            });
+            inbound_outcomes.record(
+                "whatsapp",
+                Some(&message.user_id),
+                InboundOutcome::RateLimited,
+            );
+            continue;
         };
         let user_lock = per_user_locks
             .entry(message.user_id.clone())
@@ -272,106 +533,73 @@ pub async fn run(
         let agent_router = agent_router.clone();
         let session_manager = session_manager.clone();
         let memory_retriever = memory_retriever.clone();
-        let memory_config = memory_config.clone();
         let outbound = outbound.clone();
         let media_root = media_root.clone();
         let base_kernel = base_kernel.clone();
+        let messages = whatsapp_config.messages();
+        let inbound_outcomes = Arc::clone(&inbound_outcomes);
+        let session_id = account.session_id(&message.user_id);
+        let account_id = account.id.clone();
         tokio::spawn(async move {
-            let _permit = permit;
+            let Some(_permit) = admitted
+                .ready(busy_timeout, &outbound, &message.user_id, messages.busy())
+                .await
+            else {
+                inbound_outcomes.record(
+                    "whatsapp",
+                    Some(&message.user_id),
+                    InboundOutcome::RateLimited,
+                );
+                return;
+            };
             let _user_guard = user_lock.lock().await;
             let user_id = message.user_id.clone();
-            let session_id = format!("whatsapp:{user_id}");
             tracing::info!(
                 event = "channel_prompt",
                 channel_id = "whatsapp",
+                account = %account_id,
                 user_id = %user_id,
                 session_id = %session_id,
                 prompt_len = message.text.len(),
                 "whatsapp prompt received"
             );
-            let session = match session_manager.get_session(&session_id) {
-                Ok(Some(session)) => session,
-                Ok(None) => match session_manager.create_session(
-                    session_id,
-                    "whatsapp".to_string(),
-                    "whatsapp".to_string(),
-                    user_id.clone(),
-                    base_kernel.context().capabilities.as_ref().clone(),
-                ) {
-                    Ok(session) => session,
-                    Err(err) => {
-                        let _ = outbound
-                            .send(&user_id, &format!("Sorry, session error: {err}"))
-                            .await;
-                        return;
-                    }
-                },
+            let session = match load_or_create_session(
+                &session_manager,
+                &session_id,
+                "whatsapp",
+                &user_id,
+                base_kernel.context().capabilities.as_ref(),
+            ) {
+                Ok(session) => session,
                 Err(err) => {
-                    let _ = outbound
-                        .send(&user_id, &format!("Sorry, session error: {err}"))
-                        .await;
+                    let reply = error_reply("session", messages.session_error(), &user_id, &err);
+                    let _ = outbound.send(&user_id, &reply).await;
+                    inbound_outcomes.record("whatsapp", Some(&user_id), InboundOutcome::Failed);
                     return;
                 }
             };
-
-            let existing_messages = session_manager
-                .get_messages(
-                    &session.id,
-                    memory_config.max_session_messages.unwrap_or(50),
-                )
-                .unwrap_or_default();
-            let filtered_messages = if memory_config.include_tool_messages() {
-                existing_messages
-            } else {
-                existing_messages
-                    .into_iter()
-                    .filter(|message| message.message_type != MessageType::Tool)
-                    .collect::<Vec<_>>()
-            };
-            let context_messages = memory_retriever.build_context(
-                Some(&user_id),
-                Some(&session.id),
-                &filtered_messages,
+            let turn = ChannelTurn::new(
+                &session_manager,
+                &memory_retriever,
+                "whatsapp",
+                &user_id,
+                session,
+                TurnInput {
+                    text: &message.text,
+                    attachments: &message.attachments,
+                    omitted_attachments: message.omitted_attachments,
+                    shared: &message.shared,
+                },
             );
-            let context_snippet = MemoryRetriever::to_prompt_snippet(&context_messages);
-            let attachment_prompt = format_attachments_prompt(&message.attachments);
-            let user_text = if attachment_prompt.is_empty() {
-                message.text.clone()
-            } else if message.text.trim().is_empty() {
-                attachment_prompt
-            } else {
-                format!("{}\n\n{}", attachment_prompt, message.text)
-            };
-            let prompt_to_send = if let Some(context) = context_snippet {
-                format!("Context:\n{context}\n\nUser: {user_text}")
-            } else {
-                user_text.clone()
-            };
-
-            let mut seq_order = match session_manager.get_messages(&session.id, 1) {
-                Ok(messages) => messages
-                    .last()
-                    .map(|message| message.seq_order + 1)
-                    .unwrap_or(0),
-                Err(_) => 0,
-            };
-
-            let user_message = StoredMessage {
-                message_type: MessageType::User,
-                content: user_text.clone(),
-                tool_call_id: None,
-                seq_order,
-                token_estimate: None,
-            };
-            match session_manager.append_message(&session.id, &user_message) {
-                Ok(()) => seq_order += 1,
-                Err(err) => {
-                    tracing::warn!(error = %err, "failed to store user message");
-                }
-            }
+            turn.record_user_message();
 
             let message_kernel = Arc::new(
-                base_kernel.clone_with_context(Some(user_id.clone()), Some(session.id.clone())),
+                base_kernel
+                    .clone_with_context(Some(user_id.clone()), Some(turn.session().id.clone()))
+                    .with_message_sink(Some(Arc::new(WhatsAppMessageSink::new(
+                        Arc::clone(&outbound),
+                        user_id.clone(),
+                    )))),
             );
             let message_kernel = with_media_permissions(
                 message_kernel,
@@ -383,23 +611,37 @@ pub async fn run(
                 &config,
                 &agent_builder,
                 agent_router.as_ref(),
-                message_kernel,
+                Arc::clone(&message_kernel),
+                max_turns,
             ) {
                 Ok(agent) => agent,
                 Err(err) => {
-                    let _ = outbound
-                        .send(&user_id, &format!("Sorry, agent error: {err}"))
-                        .await;
+                    let reply = error_reply("agent", messages.agent_error(), &user_id, &err);
+                    let _ = outbound.send(&user_id, &reply).await;
+                    inbound_outcomes.record("whatsapp", Some(&user_id), InboundOutcome::Failed);
                     return;
                 }
             };
+            let mut outcome = InboundOutcome::Processed;
             let response =
-                match prompt_with_agent(&agent, &prompt_to_send, config.max_turns()).await {
+                match prompt_with_agent(
+                    &agent,
+                    turn.prompt(),
+                    max_turns,
+                    config.provider_max_retries(),
+                )
+                .await
+                {
                     Ok(response) => response,
                     Err(err) => {
-                        tracing::error!(error = %err, "prompt failed");
+                        outcome = InboundOutcome::Failed;
                         PromptWithUsageResult {
-                            response: format!("Sorry, something went wrong: {err}"),
+                            response: error_reply(
+                                "prompt",
+                                messages.prompt_error(),
+                                &user_id,
+                                &err,
+                            ),
                             usage: rig::completion::Usage::new(),
                         }
                     }
@@ -408,78 +650,253 @@ pub async fn run(
                 event = "channel_prompt_complete",
                 channel_id = "whatsapp",
                 user_id = %user_id,
-                session_id = %session.id,
+                session_id = %turn.session().id,
                 response_len = response.response.len(),
                 input_tokens = response.usage.input_tokens,
                 output_tokens = response.usage.output_tokens,
                 total_tokens = response.usage.total_tokens,
                 "whatsapp prompt completed"
             );
-            let usage_event = crate::session::types::UsageEvent {
-                session_id: Some(session.id.clone()),
-                channel_id: Some("whatsapp".to_string()),
-                user_id: Some(user_id.clone()),
-                provider: Some(agent.provider_name().to_string()),
-                model: agent.model_name(),
-                input_tokens: response.usage.input_tokens,
-                output_tokens: response.usage.output_tokens,
-                total_tokens: response.usage.total_tokens,
-                cached_input_tokens: response.usage.cached_input_tokens,
-            };
-            if let Err(err) = session_manager.record_usage(&usage_event) {
-                tracing::warn!(error = %err, "failed to record usage");
-            }
-            let assistant_message = StoredMessage {
-                message_type: MessageType::Assistant,
-                content: response.response.clone(),
-                tool_call_id: None,
-                seq_order,
-                token_estimate: None,
-            };
-            if let Err(err) = session_manager.append_message(&session.id, &assistant_message) {
-                tracing::warn!(error = %err, "failed to store assistant message");
-            }
-            if let Err(err) = session_manager.touch(&session.id) {
-                tracing::warn!(error = %err, "failed to update session activity");
-            }
-
-            let _ = outbound.send(&user_id, &response.response).await;
+            turn.record_tool_calls(message_kernel.take_tool_calls());
+            turn.record_usage(&agent, &response.usage);
+            turn.record_response(&response.response);
+
+            outbound
+                .send_reply(&user_id, &response.response, &message_kernel.artifacts())
+                .await;
+            inbound_outcomes.record("whatsapp", Some(&user_id), outcome);
         });
     }
 
+    shutdown.cancel();
+    if let Some(worker) = notification_worker {
+        let _ = worker.await;
+    }
     Ok(())
 }
 
+/// Runs the account's notification worker until `shutdown` is cancelled.
+fn spawn_notification_worker(
+    notifications: crate::notifications::service::NotificationService,
+    shutdown: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = notifications.worker_loop() => {}
+        }
+        tracing::debug!(
+            event = "whatsapp_notification_worker_stopped",
+            "notification worker stopped"
+        );
+    })
+}
+
+/// Handler slots plus, when shedding, an equally sized set of places to wait for one.
+/// The inbound loop only ever tries to acquire, so it keeps draining the stream; the wait
+/// for a handler slot happens in the per-message task.
+struct AdmissionSlots {
+    running: Arc<Semaphore>,
+    waiting: Arc<Semaphore>,
+    shed_when_busy: bool,
+}
+
+/// A message that either holds a handler slot or a place in line for one.
+enum Admission {
+    Running(OwnedSemaphorePermit),
+    Waiting {
+        running: Arc<Semaphore>,
+        /// `None` past the bounded line when shedding is off.
+        _place: Option<OwnedSemaphorePermit>,
+    },
+}
+
+impl AdmissionSlots {
+    fn new(max_concurrent: usize, shed_when_busy: bool) -> Self {
+        Self {
+            running: Arc::new(Semaphore::new(max_concurrent)),
+            waiting: Arc::new(Semaphore::new(max_concurrent)),
+            shed_when_busy,
+        }
+    }
+
+    /// `None` when every handler slot is taken and the line is full; without shedding
+    /// the line has no end and every message is admitted.
+    fn try_admit(&self) -> Option<Admission> {
+        if let Ok(permit) = Arc::clone(&self.running).try_acquire_owned() {
+            return Some(Admission::Running(permit));
+        }
+        let place = Arc::clone(&self.waiting).try_acquire_owned().ok();
+        if place.is_none() && self.shed_when_busy {
+            return None;
+        }
+        Some(Admission::Waiting {
+            running: Arc::clone(&self.running),
+            _place: place,
+        })
+    }
+}
+
+impl Admission {
+    /// The handler slot, waiting for one (see [`admit_message`]) if still in line.
+    async fn ready(
+        self,
+        busy_timeout: Option<Duration>,
+        outbound: &WhatsAppOutboundSender,
+        user_id: &str,
+        busy_reply: &str,
+    ) -> Option<OwnedSemaphorePermit> {
+        match self {
+            Admission::Running(permit) => Some(permit),
+            Admission::Waiting { running, .. } => {
+                admit_message(&running, busy_timeout, outbound, user_id, busy_reply).await
+            }
+        }
+    }
+}
+
+/// Waits for a handler slot. If none frees up within `busy_timeout`, the sender gets
+/// `busy_reply` and the message is dropped instead of queueing without bound; `None`
+/// waits indefinitely.
+async fn admit_message(
+    semaphore: &Arc<Semaphore>,
+    busy_timeout: Option<Duration>,
+    outbound: &WhatsAppOutboundSender,
+    user_id: &str,
+    busy_reply: &str,
+) -> Option<OwnedSemaphorePermit> {
+    let acquire = Arc::clone(semaphore).acquire_owned();
+    let Some(busy_timeout) = busy_timeout else {
+        return acquire.await.ok();
+    };
+    match tokio::time::timeout(busy_timeout, acquire).await {
+        Ok(permit) => permit.ok(),
+        Err(_) => {
+            tracing::warn!(
+                event = "whatsapp_message_shed",
+                user_id = %user_id,
+                wait_ms = busy_timeout.as_millis() as u64,
+                "no handler slot available; dropping message"
+            );
+            let _ = outbound.send(user_id, busy_reply).await;
+            None
+        }
+    }
+}
+
+/// Renders a user-facing error from `template`, logging the full error first so templates
+/// can leave `{error}` out without losing the detail.
+fn error_reply(
+    stage: &'static str,
+    template: &str,
+    user_id: &str,
+    err: &dyn std::fmt::Display,
+) -> String {
+    tracing::error!(
+        event = "whatsapp_error_reply",
+        stage,
+        user_id = %user_id,
+        error = %err,
+        "whatsapp request failed"
+    );
+    template.replace("{error}", &err.to_string())
+}
+
 fn build_agent_for_kernel(
     config: &Config,
     agent_builder: &ProviderAgentBuilder,
     agent_router: Option<&crate::providers::factory::ModelRouter>,
     kernel: Arc<Kernel>,
+    max_turns: usize,
 ) -> Result<ProviderAgent> {
     let registry = kernel.tool_registry();
     let kernel_clone = Arc::clone(&kernel);
     if let Some(router) = agent_router {
-        router.build_default(config, registry, kernel_clone, config.max_turns())
+        router.build_default(config, registry, kernel_clone, max_turns)
     } else {
-        agent_builder
-            .clone()
-            .build(registry, kernel_clone, config.max_turns())
+        agent_builder.clone().build(registry, kernel_clone, max_turns)
+    }
+}
+
+/// A WhatsApp number served by this process, resolved from `[whatsapp]` or one
+/// `[[whatsapp.accounts]]` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhatsappAccount {
+    pub id: String,
+    pub store_path: String,
+    pub allowed_senders: Option<Vec<String>>,
+    /// Channel profile (`[channels.profiles.<name>]`) applied to this account's messages.
+    pub profile: String,
+    /// Prefix of this account's session ids, so one sender talking to two numbers gets
+    /// two separate conversations.
+    pub session_prefix: String,
+}
+
+impl WhatsappAccount {
+    pub fn session_id(&self, user_id: &str) -> String {
+        format!("{}:{user_id}", self.session_prefix)
+    }
+
+    /// How the scheduler recognises and runs jobs created on this account.
+    pub fn job_account(&self) -> JobAccount {
+        JobAccount {
+            id: self.id.clone(),
+            channel_id: "whatsapp".to_string(),
+            session_prefix: self.session_prefix.clone(),
+            profile: self.profile.clone(),
+        }
     }
 }
 
+/// Accounts to serve. Without `[[whatsapp.accounts]]` this is the single `[whatsapp]` account
+/// with its existing `whatsapp:<sender>` sessions; listed accounts get `whatsapp:<id>:<sender>`
+/// sessions and their own `whatsapp-<id>.db` store unless `store_path` is set.
+pub fn whatsapp_accounts(config: &Config, channel: &WhatsappConfig) -> Vec<WhatsappAccount> {
+    let accounts = channel.accounts.as_deref().unwrap_or_default();
+    if accounts.is_empty() {
+        return vec![WhatsappAccount {
+            id: "default".to_string(),
+            store_path: whatsapp_store_path(config, channel),
+            allowed_senders: whatsapp_allowed_senders(channel.allowed_senders.as_ref()),
+            profile: "whatsapp".to_string(),
+            session_prefix: "whatsapp".to_string(),
+        }];
+    }
+    accounts
+        .iter()
+        .map(|account| WhatsappAccount {
+            id: account.id.clone(),
+            store_path: account.store_path.clone().unwrap_or_else(|| {
+                config
+                    .data_dir()
+                    .join(format!("whatsapp-{}.db", account.id))
+                    .to_string_lossy()
+                    .to_string()
+            }),
+            allowed_senders: whatsapp_allowed_senders(
+                account
+                    .allowed_senders
+                    .as_ref()
+                    .or(channel.allowed_senders.as_ref()),
+            ),
+            profile: account
+                .profile
+                .clone()
+                .unwrap_or_else(|| "whatsapp".to_string()),
+            session_prefix: format!("whatsapp:{}", account.id),
+        })
+        .collect()
+}
+
 fn whatsapp_store_path(config: &Config, channel: &WhatsappConfig) -> String {
     if let Some(path) = &channel.store_path {
         return path.to_string();
     }
-    config
-        .data_dir()
-        .join("whatsapp.db")
-        .to_string_lossy()
-        .to_string()
+    config.whatsapp_db_path().to_string_lossy().to_string()
 }
 
-fn whatsapp_allowed_senders(channel: &WhatsappConfig) -> Option<Vec<String>> {
-    channel.allowed_senders.as_ref().and_then(|list| {
+fn whatsapp_allowed_senders(senders: Option<&Vec<String>>) -> Option<Vec<String>> {
+    senders.and_then(|list| {
         if list.is_empty() {
             None
         } else {
@@ -488,6 +905,32 @@ fn whatsapp_allowed_senders(channel: &WhatsappConfig) -> Option<Vec<String>> {
     })
 }
 
+/// The `allowed_senders` allowlist, counting rejected senders as filtered.
+pub struct SenderFilter {
+    pub allowed: Option<Vec<String>>,
+    pub outcomes: Arc<InboundOutcomes>,
+}
+
+impl SenderFilter {
+    fn admits(&self, sender: &str) -> bool {
+        let Some(allowed) = self.allowed.as_ref() else {
+            return true;
+        };
+        if is_allowed_sender(sender, allowed) {
+            return true;
+        }
+        tracing::info!(
+            event = "channel_sender_filtered",
+            channel_id = "whatsapp",
+            user_id = %sender,
+            "WhatsApp ignored message (not in allowlist)"
+        );
+        self.outcomes
+            .record("whatsapp", Some(sender), InboundOutcome::Filtered);
+        false
+    }
+}
+
 fn is_allowed_sender(sender: &str, allowed: &[String]) -> bool {
     if allowed
         .iter()
@@ -517,19 +960,23 @@ async fn prompt_with_agent(
     agent: &ProviderAgent,
     prompt: &str,
     max_turns: usize,
+    max_retries: usize,
 ) -> Result<PromptWithUsageResult> {
-    let (response, usage) = agent
-        .prompt_with_turns_retry_usage(prompt.to_string(), max_turns, DEFAULT_PROVIDER_RETRIES)
+    let reply = agent
+        .prompt_with_turns_retry_usage(prompt.to_string(), max_turns, max_retries)
         .await
         .map_err(|err| anyhow::anyhow!(err))?;
-    Ok(PromptWithUsageResult { response, usage })
+    Ok(PromptWithUsageResult {
+        response: reply.text_with_note(),
+        usage: reply.usage,
+    })
 }
 
 async fn run_whatsapp_loop(
     store_path: String,
     media_root: PathBuf,
-    max_media_size_bytes: u64,
-    allowed_senders: Option<Vec<String>>,
+    media_limits: MediaLimits,
+    sender_filter: SenderFilter,
     inbound_tx: mpsc::UnboundedSender<InboundMessage>,
     mut outbound_rx: mpsc::UnboundedReceiver<WhatsappOutbound>,
     qr_cache: watch::Sender<Option<String>>,
@@ -540,7 +987,6 @@ async fn run_whatsapp_loop(
     use whatsapp_rust::bot::Bot;
     use whatsapp_rust_sqlite_storage::SqliteStore;
     use whatsapp_rust_tokio_transport::TokioWebSocketTransportFactory;
-    use whatsapp_rust_ureq_http_client::UreqHttpClient;
 
     let backend = match SqliteStore::new(&store_path).await {
         Ok(store) => StdArc::new(store),
@@ -551,18 +997,18 @@ async fn run_whatsapp_loop(
     };
 
     let (client_tx, mut client_rx) = mpsc::unbounded_channel();
-    let allowed_senders = Arc::new(allowed_senders);
+    let sender_filter = Arc::new(sender_filter);
 
     let mut bot = match Bot::builder()
         .with_backend(backend)
         .with_transport_factory(TokioWebSocketTransportFactory::new())
-        .with_http_client(UreqHttpClient::new())
+        .with_http_client(MediaCappedHttpClient::new(media_limits.max_size_bytes))
         .on_event(move |event, client| {
             let inbound_tx = inbound_tx.clone();
             let qr_cache = qr_cache.clone();
             let client_tx = client_tx.clone();
             let media_root = media_root.clone();
-            let allowed_senders = Arc::clone(&allowed_senders);
+            let sender_filter = Arc::clone(&sender_filter);
             async move {
                 let _ = client_tx.send(StdArc::clone(&client));
                 match event {
@@ -571,24 +1017,19 @@ async fn run_whatsapp_loop(
                     }
                     Event::Message(message, info) => {
                         let from = info.source.sender.to_string();
-                        if let Some(allowed) = allowed_senders.as_ref()
-                            && !is_allowed_sender(&from, allowed)
-                        {
-                            tracing::info!(
-                                event = "channel_sender_filtered",
-                                channel_id = "whatsapp",
-                                user_id = %from,
-                                "WhatsApp ignored message (not in allowlist)"
-                            );
+                        if !sender_filter.admits(&from) {
                             return;
                         }
                         let text = message.text_content().unwrap_or_default().to_string();
                         let base = message.get_base_message();
+                        let mut pending = pending_media(base);
+                        let omitted_attachments =
+                            cap_pending_media(&mut pending, media_limits.max_per_message, &from);
                         let attachments = match extract_media_attachments(
                             &client,
-                            base,
+                            pending,
                             &media_root,
-                            max_media_size_bytes,
+                            media_limits.max_size_bytes,
                             &from,
                         )
                         .await
@@ -599,7 +1040,12 @@ async fn run_whatsapp_loop(
                                 Vec::new()
                             }
                         };
-                        if text.trim().is_empty() && attachments.is_empty() {
+                        let shared = extract_shared_content(base);
+                        if text.trim().is_empty()
+                            && attachments.is_empty()
+                            && omitted_attachments == 0
+                            && shared.is_empty()
+                        {
                             return;
                         }
                         let _ = inbound_tx.send(InboundMessage {
@@ -608,6 +1054,8 @@ async fn run_whatsapp_loop(
                             text,
                             message_id: Some(info.id.to_string()),
                             attachments,
+                            omitted_attachments,
+                            shared,
                         });
                     }
                     _ => {}
@@ -643,9 +1091,16 @@ async fn run_whatsapp_loop(
     };
 
     while let Some(command) = outbound_rx.recv().await {
-        let reply = send_outbound_message(&client, &command.to, &command.text).await;
+        let reply = match &command.content {
+            OutboundContent::Text(text) => send_outbound_message(&client, &command.to, text).await,
+            OutboundContent::Media(artifact) => {
+                send_outbound_media(&client, &command.to, artifact).await
+            }
+        };
         let _ = command.reply.send(reply);
     }
+    // The backend was dropped; stop the bot and its reconnect loop with it.
+    run_task.abort();
 }
 
 async fn send_outbound_message(
@@ -665,55 +1120,94 @@ async fn send_outbound_message(
     Ok(message_id)
 }
 
-fn format_attachments_prompt(attachments: &[MediaAttachment]) -> String {
-    if attachments.is_empty() {
-        return String::new();
-    }
-    let mut lines = Vec::new();
-    lines.push(
-        "User sent attachments (use multimodal_looker for images, documents, audio, or video if needed):"
-            .to_string(),
-    );
-    for (idx, attachment) in attachments.iter().enumerate() {
-        let label = format!("{}. {}", idx + 1, attachment_label(attachment));
-        lines.push(label);
-    }
-    lines.join("\n")
-}
+async fn send_outbound_media(
+    client: &Arc<whatsapp_rust::Client>,
+    to: &str,
+    artifact: &Artifact,
+) -> Result<String> {
+    use wacore::download::MediaType as UploadType;
+    use wacore_binary::jid::Jid;
+    use waproto::whatsapp as wa;
 
-fn attachment_label(attachment: &MediaAttachment) -> String {
-    let kind = match attachment.media_type {
-        MediaType::Image => "image",
-        MediaType::Document => "document",
-        MediaType::Audio => "audio",
-        MediaType::Video => "video",
-        MediaType::Sticker => "sticker",
-    };
-    let mut parts = Vec::new();
-    parts.push(format!("type={kind}"));
-    parts.push(format!("path={}", attachment.local_path.display()));
-    if let Some(name) = &attachment.file_name {
-        parts.push(format!("name={name}"));
-    }
-    if let Some(mime) = &attachment.mime_type {
-        parts.push(format!("mime={mime}"));
-    }
-    if let Some(size) = attachment.size_bytes {
-        parts.push(format!("bytes={size}"));
-    }
-    if let Some(caption) = &attachment.caption {
-        parts.push(format!("caption={caption}"));
-    }
-    if let Some(thumbnail_path) = &attachment.thumbnail_path {
-        parts.push(format!("thumbnail_path={}", thumbnail_path.display()));
-    }
-    if let Some(thumbnail_mime) = &attachment.thumbnail_mime_type {
-        parts.push(format!("thumbnail_mime={thumbnail_mime}"));
-    }
-    if let Some(thumbnail_size) = attachment.thumbnail_size_bytes {
-        parts.push(format!("thumbnail_bytes={thumbnail_size}"));
+    let jid: Jid = to.parse().context("invalid whatsapp jid")?;
+    // The kernel admitted the canonical path; refuse it if it has since become a symlink.
+    let metadata = tokio::fs::symlink_metadata(&artifact.path)
+        .await
+        .with_context(|| format!("failed to stat artifact {}", artifact.path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("artifact {} is not a regular file", artifact.path.display());
     }
-    parts.join(" ")
+    let data = tokio::fs::read(&artifact.path)
+        .await
+        .with_context(|| format!("failed to read artifact {}", artifact.path.display()))?;
+    let upload_type = match artifact.kind {
+        ArtifactKind::Image => UploadType::Image,
+        ArtifactKind::Audio => UploadType::Audio,
+        ArtifactKind::Video => UploadType::Video,
+        ArtifactKind::File => UploadType::Document,
+    };
+    let upload = client.upload(data, upload_type).await?;
+    let mimetype = Some(artifact.mime.clone());
+    let message = match artifact.kind {
+        ArtifactKind::Image => wa::Message {
+            image_message: Some(Box::new(wa::message::ImageMessage {
+                url: Some(upload.url),
+                direct_path: Some(upload.direct_path),
+                mimetype,
+                media_key: Some(upload.media_key),
+                file_enc_sha256: Some(upload.file_enc_sha256),
+                file_sha256: Some(upload.file_sha256),
+                file_length: Some(upload.file_length),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        ArtifactKind::Audio => wa::Message {
+            audio_message: Some(Box::new(wa::message::AudioMessage {
+                url: Some(upload.url),
+                direct_path: Some(upload.direct_path),
+                mimetype,
+                media_key: Some(upload.media_key),
+                file_enc_sha256: Some(upload.file_enc_sha256),
+                file_sha256: Some(upload.file_sha256),
+                file_length: Some(upload.file_length),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        ArtifactKind::Video => wa::Message {
+            video_message: Some(Box::new(wa::message::VideoMessage {
+                url: Some(upload.url),
+                direct_path: Some(upload.direct_path),
+                mimetype,
+                media_key: Some(upload.media_key),
+                file_enc_sha256: Some(upload.file_enc_sha256),
+                file_sha256: Some(upload.file_sha256),
+                file_length: Some(upload.file_length),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        ArtifactKind::File => wa::Message {
+            document_message: Some(Box::new(wa::message::DocumentMessage {
+                url: Some(upload.url),
+                direct_path: Some(upload.direct_path),
+                mimetype,
+                media_key: Some(upload.media_key),
+                file_enc_sha256: Some(upload.file_enc_sha256),
+                file_sha256: Some(upload.file_sha256),
+                file_length: Some(upload.file_length),
+                file_name: artifact
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+    };
+    let message_id = client.send_message(jid, message).await?;
+    Ok(message_id)
 }
 
 fn with_media_permissions(
@@ -815,22 +1309,19 @@ fn should_delete(path: &Path, cutoff: Option<SystemTime>) -> bool {
     false
 }
 
-async fn extract_media_attachments(
-    client: &Arc<whatsapp_rust::Client>,
-    message: &waproto::whatsapp::Message,
-    media_root: &Path,
-    max_media_size_bytes: u64,
-    user_id: &str,
-) -> Result<Vec<MediaAttachment>> {
-    let mut attachments = Vec::new();
-    let base = message.get_base_message();
-    if let Some(msg) = base.image_message.as_deref()
-        && let Some(attachment) = download_media(
-            client,
-            msg,
-            media_root,
-            max_media_size_bytes,
-            MediaMeta {
+/// A media item found on an inbound message, before it is downloaded.
+struct PendingMedia<'m> {
+    media: &'m dyn whatsapp_rust::download::Downloadable,
+    meta: MediaMeta,
+}
+
+/// Media items on `message`, in the order they are downloaded.
+fn pending_media(message: &waproto::whatsapp::Message) -> Vec<PendingMedia<'_>> {
+    let mut pending = Vec::new();
+    if let Some(msg) = message.image_message.as_deref() {
+        pending.push(PendingMedia {
+            media: msg,
+            meta: MediaMeta {
                 media_type: MediaType::Image,
                 mime_type: msg.mimetype.clone(),
                 file_name: None,
@@ -838,19 +1329,12 @@ async fn extract_media_attachments(
                 file_length: msg.file_length,
                 thumbnail_bytes: msg.jpeg_thumbnail.clone(),
             },
-            user_id,
-        )
-        .await?
-    {
-        attachments.push(attachment);
+        });
     }
-    if let Some(msg) = base.document_message.as_deref()
-        && let Some(attachment) = download_media(
-            client,
-            msg,
-            media_root,
-            max_media_size_bytes,
-            MediaMeta {
+    if let Some(msg) = message.document_message.as_deref() {
+        pending.push(PendingMedia {
+            media: msg,
+            meta: MediaMeta {
                 media_type: MediaType::Document,
                 mime_type: msg.mimetype.clone(),
                 file_name: msg.file_name.clone(),
@@ -858,19 +1342,12 @@ async fn extract_media_attachments(
                 file_length: msg.file_length,
                 thumbnail_bytes: msg.jpeg_thumbnail.clone(),
             },
-            user_id,
-        )
-        .await?
-    {
-        attachments.push(attachment);
+        });
     }
-    if let Some(msg) = base.audio_message.as_deref()
-        && let Some(attachment) = download_media(
-            client,
-            msg,
-            media_root,
-            max_media_size_bytes,
-            MediaMeta {
+    if let Some(msg) = message.audio_message.as_deref() {
+        pending.push(PendingMedia {
+            media: msg,
+            meta: MediaMeta {
                 media_type: MediaType::Audio,
                 mime_type: msg
                     .mimetype
@@ -881,19 +1358,12 @@ async fn extract_media_attachments(
                 file_length: msg.file_length,
                 thumbnail_bytes: None,
             },
-            user_id,
-        )
-        .await?
-    {
-        attachments.push(attachment);
+        });
     }
-    if let Some(msg) = base.video_message.as_deref()
-        && let Some(attachment) = download_media(
-            client,
-            msg,
-            media_root,
-            max_media_size_bytes,
-            MediaMeta {
+    if let Some(msg) = message.video_message.as_deref() {
+        pending.push(PendingMedia {
+            media: msg,
+            meta: MediaMeta {
                 media_type: MediaType::Video,
                 mime_type: msg.mimetype.clone(),
                 file_name: None,
@@ -901,19 +1371,12 @@ async fn extract_media_attachments(
                 file_length: msg.file_length,
                 thumbnail_bytes: msg.jpeg_thumbnail.clone(),
             },
-            user_id,
-        )
-        .await?
-    {
-        attachments.push(attachment);
+        });
     }
-    if let Some(msg) = base.sticker_message.as_deref()
-        && let Some(attachment) = download_media(
-            client,
-            msg,
-            media_root,
-            max_media_size_bytes,
-            MediaMeta {
+    if let Some(msg) = message.sticker_message.as_deref() {
+        pending.push(PendingMedia {
+            media: msg,
+            meta: MediaMeta {
                 media_type: MediaType::Sticker,
                 mime_type: msg.mimetype.clone(),
                 file_name: None,
@@ -921,15 +1384,94 @@ async fn extract_media_attachments(
                 file_length: msg.file_length,
                 thumbnail_bytes: None,
             },
+        });
+    }
+    pending
+}
+
+/// Keeps the first `max` pending items and returns how many were dropped.
+fn cap_pending_media(pending: &mut Vec<PendingMedia<'_>>, max: usize, user_id: &str) -> usize {
+    let omitted = pending.len().saturating_sub(max);
+    if omitted > 0 {
+        pending.truncate(max);
+        tracing::warn!(
+            event = "whatsapp_attachments_capped",
+            user_id = %user_id,
+            max_attachments_per_message = max,
+            omitted,
+            "whatsapp message had more attachments than allowed; extra ones were not downloaded"
+        );
+    }
+    omitted
+}
+
+async fn extract_media_attachments(
+    client: &Arc<whatsapp_rust::Client>,
+    pending: Vec<PendingMedia<'_>>,
+    media_root: &Path,
+    max_media_size_bytes: u64,
+    user_id: &str,
+) -> Result<Vec<MediaAttachment>> {
+    let mut attachments = Vec::new();
+    for item in pending {
+        if let Some(attachment) = download_media(
+            client,
+            item.media,
+            media_root,
+            max_media_size_bytes,
+            item.meta,
             user_id,
         )
         .await?
-    {
-        attachments.push(attachment);
+        {
+            attachments.push(attachment);
+        }
     }
     Ok(attachments)
 }
 
+fn extract_shared_content(message: &waproto::whatsapp::Message) -> Vec<SharedContent> {
+    let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.trim().is_empty());
+    let mut shared = Vec::new();
+    if let Some(msg) = message.location_message.as_deref()
+        && let (Some(latitude), Some(longitude)) = (msg.degrees_latitude, msg.degrees_longitude)
+    {
+        shared.push(SharedContent::Location {
+            latitude,
+            longitude,
+            name: non_empty(&msg.name),
+            address: non_empty(&msg.address),
+            live: msg.is_live.unwrap_or(false),
+        });
+    }
+    if let Some(msg) = message.live_location_message.as_deref()
+        && let (Some(latitude), Some(longitude)) = (msg.degrees_latitude, msg.degrees_longitude)
+    {
+        shared.push(SharedContent::Location {
+            latitude,
+            longitude,
+            name: non_empty(&msg.caption),
+            address: None,
+            live: true,
+        });
+    }
+    if let Some(msg) = message.contact_message.as_deref() {
+        shared.push(SharedContent::Contact {
+            display_name: non_empty(&msg.display_name),
+            vcard: non_empty(&msg.vcard),
+        });
+    }
+    if let Some(msg) = message.contacts_array_message.as_deref() {
+        for contact in &msg.contacts {
+            shared.push(SharedContent::Contact {
+                display_name: non_empty(&contact.display_name),
+                vcard: non_empty(&contact.vcard),
+            });
+        }
+    }
+    shared
+}
+
 #[derive(Debug, Clone)]
 struct MediaMeta {
     media_type: MediaType,
@@ -940,9 +1482,9 @@ struct MediaMeta {
     thumbnail_bytes: Option<Vec<u8>>,
 }
 
-async fn download_media<T: whatsapp_rust::download::Downloadable>(
+async fn download_media(
     client: &Arc<whatsapp_rust::Client>,
-    media: &T,
+    media: &dyn whatsapp_rust::download::Downloadable,
     media_root: &Path,
     max_media_size_bytes: u64,
     meta: MediaMeta,
@@ -968,14 +1510,21 @@ async fn download_media<T: whatsapp_rust::download::Downloadable>(
     });
     let path = dir.join(filename);
     let file = std::fs::File::create(&path)?;
-    client.download_to_file(media, file).await?;
-    let size_bytes = std::fs::metadata(&path).ok().map(|meta| meta.len());
-    if let Some(size) = size_bytes
-        && size > max_media_size_bytes
-    {
-        let _ = std::fs::remove_file(&path);
-        return Ok(None);
+    let writer = SizeLimitedWriter::new(file, max_media_size_bytes);
+    if let Err(err) = client.download_to_file(media, writer).await {
+        let _ = std::fs::remove_dir_all(&dir);
+        if is_media_size_error(&err) {
+            tracing::warn!(
+                event = "whatsapp_media_too_large",
+                user_id = %user_id,
+                max_media_size_bytes,
+                "whatsapp media exceeded size limit; download aborted"
+            );
+            return Ok(None);
+        }
+        return Err(err);
     }
+    let size_bytes = std::fs::metadata(&path).ok().map(|meta| meta.len());
     let local_path = path.canonicalize().unwrap_or(path);
     let (thumbnail_path, thumbnail_size_bytes, thumbnail_mime_type) = match meta.thumbnail_bytes {
         Some(bytes) if !bytes.is_empty() => {
@@ -1003,6 +1552,113 @@ async fn download_media<T: whatsapp_rust::download::Downloadable>(
     }))
 }
 
+/// Slack over `max_media_size_bytes` for the encrypted body (MAC and cipher padding).
+const MEDIA_ENCRYPTION_OVERHEAD_BYTES: u64 = 1024;
+/// Floor for the HTTP body cap so small media limits don't break non-media requests.
+const MIN_HTTP_BODY_LIMIT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// HTTP client for the WhatsApp bot that caps GET response bodies, so an oversized
+/// media download stops once the cap is crossed instead of being buffered in full.
+#[derive(Debug, Clone)]
+struct MediaCappedHttpClient {
+    max_body_bytes: u64,
+    inner: whatsapp_rust_ureq_http_client::UreqHttpClient,
+}
+
+impl MediaCappedHttpClient {
+    fn new(max_media_size_bytes: u64) -> Self {
+        Self {
+            max_body_bytes: max_media_size_bytes
+                .saturating_add(MEDIA_ENCRYPTION_OVERHEAD_BYTES)
+                .max(MIN_HTTP_BODY_LIMIT_BYTES),
+            inner: whatsapp_rust_ureq_http_client::UreqHttpClient::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl wacore::net::HttpClient for MediaCappedHttpClient {
+    async fn execute(
+        &self,
+        request: wacore::net::HttpRequest,
+    ) -> Result<wacore::net::HttpResponse> {
+        if request.method != "GET" {
+            return self.inner.execute(request).await;
+        }
+        let limit = self.max_body_bytes;
+        tokio::task::spawn_blocking(move || {
+            let mut builder = ureq::get(&request.url);
+            for (key, value) in &request.headers {
+                builder = builder.header(key, value);
+            }
+            let response = builder.call()?;
+            let status_code = response.status().as_u16();
+            let body = response
+                .into_body()
+                .with_config()
+                .limit(limit)
+                .read_to_vec()?;
+            Ok(wacore::net::HttpResponse { status_code, body })
+        })
+        .await?
+    }
+}
+
+/// Writer that refuses to grow the output past `limit` bytes, so media is never written
+/// beyond the configured size regardless of the declared `file_length`.
+struct SizeLimitedWriter<W> {
+    inner: W,
+    position: u64,
+    limit: u64,
+}
+
+impl<W> SizeLimitedWriter<W> {
+    fn new(inner: W, limit: u64) -> Self {
+        Self {
+            inner,
+            position: 0,
+            limit,
+        }
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for SizeLimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.position.saturating_add(buf.len() as u64) > self.limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::FileTooLarge,
+                format!("media exceeds {} bytes", self.limit),
+            ));
+        }
+        let written = self.inner.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: std::io::Seek> std::io::Seek for SizeLimitedWriter<W> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.position = self.inner.seek(pos)?;
+        Ok(self.position)
+    }
+}
+
+fn is_media_size_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|err| err.kind() == std::io::ErrorKind::FileTooLarge)
+            || matches!(
+                cause.downcast_ref::<ureq::Error>(),
+                Some(ureq::Error::BodyExceedsLimit(_))
+            )
+    })
+}
+
 fn file_extension_from_mime(mime: Option<&str>) -> Option<String> {
     let mime = mime?.to_ascii_lowercase();
     let ext = match mime.as_str() {
@@ -1054,9 +1710,574 @@ fn render_qr_code(payload: &str) -> String {
     code.render::<unicode::Dense1x2>().quiet_zone(true).build()
 }
 
+/// Drops per-user locks that no handler holds. A handler clones the lock's `Arc` under
+/// the map's shard lock before awaiting it, and `retain` takes the same shard lock, so an
+/// entry is only removed when no handler has it or is about to acquire it.
+fn prune_idle_user_locks(locks: &DashMap<String, Arc<AsyncMutex<()>>>) -> usize {
+    let before = locks.len();
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    before - locks.len()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{is_allowed_sender, normalize_whatsapp_id, whatsapp_user_folder};
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use dashmap::DashMap;
+    use futures::Stream;
+    use tokio::sync::{Mutex as AsyncMutex, Semaphore};
+    use tokio_util::sync::CancellationToken;
+
+    use super::{
+        AdmissionSlots, InboundMessage, MediaType, SenderFilter, SharedContent, SizeLimitedWriter,
+        WhatsAppBackend, WhatsAppOutboundSender, WhatsappAccount, admit_message,
+        build_agent_for_kernel, cap_pending_media, error_reply, extract_shared_content,
+        is_allowed_sender, is_media_size_error, normalize_whatsapp_id, pending_media,
+        prompt_with_agent, prune_idle_user_locks, spawn_media_cleanup, spawn_notification_worker,
+        whatsapp_accounts, whatsapp_user_folder,
+    };
+    use crate::channels::outcomes::InboundOutcomes;
+    use crate::channels::turn::load_or_create_session;
+    use crate::config::{Config, WhatsappAccountConfig, WhatsappConfig};
+    use crate::session::manager::SessionManager;
+    use crate::tools::traits::Artifact;
+
+    #[derive(Default)]
+    struct RecordingBackend {
+        sent: std::sync::Mutex<Vec<(String, String)>>,
+        media: std::sync::Mutex<Vec<(String, Artifact)>>,
+    }
+
+    #[async_trait]
+    impl WhatsAppBackend for RecordingBackend {
+        async fn start(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn send_text(&self, to: &str, body: &str) -> anyhow::Result<String> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((to.to_string(), body.to_string()));
+            Ok("delivery".to_string())
+        }
+
+        async fn send_media(&self, to: &str, artifact: &Artifact) -> anyhow::Result<String> {
+            self.media
+                .lock()
+                .unwrap()
+                .push((to.to_string(), artifact.clone()));
+            Ok("media-delivery".to_string())
+        }
+
+        fn inbound_stream(&self) -> Pin<Box<dyn Stream<Item = InboundMessage> + Send>> {
+            Box::pin(futures::stream::empty())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn error_templates_hide_raw_errors_but_log_them() {
+        let config: WhatsappConfig = toml::from_str(
+            "[messages]\nprompt_error = \"Désolé, une erreur est survenue.\"\n",
+        )
+        .unwrap();
+        let messages = config.messages();
+        let err = anyhow::anyhow!("database locked at /srv/picobot/sessions.db");
+        let logs = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+
+        let reply = tracing::subscriber::with_default(subscriber, || {
+            error_reply("prompt", messages.prompt_error(), "15551234567", &err)
+        });
+
+        assert_eq!(reply, "Désolé, une erreur est survenue.");
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("database locked at /srv/picobot/sessions.db"), "{logs}");
+        assert_eq!(
+            error_reply("session", messages.session_error(), "15551234567", &err),
+            "Sorry, session error: database locked at /srv/picobot/sessions.db"
+        );
+    }
+
+    #[test]
+    fn location_and_contact_messages_become_shared_content() {
+        use waproto::whatsapp as wa;
+
+        let message = wa::Message {
+            location_message: Some(Box::new(wa::message::LocationMessage {
+                degrees_latitude: Some(51.5007),
+                degrees_longitude: Some(-0.1246),
+                name: Some("Big Ben".to_string()),
+                address: Some(String::new()),
+                ..Default::default()
+            })),
+            contact_message: Some(Box::new(wa::message::ContactMessage {
+                display_name: Some("Jane Doe".to_string()),
+                vcard: Some("BEGIN:VCARD\nFN:Jane Doe\nEND:VCARD".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            extract_shared_content(&message),
+            vec![
+                SharedContent::Location {
+                    latitude: 51.5007,
+                    longitude: -0.1246,
+                    name: Some("Big Ben".to_string()),
+                    address: None,
+                    live: false,
+                },
+                SharedContent::Contact {
+                    display_name: Some("Jane Doe".to_string()),
+                    vcard: Some("BEGIN:VCARD\nFN:Jane Doe\nEND:VCARD".to_string()),
+                },
+            ]
+        );
+        assert!(extract_shared_content(&wa::Message::default()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn busy_reply_is_sent_when_no_slot_frees_up() {
+        let backend = Arc::new(RecordingBackend::default());
+        let outbound = WhatsAppOutboundSender::new(backend.clone());
+        let semaphore = Arc::new(Semaphore::new(0));
+        let timeout = Duration::from_millis(50);
+
+        let started = std::time::Instant::now();
+        let permit =
+            admit_message(&semaphore, Some(timeout), &outbound, "15551234567", "busy").await;
+
+        assert!(permit.is_none());
+        assert!(started.elapsed() < timeout * 10);
+        assert_eq!(
+            backend.sent.lock().unwrap().as_slice(),
+            [("15551234567".to_string(), "busy".to_string())]
+        );
+
+        semaphore.add_permits(1);
+        let permit =
+            admit_message(&semaphore, Some(timeout), &outbound, "15551234567", "busy").await;
+        assert!(permit.is_some());
+        assert_eq!(backend.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn admission_never_blocks_and_bounds_the_line() {
+        let backend = Arc::new(RecordingBackend::default());
+        let outbound = WhatsAppOutboundSender::new(backend.clone());
+        let slots = AdmissionSlots::new(1, true);
+
+        let running = slots.try_admit().expect("free handler slot");
+        let waiting = slots.try_admit().expect("free place in line");
+        assert!(slots.try_admit().is_none());
+
+        let running = running.ready(None, &outbound, "1", "busy").await.unwrap();
+        let waiter = tokio::spawn(async move {
+            waiting.ready(None, &outbound, "2", "busy").await.is_some()
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        drop(running);
+        assert!(waiter.await.unwrap());
+        assert!(slots.try_admit().is_some());
+        assert!(backend.sent.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn admission_queues_everything_when_shedding_is_off() {
+        let backend = Arc::new(RecordingBackend::default());
+        let outbound = Arc::new(WhatsAppOutboundSender::new(backend.clone()));
+        let slots = AdmissionSlots::new(1, false);
+
+        let running = slots.try_admit().expect("free handler slot");
+        let queued = (0..5)
+            .map(|_| slots.try_admit().expect("never shed"))
+            .collect::<Vec<_>>();
+        let running = running.ready(None, &outbound, "1", "busy").await.unwrap();
+        let (done_tx, mut done_rx) = tokio::sync::mpsc::unbounded_channel();
+        for (index, admission) in queued.into_iter().enumerate() {
+            let outbound = Arc::clone(&outbound);
+            let done_tx = done_tx.clone();
+            tokio::spawn(async move {
+                let permit = admission.ready(None, &outbound, "2", "busy").await.unwrap();
+                done_tx.send(index).unwrap();
+                drop(permit);
+            });
+        }
+        drop(done_tx);
+        drop(running);
+        let mut handled = Vec::new();
+        while let Some(index) = done_rx.recv().await {
+            handled.push(index);
+        }
+        handled.sort();
+        assert_eq!(handled, [0, 1, 2, 3, 4]);
+        assert!(backend.sent.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn image_artifacts_from_tools_are_sent_as_media() {
+        use serde_json::{Value, json};
+
+        use crate::kernel::core::Kernel;
+        use crate::kernel::permissions::{CapabilitySet, PathPattern, Permission};
+        use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+        use crate::tools::registry::ToolRegistry;
+        use crate::tools::traits::{
+            Artifact, ArtifactKind, ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec,
+        };
+
+        struct ChartTool(ToolSpec);
+
+        #[async_trait]
+        impl ToolExecutor for ChartTool {
+            fn spec(&self) -> &ToolSpec {
+                &self.0
+            }
+
+            fn required_permissions(
+                &self,
+                _ctx: &ToolContext,
+                _input: &Value,
+            ) -> Result<Vec<Permission>, ToolError> {
+                Ok(Vec::new())
+            }
+
+            async fn execute(
+                &self,
+                ctx: &ToolContext,
+                _input: Value,
+            ) -> Result<ToolOutput, ToolError> {
+                for name in ["chart.png", "../secret.png"] {
+                    ctx.artifacts.attach(Artifact {
+                        path: name.into(),
+                        mime: "image/png".to_string(),
+                        kind: ArtifactKind::Image,
+                    });
+                }
+                Ok(json!({ "status": "rendered" }))
+            }
+        }
+
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::tool_call("chart", json!({})),
+            ScriptedReply::text("Here is your chart."),
+        ])
+        .await;
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(ChartTool(ToolSpec {
+                name: "chart".to_string(),
+                description: "Render a chart.".to_string(),
+                schema: json!({ "type": "object" }),
+                output_schema: None,
+            })))
+            .unwrap();
+        let registry = Arc::new(registry);
+        let root = std::env::temp_dir().join(format!("picobot-chart-{}", uuid::Uuid::new_v4()));
+        let jail = root.join("jail");
+        std::fs::create_dir_all(&jail).unwrap();
+        let root = root.canonicalize().unwrap();
+        let jail = root.join("jail");
+        std::fs::write(jail.join("chart.png"), b"png").unwrap();
+        std::fs::write(root.join("secret.png"), b"png").unwrap();
+        let mut capabilities = CapabilitySet::empty();
+        capabilities.insert(Permission::FileRead {
+            path: PathPattern(format!("{}/**", root.display())),
+        });
+        let kernel = Arc::new(
+            Kernel::new(Arc::clone(&registry))
+                .with_capabilities(capabilities)
+                .with_working_dir(jail.clone())
+                .with_jail_root(Some(jail.clone())),
+        );
+        let agent = provider
+            .builder()
+            .build_with_env(&registry, Arc::clone(&kernel), 4, |_| Some("test-key".to_string()))
+            .unwrap();
+        let backend = Arc::new(RecordingBackend::default());
+        let outbound = WhatsAppOutboundSender::new(backend.clone());
+
+        let reply = prompt_with_agent(&agent, "chart my steps", 4, 0).await.unwrap();
+        outbound
+            .send_reply("15551234567", &reply.response, &kernel.artifacts())
+            .await;
+
+        assert_eq!(
+            backend.sent.lock().unwrap().as_slice(),
+            [("15551234567".to_string(), "Here is your chart.".to_string())]
+        );
+        let media = backend.media.lock().unwrap();
+        assert_eq!(media.len(), 1);
+        assert_eq!(media[0].0, "15551234567");
+        assert_eq!(media[0].1.kind, ArtifactKind::Image);
+        assert_eq!(media[0].1.mime, "image/png");
+        assert_eq!(media[0].1.path, jail.join("chart.png"));
+        drop(media);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn say_messages_are_sent_before_the_final_reply() {
+        use serde_json::json;
+
+        use super::WhatsAppMessageSink;
+        use crate::kernel::core::Kernel;
+        use crate::kernel::permissions::{CapabilitySet, Permission};
+        use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+        use crate::tools::registry::ToolRegistry;
+        use crate::tools::say::SayTool;
+
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::tool_call("say", json!({ "message": "Crunching the numbers..." })),
+            ScriptedReply::text("Your report is ready."),
+        ])
+        .await;
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(SayTool::new())).unwrap();
+        let registry = Arc::new(registry);
+        let backend = Arc::new(RecordingBackend::default());
+        let outbound = Arc::new(WhatsAppOutboundSender::new(backend.clone()));
+        let mut capabilities = CapabilitySet::empty();
+        capabilities.insert(Permission::Notify {
+            channel: "whatsapp".to_string(),
+        });
+        let kernel = Arc::new(
+            Kernel::new(Arc::clone(&registry))
+                .with_capabilities(capabilities)
+                .with_channel_id(Some("whatsapp".to_string()))
+                .with_message_sink(Some(Arc::new(WhatsAppMessageSink::new(
+                    Arc::clone(&outbound),
+                    "15551234567",
+                )))),
+        );
+        let agent = provider
+            .builder()
+            .build_with_env(&registry, Arc::clone(&kernel), 4, |_| Some("test-key".to_string()))
+            .unwrap();
+
+        let reply = prompt_with_agent(&agent, "build my report", 4, 0).await.unwrap();
+        assert_eq!(
+            backend.sent.lock().unwrap().as_slice(),
+            [("15551234567".to_string(), "Crunching the numbers...".to_string())]
+        );
+        outbound
+            .send_reply("15551234567", &reply.response, &kernel.artifacts())
+            .await;
+
+        let sent = backend.sent.lock().unwrap();
+        assert_eq!(
+            sent.iter().map(|(_, body)| body.as_str()).collect::<Vec<_>>(),
+            ["Crunching the numbers...", "Your report is ready."]
+        );
+    }
+
+    #[tokio::test]
+    async fn whatsapp_agents_use_the_channel_turn_budget() {
+        use crate::config::{ChannelConfig, ChannelsConfig};
+        use crate::kernel::core::Kernel;
+        use crate::providers::factory::ProviderAgentBuilder;
+        use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+        use crate::tools::echo::EchoTool;
+        use crate::tools::registry::ToolRegistry;
+
+        let mut script = vec![ScriptedReply::tool_call("echo", serde_json::json!({})); 10];
+        script.push(ScriptedReply::text("partial summary"));
+        let provider = ScriptedProvider::start(script).await;
+        let config = Config {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4o-mini".to_string()),
+            base_url: Some(provider.base_url()),
+            api_key_env: Some("PATH".to_string()),
+            max_turns: Some(1),
+            channels: Some(ChannelsConfig {
+                profiles: [(
+                    "whatsapp".to_string(),
+                    ChannelConfig {
+                        max_turns: Some(3),
+                        ..Default::default()
+                    },
+                )]
+                .into(),
+            }),
+            ..Default::default()
+        };
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new())).unwrap();
+        let kernel = Arc::new(Kernel::new(Arc::new(registry)));
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        let max_turns = config.channel_max_turns("whatsapp");
+        assert_eq!(max_turns, 3);
+        assert_eq!(config.channel_max_turns("api"), 1);
+
+        let agent =
+            build_agent_for_kernel(&config, &agent_builder, None, kernel, max_turns).unwrap();
+        let reply = prompt_with_agent(&agent, "keep going", max_turns, 0).await.unwrap();
+
+        assert!(reply.response.ends_with(crate::providers::factory::TURN_LIMIT_NOTE));
+        let tool_rounds = provider
+            .requests()
+            .iter()
+            .filter(|request| request["tools"].as_array().is_some_and(|tools| !tools.is_empty()))
+            .count();
+        // rig makes max_turns + 2 requests with tools before giving up.
+        assert_eq!(tool_rounds, 5);
+    }
+
+    #[test]
+    fn attachments_beyond_the_per_message_cap_are_not_downloaded() {
+        use waproto::whatsapp as wa;
+
+        let message = wa::Message {
+            image_message: Some(Box::new(wa::message::ImageMessage {
+                mimetype: Some("image/jpeg".to_string()),
+                ..Default::default()
+            })),
+            document_message: Some(Box::new(wa::message::DocumentMessage {
+                file_name: Some("report.pdf".to_string()),
+                ..Default::default()
+            })),
+            audio_message: Some(Box::new(wa::message::AudioMessage::default())),
+            ..Default::default()
+        };
+
+        let mut pending = pending_media(&message);
+        assert_eq!(pending.len(), 3);
+        let omitted = cap_pending_media(&mut pending, 2, "15551234567");
+
+        assert_eq!(omitted, 1);
+        assert_eq!(pending.len(), 2);
+        assert!(matches!(pending[0].meta.media_type, MediaType::Image));
+        assert!(matches!(pending[1].meta.media_type, MediaType::Document));
+
+        let mut pending = pending_media(&message);
+        assert_eq!(cap_pending_media(&mut pending, 5, "15551234567"), 0);
+        assert_eq!(pending.len(), 3);
+    }
+
+    #[test]
+    fn oversized_media_stream_aborts_before_passing_limit() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("picobot-media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("media.bin");
+        let mut writer = SizeLimitedWriter::new(std::fs::File::create(&path).unwrap(), 10_000);
+
+        let chunk = [7u8; 4096];
+        let mut chunks_written = 0;
+        let err = loop {
+            match writer.write_all(&chunk) {
+                Ok(()) => chunks_written += 1,
+                Err(err) => break err,
+            }
+            assert!(chunks_written < 100, "writer never enforced its limit");
+        };
+        drop(writer);
+
+        assert_eq!(chunks_written, 2);
+        assert_eq!(err.kind(), std::io::ErrorKind::FileTooLarge);
+        assert!(is_media_size_error(&anyhow::Error::new(err)));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 8192);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn cancelling_shutdown_stops_media_cleanup_promptly() {
+        let dir = std::env::temp_dir().join(format!("picobot-media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shutdown = CancellationToken::new();
+        let cleanup =
+            spawn_media_cleanup(dir.clone(), 24, Duration::from_secs(60 * 60), shutdown.clone());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!cleanup.is_finished());
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(1), cleanup)
+            .await
+            .expect("media cleanup did not stop after shutdown")
+            .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn cancelling_shutdown_stops_the_notification_worker() {
+        use crate::notifications::queue::{NotificationQueue, NotificationQueueConfig};
+        use crate::notifications::service::NotificationService;
+        use crate::notifications::whatsapp::WhatsAppNotificationChannel;
+
+        let backend = Arc::new(RecordingBackend::default());
+        let outbound = Arc::new(WhatsAppOutboundSender::new(backend));
+        let notifications = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            Arc::new(WhatsAppNotificationChannel::new(outbound)),
+        );
+        let shutdown = CancellationToken::new();
+        let worker = spawn_notification_worker(notifications, shutdown.clone());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!worker.is_finished());
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(1), worker)
+            .await
+            .expect("notification worker did not stop after shutdown")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn idle_user_locks_are_pruned_after_handlers_finish() {
+        let locks: DashMap<String, Arc<AsyncMutex<()>>> = DashMap::new();
+        let handlers = ["alice", "bob"]
+            .into_iter()
+            .map(|user| {
+                let lock = locks
+                    .entry(user.to_string())
+                    .or_insert_with(|| Arc::new(AsyncMutex::new(())))
+                    .clone();
+                tokio::spawn(async move {
+                    let _guard = lock.lock().await;
+                })
+            })
+            .collect::<Vec<_>>();
+        let in_flight = locks
+            .entry("carol".to_string())
+            .or_insert_with(|| Arc::new(AsyncMutex::new(())))
+            .clone();
+        let _held = in_flight.lock().await;
+        for handler in handlers {
+            handler.await.unwrap();
+        }
+
+        assert_eq!(prune_idle_user_locks(&locks), 2);
+        assert_eq!(locks.len(), 1);
+        assert!(locks.contains_key("carol"));
+    }
 
     #[test]
     fn normalize_whatsapp_id_strips_device_suffix() {
@@ -1077,6 +2298,122 @@ mod tests {
         assert!(!is_allowed_sender("19999999999@c.us", &allowed));
     }
 
+    #[tokio::test]
+    async fn each_account_gets_its_own_store_and_session_namespace() {
+        use crate::notifications::channel::{NotificationPriority, NotificationRequest};
+        use crate::notifications::queue::{NotificationQueue, NotificationQueueConfig};
+        use crate::session::types::{MessageType, NewMessage};
+
+        let data_dir =
+            std::env::temp_dir().join(format!("picobot-accounts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let config = Config {
+            data_dir: Some(data_dir.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let single = whatsapp_accounts(&config, &WhatsappConfig::default());
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].store_path, data_dir.join("whatsapp.db").to_string_lossy());
+        assert_eq!(single[0].session_id("15551234567@c.us"), "whatsapp:15551234567@c.us");
+        let sales_store = data_dir.join("sales.db").to_string_lossy().to_string();
+
+        let channel = WhatsappConfig {
+            allowed_senders: Some(vec!["15550000000".to_string()]),
+            accounts: Some(vec![
+                WhatsappAccountConfig {
+                    id: "support".to_string(),
+                    ..Default::default()
+                },
+                WhatsappAccountConfig {
+                    id: "sales".to_string(),
+                    store_path: Some(sales_store.clone()),
+                    allowed_senders: Some(vec!["15551234567".to_string()]),
+                    profile: Some("whatsapp-sales".to_string()),
+                },
+            ]),
+            ..Default::default()
+        };
+        let accounts = whatsapp_accounts(&config, &channel);
+        let [support, sales] = accounts.as_slice() else {
+            panic!("expected two accounts: {accounts:?}");
+        };
+        assert_eq!(
+            support.store_path,
+            data_dir.join("whatsapp-support.db").to_string_lossy()
+        );
+        assert_eq!(sales.store_path, sales_store);
+        assert_eq!(support.allowed_senders, Some(vec!["15550000000".to_string()]));
+        assert_eq!(sales.allowed_senders, Some(vec!["15551234567".to_string()]));
+        assert_eq!(support.profile, "whatsapp");
+        assert_eq!(sales.profile, "whatsapp-sales");
+
+        // Each account's device store is its own file.
+        for account in [support, sales] {
+            whatsapp_rust_sqlite_storage::SqliteStore::new(&account.store_path)
+                .await
+                .unwrap();
+            assert!(std::path::Path::new(&account.store_path).is_file());
+        }
+
+        // One sender on both numbers gets two conversations in the shared sessions db.
+        let store = crate::session::db::SqliteStore::open_migrated(
+            &config.sessions_db_path(),
+            config.sqlite_pragmas(),
+        )
+        .unwrap();
+        let sessions = SessionManager::new(store.clone());
+        let sender = "15551234567@s.whatsapp.net";
+        let capabilities = crate::kernel::permissions::CapabilitySet::empty();
+        for account in [support, sales] {
+            load_or_create_session(
+                &sessions,
+                &account.session_id(sender),
+                "whatsapp",
+                sender,
+                &capabilities,
+            )
+            .unwrap();
+        }
+        sessions
+            .append_message(
+                &support.session_id(sender),
+                &NewMessage::new(MessageType::User, "hi support"),
+            )
+            .unwrap();
+        assert_eq!(sessions.get_messages(&support.session_id(sender), 10).unwrap().len(), 1);
+        assert!(sessions.get_messages(&sales.session_id(sender), 10).unwrap().is_empty());
+
+        // Notifications persisted by one account are not resumed by the other.
+        let queue = |account: &WhatsappAccount| {
+            NotificationQueue::new(NotificationQueueConfig::default())
+                .with_store(store.clone(), account.session_prefix.clone())
+        };
+        queue(support)
+            .enqueue(NotificationRequest {
+                user_id: sender.to_string(),
+                channel_id: "whatsapp".to_string(),
+                message: "done".to_string(),
+                priority: NotificationPriority::Normal,
+            })
+            .await;
+        assert_eq!(queue(sales).resume().await, 0);
+        assert_eq!(queue(support).resume().await, 1);
+        std::fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn filtered_senders_are_counted() {
+        let outcomes = Arc::new(InboundOutcomes::default());
+        let filter = SenderFilter {
+            allowed: Some(vec!["15551234567".to_string()]),
+            outcomes: Arc::clone(&outcomes),
+        };
+        assert!(filter.admits("15551234567:3@s.whatsapp.net"));
+        assert!(!filter.admits("19999999999@c.us"));
+        assert_eq!(outcomes.counts().filtered, 1);
+        assert_eq!(outcomes.counts().processed, 0);
+    }
+
     #[test]
     fn whatsapp_user_folder_normalizes_device_suffix() {
         let lid_folder = whatsapp_user_folder("19683919028456:12@lid");
@@ -2,11 +2,18 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-use crate::kernel::permissions::parse_permission_with_base;
+use crate::kernel::permissions::{
+    GlobalMemoryGrant, PromptTimeoutAction, parse_permission_with_base,
+};
+use crate::tools::search::default_search_api_key_env;
 use crate::tools::shell_policy::ShellRisk;
 
+/// Provider request timeout when `provider_request_timeout_secs` is unset or 0.
+pub const DEFAULT_PROVIDER_REQUEST_TIMEOUT_SECS: u64 = 120;
+
 const DEFAULT_SYSTEM_PROMPT: &str = r#"You are PicoBot, an execution-oriented assistant with access to tools.
 
 Rules:
@@ -21,16 +28,35 @@ Rules:
 - Be concise and summarize results.
 "#;
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct Config {
+    /// `openai`, `openrouter`, or `gemini`.
+    #[schemars(default = "schema_defaults::provider")]
     pub provider: Option<String>,
+    #[schemars(default = "schema_defaults::model")]
     pub model: Option<String>,
     pub base_url: Option<String>,
     pub api_key_env: Option<String>,
+    /// Placeholders `{date}`, `{user_id}`, `{channel}` and `{timezone}` are filled in per
+    /// request.
+    #[schemars(default = "schema_defaults::system_prompt")]
     pub system_prompt: Option<String>,
+    /// Legacy name for `agent.max_tool_rounds`, used when that is unset.
     pub max_turns: Option<usize>,
+    /// Seconds per model provider request; 0 means the default.
+    #[schemars(default = "schema_defaults::provider_request_timeout_secs")]
+    pub provider_request_timeout_secs: Option<u64>,
+    /// Retries after transient provider errors (0-10).
+    #[schemars(default = "schema_defaults::provider_max_retries")]
+    pub provider_max_retries: Option<usize>,
+    /// Checks each configured `base_url` during validation by listing its models.
+    #[schemars(default = "schema_defaults::probe_base_url")]
+    pub probe_base_url: Option<bool>,
+    /// Address the API server listens on.
+    #[schemars(default = "schema_defaults::bind")]
     pub bind: Option<String>,
     pub data_dir: Option<String>,
+    pub data: Option<DataConfig>,
     pub api: Option<ApiConfig>,
     pub permissions: Option<PermissionsConfig>,
     pub scheduler: Option<SchedulerConfig>,
@@ -43,6 +69,12 @@ pub struct Config {
     pub multimodal: Option<MultimodalConfig>,
     pub vision: Option<VisionConfig>,
     pub search: Option<SearchConfig>,
+    pub tools: Option<ToolsConfig>,
+    pub agent: Option<AgentConfig>,
+    /// Not read from the file; set by [`Config::resolve_paths`], see [`Config::base_dir`].
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub base_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -52,16 +84,68 @@ impl Config {
     }
 
     pub fn load_from(path: PathBuf) -> Result<Self> {
-        if !path.exists() {
-            return Ok(Self::default());
-        }
-        let contents = std::fs::read_to_string(&path)
-            .with_context(|| format!("failed to read config at {}", path.display()))?;
-        let config = toml::from_str(&contents)
-            .with_context(|| format!("failed to parse config at {}", path.display()))?;
+        let mut config = if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read config at {}", path.display()))?;
+            toml::from_str(&contents)
+                .with_context(|| format!("failed to parse config at {}", path.display()))?
+        } else {
+            Self::default()
+        };
+        config.normalize_senders();
+        let cwd = std::env::current_dir().context("failed to read current directory")?;
+        config.resolve_paths(&cwd);
         Ok(config)
     }
 
+    /// Records `base_dir` as the directory relative config paths (permissions, jail roots,
+    /// working dirs) resolve against, and pins `data_dir` to an absolute path under it
+    /// (expanding `~`), so database, media, and working paths agree no matter what the
+    /// process cwd is later. Existing directories are canonicalized.
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        self.base_dir = Some(base_dir.to_path_buf());
+        let raw = self.data_dir().to_string_lossy().to_string();
+        let expanded = match raw.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+                Some(home) => home.join(rest.trim_start_matches('/')),
+                None => PathBuf::from(&raw),
+            },
+            _ => PathBuf::from(&raw),
+        };
+        let absolute = if expanded.is_absolute() {
+            expanded
+        } else {
+            base_dir.join(expanded)
+        };
+        let resolved = std::fs::canonicalize(&absolute).unwrap_or(absolute);
+        self.data_dir = Some(resolved.to_string_lossy().to_string());
+    }
+
+    /// Directory relative config paths resolve against: the cwd when the config was loaded.
+    /// Configs built in code without [`Config::resolve_paths`] fall back to the current cwd.
+    pub fn base_dir(&self) -> PathBuf {
+        self.base_dir.clone().unwrap_or_else(|| {
+            std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+        })
+    }
+
+    /// Rewrites WhatsApp `allowed_senders` (top-level and per account) into the form sender
+    /// ids are matched in. Entries that cannot be normalized are kept as written; validation
+    /// warns about them.
+    fn normalize_senders(&mut self) {
+        let Some(whatsapp) = self.whatsapp.as_mut() else {
+            return;
+        };
+        let accounts = whatsapp.accounts.iter_mut().flatten();
+        let lists = std::iter::once(&mut whatsapp.allowed_senders)
+            .chain(accounts.map(|account| &mut account.allowed_senders));
+        for sender in lists.flatten().flatten() {
+            if let Some(normalized) = normalize_sender_id(sender) {
+                *sender = normalized;
+            }
+        }
+    }
+
     pub fn provider(&self) -> &str {
         self.provider.as_deref().unwrap_or("openai")
     }
@@ -76,8 +160,35 @@ impl Config {
             .unwrap_or(DEFAULT_SYSTEM_PROMPT)
     }
 
-    pub fn max_turns(&self) -> usize {
-        self.max_turns.unwrap_or(5)
+    /// Tool-call rounds an agent may run per turn: `agent.max_tool_rounds`, then the legacy
+    /// top-level `max_turns`, then 5.
+    pub fn max_tool_rounds(&self) -> usize {
+        self.agent
+            .as_ref()
+            .and_then(|agent| agent.max_tool_rounds)
+            .or(self.max_turns)
+            .unwrap_or(5)
+    }
+
+    /// Per-request timeout for model provider calls. Unset or 0 uses
+    /// [`DEFAULT_PROVIDER_REQUEST_TIMEOUT_SECS`].
+    pub fn provider_request_timeout(&self) -> std::time::Duration {
+        match self.provider_request_timeout_secs {
+            Some(0) | None => {
+                std::time::Duration::from_secs(DEFAULT_PROVIDER_REQUEST_TIMEOUT_SECS)
+            }
+            Some(value) => std::time::Duration::from_secs(value),
+        }
+    }
+
+    /// Retries after a failed provider call, for errors that are worth retrying.
+    pub fn provider_max_retries(&self) -> usize {
+        self.provider_max_retries
+            .unwrap_or(crate::providers::factory::DEFAULT_PROVIDER_RETRIES)
+    }
+
+    pub fn probe_base_url(&self) -> bool {
+        self.probe_base_url.unwrap_or(false)
     }
 
     pub fn bind(&self) -> &str {
@@ -93,6 +204,46 @@ impl Config {
             .join("picobot")
     }
 
+    /// Sessions, messages, memories, and usage.
+    pub fn sessions_db_path(&self) -> PathBuf {
+        self.data_file(|data| data.sessions_db.as_deref(), "sessions.db")
+    }
+
+    /// Schedules and their executions.
+    pub fn scheduler_db_path(&self) -> PathBuf {
+        self.data_file(|data| data.scheduler_db.as_deref(), "picobot.db")
+    }
+
+    /// WhatsApp pairing and device state; `whatsapp.store_path` takes precedence.
+    pub fn whatsapp_db_path(&self) -> PathBuf {
+        self.data_file(|data| data.whatsapp_db.as_deref(), "whatsapp.db")
+    }
+
+    /// `[data.sqlite]` pragmas; invalid values fall back to the defaults (validation
+    /// reports them).
+    pub fn sqlite_pragmas(&self) -> crate::session::db::SqlitePragmas {
+        self.data
+            .as_ref()
+            .and_then(|data| data.sqlite.as_ref())
+            .and_then(|sqlite| sqlite.pragmas().ok())
+            .unwrap_or_default()
+    }
+
+    /// Resolves a `[data]` database path; relative paths are under `data_dir`.
+    fn data_file(
+        &self,
+        configured: impl Fn(&DataConfig) -> Option<&str>,
+        default: &str,
+    ) -> PathBuf {
+        let path = self
+            .data
+            .as_ref()
+            .and_then(configured)
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or(default);
+        self.data_dir().join(path)
+    }
+
     pub fn api(&self) -> ApiConfig {
         self.api.clone().unwrap_or_default()
     }
@@ -121,10 +272,33 @@ impl Config {
         self.channels.clone().unwrap_or_default()
     }
 
+    /// Agent turn budget for `channel_id`, falling back to `agent.max_tool_rounds`.
+    pub fn channel_max_turns(&self, channel_id: &str) -> usize {
+        self.channels
+            .as_ref()
+            .and_then(|channels| channels.profiles.get(channel_id))
+            .and_then(|channel| channel.max_turns)
+            .unwrap_or_else(|| self.max_tool_rounds())
+    }
+
     pub fn whatsapp(&self) -> WhatsappConfig {
         self.whatsapp.clone().unwrap_or_default()
     }
 
+    pub fn tools(&self) -> ToolsConfig {
+        self.tools.clone().unwrap_or_default()
+    }
+
+    /// Smallest `context_window_tokens` among `[[models]]`, since a session may switch to
+    /// any of them. `None` when no model declares one.
+    pub fn context_window_tokens(&self) -> Option<u32> {
+        self.models
+            .iter()
+            .flatten()
+            .filter_map(|model| model.context_window_tokens)
+            .min()
+    }
+
     pub fn default_model_id(&self) -> Option<&str> {
         self.routing
             .as_ref()
@@ -132,9 +306,22 @@ impl Config {
     }
 
     pub fn validate(&self) -> Result<ConfigValidation> {
+        let validation = self.validation_report();
+        if validation.errors.is_empty() {
+            Ok(validation)
+        } else {
+            Err(anyhow::anyhow!(format!(
+                "config validation failed: {}",
+                validation.errors.join("; ")
+            )))
+        }
+    }
+
+    /// Runs every validation rule and collects errors alongside warnings instead of failing.
+    pub fn validation_report(&self) -> ConfigValidation {
         let mut errors = Vec::new();
         let mut warnings = Vec::new();
-        let base_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let base_dir = self.base_dir();
 
         let provider = self.provider();
         let multimodal_config = self
@@ -156,6 +343,72 @@ impl Config {
                 warnings.push("max_turns is unusually high".to_string());
             }
         }
+        if let Some(rounds) = self.agent.as_ref().and_then(|agent| agent.max_tool_rounds) {
+            if rounds == 0 {
+                errors.push("agent max_tool_rounds must be >= 1".to_string());
+            } else if rounds > 50 {
+                warnings.push("agent max_tool_rounds is unusually high".to_string());
+            }
+            if self.max_turns.is_some() {
+                warnings.push(
+                    "max_turns is ignored because agent.max_tool_rounds is set".to_string(),
+                );
+            }
+        }
+
+        if let Some(timeout) = self.provider_request_timeout_secs {
+            if timeout == 0 {
+                warnings.push("provider_request_timeout_secs is 0; using default".to_string());
+            } else if timeout > 900 {
+                warnings.push("provider_request_timeout_secs is unusually high".to_string());
+            }
+        }
+        if let Some(retries) = self.provider_max_retries
+            && retries > 10
+        {
+            errors.push("provider_max_retries must be <= 10".to_string());
+        }
+        if let Some(sqlite) = self.data.as_ref().and_then(|data| data.sqlite.as_ref()) {
+            if let Err(err) = sqlite.pragmas() {
+                errors.push(format!("data.sqlite {err}"));
+            }
+            if sqlite.busy_timeout_ms == Some(0) {
+                warnings.push(
+                    "data.sqlite busy_timeout_ms is 0; locked writes fail immediately".to_string(),
+                );
+            }
+            if sqlite
+                .synchronous
+                .as_deref()
+                .is_some_and(|mode| mode.trim().eq_ignore_ascii_case("off"))
+            {
+                warnings.push(
+                    "data.sqlite synchronous = off can lose or corrupt data on power loss"
+                        .to_string(),
+                );
+            }
+        }
+        let mut base_urls = Vec::new();
+        for raw in self
+            .base_url
+            .iter()
+            .chain(self.models.iter().flatten().filter_map(|model| model.base_url.as_ref()))
+            .chain(multimodal_config.iter().filter_map(|multimodal| multimodal.base_url.as_ref()))
+        {
+            match crate::providers::factory::normalize_base_url(raw) {
+                Ok(url) if !base_urls.contains(&url) => base_urls.push(url),
+                Ok(_) => {}
+                Err(err) => errors.push(format!("invalid base_url: {err}")),
+            }
+        }
+        if self.probe_base_url() {
+            for url in &base_urls {
+                let timeout = std::time::Duration::from_secs(5);
+                if let Err(err) = crate::providers::factory::probe_base_url(url, timeout) {
+                    warnings.push(format!("base_url probe failed: {err}"));
+                }
+            }
+        }
 
         let data_dir = self.data_dir();
         if let Err(err) = std::fs::create_dir_all(&data_dir) {
@@ -316,6 +569,46 @@ impl Config {
             }
         }
 
+        if let Some(memory) = &self.memory
+            && let Some(max_stored) = memory.max_stored_messages()
+            && let Some(max_session) = memory.max_session_messages
+            && max_stored < max_session
+        {
+            warnings.push(format!(
+                "memory max_stored_messages ({max_stored}) is below max_session_messages \
+                 ({max_session}); older context will only come from the session summary"
+            ));
+        }
+
+        if let Some(budget) = self.memory.as_ref().and_then(|memory| memory.context_budget_tokens)
+        {
+            let cap = crate::session::memory::context_budget_cap(self.context_window_tokens());
+            if budget == 0 {
+                warnings.push(format!(
+                    "memory context_budget_tokens is 0; context is not trimmed to a budget \
+                     but is still capped at {cap} tokens"
+                ));
+            } else if budget as usize > cap {
+                warnings.push(format!(
+                    "memory context_budget_tokens ({budget}) is above the safe maximum; \
+                     context is capped at {cap} tokens"
+                ));
+            }
+        }
+
+        for (index, access) in self.memory().global_access.iter().flatten().enumerate() {
+            if access.channel.trim().is_empty() {
+                errors.push(format!("memory.global_access[{index}] channel cannot be empty"));
+            }
+            if access.users.as_ref().is_some_and(|users| users.is_empty()) {
+                warnings.push(format!(
+                    "memory.global_access[{index}] has an empty users list; \
+                     every user on '{}' gets global memory",
+                    access.channel
+                ));
+            }
+        }
+
         if let Some(channels) = &self.channels {
             for (channel_id, channel) in &channels.profiles {
                 if let Some(timeout) = channel.prompt_timeout_secs
@@ -323,6 +616,52 @@ impl Config {
                 {
                     warnings.push(format!("channel '{channel_id}' prompt_timeout_secs is 0"));
                 }
+                if let Some(max_turns) = channel.max_turns {
+                    if max_turns == 0 {
+                        errors.push(format!("channel '{channel_id}' max_turns must be >= 1"));
+                    } else if max_turns > 50 {
+                        warnings
+                            .push(format!("channel '{channel_id}' max_turns is unusually high"));
+                    }
+                }
+                if let Some(action) = channel.prompt_timeout_action.as_deref()
+                    && PromptTimeoutAction::parse(action).is_none()
+                {
+                    errors.push(format!(
+                        "channel '{channel_id}' prompt_timeout_action '{action}' must be \
+                         'deny' or 'allow_once'"
+                    ));
+                }
+                for (field, value) in [
+                    ("jail_root", &channel.jail_root),
+                    ("working_dir", &channel.working_dir),
+                ] {
+                    let Some(value) = value else {
+                        continue;
+                    };
+                    let resolved = resolve_config_path(&base_dir, value);
+                    let path = PathBuf::from(&resolved);
+                    if !path.exists() {
+                        errors.push(format!(
+                            "channel '{channel_id}' {field} '{resolved}' does not exist"
+                        ));
+                    } else if !path.is_dir() {
+                        errors.push(format!(
+                            "channel '{channel_id}' {field} '{resolved}' is not a directory"
+                        ));
+                    }
+                }
+                if channel.auto_grant_within_max_allowed() {
+                    if channel.max_allowed.as_ref().is_none_or(|entries| entries.is_empty()) {
+                        warnings.push(format!(
+                            "channel '{channel_id}' auto_grant_within_max_allowed has no max_allowed to grant"
+                        ));
+                    } else {
+                        warnings.push(format!(
+                            "channel '{channel_id}' auto-grants every max_allowed permission without prompting"
+                        ));
+                    }
+                }
                 let mut pre_auth = Vec::new();
                 let mut max_allowed = Vec::new();
                 if let Some(entries) = channel.pre_authorized.as_ref() {
@@ -370,6 +709,23 @@ impl Config {
                     warnings.push("api.max_body_bytes is very large".to_string());
                 }
             }
+            if let Some(limits) = &api.body_limits {
+                for (route, limit) in [
+                    ("prompt", limits.prompt),
+                    ("chat", limits.chat),
+                    ("schedules", limits.schedules),
+                ] {
+                    match limit {
+                        Some(0) => warnings.push(format!(
+                            "api.body_limits.{route} is 0; max_body_bytes applies instead"
+                        )),
+                        Some(limit) if limit > 50 * 1024 * 1024 => {
+                            warnings.push(format!("api.body_limits.{route} is very large"))
+                        }
+                        _ => {}
+                    }
+                }
+            }
             if let Some(auth) = &api.auth {
                 let mut seen = HashSet::new();
                 for key in &auth.api_keys {
@@ -381,6 +737,14 @@ impl Config {
                     }
                 }
             }
+            for entry in api.allowed_ips.iter().flatten() {
+                if let Err(err) = parse_ip_range(entry) {
+                    errors.push(format!("api.allowed_ips: {err}"));
+                }
+            }
+            if api.allowed_ips.as_ref().is_some_and(|ips| ips.is_empty()) {
+                warnings.push("api.allowed_ips is empty; every source IP is allowed".to_string());
+            }
             if let Some(rate) = &api.rate_limit
                 && let Some(limit) = rate.requests_per_minute
             {
@@ -422,6 +786,63 @@ impl Config {
                     warnings.push("whatsapp max_media_size_bytes is very large".to_string());
                 }
             }
+            if whatsapp.max_attachments_per_message == Some(0) {
+                errors.push("whatsapp max_attachments_per_message must be at least 1".to_string());
+            }
+            let account_senders = whatsapp
+                .accounts
+                .iter()
+                .flatten()
+                .filter_map(|account| account.allowed_senders.as_ref());
+            for sender in whatsapp
+                .allowed_senders
+                .iter()
+                .chain(account_senders)
+                .flatten()
+            {
+                if normalize_sender_id(sender).is_none() {
+                    warnings.push(format!(
+                        "whatsapp allowed_senders entry '{sender}' is not a phone number or \
+                         WhatsApp JID and will never match"
+                    ));
+                }
+            }
+            let mut account_ids = std::collections::HashSet::new();
+            let mut store_paths = std::collections::HashSet::new();
+            for account in whatsapp.accounts.iter().flatten() {
+                let valid_id = !account.id.is_empty()
+                    && account
+                        .id
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+                if !valid_id {
+                    errors.push(format!(
+                        "whatsapp account id '{}' must be non-empty letters, digits, '-' or '_'",
+                        account.id
+                    ));
+                } else if !account_ids.insert(account.id.as_str()) {
+                    errors.push(format!("duplicate whatsapp account id '{}'", account.id));
+                }
+                if let Some(path) = &account.store_path
+                    && !store_paths.insert(path.as_str())
+                {
+                    errors.push(format!(
+                        "whatsapp accounts share store_path '{path}'; each number needs its own"
+                    ));
+                }
+                if let Some(profile) = &account.profile
+                    && profile != "whatsapp"
+                    && !self
+                        .channels
+                        .as_ref()
+                        .is_some_and(|channels| channels.profiles.contains_key(profile))
+                {
+                    errors.push(format!(
+                        "whatsapp account '{}' uses unknown channel profile '{profile}'",
+                        account.id
+                    ));
+                }
+            }
             if let Some(retention) = whatsapp.media_retention_hours
                 && retention == 0
             {
@@ -445,6 +866,24 @@ impl Config {
             {
                 warnings.push("scheduler max_concurrent_per_user is 0".to_string());
             }
+            if let Some(max_turns) = scheduler.job_max_turns {
+                if max_turns == 0 {
+                    errors.push("scheduler job_max_turns must be >= 1".to_string());
+                } else if max_turns > 50 {
+                    warnings.push("scheduler job_max_turns is unusually high".to_string());
+                }
+            }
+            if let Some(after) = scheduler.progress_notify_after_secs {
+                if after == 0 {
+                    errors.push("scheduler progress_notify_after_secs must be >= 1".to_string());
+                } else if after >= scheduler.job_timeout_secs() {
+                    warnings.push(
+                        "scheduler progress_notify_after_secs is not below job_timeout_secs; \
+                         progress notifications will never be sent"
+                            .to_string(),
+                    );
+                }
+            }
         }
 
         if let Some(notifications) = &self.notifications {
@@ -463,10 +902,37 @@ impl Config {
             {
                 warnings.push("notifications max_records is 0".to_string());
             }
+            if let Some(workers) = notifications.workers
+                && workers == 0
+            {
+                warnings.push("notifications workers is 0; using 1".to_string());
+            }
+            let templates = notifications
+                .template
+                .iter()
+                .chain(notifications.templates.iter().flat_map(|map| map.values()));
+            for template in templates {
+                if template.trim().is_empty() {
+                    warnings.push("notifications template is empty".to_string());
+                }
+            }
+            for window in notifications.quiet_hours.iter().flatten() {
+                if let Err(err) = window.parse() {
+                    errors.push(format!("notifications {err}"));
+                }
+            }
         }
 
         let mut seen_ids = HashSet::new();
         if let Some(models) = &self.models {
+            if models.is_empty() {
+                errors.push("[[models]] is present but has no entries".to_string());
+            } else if self.default_model_id().is_none() {
+                warnings.push(format!(
+                    "routing.default_model not set; using first model '{}'",
+                    models[0].id
+                ));
+            }
             for model in models {
                 if model.id.trim().is_empty() {
                     errors.push("model id cannot be empty".to_string());
@@ -526,6 +992,12 @@ impl Config {
             {
                 warnings.push("multimodal max_image_size_bytes is 0".to_string());
             }
+            if multimodal.max_calls_per_minute == Some(0) {
+                warnings.push(
+                    "multimodal max_calls_per_minute is 0; vision calls are not rate limited"
+                        .to_string(),
+                );
+            }
         }
 
         if let Some(search) = &self.search {
@@ -574,6 +1046,7 @@ impl Config {
                         );
                     }
                 }
+                "brave" => {}
                 other => {
                     errors.push(format!("unsupported search provider '{other}'"));
                 }
@@ -605,13 +1078,113 @@ impl Config {
             {
                 warnings.push("search.max_snippet_chars is 0".to_string());
             }
+            if let Some(max_total_chars) = search.max_total_chars
+                && max_total_chars == 0
+            {
+                warnings.push("search.max_total_chars is 0".to_string());
+            }
+        }
+
+        if self.tools().dry_run() {
+            warnings.push("tools.dry_run is enabled; mutating tools will not execute".to_string());
+        }
+        let tools = self.tools();
+        let known_tools = BUILTIN_TOOL_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain(tools.external().into_iter().map(|external| external.name))
+            .collect::<HashSet<_>>();
+        for (key, names) in [("enabled", &tools.enabled), ("disabled", &tools.disabled)] {
+            for name in names.iter().flatten() {
+                if !known_tools.contains(name.trim()) {
+                    warnings.push(format!("tools.{key} lists unknown tool '{name}'"));
+                }
+            }
+        }
+        for (name, retry) in tools.retry() {
+            if !known_tools.contains(name.trim()) {
+                warnings.push(format!("tools.retry lists unknown tool '{name}'"));
+            }
+            if retry.max_attempts() == 0 {
+                errors.push(format!("tools.retry.{name}.max_attempts must be at least 1"));
+            }
+        }
+        if let (Some(enabled), Some(disabled)) = (&tools.enabled, &tools.disabled) {
+            for name in enabled.iter().filter(|name| disabled.contains(name)) {
+                warnings.push(format!(
+                    "tools.enabled and tools.disabled both list '{name}'; it will be disabled"
+                ));
+            }
+        }
+        if tools.enabled.as_ref().is_some_and(|enabled| enabled.is_empty()) {
+            warnings.push("tools.enabled is empty; no tools will be registered".to_string());
+        }
+        for (index, policy) in tools.policies().iter().enumerate() {
+            if policy.tool.trim().is_empty() {
+                errors.push(format!("tools.policies[{index}] tool cannot be empty"));
+            }
+            if crate::kernel::tool_policy::parse_decision(&policy.decision).is_none() {
+                errors.push(format!(
+                    "tools.policies[{index}] has unsupported decision '{}'",
+                    policy.decision
+                ));
+            }
+            if let Some(path) = &policy.path
+                && !path.is_empty()
+                && !path.starts_with('/')
+            {
+                errors.push(format!(
+                    "tools.policies[{index}] path '{path}' must be a JSON pointer like '/command'"
+                ));
+            }
+        }
+        let mut external_names = HashSet::new();
+        for external in self.tools().external() {
+            let name = external.name.trim();
+            if name.is_empty() {
+                errors.push("tools.external name cannot be empty".to_string());
+                continue;
+            }
+            if !external_names.insert(name.to_string()) {
+                errors.push(format!("tools.external '{name}' is declared more than once"));
+            }
+            if external.command.trim().is_empty() {
+                errors.push(format!("tools.external '{name}' command cannot be empty"));
+            }
+            if let Some(schema) = &external.schema
+                && !schema.is_object()
+            {
+                errors.push(format!("tools.external '{name}' schema must be a table"));
+            }
+            if let Some(schema) = &external.output_schema
+                && !schema.is_object()
+            {
+                errors.push(format!("tools.external '{name}' output_schema must be a table"));
+            }
+            let permissions = external.permissions.clone().unwrap_or_default();
+            if permissions.is_empty() {
+                errors.push(format!(
+                    "tools.external '{name}' must declare at least one permission"
+                ));
+            }
+            for value in permissions {
+                if let Err(err) = parse_permission_with_base(&value, &base_dir) {
+                    errors.push(format!(
+                        "tools.external '{name}' has invalid permission '{value}': {err}"
+                    ));
+                }
+            }
+            if external.timeout_secs == Some(0) {
+                warnings.push(format!("tools.external '{name}' timeout_secs is 0"));
+            }
         }
 
         if let Some(default_model) = self.default_model_id() {
             if let Some(models) = &self.models {
                 if !models.iter().any(|model| model.id == default_model) {
                     warnings.push(format!(
-                        "routing.default_model '{default_model}' not found in models"
+                        "routing.default_model '{default_model}' not found in models; \
+                         using first model"
                     ));
                 }
             } else {
@@ -687,11 +1260,11 @@ impl Config {
 
         if let Some(search) = &self.search {
             let provider = search.provider.as_deref().unwrap_or("google");
-            if provider.trim().eq_ignore_ascii_case("google") {
+            if let Some(default_env) = default_search_api_key_env(provider) {
                 let env_name = search
                     .api_key_env
                     .as_deref()
-                    .unwrap_or("GOOGLE_CSE_API_KEY")
+                    .unwrap_or(default_env)
                     .to_string();
                 if checked_envs.insert(env_name.clone()) && std::env::var(&env_name).is_err() {
                     errors.push(format!("missing search API key in env '{env_name}'"));
@@ -699,18 +1272,11 @@ impl Config {
             }
         }
 
-        if errors.is_empty() {
-            Ok(ConfigValidation { warnings })
-        } else {
-            Err(anyhow::anyhow!(format!(
-                "config validation failed: {}",
-                errors.join("; ")
-            )))
-        }
+        ConfigValidation { errors, warnings }
     }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct PermissionsConfig {
     pub filesystem: Option<FilesystemPermissions>,
     pub network: Option<NetworkPermissions>,
@@ -719,21 +1285,21 @@ pub struct PermissionsConfig {
     pub tool_limits: Option<ToolLimitsConfig>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct FilesystemPermissions {
     pub read_paths: Vec<String>,
     pub write_paths: Vec<String>,
     pub jail_root: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct NetworkPermissions {
     pub allowed_domains: Vec<String>,
     pub max_response_bytes: Option<u64>,
     pub max_response_chars: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ShellPermissions {
     pub allowed_commands: Vec<String>,
     pub runner: Option<String>,
@@ -743,7 +1309,7 @@ pub struct ShellPermissions {
     pub policy: Option<ShellPolicyConfig>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ShellPolicyConfig {
     pub default_risk: Option<String>,
     pub deny_patterns: Option<Vec<String>>,
@@ -751,12 +1317,12 @@ pub struct ShellPolicyConfig {
     pub safe_commands: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct SchedulePermissions {
     pub allowed_actions: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ToolLimitsConfig {
     pub default_timeout_secs: Option<u64>,
     pub soft_timeout_ratio: Option<f64>,
@@ -768,39 +1334,167 @@ pub struct ToolLimitsConfig {
     pub multimodal_timeout_secs: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct ToolsConfig {
+    /// Log mutating tool calls and return a simulated result instead of executing them.
+    #[schemars(default = "schema_defaults::tools_dry_run")]
+    pub dry_run: Option<bool>,
+    /// Register the `echo` debug tool, which returns its input unchanged.
+    #[schemars(default = "schema_defaults::tools_debug_enabled")]
+    pub debug_enabled: Option<bool>,
+    /// Fail tool calls whose output breaks the tool's declared output schema (default: warn).
+    #[schemars(default = "schema_defaults::tools_strict_output_schemas")]
+    pub strict_output_schemas: Option<bool>,
+    pub enabled: Option<Vec<String>>,
+    pub disabled: Option<Vec<String>>,
+    pub policies: Option<Vec<ToolPolicyConfig>>,
+    pub retry: Option<HashMap<String, ToolRetryConfig>>,
+    pub external: Option<Vec<ExternalToolConfig>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct ToolRetryConfig {
+    #[schemars(default = "schema_defaults::tool_retry_max_attempts")]
+    pub max_attempts: Option<u32>,
+    #[schemars(default = "schema_defaults::tool_retry_base_backoff_ms")]
+    pub base_backoff_ms: Option<u64>,
+    #[schemars(default = "schema_defaults::tool_retry_jitter_ms")]
+    pub jitter_ms: Option<u64>,
+}
+
+impl ToolRetryConfig {
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.unwrap_or(3)
+    }
+
+    pub fn base_backoff_ms(&self) -> u64 {
+        self.base_backoff_ms.unwrap_or(250)
+    }
+
+    pub fn jitter_ms(&self) -> u64 {
+        self.jitter_ms.unwrap_or(100)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct ToolPolicyConfig {
+    pub tool: String,
+    pub path: Option<String>,
+    pub contains: Option<String>,
+    pub decision: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct ExternalToolConfig {
+    pub name: String,
+    pub description: Option<String>,
+    pub command: String,
+    pub args: Option<Vec<String>>,
+    pub schema: Option<serde_json::Value>,
+    /// JSON Schema the command's stdout must match; see `tools.strict_output_schemas`.
+    pub output_schema: Option<serde_json::Value>,
+    pub permissions: Option<Vec<String>>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct SchedulerConfig {
+    #[schemars(default = "schema_defaults::scheduler_enabled")]
     pub enabled: Option<bool>,
+    /// Seconds between checks for due jobs.
+    #[schemars(default = "schema_defaults::scheduler_tick_interval_secs")]
     pub tick_interval_secs: Option<u64>,
+    #[schemars(default = "schema_defaults::scheduler_max_concurrent_jobs")]
     pub max_concurrent_jobs: Option<usize>,
+    #[schemars(default = "schema_defaults::scheduler_max_concurrent_per_user")]
     pub max_concurrent_per_user: Option<usize>,
+    #[schemars(default = "schema_defaults::scheduler_max_jobs_per_user")]
     pub max_jobs_per_user: Option<u32>,
+    /// Jobs one user may create per `window_duration_secs`.
+    #[schemars(default = "schema_defaults::scheduler_max_jobs_per_window")]
     pub max_jobs_per_window: Option<u32>,
+    #[schemars(default = "schema_defaults::scheduler_window_duration_secs")]
     pub window_duration_secs: Option<u64>,
+    #[schemars(default = "schema_defaults::scheduler_job_timeout_secs")]
     pub job_timeout_secs: Option<u64>,
+    /// Cap on the jittered exponential retry delay after failures.
+    #[schemars(default = "schema_defaults::scheduler_max_backoff_secs")]
     pub max_backoff_secs: Option<u64>,
+    /// Agent tool-call rounds per job run; defaults to `agent.max_tool_rounds`.
+    #[schemars(default = "schema_defaults::scheduler_job_max_turns")]
+    pub job_max_turns: Option<usize>,
+    /// Warn each tick while a due job has waited this long.
+    #[schemars(default = "schema_defaults::scheduler_lag_warn_secs")]
+    pub lag_warn_secs: Option<u64>,
+    pub progress_notify_after_secs: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct NotificationsConfig {
+    #[schemars(default = "schema_defaults::notifications_enabled")]
     pub enabled: Option<bool>,
+    #[schemars(default = "schema_defaults::notifications_max_attempts")]
     pub max_attempts: Option<usize>,
+    #[schemars(default = "schema_defaults::notifications_base_backoff_ms")]
     pub base_backoff_ms: Option<u64>,
+    #[schemars(default = "schema_defaults::notifications_max_backoff_ms")]
     pub max_backoff_ms: Option<u64>,
+    /// Notification records kept in memory and, for failures, in the sessions database.
+    #[schemars(default = "schema_defaults::notifications_max_records")]
     pub max_records: Option<usize>,
+    /// Drop identical notifications (same channel, user and message) within this window;
+    /// 0 disables.
+    #[schemars(default = "schema_defaults::notifications_dedup_window_secs")]
+    pub dedup_window_secs: Option<u64>,
+    /// Notifications delivered in parallel; ones for the same recipient still go out in
+    /// order.
+    #[schemars(default = "schema_defaults::notifications_workers")]
+    pub workers: Option<usize>,
+    pub template: Option<String>,
+    pub templates: Option<HashMap<String, String>>,
+    pub quiet_hours: Option<Vec<QuietHoursConfig>>,
+}
+
+/// A daily window in which notifications are held and sent together when it ends.
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct QuietHoursConfig {
+    pub start: String,
+    pub end: String,
+    pub timezone: Option<String>,
+    pub users: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct MemoryConfig {
+    #[schemars(default = "schema_defaults::memory_enable_user_memories")]
     pub enable_user_memories: Option<bool>,
+    /// Estimated tokens of prompt context; 0 means no budget. Always capped at 3/4 of the
+    /// smallest `context_window_tokens` in `[[models]]`, else 128000.
+    #[schemars(default = "schema_defaults::memory_context_budget_tokens")]
     pub context_budget_tokens: Option<u32>,
+    /// Most recent session messages included in the prompt context.
+    #[schemars(default = "schema_defaults::memory_max_session_messages")]
     pub max_session_messages: Option<usize>,
+    #[schemars(default = "schema_defaults::memory_max_user_memories")]
     pub max_user_memories: Option<usize>,
+    #[schemars(default = "schema_defaults::memory_include_summary_on_truncation")]
     pub include_summary_on_truncation: Option<bool>,
+    /// Replay stored tool-call transcripts in the prompt context.
+    #[schemars(default = "schema_defaults::memory_include_tool_messages")]
     pub include_tool_messages: Option<bool>,
+    pub max_stored_messages: Option<usize>,
+    pub global_access: Option<Vec<GlobalMemoryAccessConfig>>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct GlobalMemoryAccessConfig {
+    pub channel: String,
+    pub users: Option<Vec<String>>,
+    pub write: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ModelConfig {
     pub id: String,
     pub provider: Option<String>,
@@ -809,26 +1503,90 @@ pub struct ModelConfig {
     pub api_key_env: Option<String>,
     pub system_prompt: Option<String>,
     pub max_turns: Option<usize>,
+    /// Tokens the model accepts per request; caps `memory.context_budget_tokens`.
+    pub context_window_tokens: Option<u32>,
+}
+
+/// Per-subsystem database files, so write-heavy scheduler state can live apart from
+/// session reads. Each file gets the full schema when first opened.
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct DataConfig {
+    /// Sessions, messages, memories and usage; relative paths resolve under `data_dir`.
+    #[schemars(default = "schema_defaults::data_sessions_db")]
+    pub sessions_db: Option<String>,
+    /// Schedules and executions.
+    #[schemars(default = "schema_defaults::data_scheduler_db")]
+    pub scheduler_db: Option<String>,
+    /// WhatsApp device state; `whatsapp.store_path` wins if set.
+    #[schemars(default = "schema_defaults::data_whatsapp_db")]
+    pub whatsapp_db: Option<String>,
+    pub sqlite: Option<SqliteConfig>,
+}
+
+/// Pragmas applied to every SQLite connection picobot opens.
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct SqliteConfig {
+    /// `wal` (default), `delete`, `truncate`, `persist`, or `memory`.
+    #[schemars(default = "schema_defaults::sqlite_journal_mode")]
+    pub journal_mode: Option<String>,
+    /// `normal` (default), `full`, `extra`, or `off`.
+    #[schemars(default = "schema_defaults::sqlite_synchronous")]
+    pub synchronous: Option<String>,
+    /// How long a writer waits on a locked database.
+    #[schemars(default = "schema_defaults::sqlite_busy_timeout_ms")]
+    pub busy_timeout_ms: Option<u64>,
+}
+
+impl SqliteConfig {
+    pub fn pragmas(&self) -> Result<crate::session::db::SqlitePragmas, String> {
+        crate::session::db::SqlitePragmas::new(
+            self.journal_mode.as_deref(),
+            self.synchronous.as_deref(),
+            self.busy_timeout_ms,
+        )
+    }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct RoutingConfig {
     pub default_model: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct AgentConfig {
+    /// Tool-call rounds per turn before the agent must answer; channels and models can
+    /// override it.
+    #[schemars(default = "schema_defaults::agent_max_tool_rounds")]
+    pub max_tool_rounds: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ChannelsConfig {
     pub profiles: HashMap<String, ChannelConfig>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ApiConfig {
     pub auth: Option<ApiAuthConfig>,
     pub rate_limit: Option<ApiRateLimitConfig>,
+    /// Largest request body accepted, in bytes.
+    #[schemars(default = "schema_defaults::api_max_body_bytes")]
     pub max_body_bytes: Option<u64>,
+    /// Per-route overrides of `max_body_bytes`.
+    pub body_limits: Option<ApiBodyLimitsConfig>,
+    /// Allow binding a non-localhost address without `api.auth` keys.
+    #[schemars(default = "schema_defaults::api_allow_insecure_external")]
+    pub allow_insecure_external: Option<bool>,
+    pub allowed_ips: Option<Vec<String>>,
+    pub admin_identities: Option<Vec<String>>,
+    /// Identities that may only watch session activity; prompts from them are refused.
+    pub observer_identities: Option<Vec<String>>,
+    /// How long an `Idempotency-Key` response is replayed.
+    #[schemars(default = "schema_defaults::api_idempotency_ttl_secs")]
+    pub idempotency_ttl_secs: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ApiAuthConfig {
     pub api_keys: Vec<String>,
 }
@@ -839,30 +1597,164 @@ impl ApiAuthConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+/// Request body caps in bytes for individual API routes; unset routes use `max_body_bytes`.
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct ApiBodyLimitsConfig {
+    /// `POST /v1/prompt`.
+    pub prompt: Option<u64>,
+    /// `POST /v1/chat`, which may carry base64 attachments.
+    pub chat: Option<u64>,
+    /// `POST /v1/schedules`.
+    pub schedules: Option<u64>,
+}
+
+impl ApiBodyLimitsConfig {
+    /// Body cap for `route` (`prompt`, `chat` or `schedules`), or `default` when unset.
+    pub fn limit(&self, route: &str, default: usize) -> usize {
+        let configured = match route {
+            "prompt" => self.prompt,
+            "chat" => self.chat,
+            "schedules" => self.schedules,
+            _ => None,
+        };
+        match configured {
+            Some(0) | None => default,
+            Some(value) => value as usize,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ApiRateLimitConfig {
     pub requests_per_minute: Option<u32>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct ChannelConfig {
     pub pre_authorized: Option<Vec<String>>,
     pub max_allowed: Option<Vec<String>>,
+    #[schemars(default = "schema_defaults::channel_allow_user_prompts")]
     pub allow_user_prompts: Option<bool>,
+    #[schemars(default = "schema_defaults::channel_prompt_timeout_secs")]
     pub prompt_timeout_secs: Option<u64>,
+    /// `deny` (default) or `allow_once` when a permission prompt goes unanswered.
+    pub prompt_timeout_action: Option<String>,
+    #[schemars(default = "schema_defaults::channel_auto_grant_within_max_allowed")]
+    pub auto_grant_within_max_allowed: Option<bool>,
+    pub jail_root: Option<String>,
+    pub working_dir: Option<String>,
+    /// Agent turn budget for this channel, overriding `agent.max_tool_rounds`.
+    pub max_turns: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+/// Normalizes a configured sender to a digit-only number (`+1 (555) 123-4567` becomes
+/// `15551234567`) or a `number@server` JID without device suffix. Returns `None` for entries
+/// that can never match a WhatsApp sender.
+pub fn normalize_sender_id(entry: &str) -> Option<String> {
+    let entry = entry.trim();
+    if let Some((user, server)) = entry.split_once('@') {
+        let user = user.split(':').next().unwrap_or(user);
+        let server = server.trim().to_ascii_lowercase();
+        let valid_server = !server.is_empty()
+            && server
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '.');
+        if user.is_empty() || !user.chars().all(|ch| ch.is_ascii_digit()) || !valid_server {
+            return None;
+        }
+        return Some(format!("{user}@{server}"));
+    }
+    let digits: String = entry
+        .chars()
+        .filter(|ch| !matches!(ch, ' ' | '+' | '-' | '(' | ')' | '.'))
+        .collect();
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    Some(digits)
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct WhatsappConfig {
     pub enabled: Option<bool>,
     pub store_path: Option<String>,
     pub allowed_senders: Option<Vec<String>>,
+    /// Inbound messages handled at once across all chats.
+    #[schemars(default = "schema_defaults::whatsapp_max_concurrent_messages")]
     pub max_concurrent_messages: Option<usize>,
+    /// Larger media attachments are not downloaded.
+    #[schemars(default = "schema_defaults::whatsapp_max_media_size_bytes")]
     pub max_media_size_bytes: Option<u64>,
+    /// Media items downloaded from one message; the rest are skipped and noted in the prompt.
+    #[schemars(default = "schema_defaults::whatsapp_max_attachments_per_message")]
+    pub max_attachments_per_message: Option<usize>,
+    /// Downloaded media older than this is deleted.
+    #[schemars(default = "schema_defaults::whatsapp_media_retention_hours")]
     pub media_retention_hours: Option<u64>,
+    /// How long an inbound message waits for a free handler slot before being shed.
+    #[schemars(default = "schema_defaults::whatsapp_busy_timeout_secs")]
+    pub busy_timeout_secs: Option<u64>,
+    /// When false, messages wait for a slot indefinitely instead of being shed.
+    #[schemars(default = "schema_defaults::whatsapp_shed_when_busy")]
+    pub shed_when_busy: Option<bool>,
+    pub messages: Option<WhatsappMessagesConfig>,
+    /// Numbers served by this process; when unset the `[whatsapp]` table is the one account.
+    pub accounts: Option<Vec<WhatsappAccountConfig>>,
+}
+
+/// One `[[whatsapp.accounts]]` entry. Settings not listed here (concurrency, media, replies)
+/// come from `[whatsapp]` and apply to every account.
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct WhatsappAccountConfig {
+    pub id: String,
+    pub store_path: Option<String>,
+    /// Falls back to `whatsapp.allowed_senders`.
+    pub allowed_senders: Option<Vec<String>>,
+    /// Channel profile name for this account's permissions; defaults to `whatsapp`.
+    pub profile: Option<String>,
+}
+
+/// User-facing WhatsApp replies. `{error}` expands to the raw error; omit it to keep
+/// internal details out of chats (the full error is always logged).
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
+pub struct WhatsappMessagesConfig {
+    #[schemars(default = "schema_defaults::whatsapp_messages_session_error")]
+    pub session_error: Option<String>,
+    #[schemars(default = "schema_defaults::whatsapp_messages_agent_error")]
+    pub agent_error: Option<String>,
+    #[schemars(default = "schema_defaults::whatsapp_messages_prompt_error")]
+    pub prompt_error: Option<String>,
+    #[schemars(default = "schema_defaults::whatsapp_messages_busy")]
+    pub busy: Option<String>,
+}
+
+impl WhatsappMessagesConfig {
+    pub fn session_error(&self) -> &str {
+        self.session_error
+            .as_deref()
+            .unwrap_or("Sorry, session error: {error}")
+    }
+
+    pub fn agent_error(&self) -> &str {
+        self.agent_error
+            .as_deref()
+            .unwrap_or("Sorry, agent error: {error}")
+    }
+
+    pub fn prompt_error(&self) -> &str {
+        self.prompt_error
+            .as_deref()
+            .unwrap_or("Sorry, something went wrong: {error}")
+    }
+
+    pub fn busy(&self) -> &str {
+        self.busy
+            .as_deref()
+            .unwrap_or("I'm handling too many messages right now. Please try again in a minute.")
+    }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct MultimodalConfig {
     pub model_id: Option<String>,
     pub provider: Option<String>,
@@ -872,6 +1764,8 @@ pub struct MultimodalConfig {
     pub system_prompt: Option<String>,
     pub max_media_size_bytes: Option<u64>,
     pub max_image_size_bytes: Option<u64>,
+    /// Per-user cap on `multimodal_looker` model calls in any 60 second window; 0 disables it.
+    pub max_calls_per_minute: Option<u32>,
 }
 
 impl MultimodalConfig {
@@ -883,9 +1777,13 @@ impl MultimodalConfig {
         self.max_image_size_bytes
             .unwrap_or_else(|| self.max_media_size_bytes())
     }
+
+    pub fn max_calls_per_minute(&self) -> u32 {
+        self.max_calls_per_minute.unwrap_or(20)
+    }
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct VisionConfig {
     pub model_id: Option<String>,
     pub provider: Option<String>,
@@ -895,9 +1793,11 @@ pub struct VisionConfig {
     pub system_prompt: Option<String>,
     pub max_media_size_bytes: Option<u64>,
     pub max_image_size_bytes: Option<u64>,
+    /// Per-user cap on `multimodal_looker` model calls in any 60 second window; 0 disables it.
+    pub max_calls_per_minute: Option<u32>,
 }
 
-#[derive(Debug, Deserialize, Default, Clone)]
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct SearchConfig {
     pub provider: Option<String>,
     pub api_key_env: Option<String>,
@@ -907,6 +1807,8 @@ pub struct SearchConfig {
     pub allow_private_base_urls: Option<bool>,
     pub max_results: Option<usize>,
     pub max_snippet_chars: Option<usize>,
+    /// Cap on the combined title/url/snippet characters returned per search.
+    pub max_total_chars: Option<usize>,
     pub searxng_engines: Option<String>,
     pub searxng_categories: Option<String>,
     pub searxng_safesearch: Option<u8>,
@@ -923,6 +1825,7 @@ impl From<VisionConfig> for MultimodalConfig {
             system_prompt: value.system_prompt,
             max_media_size_bytes: value.max_media_size_bytes,
             max_image_size_bytes: value.max_image_size_bytes,
+            max_calls_per_minute: value.max_calls_per_minute,
         }
     }
 }
@@ -935,6 +1838,17 @@ impl ChannelConfig {
     pub fn prompt_timeout_secs(&self) -> u64 {
         self.prompt_timeout_secs.unwrap_or(30)
     }
+
+    pub fn prompt_timeout_action(&self) -> PromptTimeoutAction {
+        self.prompt_timeout_action
+            .as_deref()
+            .and_then(PromptTimeoutAction::parse)
+            .unwrap_or_default()
+    }
+
+    pub fn auto_grant_within_max_allowed(&self) -> bool {
+        self.auto_grant_within_max_allowed.unwrap_or(false)
+    }
 }
 
 impl ApiConfig {
@@ -952,6 +1866,57 @@ impl ApiConfig {
             Some(value) => value as usize,
         }
     }
+
+    pub fn body_limits(&self) -> ApiBodyLimitsConfig {
+        self.body_limits.clone().unwrap_or_default()
+    }
+
+    pub fn allow_insecure_external(&self) -> bool {
+        self.allow_insecure_external.unwrap_or(false)
+    }
+
+    /// How long a response sent for an `Idempotency-Key` is replayed to retries.
+    pub fn idempotency_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.idempotency_ttl_secs.unwrap_or(24 * 60 * 60))
+    }
+
+    /// API identities (as produced by `api.auth.api_keys`) allowed to call `/v1/admin/*`.
+    pub fn admin_identities(&self) -> Vec<String> {
+        self.admin_identities.clone().unwrap_or_default()
+    }
+
+    pub fn observer_identities(&self) -> Vec<String> {
+        self.observer_identities.clone().unwrap_or_default()
+    }
+
+    /// Source IP ranges allowed to reach the API; empty allows everyone. Invalid entries are
+    /// reported by validation and skipped here.
+    pub fn allowed_ips(&self) -> Vec<ipnet::IpNet> {
+        self.allowed_ips
+            .iter()
+            .flatten()
+            .filter_map(|entry| parse_ip_range(entry).ok())
+            .flatten()
+            .collect()
+    }
+}
+
+/// Parses a CIDR (`10.0.0.0/8`), a bare address, or `*` for every address.
+fn parse_ip_range(entry: &str) -> Result<Vec<ipnet::IpNet>, String> {
+    let entry = entry.trim();
+    if entry == "*" {
+        return Ok(vec![
+            ipnet::IpNet::V4(ipnet::Ipv4Net::default()),
+            ipnet::IpNet::V6(ipnet::Ipv6Net::default()),
+        ]);
+    }
+    if let Ok(net) = entry.parse::<ipnet::IpNet>() {
+        return Ok(vec![net.trunc()]);
+    }
+    entry
+        .parse::<std::net::IpAddr>()
+        .map(|ip| vec![ipnet::IpNet::from(ip)])
+        .map_err(|_| format!("'{entry}' is not an IP address or CIDR range"))
 }
 
 impl ApiRateLimitConfig {
@@ -965,11 +1930,97 @@ impl ApiRateLimitConfig {
 }
 
 impl MemoryConfig {
+    pub fn enable_user_memories(&self) -> bool {
+        self.enable_user_memories.unwrap_or(true)
+    }
+
+    pub fn context_budget_tokens(&self) -> u32 {
+        self.context_budget_tokens.unwrap_or(4000)
+    }
+
+    pub fn max_session_messages(&self) -> usize {
+        self.max_session_messages.unwrap_or(20)
+    }
+
+    pub fn max_user_memories(&self) -> usize {
+        self.max_user_memories.unwrap_or(50)
+    }
+
+    pub fn include_summary_on_truncation(&self) -> bool {
+        self.include_summary_on_truncation.unwrap_or(true)
+    }
+
     pub fn include_tool_messages(&self) -> bool {
         self.include_tool_messages.unwrap_or(true)
     }
+
+    /// Per-session message cap before old messages are compacted; `None` keeps everything.
+    pub fn max_stored_messages(&self) -> Option<usize> {
+        self.max_stored_messages.filter(|max| *max > 0)
+    }
+
+    pub fn global_access(&self) -> Vec<GlobalMemoryGrant> {
+        self.global_access
+            .iter()
+            .flatten()
+            .map(|access| GlobalMemoryGrant {
+                channel: access.channel.trim().to_string(),
+                users: access.users.clone().unwrap_or_default(),
+                write: access.write.unwrap_or(false),
+            })
+            .collect()
+    }
+}
+
+impl ToolsConfig {
+    pub fn dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
+    }
+
+    pub fn debug_enabled(&self) -> bool {
+        self.debug_enabled.unwrap_or(false)
+    }
+
+    pub fn strict_output_schemas(&self) -> bool {
+        self.strict_output_schemas.unwrap_or(false)
+    }
+
+    pub fn external(&self) -> Vec<ExternalToolConfig> {
+        self.external.clone().unwrap_or_default()
+    }
+
+    pub fn policies(&self) -> Vec<ToolPolicyConfig> {
+        self.policies.clone().unwrap_or_default()
+    }
+
+    pub fn retry(&self) -> HashMap<String, ToolRetryConfig> {
+        self.retry.clone().unwrap_or_default()
+    }
+
+    /// Whether a tool should be registered: listed in `enabled` (when set) and not `disabled`.
+    pub fn is_tool_enabled(&self, name: &str) -> bool {
+        let listed = |names: &Option<Vec<String>>| {
+            names
+                .as_ref()
+                .map(|names| names.iter().any(|entry| entry.trim() == name))
+        };
+        listed(&self.enabled).unwrap_or(true) && !listed(&self.disabled).unwrap_or(false)
+    }
 }
 
+pub const BUILTIN_TOOL_NAMES: &[&str] = &[
+    "filesystem",
+    "shell",
+    "http_fetch",
+    "schedule",
+    "notify",
+    "say",
+    "memory",
+    "web_search",
+    "multimodal_looker",
+    "echo",
+];
+
 impl SchedulerConfig {
     pub fn enabled(&self) -> bool {
         self.enabled.unwrap_or(false)
@@ -1006,6 +2057,22 @@ impl SchedulerConfig {
     pub fn max_backoff_secs(&self) -> u64 {
         self.max_backoff_secs.unwrap_or(3600)
     }
+
+    /// Lag past a job's due time at which each tick logs a backlog warning.
+    pub fn lag_warn_secs(&self) -> u64 {
+        self.lag_warn_secs.unwrap_or(60)
+    }
+
+    /// Run time after which a job with a channel gets one "still working" notification;
+    /// unset disables progress notifications.
+    pub fn progress_notify_after(&self) -> Option<std::time::Duration> {
+        self.progress_notify_after_secs.map(std::time::Duration::from_secs)
+    }
+
+    /// Agent turn budget for scheduled jobs, falling back to `agent.max_tool_rounds`.
+    pub fn job_max_turns(&self, max_tool_rounds: usize) -> usize {
+        self.job_max_turns.unwrap_or(max_tool_rounds)
+    }
 }
 
 impl NotificationsConfig {
@@ -1028,6 +2095,45 @@ impl NotificationsConfig {
     pub fn max_records(&self) -> usize {
         self.max_records.unwrap_or(1000)
     }
+
+    pub fn dedup_window_secs(&self) -> u64 {
+        self.dedup_window_secs.unwrap_or(0)
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.unwrap_or(1)
+    }
+
+    /// Parsed `quiet_hours` windows, in config order; invalid entries are skipped (validation
+    /// reports them).
+    pub fn quiet_hours(&self) -> Vec<crate::notifications::quiet_hours::QuietHours> {
+        self.quiet_hours
+            .iter()
+            .flatten()
+            .filter_map(|window| window.parse().ok())
+            .collect()
+    }
+
+    /// Template for job notifications on `channel_id`: the per-channel entry, then the
+    /// global `template`. `None` means the raw job result is sent.
+    pub fn template_for(&self, channel_id: &str) -> Option<&str> {
+        self.templates
+            .as_ref()
+            .and_then(|templates| templates.get(channel_id))
+            .or(self.template.as_ref())
+            .map(String::as_str)
+    }
+}
+
+impl QuietHoursConfig {
+    pub fn parse(&self) -> Result<crate::notifications::quiet_hours::QuietHours, String> {
+        crate::notifications::quiet_hours::QuietHours::parse(
+            &self.start,
+            &self.end,
+            self.timezone.as_deref(),
+            self.users.clone(),
+        )
+    }
 }
 
 impl WhatsappConfig {
@@ -1039,13 +2145,197 @@ impl WhatsappConfig {
         self.max_media_size_bytes.unwrap_or(10 * 1024 * 1024)
     }
 
+    pub fn max_attachments_per_message(&self) -> usize {
+        self.max_attachments_per_message.unwrap_or(5)
+    }
+
     pub fn media_retention_hours(&self) -> u64 {
         self.media_retention_hours.unwrap_or(24)
     }
+
+    pub fn messages(&self) -> WhatsappMessagesConfig {
+        self.messages.clone().unwrap_or_default()
+    }
+
+    /// Longest wait for a handler slot, or `None` to queue messages indefinitely.
+    pub fn busy_timeout(&self) -> Option<std::time::Duration> {
+        self.shed_when_busy
+            .unwrap_or(true)
+            .then(|| std::time::Duration::from_secs(self.busy_timeout_secs.unwrap_or(30)))
+    }
+}
+
+/// Schema `default`s for config fields, named `<table>_<field>`. Each reads the accessor's
+/// own fallback, so `config-schema` and `--example` report what an unset field really does.
+mod schema_defaults {
+    use super::*;
+
+    macro_rules! defaults {
+        ($($name:ident: $ty:ty = $value:expr;)*) => {
+            $(pub(super) fn $name() -> Option<$ty> { Some($value) })*
+        };
+    }
+
+    fn file_name(path: PathBuf) -> String {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn config() -> Config {
+        Config::default()
+    }
+
+    fn pragmas() -> crate::session::db::SqlitePragmas {
+        crate::session::db::SqlitePragmas::default()
+    }
+
+    defaults! {
+        provider: String = config().provider().to_string();
+        model: String = config().model().to_string();
+        system_prompt: String = config().system_prompt().to_string();
+        provider_request_timeout_secs: u64 = config().provider_request_timeout().as_secs();
+        provider_max_retries: usize = config().provider_max_retries();
+        probe_base_url: bool = config().probe_base_url();
+        bind: String = config().bind().to_string();
+        agent_max_tool_rounds: usize = config().max_tool_rounds();
+        data_sessions_db: String = file_name(config().sessions_db_path());
+        data_scheduler_db: String = file_name(config().scheduler_db_path());
+        data_whatsapp_db: String = file_name(config().whatsapp_db_path());
+        sqlite_journal_mode: String = pragmas().journal_mode().to_ascii_lowercase();
+        sqlite_synchronous: String = pragmas().synchronous().to_ascii_lowercase();
+        sqlite_busy_timeout_ms: u64 = pragmas().busy_timeout().as_millis() as u64;
+        api_max_body_bytes: u64 = ApiConfig::default().max_body_bytes() as u64;
+        api_allow_insecure_external: bool = ApiConfig::default().allow_insecure_external();
+        api_idempotency_ttl_secs: u64 = ApiConfig::default().idempotency_ttl().as_secs();
+        scheduler_enabled: bool = config().scheduler().enabled();
+        scheduler_tick_interval_secs: u64 = config().scheduler().tick_interval_secs();
+        scheduler_max_concurrent_jobs: usize = config().scheduler().max_concurrent_jobs();
+        scheduler_max_concurrent_per_user: usize = config().scheduler().max_concurrent_per_user();
+        scheduler_max_jobs_per_user: u32 = config().scheduler().max_jobs_per_user();
+        scheduler_max_jobs_per_window: u32 = config().scheduler().max_jobs_per_window();
+        scheduler_window_duration_secs: u64 = config().scheduler().window_duration_secs();
+        scheduler_job_timeout_secs: u64 = config().scheduler().job_timeout_secs();
+        scheduler_max_backoff_secs: u64 = config().scheduler().max_backoff_secs();
+        scheduler_job_max_turns: usize =
+            config().scheduler().job_max_turns(config().max_tool_rounds());
+        scheduler_lag_warn_secs: u64 = config().scheduler().lag_warn_secs();
+        notifications_enabled: bool = NotificationsConfig::default().enabled();
+        notifications_max_attempts: usize = NotificationsConfig::default().max_attempts();
+        notifications_base_backoff_ms: u64 = NotificationsConfig::default().base_backoff_ms();
+        notifications_max_backoff_ms: u64 = NotificationsConfig::default().max_backoff_ms();
+        notifications_max_records: usize = NotificationsConfig::default().max_records();
+        notifications_dedup_window_secs: u64 = NotificationsConfig::default().dedup_window_secs();
+        notifications_workers: usize = NotificationsConfig::default().workers();
+        memory_enable_user_memories: bool = MemoryConfig::default().enable_user_memories();
+        memory_context_budget_tokens: u32 = MemoryConfig::default().context_budget_tokens();
+        memory_max_session_messages: usize = MemoryConfig::default().max_session_messages();
+        memory_max_user_memories: usize = MemoryConfig::default().max_user_memories();
+        memory_include_summary_on_truncation: bool =
+            MemoryConfig::default().include_summary_on_truncation();
+        memory_include_tool_messages: bool = MemoryConfig::default().include_tool_messages();
+        channel_allow_user_prompts: bool = ChannelConfig::default().allow_user_prompts();
+        channel_prompt_timeout_secs: u64 = ChannelConfig::default().prompt_timeout_secs();
+        channel_auto_grant_within_max_allowed: bool =
+            ChannelConfig::default().auto_grant_within_max_allowed();
+        whatsapp_max_concurrent_messages: usize =
+            WhatsappConfig::default().max_concurrent_messages();
+        whatsapp_max_media_size_bytes: u64 = WhatsappConfig::default().max_media_size_bytes();
+        whatsapp_max_attachments_per_message: usize =
+            WhatsappConfig::default().max_attachments_per_message();
+        whatsapp_media_retention_hours: u64 = WhatsappConfig::default().media_retention_hours();
+        whatsapp_shed_when_busy: bool = WhatsappConfig::default().busy_timeout().is_some();
+        whatsapp_messages_session_error: String = messages().session_error().to_string();
+        whatsapp_messages_agent_error: String = messages().agent_error().to_string();
+        whatsapp_messages_prompt_error: String = messages().prompt_error().to_string();
+        whatsapp_messages_busy: String = messages().busy().to_string();
+        tools_dry_run: bool = ToolsConfig::default().dry_run();
+        tools_debug_enabled: bool = ToolsConfig::default().debug_enabled();
+        tools_strict_output_schemas: bool = ToolsConfig::default().strict_output_schemas();
+        tool_retry_max_attempts: u32 = ToolRetryConfig::default().max_attempts();
+        tool_retry_base_backoff_ms: u64 = ToolRetryConfig::default().base_backoff_ms();
+        tool_retry_jitter_ms: u64 = ToolRetryConfig::default().jitter_ms();
+    }
+
+    fn messages() -> WhatsappMessagesConfig {
+        WhatsappConfig::default().messages()
+    }
+
+    /// Unset when shedding is off by default, since the timeout then does nothing.
+    pub(super) fn whatsapp_busy_timeout_secs() -> Option<u64> {
+        WhatsappConfig::default()
+            .busy_timeout()
+            .map(|timeout| timeout.as_secs())
+    }
+}
+
+/// Values the accessors fall back to for unset fields, laid out like `picobot.toml`: every
+/// `default` in [`json_schema`]. Fields without a default, or whose default depends on the
+/// machine (`data_dir`), are left out, as is `[multimodal]`, which needs a model before its
+/// limits mean anything.
+pub fn default_values() -> serde_json::Value {
+    let schema = json_schema();
+    collect_schema_defaults(&schema, &schema).unwrap_or_else(|| serde_json::json!({}))
+}
+
+/// The `default` of each property of `node`, recursing through `$ref`s into nested tables;
+/// `None` when there are none.
+fn collect_schema_defaults(
+    root: &serde_json::Value,
+    node: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    let mut defaults = serde_json::Map::new();
+    for (key, property) in node.get("properties")?.as_object()? {
+        let value = match property.get("default") {
+            Some(serde_json::Value::Null) => None,
+            Some(default) => Some(default.clone()),
+            None => schema_ref(property).and_then(|name| {
+                collect_schema_defaults(root, root.pointer(&format!("/$defs/{name}"))?)
+            }),
+        };
+        if let Some(value) = value {
+            defaults.insert(key.clone(), value);
+        }
+    }
+    (!defaults.is_empty()).then_some(serde_json::Value::Object(defaults))
+}
+
+/// JSON Schema for `picobot.toml`, generated from [`Config`]: doc comments become
+/// descriptions and `schema_defaults` the fields' `default`s.
+pub fn json_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(Config)).expect("config schema serializes to JSON")
+}
+
+/// Definition name a property points at, directly or through an `Option` (`anyOf` with null).
+fn schema_ref(property: &serde_json::Value) -> Option<String> {
+    let reference = property.get("$ref").or_else(|| {
+        property
+            .get("anyOf")?
+            .as_array()?
+            .iter()
+            .find_map(|variant| variant.get("$ref"))
+    })?;
+    reference
+        .as_str()?
+        .strip_prefix("#/$defs/")
+        .map(str::to_string)
+}
+
+/// `picobot.toml` with every field that has a default spelled out, as printed by
+/// `picobot config-schema --example`.
+pub fn example_toml() -> Result<String> {
+    let body = toml::to_string_pretty(&default_values())
+        .context("failed to render example config")?;
+    Ok(format!(
+        "# picobot.toml with every default spelled out; delete what you do not change.\n\
+         # `picobot config-schema` prints the JSON Schema with all fields and their docs.\n\n\
+         {body}"
+    ))
 }
 
 #[derive(Debug, Default)]
 pub struct ConfigValidation {
+    pub errors: Vec<String>,
     pub warnings: Vec<String>,
 }
 
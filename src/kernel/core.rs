@@ -5,10 +5,18 @@ use std::time::Duration;
 use serde_json::{Value, json};
 use tokio::time::Instant;
 
-use crate::kernel::permissions::{CapabilitySet, ChannelPermissionProfile, PermissionPrompter};
+use crate::kernel::events::{SessionEvent, SessionEvents};
+use crate::kernel::transcript::ToolCallRecord;
+use crate::kernel::permissions::{
+    CapabilitySet, ChannelPermissionProfile, GlobalMemoryGrant, PathPattern, Permission,
+    PermissionPrompter, PromptTimeoutAction,
+};
+use crate::kernel::tool_policy::ToolPolicyRules;
 use crate::scheduler::service::SchedulerService;
 use crate::tools::registry::ToolRegistry;
 use crate::tools::traits::{
+    Artifact,
+    ArtifactSink,
     ExecutionMode,
     PreExecutionDecision,
     ToolContext,
@@ -23,12 +31,78 @@ pub enum SoftTimeoutPolicy {
     AutoExtend,
 }
 
+/// Opt-in retries for a tool's transient failures; all attempts share the tool's timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolRetryPolicy {
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub jitter: Duration,
+}
+
+impl ToolRetryPolicy {
+    /// Exponential backoff before retry `attempt` (1-based), plus up to `jitter` at random.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let jitter = random_millis(self.jitter.as_millis() as u64);
+        exponential.saturating_add(Duration::from_millis(jitter))
+    }
+}
+
+/// A random delay in `0..=max_ms` milliseconds, for spreading out retries.
+pub(crate) fn random_millis(max_ms: u64) -> u64 {
+    if max_ms == 0 {
+        return 0;
+    }
+    (uuid::Uuid::new_v4().as_u128() as u64) % (max_ms + 1)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TimeoutExtensionDecision {
     Extended,
     Declined,
 }
 
+/// Takes the guard from a lock poisoned by a panicking holder, so one failed handler
+/// does not cascade into every later caller.
+fn recover_poisoned<G>(lock: &'static str, err: std::sync::PoisonError<G>) -> G {
+    tracing::warn!(event = "lock_poisoned", lock, "recovering poisoned lock");
+    err.into_inner()
+}
+
+/// Canonicalizes an artifact's path and checks it against the jail and `FileRead` grants,
+/// so a tool cannot hand channels a file the caller could not read itself.
+fn admit_artifact(
+    ctx: &ToolContext,
+    granted: &CapabilitySet,
+    mut artifact: Artifact,
+) -> Result<Artifact, String> {
+    let path = ctx.working_dir.join(&artifact.path);
+    let canonical = path
+        .canonicalize()
+        .map_err(|err| format!("{}: {err}", path.display()))?;
+    if !canonical.is_file() {
+        return Err(format!("{} is not a file", canonical.display()));
+    }
+    if let Some(jail_root) = &ctx.jail_root {
+        let jail_root = jail_root
+            .canonicalize()
+            .map_err(|err| format!("invalid jail_root: {err}"))?;
+        if !canonical.starts_with(&jail_root) {
+            return Err(format!("{} escapes jail_root", canonical.display()));
+        }
+    }
+    let required = Permission::FileRead {
+        path: PathPattern(canonical.to_string_lossy().into_owned()),
+    };
+    if !granted.allows(&required) {
+        return Err(format!("missing permission {required}"));
+    }
+    artifact.path = canonical;
+    Ok(artifact)
+}
+
 pub fn soft_timeout_duration(hard_timeout: Duration, ratio: f64) -> Duration {
     if hard_timeout.is_zero() || !ratio.is_finite() || ratio <= 0.0 {
         return Duration::ZERO;
@@ -42,6 +116,91 @@ pub fn soft_timeout_duration(hard_timeout: Duration, ratio: f64) -> Duration {
     }
 }
 
+const TRUNCATION_MARKER: &str = " [truncated]";
+
+fn cap_tool_output(
+    output: ToolOutput,
+    max_bytes: Option<u64>,
+    max_chars: Option<usize>,
+) -> (ToolOutput, bool) {
+    if max_bytes.is_none() && max_chars.is_none() {
+        return (output, false);
+    }
+    let overflow = |value: &Value| -> usize {
+        let serialized = value.to_string();
+        let over_chars = max_chars
+            .map(|limit| serialized.chars().count().saturating_sub(limit))
+            .unwrap_or(0);
+        let over_bytes = max_bytes
+            .map(|limit| (serialized.len() as u64).saturating_sub(limit) as usize)
+            .unwrap_or(0);
+        over_chars.max(over_bytes)
+    };
+    if overflow(&output) == 0 {
+        return (output, false);
+    }
+
+    let mut capped = output.clone();
+    for _ in 0..32 {
+        let excess = overflow(&capped);
+        if excess == 0 {
+            return (capped, true);
+        }
+        let Some(leaf) = longest_string_leaf(&mut capped) else {
+            break;
+        };
+        let current = leaf.trim_end_matches(TRUNCATION_MARKER).chars().count();
+        let keep = current.saturating_sub(excess + TRUNCATION_MARKER.len());
+        if keep == 0 && current == 0 {
+            break;
+        }
+        let mut truncated = leaf.chars().take(keep).collect::<String>();
+        truncated.push_str(TRUNCATION_MARKER);
+        *leaf = truncated;
+    }
+    if overflow(&capped) == 0 {
+        return (capped, true);
+    }
+
+    let serialized = output.to_string();
+    let envelope = json!({"truncated": true, "output": ""});
+    let budget = max_chars
+        .unwrap_or(usize::MAX)
+        .min(max_bytes.map(|limit| limit as usize).unwrap_or(usize::MAX))
+        .saturating_sub(envelope.to_string().len() + TRUNCATION_MARKER.len());
+    let mut prefix = String::new();
+    let mut used = 0;
+    for ch in serialized.chars() {
+        let escaped = match ch {
+            '"' | '\\' | '\n' | '\r' | '\t' => 2,
+            ch if (ch as u32) < 0x20 => 6,
+            ch => ch.len_utf8(),
+        };
+        if used + escaped > budget {
+            break;
+        }
+        used += escaped;
+        prefix.push(ch);
+    }
+    prefix.push_str(TRUNCATION_MARKER);
+    (json!({"truncated": true, "output": prefix}), true)
+}
+
+fn longest_string_leaf(value: &mut Value) -> Option<&mut String> {
+    match value {
+        Value::String(text) => Some(text),
+        Value::Array(items) => items
+            .iter_mut()
+            .filter_map(longest_string_leaf)
+            .max_by_key(|text| text.len()),
+        Value::Object(map) => map
+            .values_mut()
+            .filter_map(longest_string_leaf)
+            .max_by_key(|text| text.len()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod timeout_extension_tests {
     use super::{SoftTimeoutPolicy, TimeoutExtensionDecision};
@@ -137,6 +296,7 @@ enum DecisionSource {
     ExtraGrants,
     PreAuthorized,
     SessionGrants,
+    GlobalMemoryGrant,
     AutoGranted,
 }
 
@@ -147,11 +307,19 @@ pub struct Kernel {
     prompt_profile: ChannelPermissionProfile,
     prompter: Option<Arc<dyn PermissionPrompter>>,
     session_grants: Arc<std::sync::RwLock<CapabilitySet>>,
+    unprompted_denials: Arc<std::sync::RwLock<Vec<Permission>>>,
+    artifacts: Arc<std::sync::RwLock<Vec<Artifact>>>,
+    tool_calls: Arc<std::sync::RwLock<Vec<ToolCallRecord>>>,
     default_timeout: Duration,
     tool_timeouts: std::collections::HashMap<String, Duration>,
+    tool_retries: std::collections::HashMap<String, ToolRetryPolicy>,
     soft_timeout_ratio: f64,
     soft_timeout_policy: SoftTimeoutPolicy,
     soft_timeout_extension: Option<Duration>,
+    tool_policies: Arc<ToolPolicyRules>,
+    global_memory_grants: Arc<Vec<GlobalMemoryGrant>>,
+    session_events: Option<SessionEvents>,
+    strict_output_schemas: bool,
 }
 
 impl Kernel {
@@ -169,20 +337,32 @@ impl Kernel {
                 scheduler: None,
                 notifications: None,
                 notify_tool_used: Arc::new(AtomicBool::new(false)),
+                message_sink: None,
+                artifacts: Default::default(),
                 execution_mode: ExecutionMode::User,
                 timezone_offset: "+00:00".to_string(),
                 timezone_name: "UTC".to_string(),
+                timezone_requested: false,
                 max_response_bytes: None,
                 max_response_chars: None,
+                dry_run: false,
             },
             prompt_profile: ChannelPermissionProfile::default(),
             prompter: None,
             session_grants: Arc::new(std::sync::RwLock::new(CapabilitySet::empty())),
+            unprompted_denials: Arc::new(std::sync::RwLock::new(Vec::new())),
+            artifacts: Arc::new(std::sync::RwLock::new(Vec::new())),
+            tool_calls: Arc::new(std::sync::RwLock::new(Vec::new())),
             default_timeout: Duration::from_secs(60),
             tool_timeouts: std::collections::HashMap::new(),
+            tool_retries: std::collections::HashMap::new(),
             soft_timeout_ratio: 0.0,
             soft_timeout_policy: SoftTimeoutPolicy::Prompt,
             soft_timeout_extension: None,
+            tool_policies: Arc::new(ToolPolicyRules::default()),
+            global_memory_grants: Arc::new(Vec::new()),
+            session_events: None,
+            strict_output_schemas: false,
         }
     }
 
@@ -220,6 +400,15 @@ impl Kernel {
         self
     }
 
+    /// Lets the `say` tool reach the user of this turn before the final response.
+    pub fn with_message_sink(
+        mut self,
+        sink: Option<Arc<dyn crate::tools::say::MessageSink>>,
+    ) -> Self {
+        self.context.message_sink = sink;
+        self
+    }
+
     pub fn with_notifications(
         mut self,
         notifications: Option<Arc<crate::notifications::service::NotificationService>>,
@@ -233,9 +422,10 @@ impl Kernel {
         self
     }
 
-    pub fn with_timezone(mut self, offset: String, name: String) -> Self {
+    pub fn with_timezone(mut self, offset: String, name: String, requested: bool) -> Self {
         self.context.timezone_offset = offset;
         self.context.timezone_name = name;
+        self.context.timezone_requested = requested;
         self
     }
 
@@ -249,6 +439,11 @@ impl Kernel {
         self
     }
 
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.context.dry_run = dry_run;
+        self
+    }
+
     pub fn with_execution_mode(mut self, mode: ExecutionMode) -> Self {
         self.context.execution_mode = mode;
         self
@@ -264,6 +459,14 @@ impl Kernel {
         self
     }
 
+    pub fn with_tool_retries(
+        mut self,
+        tool_retries: std::collections::HashMap<String, ToolRetryPolicy>,
+    ) -> Self {
+        self.tool_retries = tool_retries;
+        self
+    }
+
     pub fn with_soft_timeouts(
         mut self,
         ratio: f64,
@@ -276,6 +479,29 @@ impl Kernel {
         self
     }
 
+    pub fn with_global_memory_grants(mut self, grants: Vec<GlobalMemoryGrant>) -> Self {
+        self.global_memory_grants = Arc::new(grants);
+        self
+    }
+
+    /// Publishes a [`SessionEvent::ToolEvent`] for each tool call made on a session.
+    pub fn with_session_events(mut self, events: Option<SessionEvents>) -> Self {
+        self.session_events = events;
+        self
+    }
+
+    pub fn with_tool_policies(mut self, policies: ToolPolicyRules) -> Self {
+        self.tool_policies = Arc::new(policies);
+        self
+    }
+
+    /// Fails tool calls whose output does not match the tool's `output_schema`, instead of
+    /// only logging the mismatch.
+    pub fn with_strict_output_schemas(mut self, strict: bool) -> Self {
+        self.strict_output_schemas = strict;
+        self
+    }
+
     pub fn clone_with_context(&self, user_id: Option<String>, session_id: Option<String>) -> Self {
         let mut context = self.context.clone();
         context.user_id = user_id;
@@ -287,11 +513,19 @@ impl Kernel {
             prompt_profile: self.prompt_profile.clone(),
             prompter: self.prompter.clone(),
             session_grants: Arc::new(std::sync::RwLock::new(CapabilitySet::empty())),
+            unprompted_denials: Arc::new(std::sync::RwLock::new(Vec::new())),
+            artifacts: Arc::new(std::sync::RwLock::new(Vec::new())),
+            tool_calls: Arc::new(std::sync::RwLock::new(Vec::new())),
             default_timeout: self.default_timeout,
             tool_timeouts: self.tool_timeouts.clone(),
+            tool_retries: self.tool_retries.clone(),
             soft_timeout_ratio: self.soft_timeout_ratio,
             soft_timeout_policy: self.soft_timeout_policy,
             soft_timeout_extension: self.soft_timeout_extension,
+            tool_policies: Arc::clone(&self.tool_policies),
+            global_memory_grants: Arc::clone(&self.global_memory_grants),
+            session_events: self.session_events.clone(),
+            strict_output_schemas: self.strict_output_schemas,
         }
     }
 
@@ -303,6 +537,167 @@ impl Kernel {
         &self.context
     }
 
+    /// Permissions the channel could have granted but that were denied because no
+    /// interactive prompt was available (prompts disabled or no prompter attached).
+    pub fn unprompted_denials(&self) -> Vec<Permission> {
+        self.unprompted_denials
+            .read()
+            .unwrap_or_else(|err| recover_poisoned("unprompted_denials", err))
+            .clone()
+    }
+
+    /// Artifacts that tools returned through this kernel, in call order.
+    pub fn artifacts(&self) -> Vec<Artifact> {
+        self.artifacts
+            .read()
+            .unwrap_or_else(|err| recover_poisoned("artifacts", err))
+            .clone()
+    }
+
+    /// Tool calls executed since the last call, oldest first, for the turn transcript.
+    pub fn take_tool_calls(&self) -> Vec<ToolCallRecord> {
+        std::mem::take(
+            &mut *self
+                .tool_calls
+                .write()
+                .unwrap_or_else(|err| recover_poisoned("tool_calls", err)),
+        )
+    }
+
+    fn record_tool_call(
+        &self,
+        tool: &dyn ToolExecutor,
+        input: &Value,
+        output: &Result<ToolOutput, ToolError>,
+    ) {
+        let error = output.as_ref().err().map(|err| err.to_string());
+        self.tool_calls
+            .write()
+            .unwrap_or_else(|err| recover_poisoned("tool_calls", err))
+            .push(ToolCallRecord::new(&tool.spec().name, input, error));
+        let (Some(events), Some(session_id)) = (&self.session_events, &self.context.session_id)
+        else {
+            return;
+        };
+        events.publish(SessionEvent::ToolEvent {
+            session_id: session_id.clone(),
+            tool: tool.spec().name.clone(),
+            outcome: if output.is_ok() { "success" } else { "error" }.to_string(),
+            error: output.as_ref().err().map(|err| err.to_string()),
+        });
+    }
+
+    /// Keeps the artifacts `scoped` collected that resolve inside `jail_root` and that the
+    /// call could read without a prompt; the rest are logged and dropped.
+    fn record_artifacts(
+        &self,
+        tool: &dyn ToolExecutor,
+        scoped: &ToolContext,
+        extra_grants: Option<&CapabilitySet>,
+    ) {
+        let attached = scoped.artifacts.take();
+        if attached.is_empty() {
+            return;
+        }
+        let granted = self.granted_permissions(extra_grants);
+        let mut admitted = Vec::with_capacity(attached.len());
+        for artifact in attached {
+            match admit_artifact(scoped, &granted, artifact) {
+                Ok(artifact) => admitted.push(artifact),
+                Err(reason) => tracing::warn!(
+                    event = "tool_artifact_rejected",
+                    tool = %tool.spec().name,
+                    user_id = ?self.context.user_id,
+                    session_id = ?self.context.session_id,
+                    channel_id = ?self.context.channel_id,
+                    reason = %reason,
+                    "tool artifact rejected"
+                ),
+            }
+        }
+        if admitted.is_empty() {
+            return;
+        }
+        tracing::info!(
+            event = "tool_artifacts",
+            tool = %tool.spec().name,
+            user_id = ?self.context.user_id,
+            session_id = ?self.context.session_id,
+            channel_id = ?self.context.channel_id,
+            count = admitted.len(),
+            "tool returned artifacts"
+        );
+        self.artifacts
+            .write()
+            .unwrap_or_else(|err| recover_poisoned("artifacts", err))
+            .extend(admitted);
+    }
+
+    fn session_grants(&self) -> std::sync::RwLockReadGuard<'_, CapabilitySet> {
+        self.session_grants.read().unwrap_or_else(|err| {
+            self.session_grants.clear_poison();
+            recover_poisoned("session_grants", err)
+        })
+    }
+
+    /// Global memory permissions configured for the current channel and user.
+    fn global_memory_grants(&self) -> CapabilitySet {
+        let channel_id = self.context.channel_id.as_deref();
+        let user_id = self.context.user_id.as_deref();
+        let permissions = self
+            .global_memory_grants
+            .iter()
+            .flat_map(|grant| grant.permissions_for(channel_id, user_id))
+            .collect::<Vec<_>>();
+        CapabilitySet::from_permissions(&permissions)
+    }
+
+    /// Everything the current session may use without a prompt: context capabilities,
+    /// the channel's pre-authorized set, session grants, and global memory grants.
+    /// Session grants do not expire, so nothing is filtered out.
+    pub fn effective_capabilities(&self) -> CapabilitySet {
+        self.granted_permissions(None)
+    }
+
+    /// Union of every source a tool call can draw permissions from.
+    fn granted_permissions(&self, extra_grants: Option<&CapabilitySet>) -> CapabilitySet {
+        let mut granted = self.context.capabilities.as_ref().clone();
+        let sources = [
+            extra_grants.cloned(),
+            Some(self.prompt_profile.pre_authorized.clone()),
+            Some(self.session_grants().clone()),
+            Some(self.global_memory_grants()),
+        ];
+        for source in sources.iter().flatten() {
+            for permission in source.permissions() {
+                granted.insert(permission.clone());
+            }
+        }
+        granted
+    }
+
+    fn is_promptable(&self, tool: &dyn ToolExecutor, required: &[Permission]) -> bool {
+        match tool.spec().name.as_str() {
+            "schedule" => self.prompt_profile.max_allowed.allows_any(required),
+            _ => self.prompt_profile.max_allowed.allows_all(required),
+        }
+    }
+
+    fn record_unprompted_denial(&self, tool: &dyn ToolExecutor, required: &[Permission]) {
+        if self.context.execution_mode.is_scheduled_job() || !self.is_promptable(tool, required) {
+            return;
+        }
+        let mut denials = self
+            .unprompted_denials
+            .write()
+            .unwrap_or_else(|err| recover_poisoned("unprompted_denials", err));
+        for permission in required {
+            if !denials.contains(permission) {
+                denials.push(permission.clone());
+            }
+        }
+    }
+
     pub async fn invoke_tool(
         &self,
         tool: &dyn ToolExecutor,
@@ -385,12 +780,7 @@ impl Kernel {
                 Some(DecisionSource::ExtraGrants)
             } else if self.prompt_profile.pre_authorized.allows_any(&required) {
                 Some(DecisionSource::PreAuthorized)
-            } else if self
-                .session_grants
-                .read()
-                .map(|grants| grants.allows_any(&required))
-                .unwrap_or(false)
-            {
+            } else if self.session_grants().allows_any(&required) {
                 Some(DecisionSource::SessionGrants)
             } else if required
                 .iter()
@@ -409,13 +799,10 @@ impl Kernel {
             Some(DecisionSource::ExtraGrants)
         } else if self.prompt_profile.pre_authorized.allows_all(&required) {
             Some(DecisionSource::PreAuthorized)
-        } else if self
-            .session_grants
-            .read()
-            .map(|grants| grants.allows_all(&required))
-            .unwrap_or(false)
-        {
+        } else if self.session_grants().allows_all(&required) {
             Some(DecisionSource::SessionGrants)
+        } else if self.global_memory_grants().allows_all(&required) {
+            Some(DecisionSource::GlobalMemoryGrant)
         } else if required
             .iter()
             .all(|permission| permission.is_auto_granted(&self.context))
@@ -450,12 +837,36 @@ impl Kernel {
                 permissions = ?required,
                 "tool permission denied"
             );
+            let missing = self
+                .granted_permissions(extra_grants)
+                .missing(&required)
+                .into_iter()
+                .filter(|permission| !permission.is_auto_granted(&self.context))
+                .map(|permission| permission.to_string())
+                .collect::<Vec<_>>();
+            // Each source is checked on its own, so grants split across sources can leave
+            // nothing missing from the union; name every requirement in that case.
+            let missing = if missing.is_empty() {
+                required.iter().map(ToString::to_string).collect()
+            } else {
+                missing
+            };
+            let label = if any_mode { "missing one of" } else { "missing" };
             return Err(ToolError::permission_denied(
-                format!("permission denied for tool '{}'", tool.spec().name),
+                format!(
+                    "permission denied for tool '{}' ({label}: {})",
+                    tool.spec().name,
+                    missing.join(", ")
+                ),
                 required,
             ));
         }
-        if let Some(policy) = tool.pre_execution_policy(&self.context, &input)? {
+        // Configured rules run first; an `allow` match still defers to the tool's own policy.
+        let policy = match self.tool_policies.evaluate(&tool.spec().name, &input) {
+            Some(policy) if policy.decision != PreExecutionDecision::Allow => Some(policy),
+            configured => tool.pre_execution_policy(&self.context, &input)?.or(configured),
+        };
+        if let Some(policy) = policy {
             let policy_reason = policy.reason.as_deref().unwrap_or("unspecified");
             let policy_key = policy.policy_key.as_deref().unwrap_or("");
             match policy.decision {
@@ -511,69 +922,104 @@ impl Kernel {
                 }
             }
         }
+        let recorded_input = input.clone();
+        let mut scoped = self.context.clone();
+        scoped.artifacts = ArtifactSink::default();
         if let Some(grants) = extra_grants {
             let mut merged = self.context.capabilities.as_ref().clone();
             for permission in grants.permissions() {
                 merged.insert(permission.clone());
             }
-            let mut scoped = self.context.clone();
             scoped.capabilities = Arc::new(merged);
-            let output = self.execute_with_timeout(tool, &scoped, input).await;
-            match &output {
-                Ok(_) => tracing::info!(
-                    event = "tool_outcome",
-                    tool = %tool.spec().name,
-                    user_id = ?self.context.user_id,
-                    session_id = ?self.context.session_id,
-                    channel_id = ?self.context.channel_id,
-                    scheduled = self.context.execution_mode.is_scheduled_job(),
-                    outcome = "success",
-                    "tool execution succeeded"
-                ),
-                Err(err) => tracing::error!(
-                    event = "tool_outcome",
-                    tool = %tool.spec().name,
-                    user_id = ?self.context.user_id,
-                    session_id = ?self.context.session_id,
-                    channel_id = ?self.context.channel_id,
-                    scheduled = self.context.execution_mode.is_scheduled_job(),
-                    outcome = "error",
-                    timed_out = err.is_timeout(),
-                    error = %err,
-                    "tool execution failed"
-                ),
-            }
-            output
+        }
+        let output = self
+            .execute_with_timeout(tool, &scoped, input)
+            .await
+            .and_then(|output| self.check_output(tool, output))
+            .map(|output| {
+                self.record_artifacts(tool, &scoped, extra_grants);
+                self.cap_output(tool, output)
+            });
+        self.record_tool_call(tool, &recorded_input, &output);
+        match &output {
+            Ok(_) => tracing::info!(
+                event = "tool_outcome",
+                tool = %tool.spec().name,
+                user_id = ?self.context.user_id,
+                session_id = ?self.context.session_id,
+                channel_id = ?self.context.channel_id,
+                scheduled = self.context.execution_mode.is_scheduled_job(),
+                outcome = "success",
+                "tool execution succeeded"
+            ),
+            Err(err) => tracing::error!(
+                event = "tool_outcome",
+                tool = %tool.spec().name,
+                user_id = ?self.context.user_id,
+                session_id = ?self.context.session_id,
+                channel_id = ?self.context.channel_id,
+                scheduled = self.context.execution_mode.is_scheduled_job(),
+                outcome = "error",
+                timed_out = err.is_timeout(),
+                error = %err,
+                "tool execution failed"
+            ),
+        }
+        output
+    }
+
+    /// Holds a tool to its declared `output_schema`: a mismatch is logged, and fails the call
+    /// under strict output schemas.
+    fn check_output(
+        &self,
+        tool: &dyn ToolExecutor,
+        output: ToolOutput,
+    ) -> Result<ToolOutput, ToolError> {
+        // Dry-run stand-ins share one shape across tools rather than each tool's own.
+        if self.context.dry_run && output.get("status") == Some(&json!("dry_run")) {
+            return Ok(output);
+        }
+        let Err(err) = self.tool_registry.validate_output(tool, &output) else {
+            return Ok(output);
+        };
+        tracing::warn!(
+            event = "tool_output_schema_mismatch",
+            tool = %tool.spec().name,
+            user_id = ?self.context.user_id,
+            session_id = ?self.context.session_id,
+            channel_id = ?self.context.channel_id,
+            strict = self.strict_output_schemas,
+            error = %err,
+            "tool output does not match its output schema"
+        );
+        if self.strict_output_schemas {
+            Err(err)
         } else {
-            let output = self.execute_with_timeout(tool, &self.context, input).await;
-            match &output {
-                Ok(_) => tracing::info!(
-                    event = "tool_outcome",
-                    tool = %tool.spec().name,
-                    user_id = ?self.context.user_id,
-                    session_id = ?self.context.session_id,
-                    channel_id = ?self.context.channel_id,
-                    scheduled = self.context.execution_mode.is_scheduled_job(),
-                    outcome = "success",
-                    "tool execution succeeded"
-                ),
-                Err(err) => tracing::error!(
-                    event = "tool_outcome",
-                    tool = %tool.spec().name,
-                    user_id = ?self.context.user_id,
-                    session_id = ?self.context.session_id,
-                    channel_id = ?self.context.channel_id,
-                    scheduled = self.context.execution_mode.is_scheduled_job(),
-                    outcome = "error",
-                    timed_out = err.is_timeout(),
-                    error = %err,
-                    "tool execution failed"
-                ),
-            }
-            output
+            Ok(output)
         }
     }
 
+    fn cap_output(&self, tool: &dyn ToolExecutor, output: ToolOutput) -> ToolOutput {
+        let (output, truncated) = cap_tool_output(
+            output,
+            self.context.max_response_bytes,
+            self.context.max_response_chars,
+        );
+        if truncated {
+            tracing::info!(
+                event = "tool_output_truncated",
+                tool = %tool.spec().name,
+                user_id = ?self.context.user_id,
+                session_id = ?self.context.session_id,
+                channel_id = ?self.context.channel_id,
+                max_response_bytes = ?self.context.max_response_bytes,
+                max_response_chars = ?self.context.max_response_chars,
+                "tool output truncated"
+            );
+        }
+        output
+    }
+
     async fn execute_with_timeout(
         &self,
         tool: &dyn ToolExecutor,
@@ -585,7 +1031,7 @@ impl Kernel {
             .get(tool.spec().name.as_str())
             .copied()
             .unwrap_or(self.default_timeout);
-        let mut task = Box::pin(tool.execute(ctx, input));
+        let mut task = Box::pin(self.execute_with_retries(tool, ctx, input));
         let ratio = self.soft_timeout_ratio;
         let soft_timeout = soft_timeout_duration(timeout, ratio);
         if soft_timeout.is_zero() {
@@ -650,6 +1096,39 @@ impl Kernel {
         }
     }
 
+    /// Runs the tool, retrying transient failures per its retry policy. The caller's timeout
+    /// wraps every attempt and backoff, so retries never extend the hard deadline.
+    async fn execute_with_retries(
+        &self,
+        tool: &dyn ToolExecutor,
+        ctx: &ToolContext,
+        input: Value,
+    ) -> Result<ToolOutput, ToolError> {
+        let Some(policy) = self.tool_retries.get(tool.spec().name.as_str()) else {
+            return tool.execute(ctx, input).await;
+        };
+        let mut attempt = 1;
+        loop {
+            match tool.execute(ctx, input.clone()).await {
+                Err(err) if err.is_transient() && attempt < policy.max_attempts => {
+                    let backoff = policy.backoff(attempt);
+                    tracing::warn!(
+                        event = "tool_retry",
+                        tool = %tool.spec().name,
+                        attempt,
+                        max_attempts = policy.max_attempts,
+                        backoff_ms = backoff.as_millis() as u64,
+                        error = %err,
+                        "retrying transient tool failure"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     async fn maybe_extend_timeout(
         &self,
         tool_name: &str,
@@ -742,6 +1221,7 @@ impl Kernel {
                     let reason = if self.context.execution_mode.is_scheduled_job() {
                         "scheduled_job"
                     } else if !self.prompt_profile.allow_user_prompts {
+                        self.record_unprompted_denial(tool, required);
                         "prompts_disabled"
                     } else {
                         "auto_granted"
@@ -758,11 +1238,7 @@ impl Kernel {
                     );
                     return Err(err);
                 }
-                let promptable = match tool.spec().name.as_str() {
-                    "schedule" => self.prompt_profile.max_allowed.allows_any(required),
-                    _ => self.prompt_profile.max_allowed.allows_all(required),
-                };
-                if !promptable {
+                if !self.is_promptable(tool, required) {
                     tracing::debug!(
                         event = "prompt_skipped",
                         reason = "not_in_max_allowed",
@@ -778,6 +1254,7 @@ impl Kernel {
                 let prompter = match self.prompter.as_ref() {
                     Some(prompter) => prompter.clone(),
                     None => {
+                        self.record_unprompted_denial(tool, required);
                         tracing::debug!(
                             event = "prompt_skipped",
                             reason = "no_prompter",
@@ -800,13 +1277,19 @@ impl Kernel {
                     permissions = ?required,
                     "prompt issued"
                 );
-                let decision = prompter
-                    .prompt(
+                // The kernel enforces the deadline, so a prompter that never answers still
+                // ends in the channel's `prompt_timeout_action`.
+                let decision = tokio::time::timeout(
+                    Duration::from_secs(self.prompt_profile.prompt_timeout_secs),
+                    prompter.prompt(
                         tool.spec().name.as_str(),
                         required,
                         self.prompt_profile.prompt_timeout_secs,
-                    )
-                    .await;
+                    ),
+                )
+                .await
+                .ok()
+                .flatten();
                 match decision {
                     Some(crate::kernel::permissions::PromptDecision::AllowOnce) => {
                         tracing::info!(
@@ -818,12 +1301,7 @@ impl Kernel {
                             decision = "allow_once",
                             "prompt decision"
                         );
-                        let mut grants = CapabilitySet::from_permissions(required);
-                        for permission in self.prompt_profile.pre_authorized.permissions() {
-                            grants.insert(permission.clone());
-                        }
-                        self.invoke_tool_with_grants(tool, input, Some(&grants))
-                            .await
+                        self.invoke_tool_allowed_once(tool, input, required).await
                     }
                     Some(crate::kernel::permissions::PromptDecision::AllowSession) => {
                         tracing::info!(
@@ -835,7 +1313,14 @@ impl Kernel {
                             decision = "allow_session",
                             "prompt decision"
                         );
-                        if let Ok(mut session_grants) = self.session_grants.write() {
+                        {
+                            let mut session_grants = self
+                                .session_grants
+                                .write()
+                                .unwrap_or_else(|err| {
+                                    self.session_grants.clear_poison();
+                                    recover_poisoned("session_grants", err)
+                                });
                             for permission in required {
                                 session_grants.insert(permission.clone());
                             }
@@ -855,6 +1340,7 @@ impl Kernel {
                         Err(err)
                     }
                     None => {
+                        let action = self.prompt_profile.prompt_timeout_action;
                         tracing::info!(
                             event = "prompt_decision",
                             tool = %tool.spec().name,
@@ -862,31 +1348,56 @@ impl Kernel {
                             session_id = ?self.context.session_id,
                             channel_id = ?self.context.channel_id,
                             decision = "timeout",
+                            timeout_action = ?action,
                             "prompt decision"
                         );
-                        Err(err)
+                        match action {
+                            PromptTimeoutAction::Deny => Err(err),
+                            PromptTimeoutAction::AllowOnce => {
+                                self.invoke_tool_allowed_once(tool, input, required).await
+                            }
+                        }
                     }
                 }
             }
         }
     }
+
+    /// Runs `tool` with `required` granted for this call only, on top of pre-authorized ones.
+    async fn invoke_tool_allowed_once(
+        &self,
+        tool: &dyn ToolExecutor,
+        input: Value,
+        required: &[Permission],
+    ) -> Result<ToolOutput, ToolError> {
+        let mut grants = CapabilitySet::from_permissions(required);
+        for permission in self.prompt_profile.pre_authorized.permissions() {
+            grants.insert(permission.clone());
+        }
+        self.invoke_tool_with_grants(tool, input, Some(&grants))
+            .await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
     use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
 
     use async_trait::async_trait;
     use serde_json::json;
 
-    use super::Kernel;
+    use super::{Kernel, ToolRetryPolicy};
+    use crate::config::ToolPolicyConfig;
     use crate::kernel::permissions::{
         CapabilitySet, ChannelPermissionProfile, PathPattern, Permission, PermissionPrompter,
-        PromptDecision,
+        PromptDecision, PromptTimeoutAction,
     };
+    use crate::kernel::tool_policy::ToolPolicyRules;
     use crate::tools::registry::ToolRegistry;
     use crate::tools::traits::{
+        ExecutionMode,
         PreExecutionPolicy,
         ToolContext,
         ToolError,
@@ -907,6 +1418,7 @@ mod tests {
                     name: "dummy".to_string(),
                     description: "dummy tool".to_string(),
                     schema: json!({"type": "object"}),
+                    output_schema: None,
                 },
             }
         }
@@ -926,6 +1438,7 @@ mod tests {
                     name: name.to_string(),
                     description: "static tool".to_string(),
                     schema,
+                    output_schema: None,
                 },
                 required,
                 output: json!({"status": "ok"}),
@@ -996,6 +1509,21 @@ mod tests {
         }
     }
 
+    /// Never answers, like a user who walked away from the prompt.
+    struct SilentPrompter;
+
+    #[async_trait]
+    impl PermissionPrompter for SilentPrompter {
+        async fn prompt(
+            &self,
+            _tool_name: &str,
+            _permissions: &[Permission],
+            _timeout_secs: u64,
+        ) -> Option<PromptDecision> {
+            std::future::pending().await
+        }
+    }
+
     fn read_permission() -> Permission {
         Permission::FileRead {
             path: PathPattern("/tmp/allowed.txt".to_string()),
@@ -1008,6 +1536,7 @@ mod tests {
             max_allowed: CapabilitySet::from_permissions(required),
             allow_user_prompts: true,
             prompt_timeout_secs: 30,
+            prompt_timeout_action: PromptTimeoutAction::Deny,
         }
     }
 
@@ -1079,6 +1608,145 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    struct FlakyTool {
+        spec: ToolSpec,
+        failures: usize,
+        transient: bool,
+        calls: AtomicUsize,
+    }
+
+    impl FlakyTool {
+        fn new(failures: usize, transient: bool) -> Self {
+            Self {
+                spec: ToolSpec {
+                    name: "flaky".to_string(),
+                    description: "fails before succeeding".to_string(),
+                    schema: json!({"type": "object"}),
+                    output_schema: None,
+                },
+                failures,
+                transient,
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ToolExecutor for FlakyTool {
+        fn spec(&self) -> &ToolSpec {
+            &self.spec
+        }
+
+        fn required_permissions(
+            &self,
+            _ctx: &ToolContext,
+            _input: &serde_json::Value,
+        ) -> Result<Vec<Permission>, ToolError> {
+            Ok(Vec::new())
+        }
+
+        async fn execute(
+            &self,
+            _ctx: &ToolContext,
+            _input: serde_json::Value,
+        ) -> Result<ToolOutput, ToolError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                let message = format!("failure {}", call + 1);
+                return Err(if self.transient {
+                    ToolError::transient(message)
+                } else {
+                    ToolError::new(message)
+                });
+            }
+            Ok(json!({"status": "ok", "attempt": call + 1}))
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_within_policy() {
+        let policy = ToolRetryPolicy {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(1),
+            jitter: Duration::from_millis(2),
+        };
+        let run = |tool: FlakyTool| async move {
+            let tool = Arc::new(tool);
+            let mut registry = ToolRegistry::new();
+            registry.register(tool.clone()).unwrap();
+            let kernel = Kernel::new(Arc::new(registry))
+                .with_tool_retries([("flaky".to_string(), policy)].into_iter().collect());
+            let result = kernel.invoke_tool(tool.as_ref(), json!({})).await;
+            (result, tool.calls.load(Ordering::SeqCst))
+        };
+
+        let (result, calls) = run(FlakyTool::new(2, true)).await;
+        assert_eq!(result.unwrap(), json!({"status": "ok", "attempt": 3}));
+        assert_eq!(calls, 3);
+
+        let (result, calls) = run(FlakyTool::new(3, true)).await;
+        assert_eq!(result.unwrap_err().to_string(), "failure 3");
+        assert_eq!(calls, 3);
+
+        let (result, calls) = run(FlakyTool::new(2, false)).await;
+        assert_eq!(result.unwrap_err().to_string(), "failure 1");
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn configured_tool_policy_forces_approval_for_matching_input() {
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new("shell", json!({"type": "object"}), Vec::new())))
+            .unwrap();
+        let registry = Arc::new(registry);
+        let kernel = Kernel::new(Arc::clone(&registry)).with_tool_policies(
+            ToolPolicyRules::from_config(&[ToolPolicyConfig {
+                tool: "shell".to_string(),
+                path: Some("/command".to_string()),
+                contains: Some("rm".to_string()),
+                decision: "require_approval".to_string(),
+                reason: Some("destructive command".to_string()),
+            }]),
+        );
+        let tool = kernel.tool_registry().get("shell").unwrap();
+
+        let err = kernel
+            .invoke_tool(tool.as_ref(), json!({"command": "rm", "args": ["-rf", "/tmp/x"]}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("requires approval"), "{err}");
+
+        let output = kernel
+            .invoke_tool(tool.as_ref(), json!({"command": "ls"}))
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"status": "ok"}));
+    }
+
+    #[tokio::test]
+    async fn invoke_tool_checks_output_against_output_schema() {
+        let mut tool = StaticTool::new("dummy", json!({"type": "object"}), Vec::new());
+        tool.spec.output_schema = Some(json!({
+            "type": "object",
+            "properties": { "status": { "type": "integer" } },
+            "required": ["status"]
+        }));
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(tool)).unwrap();
+        let registry = Arc::new(registry);
+        let tool = registry.get("dummy").unwrap();
+
+        let lenient = Kernel::new(Arc::clone(&registry));
+        let output = lenient.invoke_tool(tool.as_ref(), json!({})).await.unwrap();
+        assert_eq!(output, json!({"status": "ok"}));
+
+        let strict = Kernel::new(Arc::clone(&registry)).with_strict_output_schemas(true);
+        let err = strict.invoke_tool(tool.as_ref(), json!({})).await.unwrap_err();
+        assert!(err.to_string().contains("does not match its output schema"), "{err}");
+        assert!(err.to_string().contains("/status"), "{err}");
+    }
+
     #[tokio::test]
     async fn invoke_tool_with_prompt_allow_once_does_not_persist() {
         let required = vec![read_permission()];
@@ -1136,6 +1804,70 @@ mod tests {
         assert!(second.is_ok());
     }
 
+    #[tokio::test]
+    async fn effective_capabilities_include_session_grants() {
+        let required = vec![read_permission()];
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "dummy",
+                json!({"type": "object"}),
+                required.clone(),
+            )))
+            .unwrap();
+        let prompter = Arc::new(MockPrompter::new(Some(PromptDecision::AllowSession)));
+        let kernel = Kernel::new(Arc::new(registry))
+            .with_prompt_profile(prompt_profile_for(&required))
+            .with_prompter(Some(prompter));
+        assert!(!kernel.effective_capabilities().allows(&required[0]));
+
+        kernel
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await
+            .unwrap();
+        let effective = kernel.effective_capabilities();
+        assert!(effective.allows(&required[0]));
+        assert_eq!(effective.permissions().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn poisoned_session_grants_still_grant_and_persist() {
+        let required = vec![read_permission()];
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "dummy",
+                json!({"type": "object"}),
+                required.clone(),
+            )))
+            .unwrap();
+        let registry = Arc::new(registry);
+        let prompter = Arc::new(MockPrompter::new(Some(PromptDecision::AllowSession)));
+        let kernel = Kernel::new(Arc::clone(&registry))
+            .with_prompt_profile(prompt_profile_for(&required))
+            .with_prompter(Some(prompter.clone()));
+
+        let grants = Arc::clone(&kernel.session_grants);
+        let _ = std::thread::spawn(move || {
+            let _guard = grants.write().unwrap();
+            panic!("handler panicked while holding session grants");
+        })
+        .join();
+        assert!(kernel.session_grants.is_poisoned());
+
+        let output = kernel
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await;
+        assert!(output.is_ok());
+        assert_eq!(prompter.calls(), 1);
+        let second = kernel
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await;
+        assert!(second.is_ok());
+        assert_eq!(prompter.calls(), 1);
+        assert!(!kernel.session_grants.is_poisoned());
+    }
+
     #[tokio::test]
     async fn invoke_tool_with_prompt_deny_returns_error() {
         let required = vec![read_permission()];
@@ -1162,6 +1894,145 @@ mod tests {
         assert!(err.required_permissions().is_some());
     }
 
+    #[tokio::test]
+    async fn invoke_tool_with_prompt_timeout_follows_channel_action() {
+        let required = vec![read_permission()];
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "dummy",
+                json!({"type": "object"}),
+                required.clone(),
+            )))
+            .unwrap();
+        let registry = Arc::new(registry);
+        let kernel_with = |action: PromptTimeoutAction, mode: ExecutionMode| {
+            let mut profile = prompt_profile_for(&required);
+            profile.prompt_timeout_action = action;
+            let prompter: Arc<dyn PermissionPrompter> = Arc::new(MockPrompter::new(None));
+            Kernel::new(Arc::clone(&registry))
+                .with_prompt_profile(profile)
+                .with_prompter(Some(prompter))
+                .with_execution_mode(mode)
+        };
+
+        let denied = kernel_with(PromptTimeoutAction::Deny, ExecutionMode::User)
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await;
+        assert!(denied.unwrap_err().required_permissions().is_some());
+
+        let kernel = kernel_with(PromptTimeoutAction::AllowOnce, ExecutionMode::User);
+        let allowed = kernel
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await;
+        assert!(allowed.is_ok());
+        let tool = kernel.tool_registry().get("dummy").unwrap();
+        assert!(kernel.invoke_tool(tool.as_ref(), json!({})).await.is_err());
+
+        let scheduled = kernel_with(PromptTimeoutAction::AllowOnce, ExecutionMode::ScheduledJob)
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await;
+        assert!(scheduled.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unanswered_prompt_times_out_into_channel_action() {
+        let required = vec![read_permission()];
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "dummy",
+                json!({"type": "object"}),
+                required.clone(),
+            )))
+            .unwrap();
+        let mut profile = prompt_profile_for(&required);
+        profile.prompt_timeout_secs = 5;
+        profile.prompt_timeout_action = PromptTimeoutAction::AllowOnce;
+        let prompter: Arc<dyn PermissionPrompter> = Arc::new(SilentPrompter);
+        let kernel = Kernel::new(Arc::new(registry))
+            .with_prompt_profile(profile)
+            .with_prompter(Some(prompter));
+
+        let started = tokio::time::Instant::now();
+        let result = kernel
+            .invoke_tool_with_prompt_by_name("dummy", json!({}))
+            .await;
+        assert!(result.is_ok(), "{result:?}");
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn permission_denied_names_missing_permissions() {
+        let write = Permission::FileWrite {
+            path: PathPattern("/data/out.txt".to_string()),
+        };
+        let required = vec![read_permission(), write];
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "dummy",
+                json!({"type": "object"}),
+                required,
+            )))
+            .unwrap();
+        let registry = Arc::new(registry);
+        let mut capabilities = CapabilitySet::empty();
+        capabilities.insert(read_permission());
+        let kernel = Kernel::new(Arc::clone(&registry)).with_capabilities(capabilities);
+
+        let tool = kernel.tool_registry().get("dummy").unwrap();
+        let err = kernel
+            .invoke_tool(tool.as_ref(), json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "permission denied for tool 'dummy' (missing: filesystem:write:/data/out.txt)"
+        );
+        assert_eq!(err.required_permissions().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unprompted_denials_record_grantable_permissions_only() {
+        let required = vec![read_permission()];
+        let ungrantable = vec![Permission::NetAccess {
+            domain: crate::kernel::permissions::DomainPattern("example.com".to_string()),
+        }];
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "dummy",
+                json!({"type": "object"}),
+                required.clone(),
+            )))
+            .unwrap();
+        registry
+            .register(Arc::new(StaticTool::new(
+                "fetch",
+                json!({"type": "object"}),
+                ungrantable,
+            )))
+            .unwrap();
+        let registry = Arc::new(registry);
+
+        let mut profile = prompt_profile_for(&required);
+        profile.allow_user_prompts = false;
+        let kernel = Kernel::new(Arc::clone(&registry)).with_prompt_profile(profile);
+
+        assert!(kernel.invoke_tool_with_prompt_by_name("fetch", json!({})).await.is_err());
+        assert!(kernel.unprompted_denials().is_empty());
+        assert!(kernel.invoke_tool_with_prompt_by_name("dummy", json!({})).await.is_err());
+        assert!(kernel.invoke_tool_with_prompt_by_name("dummy", json!({})).await.is_err());
+        assert_eq!(kernel.unprompted_denials(), required);
+
+        let granted = kernel
+            .clone_with_context(None, None)
+            .with_capabilities(CapabilitySet::from_permissions(&required));
+        assert!(granted.invoke_tool_with_prompt_by_name("dummy", json!({})).await.is_ok());
+        assert!(granted.unprompted_denials().is_empty());
+    }
+
     #[tokio::test]
     async fn invoke_tool_with_prompt_disabled_when_scheduled_job() {
         let required = vec![read_permission()];
@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::tools::say::MessageSink;
+
+/// Observers that fall this many events behind miss the oldest ones.
+const SESSION_EVENT_BUFFER: usize = 256;
+
+/// Activity on a session, as streamed to read-only observers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// A tool call finished, successfully or not.
+    ToolEvent {
+        session_id: String,
+        tool: String,
+        outcome: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// The agent sent the user a message mid-turn with the `say` tool.
+    Say { session_id: String, message: String },
+    /// The agent answered a prompt on the session.
+    Done {
+        session_id: String,
+        response: String,
+        model_id: String,
+    },
+}
+
+impl SessionEvent {
+    pub fn session_id(&self) -> &str {
+        match self {
+            Self::ToolEvent { session_id, .. }
+            | Self::Say { session_id, .. }
+            | Self::Done { session_id, .. } => session_id,
+        }
+    }
+
+    /// Name for the SSE `event:` field.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ToolEvent { .. } => "tool_event",
+            Self::Say { .. } => "say",
+            Self::Done { .. } => "done",
+        }
+    }
+}
+
+/// Publishes `say` messages as [`SessionEvent::Say`] on one session, for observers
+/// following its event stream, and keeps them for the turn's response.
+pub struct SessionEventSink {
+    events: SessionEvents,
+    session_id: String,
+    sent: Mutex<Vec<String>>,
+}
+
+impl SessionEventSink {
+    pub fn new(events: SessionEvents, session_id: impl Into<String>) -> Self {
+        Self {
+            events,
+            session_id: session_id.into(),
+            sent: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Messages sent so far, oldest first.
+    pub fn sent(&self) -> Vec<String> {
+        self.sent
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl MessageSink for SessionEventSink {
+    async fn send(&self, message: &str) -> Result<(), anyhow::Error> {
+        self.sent
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(message.to_string());
+        self.events.publish(SessionEvent::Say {
+            session_id: self.session_id.clone(),
+            message: message.to_string(),
+        });
+        Ok(())
+    }
+}
+
+/// Fan-out of [`SessionEvent`]s from every kernel and channel sharing it.
+#[derive(Debug, Clone)]
+pub struct SessionEvents {
+    sender: broadcast::Sender<SessionEvent>,
+}
+
+impl Default for SessionEvents {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(SESSION_EVENT_BUFFER);
+        Self { sender }
+    }
+}
+
+impl SessionEvents {
+    /// Sends `event` to current subscribers; it is dropped when nobody is listening.
+    pub fn publish(&self, event: SessionEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.sender.subscribe()
+    }
+}
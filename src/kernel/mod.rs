@@ -1,3 +1,6 @@
 pub mod core;
+pub mod events;
 pub mod permissions;
+pub mod tool_policy;
+pub mod transcript;
 // pub mod session;
@@ -1,8 +1,10 @@
 use std::collections::HashSet;
 use std::path::Path;
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 
 use async_trait::async_trait;
+use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::config::PermissionsConfig;
@@ -54,12 +56,47 @@ pub struct CapabilitySet {
     permissions: HashSet<Permission>,
 }
 
+/// Deliberate grant of global memory scope to a channel, or to specific users on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalMemoryGrant {
+    pub channel: String,
+    /// User ids on `channel` the grant applies to; empty means every user.
+    pub users: Vec<String>,
+    pub write: bool,
+}
+
+impl GlobalMemoryGrant {
+    /// Global memory permissions this grant gives `user_id` on `channel_id`.
+    pub fn permissions_for(
+        &self,
+        channel_id: Option<&str>,
+        user_id: Option<&str>,
+    ) -> Vec<Permission> {
+        let user_matches = self.users.is_empty()
+            || user_id.is_some_and(|user_id| self.users.iter().any(|user| user == user_id));
+        if channel_id != Some(self.channel.as_str()) || !user_matches {
+            return Vec::new();
+        }
+        let mut permissions = vec![Permission::MemoryRead {
+            scope: MemoryScope::Global,
+        }];
+        if self.write {
+            permissions.push(Permission::MemoryWrite {
+                scope: MemoryScope::Global,
+            });
+        }
+        permissions
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelPermissionProfile {
     pub pre_authorized: CapabilitySet,
     pub max_allowed: CapabilitySet,
     pub allow_user_prompts: bool,
     pub prompt_timeout_secs: u64,
+    #[serde(default)]
+    pub prompt_timeout_action: PromptTimeoutAction,
 }
 
 impl Default for ChannelPermissionProfile {
@@ -69,6 +106,27 @@ impl Default for ChannelPermissionProfile {
             max_allowed: CapabilitySet::empty(),
             allow_user_prompts: true,
             prompt_timeout_secs: 30,
+            prompt_timeout_action: PromptTimeoutAction::Deny,
+        }
+    }
+}
+
+/// What a permission prompt that gets no answer resolves to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptTimeoutAction {
+    #[default]
+    Deny,
+    /// Run the tool this once, as if the user had answered "allow once".
+    AllowOnce,
+}
+
+impl PromptTimeoutAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "deny" => Some(Self::Deny),
+            "allow_once" => Some(Self::AllowOnce),
+            _ => None,
         }
     }
 }
@@ -82,6 +140,8 @@ pub enum PromptDecision {
 
 #[async_trait]
 pub trait PermissionPrompter: Send + Sync {
+    /// Asks the user about `permissions`; `None` means no answer arrived within
+    /// `timeout_secs`, which the kernel resolves with the channel's `prompt_timeout_action`.
     async fn prompt(
         &self,
         tool_name: &str,
@@ -125,6 +185,15 @@ impl CapabilitySet {
         required.iter().any(|permission| self.allows(permission))
     }
 
+    /// Permissions in `required` that nothing in this set covers, in order.
+    pub fn missing(&self, required: &[Permission]) -> Vec<Permission> {
+        required
+            .iter()
+            .filter(|permission| !self.allows(permission))
+            .cloned()
+            .collect()
+    }
+
     pub fn from_config_with_base(config: &PermissionsConfig, base_dir: &Path) -> Self {
         let mut set = CapabilitySet::empty();
 
@@ -198,7 +267,7 @@ impl PathPattern {
     pub fn matches(&self, path: &Path) -> bool {
         let value = path.to_string_lossy();
         let pattern_value = expand_tilde(&self.0);
-        glob::Pattern::new(&pattern_value)
+        compiled_glob(&pattern_value)
             .map(|pattern| pattern.matches(&value))
             .unwrap_or(false)
     }
@@ -206,12 +275,39 @@ impl PathPattern {
 
 impl DomainPattern {
     pub fn matches(&self, domain: &str) -> bool {
-        glob::Pattern::new(&self.0)
+        compiled_glob(&self.0)
             .map(|pattern| pattern.matches(domain))
             .unwrap_or(false)
     }
 }
 
+const GLOB_CACHE_LIMIT: usize = 4096;
+
+/// Compiled glob patterns keyed by pattern text. Capabilities are matched on every tool
+/// call, so each distinct pattern is compiled once; invalid patterns are cached as `None`.
+static GLOB_CACHE: LazyLock<DashMap<String, Option<Arc<glob::Pattern>>>> =
+    LazyLock::new(DashMap::new);
+
+#[cfg(test)]
+thread_local! {
+    static GLOB_COMPILES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn compiled_glob(value: &str) -> Option<Arc<glob::Pattern>> {
+    if let Some(cached) = GLOB_CACHE.get(value) {
+        return cached.clone();
+    }
+    #[cfg(test)]
+    GLOB_COMPILES.with(|count| count.set(count.get() + 1));
+    let compiled = glob::Pattern::new(value).ok().map(Arc::new);
+    // Patterns can come from per-request grants; keep the cache bounded.
+    if GLOB_CACHE.len() >= GLOB_CACHE_LIMIT {
+        GLOB_CACHE.clear();
+    }
+    GLOB_CACHE.insert(value.to_string(), compiled.clone());
+    compiled
+}
+
 fn expand_tilde(value: &str) -> String {
     if (value == "~" || value.starts_with("~/"))
         && let Some(home) = dirs::home_dir()
@@ -435,12 +531,64 @@ impl MemoryScope {
 
 #[cfg(test)]
 mod tests {
-    use super::{CapabilitySet, DomainPattern, MemoryScope, PathPattern, Permission};
+    use super::{
+        CapabilitySet, DomainPattern, GLOB_COMPILES, MemoryScope, PathPattern, Permission,
+    };
     use crate::config::{FilesystemPermissions, PermissionsConfig};
     use crate::tools::traits::ToolContext;
     use std::path::PathBuf;
     use std::str::FromStr;
 
+    #[test]
+    fn glob_patterns_compile_once_across_matches() {
+        let pattern = DomainPattern(format!("*.{}.example", uuid::Uuid::new_v4()));
+        let host = pattern.0.replace('*', "api");
+        let before = GLOB_COMPILES.with(|count| count.get());
+        for _ in 0..100 {
+            assert!(pattern.matches(&host));
+            assert!(!pattern.matches("api.other.example"));
+        }
+        assert_eq!(GLOB_COMPILES.with(|count| count.get()) - before, 1);
+
+        let invalid = DomainPattern(format!("[{}", uuid::Uuid::new_v4()));
+        for _ in 0..10 {
+            assert!(!invalid.matches("example.com"));
+        }
+        assert_eq!(GLOB_COMPILES.with(|count| count.get()) - before, 2);
+    }
+
+    #[test]
+    fn missing_lists_only_uncovered_requirements() {
+        let mut set = CapabilitySet::empty();
+        set.insert(Permission::FileRead {
+            path: PathPattern("/data/**".to_string()),
+        });
+        set.insert(Permission::NetAccess {
+            domain: DomainPattern("*.github.com".to_string()),
+        });
+        let write = Permission::FileWrite {
+            path: PathPattern("/data/out.txt".to_string()),
+        };
+        let other_host = Permission::NetAccess {
+            domain: DomainPattern("example.com".to_string()),
+        };
+        let required = vec![
+            Permission::FileRead {
+                path: PathPattern("/data/in.txt".to_string()),
+            },
+            write.clone(),
+            Permission::NetAccess {
+                domain: DomainPattern("api.github.com".to_string()),
+            },
+            other_host.clone(),
+        ];
+
+        assert_eq!(set.missing(&required), vec![write.clone(), other_host]);
+        assert_eq!(write.to_string(), "filesystem:write:/data/out.txt");
+        assert!(set.missing(&required[..1]).is_empty());
+        assert!(set.missing(&[]).is_empty());
+    }
+
     #[test]
     fn capability_set_allows_globbed_paths() {
         let mut set = CapabilitySet::empty();
@@ -691,11 +839,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: crate::tools::traits::ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
         assert!(permission.is_auto_granted(&ctx));
     }
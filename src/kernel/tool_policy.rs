@@ -0,0 +1,138 @@
+use serde_json::Value;
+
+use crate::config::ToolPolicyConfig;
+use crate::tools::traits::{PreExecutionDecision, PreExecutionPolicy};
+
+pub fn parse_decision(value: &str) -> Option<PreExecutionDecision> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "allow" => Some(PreExecutionDecision::Allow),
+        "require_approval" => Some(PreExecutionDecision::RequireApproval),
+        "deny" => Some(PreExecutionDecision::Deny),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ToolPolicyRule {
+    key: String,
+    tool: String,
+    path: Option<String>,
+    contains: Option<String>,
+    decision: PreExecutionDecision,
+    reason: Option<String>,
+}
+
+impl ToolPolicyRule {
+    fn matches(&self, tool: &str, input: &Value) -> bool {
+        if self.tool != "*" && self.tool != tool {
+            return false;
+        }
+        let value = match self.path.as_deref() {
+            Some(path) => match input.pointer(path) {
+                Some(value) => value,
+                None => return false,
+            },
+            None => input,
+        };
+        let Some(needle) = self.contains.as_deref() else {
+            return true;
+        };
+        match value {
+            Value::String(text) => text.contains(needle),
+            other => other.to_string().contains(needle),
+        }
+    }
+}
+
+/// Config-driven `[[tools.policies]]` rules the kernel checks before a tool's own
+/// pre-execution policy. Rules are evaluated in order and the first match wins.
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicyRules {
+    rules: Vec<ToolPolicyRule>,
+}
+
+impl ToolPolicyRules {
+    /// Builds rules from config, skipping entries whose decision failed validation.
+    pub fn from_config(policies: &[ToolPolicyConfig]) -> Self {
+        let rules = policies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, policy)| {
+                Some(ToolPolicyRule {
+                    key: format!("tools.policies[{index}]"),
+                    tool: policy.tool.trim().to_string(),
+                    path: policy.path.clone(),
+                    contains: policy.contains.clone(),
+                    decision: parse_decision(&policy.decision)?,
+                    reason: policy.reason.clone(),
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn evaluate(&self, tool: &str, input: &Value) -> Option<PreExecutionPolicy> {
+        let rule = self.rules.iter().find(|rule| rule.matches(tool, input))?;
+        Some(PreExecutionPolicy {
+            decision: rule.decision,
+            reason: Some(
+                rule.reason
+                    .clone()
+                    .unwrap_or_else(|| "matched configured tool policy".to_string()),
+            ),
+            policy_key: Some(rule.key.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::ToolPolicyRules;
+    use crate::config::ToolPolicyConfig;
+    use crate::tools::traits::PreExecutionDecision;
+
+    fn rule(
+        tool: &str,
+        path: Option<&str>,
+        contains: Option<&str>,
+        decision: &str,
+    ) -> ToolPolicyConfig {
+        ToolPolicyConfig {
+            tool: tool.to_string(),
+            path: path.map(str::to_string),
+            contains: contains.map(str::to_string),
+            decision: decision.to_string(),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let rules = ToolPolicyRules::from_config(&[
+            rule("shell", Some("/args"), Some("--dry-run"), "allow"),
+            rule("shell", Some("/command"), Some("rm"), "require_approval"),
+            rule("*", None, Some("secret"), "deny"),
+        ]);
+
+        let policy = rules
+            .evaluate("shell", &json!({ "command": "rm", "args": ["-rf", "/tmp/x"] }))
+            .unwrap();
+        assert_eq!(policy.decision, PreExecutionDecision::RequireApproval);
+        assert_eq!(policy.policy_key.as_deref(), Some("tools.policies[1]"));
+
+        let policy = rules
+            .evaluate("shell", &json!({ "command": "rm", "args": ["--dry-run"] }))
+            .unwrap();
+        assert_eq!(policy.decision, PreExecutionDecision::Allow);
+
+        let policy = rules
+            .evaluate("http_fetch", &json!({ "url": "https://example.com/secret" }))
+            .unwrap();
+        assert_eq!(policy.decision, PreExecutionDecision::Deny);
+
+        assert!(rules.evaluate("shell", &json!({ "command": "ls" })).is_none());
+        assert!(rules.evaluate("filesystem", &json!({ "path": "/tmp" })).is_none());
+    }
+}
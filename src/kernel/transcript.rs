@@ -0,0 +1,144 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Input keys whose values are never written to the transcript. Keys are split into
+/// lowercase words at punctuation and camelCase humps, and a key is redacted when one of
+/// its words is listed here, so `db_password` and `x-auth-token` are covered but
+/// `max_tokens` is not.
+const REDACTED_WORDS: &[&str] = &[
+    "password",
+    "passwd",
+    "passphrase",
+    "secret",
+    "secrets",
+    "token",
+    "apikey",
+    "authorization",
+    "cookie",
+    "cookies",
+    "credential",
+    "credentials",
+];
+
+/// Adjacent key words that are only sensitive together, like `api_key` or `privateKey`.
+const REDACTED_WORD_PAIRS: &[(&str, &str)] = &[("api", "key"), ("private", "key")];
+
+/// Longer string values are cut to this many characters in the transcript.
+const MAX_TRANSCRIPT_STRING_CHARS: usize = 500;
+
+/// One tool call made during a turn, as persisted in the session history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCallRecord {
+    pub tool: String,
+    /// The call's input with sensitive values redacted and long strings truncated.
+    pub input: Value,
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ToolCallRecord {
+    pub fn new(tool: &str, input: &Value, error: Option<String>) -> Self {
+        Self {
+            tool: tool.to_string(),
+            input: redact_tool_input(input),
+            outcome: if error.is_some() { "error" } else { "success" }.to_string(),
+            error,
+        }
+    }
+
+    /// JSON form stored as the content of a `tool` message.
+    pub fn to_content(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| format!("{{\"tool\":{:?}}}", self.tool))
+    }
+}
+
+/// Copy of `input` that is safe to store: values under sensitive keys become `[redacted]`
+/// and long strings are truncated.
+pub fn redact_tool_input(input: &Value) -> Value {
+    match input {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive_key(key) {
+                        Value::String("[redacted]".to_string())
+                    } else {
+                        redact_tool_input(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_tool_input).collect()),
+        Value::String(text) if text.chars().count() > MAX_TRANSCRIPT_STRING_CHARS => {
+            let truncated = text.chars().take(MAX_TRANSCRIPT_STRING_CHARS).collect::<String>();
+            Value::String(format!("{truncated}…"))
+        }
+        other => other.clone(),
+    }
+}
+
+fn is_sensitive_key(key: &str) -> bool {
+    let words = key_words(key);
+    words.iter().any(|word| REDACTED_WORDS.contains(&word.as_str()))
+        || words.windows(2).any(|pair| {
+            REDACTED_WORD_PAIRS.contains(&(pair[0].as_str(), pair[1].as_str()))
+        })
+}
+
+/// Lowercase words of `key`, split at non-alphanumeric characters and where a lowercase
+/// letter or digit is followed by an uppercase one.
+fn key_words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut after_lower = false;
+    for ch in key.chars() {
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            after_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && after_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        after_lower = ch.is_lowercase() || ch.is_numeric();
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::redact_tool_input;
+
+    #[test]
+    fn credential_keys_are_redacted_but_token_counts_are_not() {
+        let input = serde_json::json!({
+            "api_key": "k1",
+            "apiKey": "k2",
+            "x-auth-token": "t1",
+            "accessToken": "t2",
+            "db_password": "p",
+            "client_secret": "s",
+            "max_tokens": 256,
+            "context_budget_tokens": 4000,
+            "keyword": "weather",
+            "nested": [{ "refresh_token": "t3", "tokenizer": "bpe" }],
+        });
+        let redacted = redact_tool_input(&input);
+        for key in ["api_key", "apiKey", "x-auth-token", "accessToken", "db_password"] {
+            assert_eq!(redacted[key], "[redacted]", "{key}");
+        }
+        assert_eq!(redacted["client_secret"], "[redacted]");
+        assert_eq!(redacted["max_tokens"], 256);
+        assert_eq!(redacted["context_budget_tokens"], 4000);
+        assert_eq!(redacted["keyword"], "weather");
+        assert_eq!(redacted["nested"][0]["refresh_token"], "[redacted]");
+        assert_eq!(redacted["nested"][0]["tokenizer"], "bpe");
+    }
+}
@@ -7,43 +7,44 @@ mod scheduler;
 mod session;
 mod tools;
 
+use std::io::IsTerminal;
+
 use anyhow::Result;
 use tracing_subscriber::EnvFilter;
 
 use crate::channels::{api, repl, whatsapp};
 use crate::config::Config;
-use crate::kernel::core::{Kernel, SoftTimeoutPolicy};
+use crate::kernel::core::{Kernel, SoftTimeoutPolicy, ToolRetryPolicy};
 use crate::kernel::permissions::CapabilitySet;
+use crate::kernel::tool_policy::ToolPolicyRules;
 use crate::providers::factory::{ProviderAgentBuilder, ProviderFactory};
+use crate::tools::echo::EchoTool;
 use crate::tools::filesystem::FilesystemTool;
 use crate::tools::http::HttpTool;
 use crate::tools::memory::MemoryTool;
 use crate::tools::multimodal_looker::MultimodalLookerTool;
 use crate::tools::notify::NotifyTool;
+use crate::tools::process::ProcessTool;
 use crate::tools::registry::ToolRegistry;
+use crate::tools::say::SayTool;
 use crate::tools::schedule::ScheduleTool;
 use crate::tools::search::SearchTool;
 use crate::tools::shell::ShellTool;
 use crate::tools::shell_policy::ShellPolicy;
 use crate::tools::shell_runner::{ContainerRunner, ExecutionLimits, HostRunner, ShellRunner};
+use crate::session::db::SqliteStore;
 use crate::session::manager::SessionManager;
 
 fn build_kernel(
     config: &Config,
     _agent_builder: ProviderAgentBuilder,
     scheduler: Option<std::sync::Arc<crate::scheduler::service::SchedulerService>>,
+    session_store: &SqliteStore,
 ) -> Result<Kernel> {
     let mut registry = ToolRegistry::new();
-    let session_store = crate::session::db::SqliteStore::new(
-        config
-            .data_dir()
-            .join("sessions.db")
-            .to_string_lossy()
-            .to_string(),
-    );
-    session_store.touch()?;
-    registry.register(std::sync::Arc::new(FilesystemTool::new()))?;
-    let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let tools = config.tools();
+    register_tool(&mut registry, &tools, std::sync::Arc::new(FilesystemTool::new()))?;
+    let base_dir = config.base_dir();
     let jail_root = config
         .permissions()
         .filesystem
@@ -67,19 +68,28 @@ fn build_kernel(
     } else {
         shell_timeout.saturating_add(extension)
     };
-    registry.register(std::sync::Arc::new(
-        ShellTool::with_policy(shell_policy)
-            .with_limits(shell_limits)
-            .with_limits_for_timeout(max_timeout)
-            .with_runner(shell_runner),
-    ))?;
-    registry.register(std::sync::Arc::new(HttpTool::new()?))?;
-    registry.register(std::sync::Arc::new(ScheduleTool::new()))?;
-    registry.register(std::sync::Arc::new(NotifyTool::new()))?;
-    registry.register(std::sync::Arc::new(MemoryTool::new(session_store.clone())))?;
+    register_tool(
+        &mut registry,
+        &tools,
+        std::sync::Arc::new(
+            ShellTool::with_policy(shell_policy)
+                .with_limits(shell_limits)
+                .with_limits_for_timeout(max_timeout)
+                .with_runner(shell_runner),
+        ),
+    )?;
+    register_tool(&mut registry, &tools, std::sync::Arc::new(HttpTool::new()?))?;
+    register_tool(&mut registry, &tools, std::sync::Arc::new(ScheduleTool::new()))?;
+    register_tool(&mut registry, &tools, std::sync::Arc::new(NotifyTool::new()))?;
+    register_tool(&mut registry, &tools, std::sync::Arc::new(SayTool::new()))?;
+    register_tool(
+        &mut registry,
+        &tools,
+        std::sync::Arc::new(MemoryTool::new(session_store.clone())),
+    )?;
     if let Some(search_config) = &config.search
         && let Ok(search_tool) = SearchTool::new(search_config) {
-            registry.register(std::sync::Arc::new(search_tool))?;
+            register_tool(&mut registry, &tools, std::sync::Arc::new(search_tool))?;
         }
     let multimodal_agent = ProviderFactory::build_multimodal_agent(config)?;
     let multimodal_config = config.multimodal.clone().or_else(|| {
@@ -101,8 +111,28 @@ fn build_kernel(
         max_media_size_bytes,
         max_image_size_bytes,
         SessionManager::new(session_store.clone()),
+    )
+    .with_max_calls_per_minute(
+        multimodal_config
+            .clone()
+            .unwrap_or_default()
+            .max_calls_per_minute(),
     );
-    registry.register(std::sync::Arc::new(multimodal_tool))?;
+    register_tool(&mut registry, &tools, std::sync::Arc::new(multimodal_tool))?;
+    let max_output_bytes = config
+        .permissions()
+        .tool_limits
+        .and_then(|limits| limits.max_output_bytes);
+    if tools.debug_enabled() {
+        register_tool(&mut registry, &tools, std::sync::Arc::new(EchoTool::new()))?;
+    }
+    for external in config.tools().external() {
+        let mut tool = ProcessTool::from_config(&external, &base_dir)?;
+        if let Some(max_output_bytes) = max_output_bytes {
+            tool = tool.with_max_output_bytes(max_output_bytes);
+        }
+        register_tool(&mut registry, &tools, std::sync::Arc::new(tool))?;
+    }
     let registry = std::sync::Arc::new(registry);
     let capabilities = CapabilitySet::from_config_with_base(&config.permissions(), &base_dir);
     let max_response_bytes = config.network().max_response_bytes;
@@ -116,11 +146,31 @@ fn build_kernel(
         .with_scheduler(scheduler)
         .with_max_response_bytes(max_response_bytes)
         .with_max_response_chars(max_response_chars)
+        .with_dry_run(config.tools().dry_run())
+        .with_tool_policies(ToolPolicyRules::from_config(&config.tools().policies()))
+        .with_strict_output_schemas(config.tools().strict_output_schemas())
+        .with_global_memory_grants(config.memory().global_access())
         .with_tool_timeouts(default_timeout, tool_timeouts)
+        .with_tool_retries(build_tool_retries(config))
         .with_soft_timeouts(soft_ratio, soft_policy, soft_extension);
     Ok(kernel)
 }
 
+/// Registers `tool` unless `[tools]` enables only other tools or disables it by name.
+fn register_tool(
+    registry: &mut ToolRegistry,
+    tools: &crate::config::ToolsConfig,
+    tool: std::sync::Arc<dyn crate::tools::traits::ToolExecutor>,
+) -> Result<()> {
+    let name = tool.spec().name.clone();
+    if !tools.is_tool_enabled(&name) {
+        tracing::info!(event = "tool_disabled", tool = %name, "skipping disabled tool");
+        return Ok(());
+    }
+    registry.register(tool)?;
+    Ok(())
+}
+
 fn resolve_working_path(base_dir: &std::path::Path, raw: &str) -> std::path::PathBuf {
     let expanded = if raw == "~" || raw.starts_with("~/") {
         if let Some(home) = dirs::home_dir() {
@@ -170,9 +220,33 @@ fn build_tool_timeouts(
         "multimodal_looker".to_string(),
         std::time::Duration::from_secs(multimodal_secs),
     );
+    for external in config.tools().external() {
+        if let Some(timeout_secs) = external.timeout_secs {
+            tool_timeouts.insert(
+                external.name.trim().to_string(),
+                std::time::Duration::from_secs(timeout_secs),
+            );
+        }
+    }
     (std::time::Duration::from_secs(default_secs), tool_timeouts)
 }
 
+fn build_tool_retries(config: &Config) -> std::collections::HashMap<String, ToolRetryPolicy> {
+    config
+        .tools()
+        .retry()
+        .into_iter()
+        .map(|(name, retry)| {
+            let policy = ToolRetryPolicy {
+                max_attempts: retry.max_attempts().max(1),
+                base_backoff: std::time::Duration::from_millis(retry.base_backoff_ms()),
+                jitter: std::time::Duration::from_millis(retry.jitter_ms()),
+            };
+            (name.trim().to_string(), policy)
+        })
+        .collect()
+}
+
 fn build_shell_limits(config: &Config) -> ExecutionLimits {
     let limits = config.permissions().tool_limits;
     let max_memory_bytes = config
@@ -273,55 +347,66 @@ fn container_runtime_available(runtime: &str) -> bool {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let mut args: Vec<String> = std::env::args().collect();
+    let deny_warnings =
+        take_deny_warnings(&mut args, std::env::var("PICOBOT_DENY_WARNINGS").ok().as_deref());
+    // Scripted REPL use keeps stdout for responses, so logs move to stderr.
+    let plain_repl = matches!(args.get(1).map(|arg| arg.as_str()), None | Some("repl"))
+        && (args.iter().any(|arg| arg == "--plain") || !std::io::stdin().is_terminal());
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    tracing_subscriber::fmt()
+    let logs = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_target(false)
-        .init();
+        .with_target(false);
+    if plain_repl {
+        logs.with_writer(std::io::stderr).init();
+    } else {
+        logs.init();
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("validate") {
+        let valid = run_validate_cli(&args[2..], deny_warnings, &mut std::io::stdout())?;
+        std::process::exit(if valid { 0 } else { 1 });
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("config-schema") {
+        let printed = run_config_schema_cli(&args[2..], &mut std::io::stdout())?;
+        std::process::exit(if printed { 0 } else { 1 });
+    }
     let config = Config::load()?;
-    let validation = config.validate()?;
-    for warning in validation.warnings {
-        tracing::warn!(warning = %warning, "config validation warning");
+    if args.get(1).map(|arg| arg.as_str()) == Some("models") {
+        let healthy =
+            run_models_cli(&config, &args[2..], &mut std::io::stdout(), probe_model).await?;
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+    // Every component shares this store, and with it one connection pool.
+    let session_store =
+        SqliteStore::open_migrated(&config.sessions_db_path(), config.sqlite_pragmas())?;
+    if args.get(1).map(|arg| arg.as_str()) == Some("purge") {
+        let purged =
+            run_purge_cli(&session_store, &args[2..], &mut std::io::stdout(), confirm_stdin)?;
+        std::process::exit(if purged { 0 } else { 1 });
     }
+    check_startup_config(&config, deny_warnings)?;
     tracing::info!(
         provider = %config.provider(),
         model = %config.model(),
-        max_turns = config.max_turns(),
+        max_tool_rounds = config.max_tool_rounds(),
         "config loaded"
     );
     let agent_builder = ProviderFactory::build_agent_builder(&config)?;
     let agent_router = ProviderFactory::build_agent_router(&config).ok();
-    let kernel = build_kernel(&config, agent_builder.clone(), None)?;
+    let kernel = build_kernel(&config, agent_builder.clone(), None, &session_store)?;
     let scheduler = if config.scheduler().enabled() {
-        let store = crate::session::db::SqliteStore::new(
-            config
-                .data_dir()
-                .join("picobot.db")
-                .to_string_lossy()
-                .to_string(),
-        );
-        store.touch()?;
-        let schedule_store = crate::scheduler::store::ScheduleStore::new(store.clone());
-        let executor = crate::scheduler::executor::JobExecutor::new(
-            std::sync::Arc::new(kernel.clone()),
-            schedule_store.clone(),
-            config.scheduler(),
+        Some(build_scheduler(
+            &config,
+            &kernel,
+            &session_store,
             agent_builder.clone(),
             agent_router.clone(),
-            config.clone(),
-        );
-        let scheduler = std::sync::Arc::new(crate::scheduler::service::SchedulerService::new(
-            schedule_store,
-            executor,
-            config.scheduler(),
-        ));
-        Some(scheduler)
+        )?)
     } else {
         None
     };
     let kernel = kernel.with_scheduler(scheduler.clone());
 
-    let args: Vec<String> = std::env::args().collect();
     let mode = args.get(1).map(|arg| arg.as_str()).unwrap_or("repl");
 
     if let Some(scheduler) = kernel.context().scheduler.clone() {
@@ -332,17 +417,60 @@ async fn main() -> Result<()> {
     }
 
     match mode {
-        "api" => api::serve(config, kernel, agent_builder.clone()).await,
-        "repl" => repl::run(config, kernel, agent_builder.clone()).await,
-        "whatsapp" => whatsapp::run(config, kernel, agent_builder.clone()).await,
+        "api" => api::serve(config, kernel, agent_builder.clone(), session_store).await,
+        "repl" => {
+            repl::run(config, kernel, agent_builder.clone(), session_store, plain_repl).await
+        }
+        "whatsapp" => whatsapp::run(config, kernel, agent_builder.clone(), session_store).await,
         "schedules" => run_schedules_cli(&config, kernel, &args[2..]),
         other => {
-            eprintln!("unknown mode '{other}', use 'repl', 'api', 'whatsapp', or 'schedules'");
+            eprintln!(
+                "unknown mode '{other}', use 'repl', 'api', 'whatsapp', 'schedules', 'models', \
+                 'purge', 'validate', or 'config-schema'"
+            );
             Ok(())
         }
     }
 }
 
+/// Wires up the scheduler service on `data.scheduler_db`, reusing `session_store` when
+/// both settings name the same file.
+fn build_scheduler(
+    config: &Config,
+    kernel: &Kernel,
+    session_store: &SqliteStore,
+    agent_builder: ProviderAgentBuilder,
+    agent_router: Option<crate::providers::factory::ModelRouter>,
+) -> Result<std::sync::Arc<crate::scheduler::service::SchedulerService>> {
+    let store = if config.scheduler_db_path() == config.sessions_db_path() {
+        session_store.clone()
+    } else {
+        SqliteStore::open_migrated(&config.scheduler_db_path(), config.sqlite_pragmas())?
+    };
+    let schedule_store = crate::scheduler::store::ScheduleStore::new(store);
+    let executor = crate::scheduler::executor::JobExecutor::new(
+        std::sync::Arc::new(kernel.clone()),
+        schedule_store.clone(),
+        config.scheduler(),
+        agent_builder,
+        agent_router,
+        config.clone(),
+    )
+    .with_accounts(
+        crate::channels::whatsapp::whatsapp_accounts(config, &config.whatsapp())
+            .iter()
+            .map(crate::channels::whatsapp::WhatsappAccount::job_account)
+            .collect(),
+    );
+    Ok(std::sync::Arc::new(
+        crate::scheduler::service::SchedulerService::new(
+            schedule_store,
+            executor,
+            config.scheduler(),
+        ),
+    ))
+}
+
 fn run_schedules_cli(_config: &Config, kernel: Kernel, args: &[String]) -> Result<()> {
     let Some(scheduler) = kernel.context().scheduler.clone() else {
         anyhow::bail!("scheduler is disabled; enable [scheduler].enabled = true in config");
@@ -386,3 +514,623 @@ fn run_schedules_cli(_config: &Config, kernel: Kernel, args: &[String]) -> Resul
         }
     }
 }
+
+/// Deletes stored conversation history older than `--older-than <days>`, optionally for one
+/// `--user`. Asks for confirmation through `confirm` unless `--yes` is given. Returns false
+/// when the arguments are invalid or the purge was declined.
+fn run_purge_cli(
+    store: &SqliteStore,
+    args: &[String],
+    out: &mut impl std::io::Write,
+    confirm: impl FnOnce(&str) -> bool,
+) -> Result<bool> {
+    const USAGE: &str = "usage: cargo run -- purge --older-than <days> [--user <id>] [--yes]";
+    let mut days = None;
+    let mut user_id = None;
+    let mut assume_yes = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--older-than" => days = args.next().and_then(|value| value.parse::<u32>().ok()),
+            "--user" => user_id = args.next().cloned(),
+            "--yes" => assume_yes = true,
+            _ => {
+                writeln!(out, "{USAGE}")?;
+                return Ok(false);
+            }
+        }
+    }
+    let Some(days) = days else {
+        writeln!(out, "{USAGE}")?;
+        return Ok(false);
+    };
+    let scope = match &user_id {
+        Some(user_id) => format!("user '{user_id}'"),
+        None => "all users".to_string(),
+    };
+    let question = format!("Delete messages older than {days} day(s) for {scope}?");
+    if !assume_yes && !confirm(&question) {
+        writeln!(out, "purge cancelled")?;
+        return Ok(false);
+    }
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+    let report =
+        SessionManager::new(store.clone()).purge_older_than(cutoff, user_id.as_deref())?;
+    writeln!(
+        out,
+        "purged {} message(s) and {} session(s) older than {days} day(s) for {scope}",
+        report.messages, report.sessions
+    )?;
+    Ok(true)
+}
+
+fn confirm_stdin(question: &str) -> bool {
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Removes `--deny-warnings` from `args` and reports whether it or a truthy
+/// `PICOBOT_DENY_WARNINGS` asked for config warnings to be fatal.
+fn take_deny_warnings(args: &mut Vec<String>, env: Option<&str>) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != "--deny-warnings");
+    let from_env = env.is_some_and(|value| {
+        matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes")
+    });
+    args.len() != before || from_env
+}
+
+/// Validates the config before any channel starts, logging each warning. Errors always
+/// abort; warnings abort too under `deny_warnings`.
+fn check_startup_config(config: &Config, deny_warnings: bool) -> Result<()> {
+    let validation = config.validate()?;
+    for warning in &validation.warnings {
+        tracing::warn!(
+            event = "config_validation_warning",
+            warning = %warning,
+            "config validation warning"
+        );
+    }
+    if deny_warnings && !validation.warnings.is_empty() {
+        return Err(anyhow::anyhow!(
+            "config has {} warning(s) and warnings are denied: {}",
+            validation.warnings.len(),
+            validation.warnings.join("; ")
+        ));
+    }
+    Ok(())
+}
+
+/// Prints the config JSON Schema, or with `--example` a TOML file of every default.
+fn run_config_schema_cli(args: &[String], out: &mut impl std::io::Write) -> Result<bool> {
+    match args {
+        [] => writeln!(out, "{}", serde_json::to_string_pretty(&config::json_schema())?)?,
+        [flag] if flag == "--example" => write!(out, "{}", config::example_toml()?)?,
+        _ => {
+            writeln!(out, "usage: cargo run -- config-schema [--example]")?;
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Lints a config file without starting any channel. Returns whether it is valid, which
+/// under `deny_warnings` also requires it to have no warnings.
+fn run_validate_cli(
+    args: &[String],
+    deny_warnings: bool,
+    out: &mut impl std::io::Write,
+) -> Result<bool> {
+    let path = match args {
+        [] => std::env::var("PICOBOT_CONFIG").unwrap_or_else(|_| "picobot.toml".to_string()),
+        [flag, path] if flag == "--config" => path.clone(),
+        _ => {
+            writeln!(out, "usage: cargo run -- validate [--config <path>] [--deny-warnings]")?;
+            return Ok(false);
+        }
+    };
+    let path = std::path::PathBuf::from(path);
+    if !path.exists() {
+        writeln!(out, "error: config not found at {}", path.display())?;
+        return Ok(false);
+    }
+    let config = match Config::load_from(path.clone()) {
+        Ok(config) => config,
+        Err(err) => {
+            writeln!(out, "error: {err:#}")?;
+            return Ok(false);
+        }
+    };
+    let validation = config.validation_report();
+    for error in &validation.errors {
+        writeln!(out, "error: {error}")?;
+    }
+    for warning in &validation.warnings {
+        writeln!(out, "warning: {warning}")?;
+    }
+    writeln!(
+        out,
+        "{}: {} error(s), {} warning(s)",
+        path.display(),
+        validation.errors.len(),
+        validation.warnings.len()
+    )?;
+    Ok(validation.errors.is_empty() && (!deny_warnings || validation.warnings.is_empty()))
+}
+
+/// Lists configured models (the top-level model when no `[[models]]` are set) and, with
+/// `--check`, probes each one. Returns false if any probe failed.
+async fn run_models_cli<F, Fut>(
+    config: &Config,
+    args: &[String],
+    out: &mut impl std::io::Write,
+    probe: F,
+) -> Result<bool>
+where
+    F: Fn(ProviderAgentBuilder) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let check = match args {
+        [] => false,
+        [flag] if flag == "--check" => true,
+        _ => {
+            writeln!(out, "usage: cargo run -- models [--check]")?;
+            return Ok(false);
+        }
+    };
+    let router = ProviderFactory::build_agent_router(config)?;
+    let models = if router.is_empty() {
+        vec![crate::config::ModelConfig {
+            id: "default".to_string(),
+            provider: Some(config.provider().to_string()),
+            model: config.model().to_string(),
+            ..Default::default()
+        }]
+    } else {
+        router.models().to_vec()
+    };
+    let default_id = router.default_id().unwrap_or("default");
+    let mut healthy = true;
+    for model in &models {
+        let marker = if model.id == default_id { "*" } else { " " };
+        let provider = model.provider.as_deref().unwrap_or_else(|| config.provider());
+        let mut line = format!("{marker} {}  {provider}  {}", model.id, model.model);
+        if check {
+            let result = match ProviderAgentBuilder::from_model_config(model, config) {
+                Ok(builder) => probe(builder).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(()) => line.push_str("  ok"),
+                Err(err) => {
+                    healthy = false;
+                    line.push_str(&format!("  failed: {err:#}"));
+                }
+            }
+        }
+        writeln!(out, "{line}")?;
+    }
+    Ok(healthy)
+}
+
+/// Sends a one-word prompt without tools or session state to confirm a model answers.
+async fn probe_model(builder: ProviderAgentBuilder) -> Result<()> {
+    let kernel = Kernel::new(std::sync::Arc::new(ToolRegistry::new()));
+    let agent = builder.build_without_tools()?.for_context(kernel.context());
+    agent.prompt("Reply with OK.").await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_kernel, build_scheduler, check_startup_config, run_config_schema_cli,
+        run_models_cli, run_purge_cli, run_validate_cli, take_deny_warnings,
+    };
+    use crate::config::{Config, ModelConfig, RoutingConfig};
+    use crate::providers::factory::ProviderFactory;
+    use crate::session::db::SqliteStore;
+
+    fn validate(contents: &str) -> (bool, String) {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("picobot.toml");
+        std::fs::write(&path, contents).unwrap();
+        let mut out = Vec::new();
+        let args = ["--config".to_string(), path.to_string_lossy().to_string()];
+        let valid = run_validate_cli(&args, false, &mut out).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        (valid, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn validate_subcommand_reports_errors_and_warnings() {
+        let (valid, output) = validate(
+            "provider = \"nope\"\napi_key_env = \"PATH\"\n[agent]\nmax_tool_rounds = 80\n",
+        );
+        assert!(!valid);
+        assert!(output.contains("error: unsupported provider 'nope'"), "{output}");
+        assert!(output.contains("warning: "), "{output}");
+
+        let (valid, output) = validate("provider = \"openai\"\napi_key_env = \"PATH\"\n");
+        assert!(valid, "{output}");
+        assert!(output.contains("0 error(s)"), "{output}");
+    }
+
+    #[test]
+    fn validate_subcommand_probes_base_url_when_enabled() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = format!(
+            "provider = \"openai\"\napi_key_env = \"PATH\"\n\
+             base_url = \"127.0.0.1:{port}/\"\nprobe_base_url = true\n"
+        );
+        let (valid, output) = validate(&config);
+        assert!(valid, "{output}");
+        assert!(
+            output.contains(&format!("base_url probe failed: http://127.0.0.1:{port}/models")),
+            "{output}"
+        );
+
+        let (valid, output) = validate("provider = \"openai\"\napi_key_env = \"PATH\"\n\
+                                        base_url = \"ftp://gateway.example.com\"\n");
+        assert!(!valid);
+        assert!(output.contains("invalid base_url"), "{output}");
+    }
+
+    #[test]
+    fn deny_warnings_makes_config_warnings_fatal() {
+        let config: Config = toml::from_str(
+            "provider = \"openai\"\napi_key_env = \"PATH\"\n[agent]\nmax_tool_rounds = 80\n",
+        )
+        .unwrap();
+        assert!(!config.validation_report().warnings.is_empty());
+        assert!(check_startup_config(&config, false).is_ok());
+        let err = check_startup_config(&config, true).unwrap_err();
+        assert!(err.to_string().contains("warnings are denied"), "{err}");
+
+        let clean: Config =
+            toml::from_str("provider = \"openai\"\napi_key_env = \"PATH\"\n").unwrap();
+        assert!(check_startup_config(&clean, true).is_ok());
+
+        let mut args = vec!["picobot".to_string(), "--deny-warnings".to_string()];
+        assert!(take_deny_warnings(&mut args, None));
+        assert_eq!(args, vec!["picobot"]);
+        assert!(take_deny_warnings(&mut args, Some("true")));
+        assert!(!take_deny_warnings(&mut args, Some("0")));
+    }
+
+    #[test]
+    fn validate_subcommand_rejects_missing_or_unparsable_config() {
+        let mut out = Vec::new();
+        let args = ["--config".to_string(), "/nonexistent/picobot.toml".to_string()];
+        assert!(!run_validate_cli(&args, false, &mut out).unwrap());
+
+        let (valid, output) = validate("provider = [");
+        assert!(!valid);
+        assert!(output.contains("failed to parse config"), "{output}");
+    }
+
+    #[test]
+    fn config_schema_example_round_trips_through_config() {
+        let mut out = Vec::new();
+        assert!(run_config_schema_cli(&["--example".to_string()], &mut out).unwrap());
+        let example = String::from_utf8(out).unwrap();
+        let config: Config = toml::from_str(&example).unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.system_prompt(), defaults.system_prompt());
+        assert_eq!(config.scheduler().max_concurrent_jobs(), 4);
+        assert_eq!(config.sqlite_pragmas().journal_mode(), "WAL");
+        assert_eq!(
+            config.whatsapp().messages().busy(),
+            defaults.whatsapp().messages().busy()
+        );
+        assert!(config.validation_report().warnings.is_empty());
+
+        // Unknown keys would deserialize silently, so check each default names a real field.
+        let mut out = Vec::new();
+        assert!(run_config_schema_cli(&[], &mut out).unwrap());
+        let schema: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        fn assert_documented(
+            schema: &serde_json::Value,
+            node: &serde_json::Value,
+            defaults: &serde_json::Value,
+        ) {
+            for (key, value) in defaults.as_object().unwrap() {
+                let property = &node["properties"][key];
+                if value.is_object() {
+                    let reference = property["anyOf"][0]["$ref"].as_str().unwrap();
+                    let name = reference.strip_prefix("#/$defs/").unwrap();
+                    assert_documented(schema, &schema["$defs"][name], value);
+                } else {
+                    assert_eq!(&property["default"], value, "default for {key}");
+                }
+            }
+        }
+        assert_documented(&schema, &schema, &crate::config::default_values());
+        assert!(schema["$defs"]["ChannelConfig"]["properties"]["max_turns"]["description"]
+            .as_str()
+            .is_some());
+
+        let mut out = Vec::new();
+        assert!(!run_config_schema_cli(&["--bogus".to_string()], &mut out).unwrap());
+    }
+
+    #[test]
+    fn allowed_senders_are_normalized_on_load() {
+        let contents = "provider = \"openai\"\napi_key_env = \"PATH\"\n[whatsapp]\n\
+                        allowed_senders = [\"+1 (555) 123-4567\", \"15550001111:3@C.US\", \
+                        \"not a number\"]\n";
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("picobot.toml");
+        std::fs::write(&path, contents).unwrap();
+        let config = Config::load_from(path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(
+            config.whatsapp().allowed_senders.unwrap(),
+            vec!["15551234567", "15550001111@c.us", "not a number"]
+        );
+
+        let (valid, output) = validate(contents);
+        assert!(valid, "{output}");
+        assert!(
+            output.contains("allowed_senders entry 'not a number' is not a phone number"),
+            "{output}"
+        );
+        assert_eq!(output.matches("allowed_senders entry").count(), 1, "{output}");
+    }
+
+    #[test]
+    fn relative_data_dir_is_resolved_once_on_load() {
+        let contents = "provider = \"openai\"\napi_key_env = \"PATH\"\ndata_dir = \"rel-data\"\n\
+                        [data]\nscheduler_db = \"sched/picobot.db\"\n";
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("picobot.toml");
+        std::fs::write(&path, contents).unwrap();
+        let config = Config::load_from(path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let data_dir = config.data_dir();
+        assert!(data_dir.is_absolute(), "{}", data_dir.display());
+        assert!(data_dir.ends_with("rel-data"), "{}", data_dir.display());
+        assert_eq!(config.sessions_db_path(), data_dir.join("sessions.db"));
+        assert_eq!(config.scheduler_db_path(), data_dir.join("sched/picobot.db"));
+        assert_eq!(config.whatsapp_db_path(), data_dir.join("whatsapp.db"));
+
+        // A later resolve against another base (e.g. after a cwd change) keeps the same paths.
+        let mut moved = config.clone();
+        moved.resolve_paths(std::path::Path::new("/somewhere/else"));
+        assert_eq!(moved.data_dir(), data_dir);
+        assert_eq!(moved.sessions_db_path(), config.sessions_db_path());
+
+        let mut relative = Config {
+            data_dir: Some("./state".to_string()),
+            ..Config::default()
+        };
+        relative.resolve_paths(std::path::Path::new("/srv/picobot"));
+        assert_eq!(relative.data_dir(), std::path::PathBuf::from("/srv/picobot/state"));
+        assert_eq!(relative.base_dir(), std::path::PathBuf::from("/srv/picobot"));
+    }
+
+    fn two_model_config() -> Config {
+        Config {
+            provider: Some("openai".to_string()),
+            models: Some(vec![
+                ModelConfig {
+                    id: "fast".to_string(),
+                    model: "gpt-4o-mini".to_string(),
+                    ..Default::default()
+                },
+                ModelConfig {
+                    id: "router".to_string(),
+                    provider: Some("openrouter".to_string()),
+                    model: "openai/gpt-4o".to_string(),
+                    ..Default::default()
+                },
+            ]),
+            routing: Some(RoutingConfig {
+                default_model: Some("router".to_string()),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn models_subcommand_lists_every_configured_model() {
+        let probes = std::sync::atomic::AtomicUsize::new(0);
+        let mut out = Vec::new();
+        let healthy = run_models_cli(&two_model_config(), &[], &mut out, |_| {
+            probes.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(healthy);
+        assert_eq!(probes.load(std::sync::atomic::Ordering::SeqCst), 0);
+        assert!(output.contains("  fast  openai  gpt-4o-mini"), "{output}");
+        assert!(output.contains("* router  openrouter  openai/gpt-4o"), "{output}");
+    }
+
+    #[tokio::test]
+    async fn models_check_reports_each_probe() {
+        let mut out = Vec::new();
+        let args = ["--check".to_string()];
+        let healthy = run_models_cli(&two_model_config(), &args, &mut out, |builder| {
+            let agent = builder.build_without_tools_with_env(|_| Some("test-key".to_string()));
+            async move {
+                match agent?.provider_name() {
+                    "openrouter" => anyhow::bail!("401 unauthorized"),
+                    _ => Ok(()),
+                }
+            }
+        })
+        .await
+        .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(!healthy);
+        assert!(output.contains("fast  openai  gpt-4o-mini  ok"), "{output}");
+        assert!(output.contains("router  openrouter  openai/gpt-4o  failed: 401 unauthorized"), "{output}");
+    }
+
+    #[tokio::test]
+    async fn disabled_builtin_tool_is_not_registered() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        let config: Config = toml::from_str(&format!(
+            "provider = \"openai\"\napi_key_env = \"PATH\"\ndata_dir = {:?}\n\
+             [tools]\ndisabled = [\"shell\"]\n",
+            dir.to_string_lossy()
+        ))
+        .unwrap();
+        let builder = ProviderFactory::build_agent_builder(&config).unwrap();
+        let store =
+            SqliteStore::open_migrated(&config.sessions_db_path(), config.sqlite_pragmas()).unwrap();
+        let kernel = build_kernel(&config, builder, None, &store).unwrap();
+
+        let names = kernel
+            .tool_registry()
+            .specs()
+            .into_iter()
+            .map(|spec| spec.name)
+            .collect::<Vec<_>>();
+        assert!(!names.contains(&"shell".to_string()), "{names:?}");
+        assert!(names.contains(&"filesystem".to_string()), "{names:?}");
+        let err = kernel
+            .invoke_tool_with_prompt_by_name("shell", serde_json::json!({ "command": "ls" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown tool 'shell'"), "{err}");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn purge_subcommand_removes_old_rows_and_reports_counts() {
+        use crate::kernel::permissions::CapabilitySet;
+        use crate::session::manager::SessionManager;
+        use crate::session::types::{MessageType, NewMessage};
+
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        let config: Config = toml::from_str(&format!(
+            "provider = \"openai\"\napi_key_env = \"PATH\"\ndata_dir = {:?}\n",
+            dir.to_string_lossy()
+        ))
+        .unwrap();
+        let store =
+            SqliteStore::open_migrated(&config.sessions_db_path(), config.sqlite_pragmas()).unwrap();
+        let manager = SessionManager::new(store.clone());
+        let message = NewMessage::new(MessageType::User, "hello");
+        for (session_id, user_id) in [("old", "alice"), ("mixed", "alice"), ("bob", "bob")] {
+            manager
+                .create_session(
+                    session_id.to_string(),
+                    "api".to_string(),
+                    "api".to_string(),
+                    user_id.to_string(),
+                    CapabilitySet::empty(),
+                )
+                .unwrap();
+            manager.append_message(session_id, &message).unwrap();
+        }
+        manager.append_message("mixed", &message).unwrap();
+        let old = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        manager
+            .store()
+            .with_connection(|conn| {
+                conn.execute(
+                    "UPDATE messages SET created_at = ?1
+                     WHERE session_id IN ('old', 'bob') OR (session_id = 'mixed' AND seq_order = 0)",
+                    [&old],
+                )
+                .unwrap();
+                conn.execute("UPDATE sessions SET last_active = ?1", [&old])
+                    .unwrap();
+                Ok(())
+            })
+            .unwrap();
+
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let mut out = Vec::new();
+        let purged = run_purge_cli(&store, &args(&["--older-than", "30"]), &mut out, |_| false);
+        assert!(!purged.unwrap());
+        assert!(manager.get_session("old").unwrap().is_some());
+
+        let mut out = Vec::new();
+        let purged = run_purge_cli(
+            &store,
+            &args(&["--older-than", "30", "--user", "alice", "--yes"]),
+            &mut out,
+            |_| panic!("--yes skips confirmation"),
+        );
+        assert!(purged.unwrap());
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("purged 2 message(s) and 1 session(s)"), "{output}");
+        assert!(manager.get_session("old").unwrap().is_none());
+        assert_eq!(manager.get_messages("mixed", 10).unwrap().len(), 1);
+        assert_eq!(manager.get_messages("bob", 10).unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn scheduler_uses_its_own_database_when_configured() {
+        use crate::kernel::permissions::CapabilitySet;
+        use crate::scheduler::job::{CreateJobRequest, Principal, PrincipalType, ScheduleType};
+        use crate::scheduler::store::ScheduleStore;
+
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        let config: Config = toml::from_str(&format!(
+            "provider = \"openai\"\napi_key_env = \"PATH\"\ndata_dir = {:?}\n\
+             [data]\nscheduler_db = \"scheduler/jobs.db\"\n",
+            dir.to_string_lossy()
+        ))
+        .unwrap();
+        assert_eq!(config.scheduler_db_path(), dir.join("scheduler/jobs.db"));
+        assert_eq!(config.sessions_db_path(), dir.join("sessions.db"));
+        let builder = ProviderFactory::build_agent_builder(&config).unwrap();
+        let store =
+            SqliteStore::open_migrated(&config.sessions_db_path(), config.sqlite_pragmas()).unwrap();
+        let kernel = build_kernel(&config, builder.clone(), None, &store).unwrap();
+
+        let scheduler = build_scheduler(&config, &kernel, &store, builder, None).unwrap();
+        let request = CreateJobRequest {
+            name: "job".to_string(),
+            schedule_type: ScheduleType::Interval,
+            schedule_expr: "60".to_string(),
+            task_prompt: "ping".to_string(),
+            session_id: None,
+            user_id: "alice".to_string(),
+            channel_id: None,
+            capabilities: CapabilitySet::empty(),
+            creator: Principal {
+                principal_type: PrincipalType::User,
+                id: "alice".to_string(),
+            },
+            enabled: true,
+            max_executions: None,
+            created_by_system: false,
+            metadata: None,
+        };
+        scheduler
+            .store()
+            .create_job(request, chrono::Utc::now())
+            .unwrap();
+
+        let open = |path: std::path::PathBuf| {
+            let store = SqliteStore::new(path.to_string_lossy().to_string());
+            store.touch().unwrap();
+            ScheduleStore::new(store)
+        };
+        assert!(dir.join("scheduler/jobs.db").exists());
+        assert_eq!(open(config.scheduler_db_path()).list_jobs().unwrap().len(), 1);
+        assert!(open(config.sessions_db_path()).list_jobs().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,4 +1,6 @@
 pub mod channel;
 pub mod queue;
+pub mod quiet_hours;
 pub mod service;
+pub mod template;
 pub mod whatsapp;
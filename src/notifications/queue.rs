@@ -1,10 +1,14 @@
-use std::collections::VecDeque;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use rusqlite::params;
 use tokio::sync::{Mutex, Notify};
 
-use crate::notifications::channel::NotificationRequest;
+use crate::notifications::channel::{NotificationPriority, NotificationRequest};
+use crate::session::db::SqliteStore;
+use crate::session::error::{SessionDbError, SessionDbResult};
 
 #[derive(Debug, Clone)]
 pub struct NotificationQueueConfig {
@@ -53,7 +57,49 @@ pub struct QueueItem {
 
 #[derive(Debug, Default)]
 struct QueueState {
-    pending: VecDeque<QueueItem>,
+    pending: BinaryHeap<PendingItem>,
+    next_seq: u64,
+}
+
+impl QueueState {
+    fn push(&mut self, item: QueueItem) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.pending.push(PendingItem { seq, item });
+    }
+}
+
+/// Heap entry ordered by priority, then by arrival so equal priorities stay FIFO.
+#[derive(Debug)]
+struct PendingItem {
+    seq: u64,
+    item: QueueItem,
+}
+
+impl PendingItem {
+    fn key(&self) -> (NotificationPriority, Reverse<u64>) {
+        (self.item.request.priority, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for PendingItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for PendingItem {}
+
+impl PartialOrd for PendingItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -62,6 +108,15 @@ pub struct NotificationQueue {
     notify: Arc<Notify>,
     records: Arc<Mutex<Vec<NotificationRecord>>>,
     config: NotificationQueueConfig,
+    persistence: Option<QueueStore>,
+}
+
+/// Rows of one queue in `notification_deliveries`. Unsent notifications are kept until
+/// they are sent (then deleted) or fail for good (then kept, up to `max_records`).
+#[derive(Debug, Clone)]
+struct QueueStore {
+    store: SqliteStore,
+    queue: String,
 }
 
 impl NotificationQueue {
@@ -71,9 +126,69 @@ impl NotificationQueue {
             notify: Arc::new(Notify::new()),
             records: Arc::new(Mutex::new(Vec::new())),
             config,
+            persistence: None,
         }
     }
 
+    /// Persists notifications under `queue` in `store`, so ones not yet sent can be
+    /// reloaded with [`resume`](Self::resume) after a restart.
+    pub fn with_store(mut self, store: SqliteStore, queue: impl Into<String>) -> Self {
+        self.persistence = Some(QueueStore {
+            store,
+            queue: queue.into(),
+        });
+        self
+    }
+
+    /// Re-queues notifications persisted as pending or sending by an earlier run and
+    /// returns how many were loaded.
+    pub async fn resume(&self) -> usize {
+        let Some(persistence) = &self.persistence else {
+            return 0;
+        };
+        let items = match persistence.load_unsent() {
+            Ok(items) => items,
+            Err(err) => {
+                tracing::warn!(
+                    event = "notification_queue_resume_failed",
+                    queue = %persistence.queue,
+                    error = %err,
+                    "failed to load persisted notifications"
+                );
+                return 0;
+            }
+        };
+        let count = items.len();
+        let mut records = self.records.lock().await;
+        let mut state = self.state.lock().await;
+        for item in items {
+            if records.iter().any(|record| record.id == item.id) {
+                continue;
+            }
+            records.push(NotificationRecord {
+                id: item.id.clone(),
+                user_id: item.request.user_id.clone(),
+                channel_id: item.request.channel_id.clone(),
+                status: NotificationStatus::Pending,
+                attempts: item.attempts,
+                last_error: None,
+                updated_at: chrono::Utc::now(),
+            });
+            state.push(item);
+        }
+        prune_records(&mut records, self.config.max_records);
+        if count > 0 {
+            tracing::info!(
+                event = "notification_queue_resumed",
+                queue = %persistence.queue,
+                count,
+                "resumed unsent notifications"
+            );
+            self.notify.notify_one();
+        }
+        count
+    }
+
     pub async fn enqueue(&self, request: NotificationRequest) -> String {
         let id = format!(
             "{}:{}:{}",
@@ -90,11 +205,22 @@ impl NotificationQueue {
             last_error: None,
             updated_at: chrono::Utc::now(),
         };
+        if let Some(persistence) = &self.persistence
+            && let Err(err) = persistence.insert(&id, &request)
+        {
+            tracing::warn!(
+                event = "notification_persist_failed",
+                queue = %persistence.queue,
+                notification_id = %id,
+                error = %err,
+                "failed to persist notification; it will not survive a restart"
+            );
+        }
         let mut guard = self.records.lock().await;
         guard.push(record);
         prune_records(&mut guard, self.config.max_records);
         let mut state = self.state.lock().await;
-        state.pending.push_back(QueueItem {
+        state.push(QueueItem {
             id: id.clone(),
             request,
             attempts: 0,
@@ -107,7 +233,7 @@ impl NotificationQueue {
         loop {
             if let Some(item) = {
                 let mut guard = self.state.lock().await;
-                guard.pending.pop_front()
+                guard.pending.pop().map(|pending| pending.item)
             } {
                 return item;
             }
@@ -115,6 +241,13 @@ impl NotificationQueue {
         }
     }
 
+    /// Latest delivery record for `id`, while it is still retained.
+    #[allow(dead_code)]
+    pub async fn status(&self, id: &str) -> Option<NotificationRecord> {
+        let guard = self.records.lock().await;
+        guard.iter().find(|record| record.id == id).cloned()
+    }
+
     pub async fn record_status(
         &self,
         id: &str,
@@ -122,6 +255,23 @@ impl NotificationQueue {
         attempts: usize,
         last_error: Option<String>,
     ) -> Option<NotificationRecord> {
+        if let Some(persistence) = &self.persistence
+            && let Err(err) = persistence.update(
+                id,
+                status,
+                attempts,
+                last_error.as_deref(),
+                self.config.max_records,
+            )
+        {
+            tracing::warn!(
+                event = "notification_persist_failed",
+                queue = %persistence.queue,
+                notification_id = %id,
+                error = %err,
+                "failed to persist notification status"
+            );
+        }
         let mut guard = self.records.lock().await;
         if let Some(record) = guard.iter_mut().find(|record| record.id == id) {
             record.status = status;
@@ -140,7 +290,7 @@ impl NotificationQueue {
         tokio::spawn(async move {
             tokio::time::sleep(backoff).await;
             let mut guard = state.lock().await;
-            guard.pending.push_back(item);
+            guard.push(item);
             notify.notify_one();
         });
     }
@@ -150,6 +300,107 @@ impl NotificationQueue {
     }
 }
 
+impl QueueStore {
+    fn insert(&self, id: &str, request: &NotificationRequest) -> SessionDbResult<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.store.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO notification_deliveries
+                 (id, queue, user_id, channel_id, message, priority, status, attempts,
+                  created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'pending', 0, ?7, ?7)",
+                params![
+                    id,
+                    self.queue,
+                    request.user_id,
+                    request.channel_id,
+                    request.message,
+                    request.priority.as_str(),
+                    now
+                ],
+            )
+            .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Ok(())
+        })
+    }
+
+    /// Sent rows are deleted; failed rows are kept, oldest dropped beyond `max_failed`.
+    fn update(
+        &self,
+        id: &str,
+        status: NotificationStatus,
+        attempts: usize,
+        last_error: Option<&str>,
+        max_failed: usize,
+    ) -> SessionDbResult<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let query = |err: rusqlite::Error| SessionDbError::QueryFailed(err.to_string());
+        self.store.with_connection(|conn| {
+            let status = match status {
+                NotificationStatus::Sent => {
+                    conn.execute("DELETE FROM notification_deliveries WHERE id = ?1", [id])
+                        .map_err(query)?;
+                    return Ok(());
+                }
+                NotificationStatus::Pending => "pending",
+                NotificationStatus::Sending => "sending",
+                NotificationStatus::Failed => "failed",
+            };
+            conn.execute(
+                "UPDATE notification_deliveries
+                 SET status = ?1, attempts = ?2, last_error = ?3, updated_at = ?4
+                 WHERE id = ?5",
+                params![status, attempts as i64, last_error, now, id],
+            )
+            .map_err(query)?;
+            if status == "failed" {
+                conn.execute(
+                    "DELETE FROM notification_deliveries
+                     WHERE queue = ?1 AND status = 'failed' AND id NOT IN (
+                         SELECT id FROM notification_deliveries
+                         WHERE queue = ?1 AND status = 'failed'
+                         ORDER BY updated_at DESC LIMIT ?2
+                     )",
+                    params![self.queue, max_failed as i64],
+                )
+                .map_err(query)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_unsent(&self) -> SessionDbResult<Vec<QueueItem>> {
+        let query = |err: rusqlite::Error| SessionDbError::QueryFailed(err.to_string());
+        self.store.with_connection(|conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, user_id, channel_id, message, priority, attempts
+                     FROM notification_deliveries
+                     WHERE queue = ?1 AND status IN ('pending', 'sending')
+                     ORDER BY created_at",
+                )
+                .map_err(query)?;
+            let rows = stmt
+                .query_map([&self.queue], |row| {
+                    let priority: String = row.get(4)?;
+                    let attempts: i64 = row.get(5)?;
+                    Ok(QueueItem {
+                        id: row.get(0)?,
+                        request: NotificationRequest {
+                            user_id: row.get(1)?,
+                            channel_id: row.get(2)?,
+                            message: row.get(3)?,
+                            priority: NotificationPriority::parse(&priority).unwrap_or_default(),
+                        },
+                        attempts: attempts.max(0) as usize,
+                    })
+                })
+                .map_err(query)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(query)
+        })
+    }
+}
+
 fn compute_backoff(attempt: usize, config: &NotificationQueueConfig) -> Duration {
     let exp = attempt.saturating_sub(1) as u32;
     let multiplier = 1u64.checked_shl(exp.min(10)).unwrap_or(u64::MAX);
@@ -187,3 +438,37 @@ fn prune_records(records: &mut Vec<NotificationRecord>, max_records: usize) {
         records.drain(0..drop_count);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{NotificationQueue, NotificationQueueConfig};
+    use crate::notifications::channel::{NotificationPriority, NotificationRequest};
+
+    fn request(message: &str, priority: NotificationPriority) -> NotificationRequest {
+        NotificationRequest {
+            user_id: "user".to_string(),
+            channel_id: "whatsapp".to_string(),
+            message: message.to_string(),
+            priority,
+        }
+    }
+
+    #[tokio::test]
+    async fn higher_priority_notifications_are_popped_first() {
+        let queue = NotificationQueue::new(NotificationQueueConfig::default());
+        queue.enqueue(request("digest", NotificationPriority::Low)).await;
+        queue.enqueue(request("reminder 1", NotificationPriority::Normal)).await;
+        queue.enqueue(request("alert 1", NotificationPriority::High)).await;
+        queue.enqueue(request("reminder 2", NotificationPriority::Normal)).await;
+        queue.enqueue(request("alert 2", NotificationPriority::High)).await;
+
+        let mut delivered = Vec::new();
+        for _ in 0..5 {
+            delivered.push(queue.pop().await.request.message);
+        }
+        assert_eq!(
+            delivered,
+            ["alert 1", "alert 2", "reminder 1", "reminder 2", "digest"]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+
+/// A daily window during which notifications for some users are held back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    timezone: chrono_tz::Tz,
+    users: Option<Vec<String>>,
+}
+
+impl QuietHours {
+    /// `start` and `end` are local `HH:MM` (or `HH:MM:SS`) times in `timezone` (an IANA
+    /// name, default UTC); `end` before `start` spans midnight. `users` limits the window
+    /// to those user ids; `None` applies it to everyone.
+    pub fn parse(
+        start: &str,
+        end: &str,
+        timezone: Option<&str>,
+        users: Option<Vec<String>>,
+    ) -> Result<Self, String> {
+        let timezone = match timezone.map(str::trim) {
+            Some(name) => name
+                .parse::<chrono_tz::Tz>()
+                .map_err(|_| format!("invalid quiet_hours timezone '{name}'"))?,
+            None => chrono_tz::UTC,
+        };
+        let start = parse_time(start)?;
+        let end = parse_time(end)?;
+        if start == end {
+            return Err("quiet_hours start and end must differ".to_string());
+        }
+        Ok(Self {
+            start,
+            end,
+            timezone,
+            users,
+        })
+    }
+
+    pub fn applies_to(&self, user_id: &str) -> bool {
+        self.users
+            .as_ref()
+            .is_none_or(|users| users.iter().any(|user| user == user_id))
+    }
+
+    /// When the window containing `now` ends, or `None` if `now` is outside the window.
+    pub fn window_end(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&self.timezone);
+        let time = local.time();
+        let today = local.date_naive();
+        let end_date = if self.start < self.end {
+            (self.start <= time && time < self.end).then_some(today)?
+        } else if time >= self.start {
+            today + Duration::days(1)
+        } else if time < self.end {
+            today
+        } else {
+            return None;
+        };
+        let end = end_date.and_time(self.end);
+        // A DST gap can skip the end time; fall back to an hour later.
+        let end = self
+            .timezone
+            .from_local_datetime(&end)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(end + Duration::hours(1)))
+                    .earliest()
+            })?;
+        Some(end.with_timezone(&Utc))
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    let value = value.trim();
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+        .map_err(|_| format!("invalid quiet_hours time '{value}' (expected HH:MM)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::QuietHours;
+
+    #[test]
+    fn overnight_windows_end_the_next_local_morning() {
+        let quiet =
+            QuietHours::parse("22:00", "07:00", Some("Asia/Kolkata"), None).unwrap();
+        // 23:30 in Kolkata (UTC+5:30) is 18:00 UTC.
+        let late = Utc.with_ymd_and_hms(2026, 3, 1, 18, 0, 0).unwrap();
+        assert_eq!(
+            quiet.window_end(late),
+            Some(Utc.with_ymd_and_hms(2026, 3, 2, 1, 30, 0).unwrap())
+        );
+        // 06:00 local is still quiet; 12:00 local is not.
+        let early = Utc.with_ymd_and_hms(2026, 3, 2, 0, 30, 0).unwrap();
+        assert_eq!(
+            quiet.window_end(early),
+            Some(Utc.with_ymd_and_hms(2026, 3, 2, 1, 30, 0).unwrap())
+        );
+        assert_eq!(quiet.window_end(Utc.with_ymd_and_hms(2026, 3, 2, 6, 30, 0).unwrap()), None);
+
+        let scoped =
+            QuietHours::parse("09:00", "17:00", None, Some(vec!["alice".to_string()])).unwrap();
+        assert!(scoped.applies_to("alice"));
+        assert!(!scoped.applies_to("bob"));
+        assert!(QuietHours::parse("22:00", "22:00", None, None).is_err());
+        assert!(QuietHours::parse("25:00", "07:00", None, None).is_err());
+        assert!(QuietHours::parse("22:00", "07:00", Some("Mars/Base"), None).is_err());
+    }
+}
@@ -1,109 +1,711 @@
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tokio::time::Instant as TokioInstant;
 
 use crate::notifications::channel::{NotificationChannel, NotificationRequest};
-use crate::notifications::queue::{NotificationQueue, NotificationStatus};
+use crate::notifications::queue::{
+    NotificationQueue, NotificationRecord, NotificationStatus, QueueItem,
+};
+use crate::notifications::quiet_hours::QuietHours;
 
 #[derive(Clone)]
 pub struct NotificationService {
     queue: NotificationQueue,
     channel: Arc<dyn NotificationChannel>,
+    dedup_window: Duration,
+    recent: Arc<Mutex<HashMap<DedupKey, RecentNotification>>>,
+    quiet_hours: Arc<Vec<QuietHours>>,
+    workers: usize,
+}
+
+/// (channel, user, message) of a notification, compared in full for deduplication.
+type DedupKey = (String, String, String);
+
+#[derive(Debug)]
+struct RecentNotification {
+    id: String,
+    enqueued_at: Instant,
+    duplicates: usize,
 }
 
 impl NotificationService {
     pub fn new(queue: NotificationQueue, channel: Arc<dyn NotificationChannel>) -> Self {
-        Self { queue, channel }
+        Self {
+            queue,
+            channel,
+            dedup_window: Duration::ZERO,
+            recent: Arc::new(Mutex::new(HashMap::new())),
+            quiet_hours: Arc::new(Vec::new()),
+            workers: 1,
+        }
+    }
+
+    /// Drops notifications identical (channel, user, message) to one enqueued within
+    /// `window`; a zero window disables deduplication.
+    pub fn with_dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = window;
+        self
+    }
+
+    /// Holds notifications for users inside one of `windows` until it ends. Jobs still run
+    /// on schedule; only delivery waits.
+    pub fn with_quiet_hours(mut self, windows: Vec<QuietHours>) -> Self {
+        self.quiet_hours = Arc::new(windows);
+        self
+    }
+
+    /// Delivers up to `workers` notifications at once (at least one); notifications for
+    /// the same recipient are still sent one at a time, in order.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
     }
 
     pub async fn enqueue(&self, request: NotificationRequest) -> String {
-        self.queue.enqueue(request).await
+        if self.dedup_window.is_zero() {
+            return self.queue.enqueue(request).await;
+        }
+        // Held until the new id is recorded, so identical requests racing each other
+        // cannot both miss the check and be enqueued twice.
+        let mut recent = self.recent.lock().await;
+        let window = self.dedup_window;
+        recent.retain(|_, entry| entry.enqueued_at.elapsed() < window);
+        let key = dedup_key(&request);
+        if let Some(entry) = recent.get_mut(&key) {
+            entry.duplicates += 1;
+            tracing::debug!(
+                event = "notification_deduplicated",
+                channel_id = %request.channel_id,
+                user_id = %request.user_id,
+                notification_id = %entry.id,
+                duplicates = entry.duplicates,
+                "duplicate notification dropped"
+            );
+            return entry.id.clone();
+        }
+        let id = self.queue.enqueue(request).await;
+        recent.insert(
+            key,
+            RecentNotification {
+                id: id.clone(),
+                enqueued_at: Instant::now(),
+                duplicates: 0,
+            },
+        );
+        id
+    }
+
+    #[allow(dead_code)]
+    pub async fn status(&self, id: &str) -> Option<NotificationRecord> {
+        self.queue.status(id).await
     }
 
+    /// Delivers queued notifications forever, starting with any the queue persisted
+    /// before a restart. Notifications for a user inside their quiet hours are held and
+    /// sent as one combined message when the window ends. Up to `workers` deliveries run
+    /// at once, but never two for the same (channel, user), so each recipient still gets
+    /// their notifications in order.
     pub async fn worker_loop(&self) {
+        self.queue.resume().await;
+        let mut held: HashMap<(String, String), HeldNotifications> = HashMap::new();
+        // Ids folded into a combined notification, keyed by the id it is delivered under.
+        let mut coalesced: HashMap<String, Vec<String>> = HashMap::new();
+        // Popped notifications waiting for a free worker or for their recipient's earlier one.
+        // Capped at `workers` so the backlog stays in the queue, where priority still applies.
+        let mut ready: VecDeque<QueueItem> = VecDeque::new();
+        let mut in_flight: JoinSet<Delivery> = JoinSet::new();
+        let mut recipients: HashMap<tokio::task::Id, (String, String)> = HashMap::new();
         loop {
-            let mut item = self.queue.pop().await;
-            let channel_id = self.channel.channel_id();
-            if let Some(record) = self
-                .queue
-                .record_status(&item.id, NotificationStatus::Sending, item.attempts, None)
-                .await
-            {
-                tracing::debug!(
-                    event = "notification_status",
-                    transport_channel_id = %channel_id,
-                    channel_id = %record.channel_id,
-                    user_id = %record.user_id,
-                    status = ?record.status,
-                    attempts = record.attempts,
-                    "notification marked sending"
-                );
-            }
-            match self.channel.send(item.request.clone()).await {
-                Ok(_) => {
-                    if let Some(record) = self
-                        .queue
-                        .record_status(&item.id, NotificationStatus::Sent, item.attempts + 1, None)
-                        .await
-                    {
-                        tracing::debug!(
-                            event = "notification_status",
-                            transport_channel_id = %channel_id,
-                            channel_id = %record.channel_id,
-                            user_id = %record.user_id,
-                            status = ?record.status,
-                            attempts = record.attempts,
-                            "notification sent"
-                        );
+            let next_release = held.values().map(|group| group.release_at).min();
+            tokio::select! {
+                item = self.queue.pop(),
+                    if in_flight.len() < self.workers && ready.len() < self.workers =>
+                {
+                    match self.quiet_until(&item.request, Utc::now()) {
+                        Some(release_at) => self.hold(&mut held, item, release_at),
+                        None => ready.push_back(item),
                     }
                 }
-                Err(err) => {
-                    item.attempts += 1;
-                    let err_text = err.to_string();
-                    if item.attempts >= self.queue.config().max_attempts {
-                        if let Some(record) = self
-                            .queue
-                            .record_status(
-                                &item.id,
-                                NotificationStatus::Failed,
-                                item.attempts,
-                                Some(err_text),
-                            )
-                            .await
-                        {
+                Some(joined) = in_flight.join_next_with_id(), if !in_flight.is_empty() => {
+                    match joined {
+                        Ok((task, (id, delivered))) => {
+                            recipients.remove(&task);
+                            if let Some((status, attempts)) = delivered {
+                                for folded in coalesced.remove(&id).unwrap_or_default() {
+                                    self.queue.record_status(&folded, status, attempts, None).await;
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            recipients.remove(&err.id());
                             tracing::warn!(
-                                event = "notification_failed",
-                                transport_channel_id = %channel_id,
-                                channel_id = %record.channel_id,
-                                user_id = %record.user_id,
-                                status = ?record.status,
-                                attempts = record.attempts,
-                                "notification delivery failed"
+                                event = "notification_worker_failed",
+                                transport_channel_id = %self.channel.channel_id(),
+                                error = %err,
+                                "notification delivery task failed"
                             );
                         }
-                        continue;
                     }
+                }
+                _ = tokio::time::sleep_until(next_release.unwrap_or_else(TokioInstant::now)),
+                    if next_release.is_some() =>
+                {
+                    let now = TokioInstant::now();
+                    if let Some(key) = held
+                        .iter()
+                        .find(|(_, group)| group.release_at <= now)
+                        .map(|(key, _)| key.clone())
+                    {
+                        let group = held.remove(&key).expect("held group exists");
+                        ready.push_back(release(group, &mut coalesced));
+                    }
+                }
+            }
+            self.start_ready(&mut ready, &mut in_flight, &mut recipients);
+        }
+    }
+
+    /// Starts deliveries from `ready` while workers are free, skipping notifications whose
+    /// recipient already has one in flight.
+    fn start_ready(
+        &self,
+        ready: &mut VecDeque<QueueItem>,
+        in_flight: &mut JoinSet<Delivery>,
+        recipients: &mut HashMap<tokio::task::Id, (String, String)>,
+    ) {
+        while in_flight.len() < self.workers {
+            let Some(index) = ready.iter().position(|item| {
+                !recipients
+                    .values()
+                    .any(|busy| *busy == recipient(&item.request))
+            }) else {
+                return;
+            };
+            let item = ready.remove(index).expect("index is in bounds");
+            let key = recipient(&item.request);
+            let id = item.id.clone();
+            let service = self.clone();
+            let task = in_flight.spawn(async move { (id, service.deliver(item).await) });
+            recipients.insert(task.id(), key);
+        }
+    }
+
+    /// End of the quiet window `request`'s user is in at `now`, if any. The first
+    /// configured window that applies to the user decides.
+    fn quiet_until(
+        &self,
+        request: &NotificationRequest,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.quiet_hours
+            .iter()
+            .find(|window| window.applies_to(&request.user_id))?
+            .window_end(now)
+    }
+
+    fn hold(
+        &self,
+        held: &mut HashMap<(String, String), HeldNotifications>,
+        item: QueueItem,
+        release_at: DateTime<Utc>,
+    ) {
+        tracing::info!(
+            event = "notification_deferred",
+            channel_id = %item.request.channel_id,
+            user_id = %item.request.user_id,
+            notification_id = %item.id,
+            release_at = %release_at.to_rfc3339(),
+            "notification held for quiet hours"
+        );
+        // Timed on the monotonic clock from here, so the release fires when the wait ends.
+        let wait = (release_at - Utc::now()).to_std().unwrap_or_default();
+        held.entry(recipient(&item.request))
+            .or_insert_with(|| HeldNotifications {
+                release_at: TokioInstant::now() + wait,
+                items: Vec::new(),
+            })
+            .items
+            .push(item);
+    }
+
+    /// Sends one notification, recording its status. Returns the final status and
+    /// attempts once it is sent or has failed for good, `None` while it awaits a retry.
+    async fn deliver(&self, mut item: QueueItem) -> Option<(NotificationStatus, usize)> {
+        let channel_id = self.channel.channel_id();
+        if let Some(record) = self
+            .queue
+            .record_status(&item.id, NotificationStatus::Sending, item.attempts, None)
+            .await
+        {
+            tracing::debug!(
+                event = "notification_status",
+                transport_channel_id = %channel_id,
+                channel_id = %record.channel_id,
+                user_id = %record.user_id,
+                status = ?record.status,
+                attempts = record.attempts,
+                "notification marked sending"
+            );
+        }
+        match self.channel.send(item.request.clone()).await {
+            Ok(_) => {
+                if let Some(record) = self
+                    .queue
+                    .record_status(&item.id, NotificationStatus::Sent, item.attempts + 1, None)
+                    .await
+                {
+                    tracing::debug!(
+                        event = "notification_status",
+                        transport_channel_id = %channel_id,
+                        channel_id = %record.channel_id,
+                        user_id = %record.user_id,
+                        status = ?record.status,
+                        attempts = record.attempts,
+                        "notification sent"
+                    );
+                }
+                Some((NotificationStatus::Sent, item.attempts + 1))
+            }
+            Err(err) => {
+                item.attempts += 1;
+                let err_text = err.to_string();
+                if item.attempts >= self.queue.config().max_attempts {
                     if let Some(record) = self
                         .queue
                         .record_status(
                             &item.id,
-                            NotificationStatus::Pending,
+                            NotificationStatus::Failed,
                             item.attempts,
                             Some(err_text),
                         )
                         .await
                     {
-                        tracing::debug!(
-                            event = "notification_retry",
+                        tracing::warn!(
+                            event = "notification_failed",
                             transport_channel_id = %channel_id,
                             channel_id = %record.channel_id,
                             user_id = %record.user_id,
                             status = ?record.status,
                             attempts = record.attempts,
-                            "notification scheduled for retry"
+                            "notification delivery failed"
                         );
                     }
-                    self.queue.retry(item).await;
+                    return Some((NotificationStatus::Failed, item.attempts));
                 }
+                if let Some(record) = self
+                    .queue
+                    .record_status(
+                        &item.id,
+                        NotificationStatus::Pending,
+                        item.attempts,
+                        Some(err_text),
+                    )
+                    .await
+                {
+                    tracing::debug!(
+                        event = "notification_retry",
+                        transport_channel_id = %channel_id,
+                        channel_id = %record.channel_id,
+                        user_id = %record.user_id,
+                        status = ?record.status,
+                        attempts = record.attempts,
+                        "notification scheduled for retry"
+                    );
+                }
+                self.queue.retry(item).await;
+                None
             }
         }
     }
 }
+
+/// Id of a finished delivery task's notification, with its final status and attempts
+/// (`None` while it awaits a retry).
+type Delivery = (String, Option<(NotificationStatus, usize)>);
+
+/// Notifications for one (channel, user) held until their quiet hours end.
+#[derive(Debug)]
+struct HeldNotifications {
+    release_at: TokioInstant,
+    items: Vec<QueueItem>,
+}
+
+/// Combines a held group into one notification delivered under the first id; the other
+/// ids are tracked in `coalesced` so they get the same final status.
+fn release(group: HeldNotifications, coalesced: &mut HashMap<String, Vec<String>>) -> QueueItem {
+    let mut items = group.items.into_iter();
+    let mut primary = items.next().expect("held groups are never empty");
+    let mut folded = Vec::new();
+    for item in items {
+        primary.request.message =
+            format!("{}\n\n{}", primary.request.message, item.request.message);
+        primary.request.priority = primary.request.priority.max(item.request.priority);
+        folded.extend(coalesced.remove(&item.id).unwrap_or_default());
+        folded.push(item.id);
+    }
+    tracing::info!(
+        event = "notification_released",
+        channel_id = %primary.request.channel_id,
+        user_id = %primary.request.user_id,
+        notification_id = %primary.id,
+        coalesced = folded.len(),
+        "quiet hours ended; sending held notifications"
+    );
+    if !folded.is_empty() {
+        coalesced.entry(primary.id.clone()).or_default().extend(folded);
+    }
+    primary
+}
+
+fn recipient(request: &NotificationRequest) -> (String, String) {
+    (request.channel_id.clone(), request.user_id.clone())
+}
+
+fn dedup_key(request: &NotificationRequest) -> DedupKey {
+    (
+        request.channel_id.clone(),
+        request.user_id.clone(),
+        request.message.clone(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::NotificationService;
+    use crate::notifications::channel::{NotificationChannel, NotificationRequest};
+    use crate::notifications::queue::{
+        NotificationQueue, NotificationQueueConfig, NotificationStatus,
+    };
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for RecordingChannel {
+        fn channel_id(&self) -> &str {
+            "test"
+        }
+
+        async fn send(&self, request: NotificationRequest) -> Result<(), anyhow::Error> {
+            self.sent.lock().unwrap().push(request.message);
+            Ok(())
+        }
+    }
+
+    fn request(message: &str) -> NotificationRequest {
+        NotificationRequest {
+            user_id: "user".to_string(),
+            channel_id: "whatsapp".to_string(),
+            message: message.to_string(),
+            priority: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn delivery_status_tracks_queued_then_sent() {
+        let channel = Arc::new(RecordingChannel::default());
+        let service = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            channel.clone(),
+        );
+        let id = service.enqueue(request("reminder")).await;
+        let record = service.status(&id).await.unwrap();
+        assert_eq!(record.status, NotificationStatus::Pending);
+        assert_eq!(record.attempts, 0);
+        assert!(service.status("missing").await.is_none());
+
+        let worker = service.clone();
+        let handle = tokio::spawn(async move { worker.worker_loop().await });
+        let mut record = service.status(&id).await.unwrap();
+        for _ in 0..100 {
+            if record.status == NotificationStatus::Sent {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            record = service.status(&id).await.unwrap();
+        }
+        handle.abort();
+        assert_eq!(record.status, NotificationStatus::Sent);
+        assert_eq!(record.attempts, 1);
+        assert_eq!(record.user_id, "user");
+    }
+
+    #[tokio::test]
+    async fn unsent_notifications_are_resumed_after_restart() {
+        use crate::session::db::SqliteStore;
+
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let persisted_queue = || {
+            NotificationQueue::new(NotificationQueueConfig::default())
+                .with_store(store.clone(), "whatsapp")
+        };
+        let unsent = |store: &SqliteStore| {
+            store
+                .with_connection(|conn| {
+                    Ok(conn
+                        .query_row("SELECT COUNT(*) FROM notification_deliveries", [], |row| {
+                            row.get::<_, i64>(0)
+                        })
+                        .unwrap())
+                })
+                .unwrap()
+        };
+
+        // First run: enqueued, then the process stops before the worker sends anything.
+        let channel = Arc::new(RecordingChannel::default());
+        let service = NotificationService::new(persisted_queue(), channel.clone());
+        service.enqueue(request("reminder")).await;
+        service.enqueue(request("digest")).await;
+        drop(service);
+        assert_eq!(unsent(&store), 2);
+
+        // After a restart the worker reloads and sends them.
+        let channel = Arc::new(RecordingChannel::default());
+        let service = NotificationService::new(persisted_queue(), channel.clone());
+        let worker = service.clone();
+        let handle = tokio::spawn(async move { worker.worker_loop().await });
+        for _ in 0..100 {
+            if channel.sent.lock().unwrap().len() >= 2 && unsent(&store) == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        handle.abort();
+        assert_eq!(*channel.sent.lock().unwrap(), ["reminder", "digest"]);
+        assert_eq!(unsent(&store), 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn quiet_hour_notifications_are_sent_together_when_the_window_ends() {
+        use crate::notifications::quiet_hours::QuietHours;
+
+        let now = chrono::Utc::now();
+        let clock = |at: chrono::DateTime<chrono::Utc>| at.format("%H:%M:%S").to_string();
+        let quiet = QuietHours::parse(
+            &clock(now - chrono::Duration::hours(1)),
+            &clock(now + chrono::Duration::seconds(2)),
+            None,
+            Some(vec!["user".to_string()]),
+        )
+        .unwrap();
+        assert!(quiet.window_end(now).is_some(), "inside the window");
+        let channel = Arc::new(RecordingChannel::default());
+        let service = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            channel.clone(),
+        )
+        .with_quiet_hours(vec![quiet]);
+
+        let first = service.enqueue(request("Job A done")).await;
+        let second = service.enqueue(request("Job B done")).await;
+        let mut awake = request("Job C done");
+        awake.user_id = "other".to_string();
+        service.enqueue(awake).await;
+        let worker = service.clone();
+        let handle = tokio::spawn(async move { worker.worker_loop().await });
+        // The window ends one to two seconds from now, on a whole second.
+        tokio::time::sleep(Duration::from_millis(900)).await;
+        assert_eq!(*channel.sent.lock().unwrap(), ["Job C done"]);
+        assert_eq!(
+            service.status(&first).await.unwrap().status,
+            NotificationStatus::Pending
+        );
+
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        handle.abort();
+        assert_eq!(
+            *channel.sent.lock().unwrap(),
+            ["Job C done", "Job A done\n\nJob B done"]
+        );
+        for id in [first, second] {
+            assert_eq!(service.status(&id).await.unwrap().status, NotificationStatus::Sent);
+        }
+    }
+
+    /// Takes `delay` per send and records overlap, overall and per user.
+    #[derive(Default)]
+    struct SlowChannel {
+        delay: Duration,
+        sent: Mutex<Vec<String>>,
+        active: Mutex<Vec<String>>,
+        peak: Mutex<usize>,
+        same_user_overlap: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for SlowChannel {
+        fn channel_id(&self) -> &str {
+            "test"
+        }
+
+        async fn send(&self, request: NotificationRequest) -> Result<(), anyhow::Error> {
+            {
+                let mut active = self.active.lock().unwrap();
+                if active.contains(&request.user_id) {
+                    *self.same_user_overlap.lock().unwrap() = true;
+                }
+                active.push(request.user_id.clone());
+                let mut peak = self.peak.lock().unwrap();
+                *peak = (*peak).max(active.len());
+            }
+            tokio::time::sleep(self.delay).await;
+            let mut active = self.active.lock().unwrap();
+            let index = active.iter().position(|user| *user == request.user_id).unwrap();
+            active.remove(index);
+            self.sent.lock().unwrap().push(request.message);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn workers_deliver_in_parallel_but_keep_per_user_order() {
+        let channel = Arc::new(SlowChannel {
+            delay: Duration::from_millis(200),
+            ..Default::default()
+        });
+        let service = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            channel.clone(),
+        )
+        .with_workers(3);
+        for (user, message) in [
+            ("alice", "alice 1"),
+            ("alice", "alice 2"),
+            ("bob", "bob 1"),
+            ("carol", "carol 1"),
+            ("alice", "alice 3"),
+        ] {
+            let mut request = request(message);
+            request.user_id = user.to_string();
+            service.enqueue(request).await;
+        }
+
+        let worker = service.clone();
+        let handle = tokio::spawn(async move { worker.worker_loop().await });
+        for _ in 0..200 {
+            if channel.sent.lock().unwrap().len() >= 5 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        handle.abort();
+
+        let sent = channel.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 5, "{sent:?}");
+        assert_eq!(*channel.peak.lock().unwrap(), 3);
+        assert!(!*channel.same_user_overlap.lock().unwrap());
+        let alice = sent
+            .iter()
+            .filter(|message| message.starts_with("alice"))
+            .collect::<Vec<_>>();
+        assert_eq!(alice, ["alice 1", "alice 2", "alice 3"]);
+    }
+
+    #[tokio::test]
+    async fn busy_recipients_leave_their_backlog_queued_by_priority() {
+        let channel = Arc::new(SlowChannel {
+            delay: Duration::from_millis(100),
+            ..Default::default()
+        });
+        let service = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            channel.clone(),
+        )
+        .with_workers(2);
+        for index in 1..=5 {
+            service.enqueue(request(&format!("normal {index}"))).await;
+        }
+
+        let worker = service.clone();
+        let handle = tokio::spawn(async move { worker.worker_loop().await });
+        while channel.active.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let mut urgent = request("urgent");
+        urgent.priority = crate::notifications::channel::NotificationPriority::High;
+        service.enqueue(urgent).await;
+        for _ in 0..200 {
+            if channel.sent.lock().unwrap().len() >= 6 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        handle.abort();
+
+        // Only `workers` notifications wait outside the queue, so the urgent one
+        // overtakes the rest of the backlog.
+        let sent = channel.sent.lock().unwrap().clone();
+        assert_eq!(
+            sent,
+            ["normal 1", "normal 2", "normal 3", "urgent", "normal 4", "normal 5"]
+        );
+    }
+
+    #[tokio::test]
+    async fn identical_notifications_within_window_are_delivered_once() {
+        let channel = Arc::new(RecordingChannel::default());
+        let service = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            channel.clone(),
+        )
+        .with_dedup_window(Duration::from_secs(60));
+
+        let first = service.enqueue(request("Job failed: timeout")).await;
+        let second = service.enqueue(request("Job failed: timeout")).await;
+        service.enqueue(request("Job completed")).await;
+        assert_eq!(first, second);
+
+        let worker = service.clone();
+        let handle = tokio::spawn(async move { worker.worker_loop().await });
+        for _ in 0..100 {
+            if channel.sent.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.abort();
+        assert_eq!(
+            *channel.sent.lock().unwrap(),
+            ["Job failed: timeout", "Job completed"]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_identical_notifications_share_one_id() {
+        let service = NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            Arc::new(RecordingChannel::default()),
+        )
+        .with_dedup_window(Duration::from_secs(60));
+
+        let handles = (0..16)
+            .map(|_| {
+                let service = service.clone();
+                tokio::spawn(async move { service.enqueue(request("Job failed: timeout")).await })
+            })
+            .collect::<Vec<_>>();
+        let mut ids = Vec::new();
+        for handle in handles {
+            ids.push(handle.await.unwrap());
+        }
+        ids.dedup();
+        assert_eq!(ids.len(), 1, "{ids:?}");
+    }
+}
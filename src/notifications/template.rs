@@ -0,0 +1,75 @@
+/// Values available to notification templates.
+#[derive(Debug, Clone)]
+pub struct NotificationFields<'a> {
+    pub job_name: &'a str,
+    pub result: &'a str,
+    pub status: &'a str,
+    pub executed_at: &'a str,
+}
+
+/// Substitutes `{job_name}`, `{result}`, `{status}` and `{executed_at}` in `template`.
+///
+/// Substitution is a single pass, so placeholder-like text inside the result is left
+/// as-is. Unknown placeholders are kept verbatim.
+pub fn render_template(template: &str, fields: &NotificationFields<'_>) -> String {
+    render_placeholders(template, |name| match name {
+        "job_name" => Some(fields.job_name),
+        "result" => Some(fields.result),
+        "status" => Some(fields.status),
+        "executed_at" => Some(fields.executed_at),
+        _ => None,
+    })
+}
+
+/// Replaces each `{name}` in `template` with `value(name)` in a single pass, keeping
+/// placeholders `value` does not know verbatim.
+pub fn render_placeholders<'a>(
+    template: &str,
+    value: impl Fn(&str) -> Option<&'a str>,
+) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let Some(end) = candidate.find('}') else {
+            rest = candidate;
+            break;
+        };
+        match value(&candidate[1..end]) {
+            Some(value) => {
+                output.push_str(value);
+                rest = &candidate[end + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NotificationFields, render_template};
+
+    #[test]
+    fn template_placeholders_are_substituted_once() {
+        let fields = NotificationFields {
+            job_name: "daily-digest",
+            result: "3 new issues {status}",
+            status: "completed",
+            executed_at: "2026-01-02T03:04:05Z",
+        };
+        let rendered = render_template(
+            "[{job_name}] {status} at {executed_at}: {result} {unknown} {",
+            &fields,
+        );
+        assert_eq!(
+            rendered,
+            "[daily-digest] completed at 2026-01-02T03:04:05Z: 3 new issues {status} {unknown} {"
+        );
+    }
+}
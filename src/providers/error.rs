@@ -10,22 +10,60 @@ pub enum ProviderError {
     Permanent { message: String },
 }
 
+const TRANSIENT_MARKERS: &[&str] = &[
+    "timeout",
+    "timed out",
+    "connection",
+    "temporar",
+    "unavailable",
+    "overloaded",
+    "server_error",
+    "internal server error",
+    "bad gateway",
+    "gateway timeout",
+    "broken pipe",
+    "reset by peer",
+];
+
 impl ProviderError {
+    pub fn from_status(status: u16, message: String) -> Self {
+        match status {
+            429 => ProviderError::RateLimit { retry_after: None },
+            408 | 500..=599 => ProviderError::Transient { message },
+            _ => ProviderError::Permanent { message },
+        }
+    }
+
     pub fn from_anyhow(err: anyhow::Error) -> Self {
         let message = err.to_string();
-        let lower = message.to_ascii_lowercase();
-        if lower.contains("rate limit") || lower.contains("429") {
-            return ProviderError::RateLimit { retry_after: None };
+        for cause in err.chain() {
+            if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+                if err.is_timeout() || err.is_connect() {
+                    return ProviderError::Transient { message };
+                }
+                if let Some(status) = err.status() {
+                    return Self::from_status(status.as_u16(), message);
+                }
+            }
+            if let Some(
+                rig::http_client::Error::InvalidStatusCode(status)
+                | rig::http_client::Error::InvalidStatusCodeWithMessage(status, _),
+            ) = cause.downcast_ref::<rig::http_client::Error>()
+            {
+                return Self::from_status(status.as_u16(), message);
+            }
+        }
+        let lower = format!("{err:#}").to_ascii_lowercase();
+        if let Some(status) = find_status_code(&lower) {
+            return Self::from_status(status, message);
         }
-        if lower.contains("timeout")
-            || lower.contains("timed out")
-            || lower.contains("connection")
-            || lower.contains("temporar")
-            || lower.contains("unavailable")
-            || lower.contains("503")
-            || lower.contains("502")
-            || lower.contains("504")
+        if lower.contains("rate limit")
+            || lower.contains("rate_limit")
+            || lower.contains("too many requests")
         {
+            return ProviderError::RateLimit { retry_after: None };
+        }
+        if TRANSIENT_MARKERS.iter().any(|marker| lower.contains(marker)) {
             return ProviderError::Transient { message };
         }
         ProviderError::Permanent { message }
@@ -45,3 +83,112 @@ impl ProviderError {
         }
     }
 }
+
+fn find_status_code(lower: &str) -> Option<u16> {
+    const PREFIXES: &[&str] = &["status code ", "status code: ", "status: ", "http ", "code: "];
+    for prefix in PREFIXES {
+        let mut rest = lower;
+        while let Some(idx) = rest.find(prefix) {
+            rest = &rest[idx + prefix.len()..];
+            let digits = rest
+                .chars()
+                .take_while(|ch| ch.is_ascii_digit())
+                .collect::<String>();
+            if digits.len() == 3
+                && let Ok(status) = digits.parse::<u16>()
+                && (400..600).contains(&status)
+            {
+                return Some(status);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProviderError;
+
+    #[test]
+    fn status_codes_map_to_variants() {
+        assert!(matches!(
+            ProviderError::from_status(429, "slow down".to_string()),
+            ProviderError::RateLimit { .. }
+        ));
+        for status in [408, 500, 502, 503, 504] {
+            assert!(
+                matches!(
+                    ProviderError::from_status(status, "upstream".to_string()),
+                    ProviderError::Transient { .. }
+                ),
+                "status {status}"
+            );
+        }
+        for status in [400, 401, 403, 404, 422] {
+            assert!(
+                matches!(
+                    ProviderError::from_status(status, "rejected".to_string()),
+                    ProviderError::Permanent { .. }
+                ),
+                "status {status}"
+            );
+        }
+    }
+
+    #[test]
+    fn rig_status_errors_are_classified() {
+        let err = rig::http_client::Error::InvalidStatusCodeWithMessage(
+            reqwest::StatusCode::BAD_GATEWAY,
+            "upstream failed".to_string(),
+        );
+        assert!(matches!(
+            ProviderError::from_anyhow(anyhow::Error::new(err)),
+            ProviderError::Transient { .. }
+        ));
+        let err = rig::http_client::Error::InvalidStatusCode(reqwest::StatusCode::UNAUTHORIZED);
+        assert!(matches!(
+            ProviderError::from_anyhow(anyhow::Error::new(err)),
+            ProviderError::Permanent { .. }
+        ));
+        let err = rig::http_client::Error::InvalidStatusCode(reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert!(matches!(
+            ProviderError::from_anyhow(anyhow::Error::new(err)),
+            ProviderError::RateLimit { .. }
+        ));
+    }
+
+    #[test]
+    fn provider_error_bodies_are_classified() {
+        let server = anyhow::Error::msg(
+            r#"ProviderError: {"error":{"message":"The server had an error","type":"server_error"}}"#
+        );
+        assert!(matches!(
+            ProviderError::from_anyhow(server),
+            ProviderError::Transient { .. }
+        ));
+        let rate = anyhow::Error::msg(
+            r#"ProviderError: {"error":{"message":"Rate limit reached","code":"rate_limit_exceeded"}}"#
+        );
+        assert!(matches!(
+            ProviderError::from_anyhow(rate),
+            ProviderError::RateLimit { .. }
+        ));
+        let invalid = anyhow::Error::msg(
+            r#"ProviderError: {"error":{"message":"Invalid schema for function","type":"invalid_request_error"}}"#
+        );
+        assert!(matches!(
+            ProviderError::from_anyhow(invalid),
+            ProviderError::Permanent { .. }
+        ));
+        let status = anyhow::anyhow!("request failed with status code 503");
+        assert!(matches!(
+            ProviderError::from_anyhow(status),
+            ProviderError::Transient { .. }
+        ));
+        let model_name = anyhow::anyhow!("model gpt-4o-2024-05-13 does not exist");
+        assert!(matches!(
+            ProviderError::from_anyhow(model_name),
+            ProviderError::Permanent { .. }
+        ));
+    }
+}
@@ -1,23 +1,52 @@
+use std::collections::HashMap;
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use rig::agent::Agent;
+use rig::agent::{Agent, PromptResponse};
 use rig::client::CompletionClient;
-use rig::completion::{Prompt, Usage};
+use rig::completion::message::{AssistantContent, Message};
+use rig::completion::{CompletionModel, Prompt, PromptError, Usage};
 use rig::providers::{gemini, openai, openrouter};
 use rig::tool::ToolDyn;
 use tokio::time::sleep;
 
-use crate::config::{Config, ModelConfig};
+use crate::config::{Config, DEFAULT_PROVIDER_REQUEST_TIMEOUT_SECS, ModelConfig};
 use crate::kernel::core::Kernel;
 use crate::providers::error::ProviderError;
 use crate::tools::registry::ToolRegistry;
 use crate::tools::rig_wrapper::KernelBackedTool;
+use crate::tools::traits::ToolContext;
 
 pub const DEFAULT_PROVIDER_RETRIES: usize = 2;
 
+/// Appended to user-facing replies that were cut short by the tool-round limit.
+pub const TURN_LIMIT_NOTE: &str =
+    "(Stopped early: hit the tool-call limit for this request, so this may be incomplete.)";
+
+const TURN_LIMIT_SUMMARY_PROMPT: &str = "You have reached the tool-call limit for this request and cannot call any more tools. Using only the results gathered so far, give your best final answer and briefly say what is still unfinished.";
+
+/// Final reply of an agent turn.
+#[derive(Debug, Clone)]
+pub struct AgentReply {
+    pub output: String,
+    pub usage: Usage,
+    /// The agent ran out of tool rounds; `output` is a tool-free summary of the partial work.
+    pub turn_limit_reached: bool,
+}
+
+impl AgentReply {
+    /// `output`, followed by [`TURN_LIMIT_NOTE`] when the turn was cut short.
+    pub fn text_with_note(&self) -> String {
+        if self.turn_limit_reached {
+            format!("{}\n\n{TURN_LIMIT_NOTE}", self.output)
+        } else {
+            self.output.clone()
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ProviderKind {
     OpenAI,
@@ -47,9 +76,11 @@ impl ProviderFactory {
         let api_key = env::var(api_key_env)
             .with_context(|| format!("missing API key in env '{api_key_env}'"))?;
 
-        let mut builder = openai::Client::builder().api_key(api_key);
+        let mut builder = openai::Client::<reqwest::Client>::builder()
+            .api_key(api_key)
+            .http_client(build_http_client(config.provider_request_timeout())?);
         if let Some(base_url) = &config.base_url {
-            builder = builder.base_url(base_url);
+            builder = builder.base_url(&normalize_base_url(base_url)?);
         }
 
         builder.build().context("failed to build OpenAI client")
@@ -63,7 +94,10 @@ impl ProviderFactory {
             .unwrap_or("OPENROUTER_API_KEY");
         let api_key = env::var(api_key_env)
             .with_context(|| format!("missing API key in env '{api_key_env}'"))?;
-        Ok(openrouter::Client::new(api_key)?)
+        Ok(openrouter::Client::<reqwest::Client>::builder()
+            .api_key(api_key)
+            .http_client(build_http_client(config.provider_request_timeout())?)
+            .build()?)
     }
 
     #[allow(dead_code)]
@@ -71,7 +105,10 @@ impl ProviderFactory {
         let api_key_env = config.api_key_env.as_deref().unwrap_or("GEMINI_API_KEY");
         let api_key = env::var(api_key_env)
             .with_context(|| format!("missing API key in env '{api_key_env}'"))?;
-        Ok(gemini::Client::builder().api_key(api_key).build()?)
+        Ok(gemini::Client::<reqwest::Client>::builder()
+            .api_key(api_key)
+            .http_client(build_http_client(config.provider_request_timeout())?)
+            .build()?)
     }
 
     #[allow(dead_code)]
@@ -81,7 +118,7 @@ impl ProviderFactory {
         kernel: Arc<Kernel>,
     ) -> Result<ProviderAgent> {
         let router = Self::build_agent_router(config)?;
-        router.build_default(config, tool_registry, kernel, config.max_turns())
+        router.build_default(config, tool_registry, kernel, config.max_tool_rounds())
     }
 
     pub fn build_agent_builder(config: &Config) -> Result<ProviderAgentBuilder> {
@@ -156,7 +193,8 @@ impl ProviderFactory {
             system_prompt,
             base_url,
             api_key_env,
-        );
+        )
+        .with_request_timeout(config.provider_request_timeout());
         builder.build_without_tools()
     }
 }
@@ -187,6 +225,95 @@ where
     }
 }
 
+/// Canonical form of an OpenAI-compatible `base_url`: adds a scheme when missing (`http`
+/// for local hosts, `https` otherwise) and drops trailing slashes. A URL with no path is
+/// kept as is, with a warning, since most OpenAI-compatible gateways serve under `/v1`.
+pub fn normalize_base_url(raw: &str) -> Result<String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(anyhow::anyhow!("base_url is empty"));
+    }
+    let with_scheme = if trimmed.contains("://") {
+        trimmed.to_string()
+    } else {
+        let local = ["localhost", "127.", "0.0.0.0", "[::1]"]
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix));
+        let scheme = if local { "http" } else { "https" };
+        format!("{scheme}://{trimmed}")
+    };
+    let url = reqwest::Url::parse(&with_scheme)
+        .with_context(|| format!("base_url '{trimmed}' is not a valid URL"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow::anyhow!(
+            "base_url '{trimmed}' must use http or https, not '{}'",
+            url.scheme()
+        ));
+    }
+    if url.host_str().is_none() {
+        return Err(anyhow::anyhow!("base_url '{trimmed}' has no host"));
+    }
+    let path = url.path().trim_end_matches('/');
+    if path.is_empty() {
+        tracing::warn!(
+            base_url = %trimmed,
+            "base_url has no path; OpenAI-compatible APIs are usually served under /v1"
+        );
+    }
+    let mut normalized = url.clone();
+    normalized.set_path(path);
+    Ok(normalized.to_string().trim_end_matches('/').to_string())
+}
+
+/// Lists models at a normalized `base_url` to confirm it points at an OpenAI-compatible
+/// API. Any answer other than 404 counts as reachable, since a missing or wrong API key
+/// still proves the URL is right.
+pub fn probe_base_url(base_url: &str, timeout: Duration) -> Result<()> {
+    let url = format!("{base_url}/models");
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let response = agent
+        .get(&url)
+        .call()
+        .map_err(|err| anyhow::anyhow!("{url} is unreachable: {err}"))?;
+    if response.status().as_u16() == 404 {
+        return Err(anyhow::anyhow!(
+            "{url} returned 404; check the path (OpenAI-compatible APIs usually end in /v1)"
+        ));
+    }
+    Ok(())
+}
+
+/// Provider HTTP clients keyed by request timeout. Agents are built per prompt, so
+/// sharing the client keeps its connection pool warm across prompts.
+static HTTP_CLIENTS: OnceLock<Mutex<HashMap<Duration, reqwest::Client>>> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    static HTTP_CLIENTS_BUILT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn build_http_client(timeout: Duration) -> Result<reqwest::Client> {
+    let clients = HTTP_CLIENTS.get_or_init(Default::default);
+    let mut clients = clients.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(client) = clients.get(&timeout) {
+        return Ok(client.clone());
+    }
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .tcp_keepalive(Duration::from_secs(60))
+        .build()
+        .context("failed to build provider HTTP client")?;
+    #[cfg(test)]
+    HTTP_CLIENTS_BUILT.with(|built| built.set(built.get() + 1));
+    clients.insert(timeout, client.clone());
+    Ok(client)
+}
+
 #[derive(Clone)]
 pub struct ProviderAgentBuilder {
     provider: ProviderKind,
@@ -194,6 +321,7 @@ pub struct ProviderAgentBuilder {
     system_prompt: String,
     base_url: Option<String>,
     api_key_env: Option<String>,
+    request_timeout: Duration,
 }
 
 impl ProviderAgentBuilder {
@@ -204,6 +332,7 @@ impl ProviderAgentBuilder {
             system_prompt: config.system_prompt().to_string(),
             base_url: config.base_url.clone(),
             api_key_env: config.api_key_env.clone(),
+            request_timeout: config.provider_request_timeout(),
         })
     }
 
@@ -224,6 +353,7 @@ impl ProviderAgentBuilder {
                 .api_key_env
                 .clone()
                 .or_else(|| fallback.api_key_env.clone()),
+            request_timeout: fallback.provider_request_timeout(),
         })
     }
 
@@ -241,8 +371,14 @@ impl ProviderAgentBuilder {
             system_prompt,
             base_url,
             api_key_env,
+            request_timeout: Duration::from_secs(DEFAULT_PROVIDER_REQUEST_TIMEOUT_SECS),
         }
     }
+
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -252,13 +388,19 @@ pub struct ModelRouter {
 }
 
 impl ModelRouter {
+    /// Builds the router from `[[models]]`. Without that section the router is empty and
+    /// agents use the top-level provider settings. A missing or unknown
+    /// `routing.default_model` falls back to the first model (config validation warns
+    /// about it once at startup); only a `[[models]]` section with no entries is an error.
     pub fn new(config: &Config) -> Result<Self> {
-        let models = config.models.clone().unwrap_or_default();
-        if models.is_empty() {
+        let Some(models) = config.models.clone() else {
             return Ok(Self {
                 models: Vec::new(),
                 default_id: None,
             });
+        };
+        if models.is_empty() {
+            return Err(anyhow::anyhow!("no usable models: [[models]] has no entries"));
         }
         let mut seen = std::collections::HashSet::new();
         for model in &models {
@@ -269,11 +411,12 @@ impl ModelRouter {
                 return Err(anyhow::anyhow!("duplicate model id '{}'", model.id));
             }
         }
-        let default_id = match config.default_model_id() {
-            Some(id) if models.iter().any(|model| model.id == id) => Some(id.to_string()),
-            Some(_) => Some(models[0].id.clone()),
-            None => Some(models[0].id.clone()),
-        };
+        let default_id = config
+            .default_model_id()
+            .filter(|id| models.iter().any(|model| model.id == *id))
+            .unwrap_or(models[0].id.as_str())
+            .to_string();
+        let default_id = Some(default_id);
         Ok(Self { models, default_id })
     }
 
@@ -281,6 +424,14 @@ impl ModelRouter {
         self.models.is_empty()
     }
 
+    pub fn models(&self) -> &[ModelConfig] {
+        &self.models
+    }
+
+    pub fn default_id(&self) -> Option<&str> {
+        self.default_id.as_deref()
+    }
+
     pub fn build_default(
         &self,
         fallback: &Config,
@@ -300,6 +451,37 @@ impl ModelRouter {
         } else {
             &self.models[0]
         };
+        Self::build_from(model, fallback, tool_registry, kernel, max_turns)
+    }
+
+    pub fn contains(&self, model_id: &str) -> bool {
+        self.models.iter().any(|model| model.id == model_id)
+    }
+
+    /// Builds an agent for a specific `[[models]]` entry rather than the default.
+    pub fn build_model(
+        &self,
+        model_id: &str,
+        fallback: &Config,
+        tool_registry: &ToolRegistry,
+        kernel: Arc<Kernel>,
+        max_turns: usize,
+    ) -> Result<ProviderAgent> {
+        let model = self
+            .models
+            .iter()
+            .find(|model| model.id == model_id)
+            .ok_or_else(|| anyhow::anyhow!("model '{model_id}' not found"))?;
+        Self::build_from(model, fallback, tool_registry, kernel, max_turns)
+    }
+
+    fn build_from(
+        model: &ModelConfig,
+        fallback: &Config,
+        tool_registry: &ToolRegistry,
+        kernel: Arc<Kernel>,
+        max_turns: usize,
+    ) -> Result<ProviderAgent> {
         let max_turns = model.max_turns.unwrap_or(max_turns);
         let builder = ProviderAgentBuilder::from_model_config(model, fallback)?;
         builder.build(tool_registry, kernel, max_turns)
@@ -328,17 +510,21 @@ impl ProviderAgentBuilder {
     where
         F: Fn(&str) -> Option<String>,
     {
+        let system_prompt =
+            render_system_prompt(&self.system_prompt, kernel.context(), chrono::Utc::now());
         match self.provider {
             ProviderKind::OpenAI => {
                 let api_key_env = self.api_key_env.as_deref().unwrap_or("OPENAI_API_KEY");
                 let api_key = env(api_key_env)
                     .ok_or_else(|| anyhow::anyhow!("missing API key in env '{api_key_env}'"))?;
-                let mut builder = rig::providers::openai::Client::builder().api_key(api_key);
+                let mut builder = rig::providers::openai::Client::<reqwest::Client>::builder()
+                    .api_key(api_key)
+                    .http_client(build_http_client(self.request_timeout)?);
                 if let Some(base_url) = &self.base_url {
-                    builder = builder.base_url(base_url);
+                    builder = builder.base_url(&normalize_base_url(base_url)?);
                 }
                 let client = builder.build().context("failed to build OpenAI client")?;
-                let agent_builder = client.agent(&self.model).preamble(&self.system_prompt);
+                let agent_builder = client.agent(&self.model).preamble(&system_prompt);
                 Ok(ProviderAgent::OpenAI(build_agent_with_tools(
                     agent_builder,
                     tool_registry,
@@ -350,9 +536,12 @@ impl ProviderAgentBuilder {
                 let api_key_env = self.api_key_env.as_deref().unwrap_or("OPENROUTER_API_KEY");
                 let api_key = env(api_key_env)
                     .ok_or_else(|| anyhow::anyhow!("missing API key in env '{api_key_env}'"))?;
-                let client = rig::providers::openrouter::Client::new(api_key)
+                let client = rig::providers::openrouter::Client::<reqwest::Client>::builder()
+                    .api_key(api_key)
+                    .http_client(build_http_client(self.request_timeout)?)
+                    .build()
                     .context("failed to build OpenRouter client")?;
-                let agent_builder = client.agent(&self.model).preamble(&self.system_prompt);
+                let agent_builder = client.agent(&self.model).preamble(&system_prompt);
                 Ok(ProviderAgent::OpenRouter(build_agent_with_tools(
                     agent_builder,
                     tool_registry,
@@ -364,11 +553,12 @@ impl ProviderAgentBuilder {
                 let api_key_env = self.api_key_env.as_deref().unwrap_or("GEMINI_API_KEY");
                 let api_key = env(api_key_env)
                     .ok_or_else(|| anyhow::anyhow!("missing API key in env '{api_key_env}'"))?;
-                let client = rig::providers::gemini::Client::builder()
+                let client = rig::providers::gemini::Client::<reqwest::Client>::builder()
                     .api_key(api_key)
+                    .http_client(build_http_client(self.request_timeout)?)
                     .build()
                     .context("failed to build Gemini client")?;
-                let agent_builder = client.agent(&self.model).preamble(&self.system_prompt);
+                let agent_builder = client.agent(&self.model).preamble(&system_prompt);
                 Ok(ProviderAgent::Gemini(build_agent_with_tools(
                     agent_builder,
                     tool_registry,
@@ -392,9 +582,11 @@ impl ProviderAgentBuilder {
                 let api_key_env = self.api_key_env.as_deref().unwrap_or("OPENAI_API_KEY");
                 let api_key = env(api_key_env)
                     .ok_or_else(|| anyhow::anyhow!("missing API key in env '{api_key_env}'"))?;
-                let mut builder = rig::providers::openai::Client::builder().api_key(api_key);
+                let mut builder = rig::providers::openai::Client::<reqwest::Client>::builder()
+                    .api_key(api_key)
+                    .http_client(build_http_client(self.request_timeout)?);
                 if let Some(base_url) = &self.base_url {
-                    builder = builder.base_url(base_url);
+                    builder = builder.base_url(&normalize_base_url(base_url)?);
                 }
                 let client = builder.build().context("failed to build OpenAI client")?;
                 let agent = client
@@ -407,7 +599,10 @@ impl ProviderAgentBuilder {
                 let api_key_env = self.api_key_env.as_deref().unwrap_or("OPENROUTER_API_KEY");
                 let api_key = env(api_key_env)
                     .ok_or_else(|| anyhow::anyhow!("missing API key in env '{api_key_env}'"))?;
-                let client = rig::providers::openrouter::Client::new(api_key)
+                let client = rig::providers::openrouter::Client::<reqwest::Client>::builder()
+                    .api_key(api_key)
+                    .http_client(build_http_client(self.request_timeout)?)
+                    .build()
                     .context("failed to build OpenRouter client")?;
                 let agent = client
                     .agent(&self.model)
@@ -419,8 +614,9 @@ impl ProviderAgentBuilder {
                 let api_key_env = self.api_key_env.as_deref().unwrap_or("GEMINI_API_KEY");
                 let api_key = env(api_key_env)
                     .ok_or_else(|| anyhow::anyhow!("missing API key in env '{api_key_env}'"))?;
-                let client = rig::providers::gemini::Client::builder()
+                let client = rig::providers::gemini::Client::<reqwest::Client>::builder()
                     .api_key(api_key)
+                    .http_client(build_http_client(self.request_timeout)?)
                     .build()
                     .context("failed to build Gemini client")?;
                 let agent = client
@@ -433,6 +629,34 @@ impl ProviderAgentBuilder {
     }
 }
 
+/// Expands `{date}`, `{user_id}`, `{channel}` and `{timezone}` in a system prompt from the
+/// request's tool context. The date is local to the context timezone; other placeholders
+/// are left untouched.
+pub fn render_system_prompt(
+    template: &str,
+    ctx: &ToolContext,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    if !template.contains('{') {
+        return template.to_string();
+    }
+    let date = if let Ok(tz) = ctx.timezone_name.parse::<chrono_tz::Tz>() {
+        now.with_timezone(&tz).date_naive()
+    } else if let Ok(offset) = ctx.timezone_offset.parse::<chrono::FixedOffset>() {
+        now.with_timezone(&offset).date_naive()
+    } else {
+        now.date_naive()
+    };
+    let date = date.format("%Y-%m-%d").to_string();
+    crate::notifications::template::render_placeholders(template, |name| match name {
+        "date" => Some(date.as_str()),
+        "user_id" => Some(ctx.user_id.as_deref().unwrap_or("unknown")),
+        "channel" => Some(ctx.channel_id.as_deref().unwrap_or("unknown")),
+        "timezone" => Some(ctx.timezone_name.as_str()),
+        _ => None,
+    })
+}
+
 #[derive(Clone)]
 pub enum ProviderAgent {
     OpenAI(Agent<openai::responses_api::ResponsesCompletionModel>),
@@ -449,6 +673,22 @@ impl ProviderAgent {
         }
     }
 
+    /// A copy with the system prompt rendered for `ctx` (see [`render_system_prompt`]), for
+    /// agents built without a request context and shared across requests.
+    pub fn for_context(&self, ctx: &ToolContext) -> Self {
+        let mut agent = self.clone();
+        let now = chrono::Utc::now();
+        let preamble = match &mut agent {
+            ProviderAgent::OpenAI(agent) => &mut agent.preamble,
+            ProviderAgent::OpenRouter(agent) => &mut agent.preamble,
+            ProviderAgent::Gemini(agent) => &mut agent.preamble,
+        };
+        if let Some(preamble) = preamble {
+            *preamble = render_system_prompt(preamble, ctx, now);
+        }
+        agent
+    }
+
     pub fn model_name(&self) -> Option<String> {
         match self {
             ProviderAgent::OpenAI(agent) => Some(agent.model.model.clone()),
@@ -472,27 +712,68 @@ impl ProviderAgent {
         &self,
         prompt: impl Into<String>,
         max_turns: usize,
-    ) -> anyhow::Result<(String, Usage)> {
+    ) -> anyhow::Result<AgentReply> {
         let prompt = prompt.into();
         let response = match self {
-            ProviderAgent::OpenAI(agent) => agent.prompt(&prompt).extended_details().max_turns(max_turns).await?,
-            ProviderAgent::OpenRouter(agent) => agent.prompt(&prompt).extended_details().max_turns(max_turns).await?,
-            ProviderAgent::Gemini(agent) => agent.prompt(&prompt).extended_details().max_turns(max_turns).await?,
+            ProviderAgent::OpenAI(agent) => agent.prompt(&prompt).extended_details().max_turns(max_turns).await,
+            ProviderAgent::OpenRouter(agent) => agent.prompt(&prompt).extended_details().max_turns(max_turns).await,
+            ProviderAgent::Gemini(agent) => agent.prompt(&prompt).extended_details().max_turns(max_turns).await,
         };
-        Ok((response.output, response.total_usage))
+        self.finish_turn(response).await
     }
 
     pub async fn prompt_message_with_usage(
         &self,
         message: rig::completion::message::Message,
         max_turns: usize,
-    ) -> anyhow::Result<(String, Usage)> {
+    ) -> anyhow::Result<AgentReply> {
         let response = match self {
-            ProviderAgent::OpenAI(agent) => agent.prompt(message.clone()).extended_details().max_turns(max_turns).await?,
-            ProviderAgent::OpenRouter(agent) => agent.prompt(message.clone()).extended_details().max_turns(max_turns).await?,
-            ProviderAgent::Gemini(agent) => agent.prompt(message.clone()).extended_details().max_turns(max_turns).await?,
+            ProviderAgent::OpenAI(agent) => agent.prompt(message.clone()).extended_details().max_turns(max_turns).await,
+            ProviderAgent::OpenRouter(agent) => agent.prompt(message.clone()).extended_details().max_turns(max_turns).await,
+            ProviderAgent::Gemini(agent) => agent.prompt(message.clone()).extended_details().max_turns(max_turns).await,
+        };
+        self.finish_turn(response).await
+    }
+
+    /// Converts a multi-turn result into a reply. When the agent ran out of tool rounds,
+    /// asks once more with tools withheld so the caller gets a summary instead of an error.
+    async fn finish_turn(
+        &self,
+        response: Result<PromptResponse, PromptError>,
+    ) -> anyhow::Result<AgentReply> {
+        let chat_history = match response {
+            Ok(response) => {
+                return Ok(AgentReply {
+                    output: response.output,
+                    usage: response.total_usage,
+                    turn_limit_reached: false,
+                });
+            }
+            Err(PromptError::MaxTurnsError {
+                max_turns,
+                chat_history,
+                ..
+            }) => {
+                tracing::warn!(
+                    event = "turn_limit_reached",
+                    provider = self.provider_name(),
+                    max_turns,
+                    "agent hit the tool-round limit, requesting a summary without tools"
+                );
+                *chat_history
+            }
+            Err(err) => return Err(err.into()),
         };
-        Ok((response.output, response.total_usage))
+        let (output, usage) = match self {
+            ProviderAgent::OpenAI(agent) => complete_without_tools(agent, chat_history).await?,
+            ProviderAgent::OpenRouter(agent) => complete_without_tools(agent, chat_history).await?,
+            ProviderAgent::Gemini(agent) => complete_without_tools(agent, chat_history).await?,
+        };
+        Ok(AgentReply {
+            output,
+            usage,
+            turn_limit_reached: true,
+        })
     }
 
     async fn prompt_with_turns_once(
@@ -546,7 +827,7 @@ impl ProviderAgent {
         prompt: impl Into<String>,
         max_turns: usize,
         max_retries: usize,
-    ) -> Result<(String, Usage), ProviderError> {
+    ) -> Result<AgentReply, ProviderError> {
         let prompt = prompt.into();
         let mut attempt = 0;
         loop {
@@ -613,7 +894,7 @@ impl ProviderAgent {
         message: rig::completion::message::Message,
         max_turns: usize,
         max_retries: usize,
-    ) -> Result<(String, Usage), ProviderError> {
+    ) -> Result<AgentReply, ProviderError> {
         let mut attempt = 0;
         loop {
             let response = self.prompt_message_with_usage(message.clone(), max_turns).await;
@@ -641,6 +922,30 @@ impl ProviderAgent {
     }
 }
 
+async fn complete_without_tools<M: CompletionModel>(
+    agent: &Agent<M>,
+    chat_history: Vec<Message>,
+) -> anyhow::Result<(String, Usage)> {
+    let mut request = agent
+        .model
+        .completion_request(Message::user(TURN_LIMIT_SUMMARY_PROMPT))
+        .messages(chat_history);
+    if let Some(preamble) = &agent.preamble {
+        request = request.preamble(preamble.clone());
+    }
+    let response = request.send().await?;
+    let output = response
+        .choice
+        .iter()
+        .filter_map(|content| match content {
+            AssistantContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok((output, response.usage))
+}
+
 fn backoff_delay(attempt: usize) -> Duration {
     let base_ms = 200u64;
     let shift = attempt.min(8) as u32;
@@ -648,3 +953,279 @@ fn backoff_delay(attempt: usize) -> Duration {
     let delay_ms = base_ms.saturating_mul(multiplier).min(2000);
     Duration::from_millis(delay_ms)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{
+        HTTP_CLIENTS_BUILT, ModelRouter, ProviderAgent, ProviderAgentBuilder, ProviderKind,
+        TURN_LIMIT_NOTE, normalize_base_url, render_system_prompt,
+    };
+    use crate::config::{Config, ModelConfig, RoutingConfig};
+    use crate::kernel::core::Kernel;
+    use crate::providers::error::ProviderError;
+    use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+    use crate::tools::registry::ToolRegistry;
+
+    #[test]
+    fn base_urls_are_normalized() {
+        let cases = [
+            ("https://gateway.example.com/v1", "https://gateway.example.com/v1"),
+            ("https://gateway.example.com/v1/", "https://gateway.example.com/v1"),
+            ("https://gateway.example.com", "https://gateway.example.com"),
+            ("https://gateway.example.com//", "https://gateway.example.com"),
+            ("gateway.example.com/openai/v1", "https://gateway.example.com/openai/v1"),
+            ("localhost:11434", "http://localhost:11434"),
+            (" 127.0.0.1:8000/v1/ ", "http://127.0.0.1:8000/v1"),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(normalize_base_url(raw).unwrap(), expected, "{raw}");
+        }
+        assert!(normalize_base_url("").is_err());
+        assert!(normalize_base_url("ftp://gateway.example.com").is_err());
+        assert!(normalize_base_url("https://").is_err());
+    }
+
+    #[test]
+    fn model_system_prompt_replaces_global_prompt() {
+        let config = Config {
+            provider: Some("openai".to_string()),
+            system_prompt: Some("global prompt".to_string()),
+            ..Default::default()
+        };
+        let mut model = ModelConfig {
+            id: "coder".to_string(),
+            model: "gpt-4o".to_string(),
+            system_prompt: Some("coder prompt".to_string()),
+            ..Default::default()
+        };
+        let builder = ProviderAgentBuilder::from_model_config(&model, &config).unwrap();
+        assert_eq!(builder.system_prompt, "coder prompt");
+
+        model.system_prompt = None;
+        let builder = ProviderAgentBuilder::from_model_config(&model, &config).unwrap();
+        assert_eq!(builder.system_prompt, "global prompt");
+    }
+
+    #[test]
+    fn system_prompt_placeholders_render_from_context() {
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()))
+            .clone_with_context(Some("alice".to_string()), None)
+            .with_channel_id(Some("whatsapp".to_string()))
+            .with_timezone("+05:30".to_string(), "Asia/Kolkata".to_string(), true);
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-01T20:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+
+        let rendered = render_system_prompt(
+            "Today is {date} ({timezone}). You are talking to {user_id} on {channel}. {unknown}",
+            kernel.context(),
+            now,
+        );
+
+        assert_eq!(
+            rendered,
+            "Today is 2026-03-02 (Asia/Kolkata). You are talking to alice on whatsapp. {unknown}"
+        );
+
+        // Values are substituted once, so one that looks like a placeholder stays literal.
+        let kernel = kernel.clone_with_context(Some("{channel}".to_string()), None);
+        let rendered = render_system_prompt("{user_id} on {channel}", kernel.context(), now);
+        assert_eq!(rendered, "{channel} on whatsapp");
+    }
+
+    #[tokio::test]
+    async fn agents_without_tools_render_the_prompt_per_context() {
+        let agent = ProviderAgentBuilder::from_parts(
+            ProviderKind::OpenAI,
+            "gpt-4o-mini".to_string(),
+            "Talking to {user_id} on {channel}.".to_string(),
+            None,
+            None,
+        )
+        .build_without_tools_with_env(|_| Some("test-key".to_string()))
+        .unwrap();
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()))
+            .clone_with_context(Some("alice".to_string()), None)
+            .with_channel_id(Some("api".to_string()));
+
+        let ProviderAgent::OpenAI(rendered) = agent.for_context(kernel.context()) else {
+            panic!("expected an OpenAI agent");
+        };
+        assert_eq!(rendered.preamble.as_deref(), Some("Talking to alice on api."));
+    }
+
+    fn model(id: &str) -> ModelConfig {
+        ModelConfig {
+            id: id.to_string(),
+            provider: Some("openai".to_string()),
+            model: "gpt-4o-mini".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn router_falls_back_to_first_model_without_usable_default() {
+        let mut config = Config {
+            models: Some(vec![model("fast"), model("smart")]),
+            ..Default::default()
+        };
+        let router = ModelRouter::new(&config).unwrap();
+        assert_eq!(router.default_id(), Some("fast"));
+
+        config.routing = Some(RoutingConfig {
+            default_model: Some("missing".to_string()),
+        });
+        let router = ModelRouter::new(&config).unwrap();
+        assert_eq!(router.default_id(), Some("fast"));
+
+        config.routing = Some(RoutingConfig {
+            default_model: Some("smart".to_string()),
+        });
+        let router = ModelRouter::new(&config).unwrap();
+        assert_eq!(router.default_id(), Some("smart"));
+    }
+
+    #[test]
+    fn router_errors_only_when_models_section_is_empty() {
+        let router = ModelRouter::new(&Config::default()).unwrap();
+        assert!(router.is_empty());
+
+        let config = Config {
+            models: Some(Vec::new()),
+            ..Default::default()
+        };
+        let err = ModelRouter::new(&config).err().unwrap();
+        assert!(err.to_string().contains("no usable models"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn provider_http_client_is_built_once_per_timeout() {
+        let builder = ProviderAgentBuilder::from_parts(
+            ProviderKind::OpenAI,
+            "gpt-4o-mini".to_string(),
+            "test".to_string(),
+            None,
+            None,
+        )
+        .with_request_timeout(std::time::Duration::from_millis(4321));
+        let built_before = HTTP_CLIENTS_BUILT.with(|built| built.get());
+        for _ in 0..20 {
+            builder
+                .clone()
+                .build_without_tools_with_env(|_| Some("test-key".to_string()))
+                .unwrap();
+        }
+        let built_after = HTTP_CLIENTS_BUILT.with(|built| built.get());
+        assert_eq!(built_after - built_before, 1);
+    }
+
+    #[tokio::test]
+    async fn stalled_provider_request_times_out_as_transient() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut open = Vec::new();
+            loop {
+                let Ok((socket, _)) = listener.accept().await else {
+                    break;
+                };
+                open.push(socket);
+            }
+        });
+
+        let agent = ProviderAgentBuilder::from_parts(
+            ProviderKind::OpenAI,
+            "gpt-4o-mini".to_string(),
+            "test".to_string(),
+            Some(format!("http://{addr}/v1")),
+            None,
+        )
+        .with_request_timeout(std::time::Duration::from_millis(300))
+        .build_without_tools_with_env(|_| Some("test-key".to_string()))
+        .unwrap();
+
+        let started = std::time::Instant::now();
+        let result = agent.prompt_with_turns_retry_usage("hello", 1, 0).await;
+        let elapsed = started.elapsed();
+        server.abort();
+
+        let err = result.unwrap_err();
+        assert!(
+            matches!(err, ProviderError::Transient { .. }),
+            "unexpected error: {err:?}"
+        );
+        assert!(elapsed < std::time::Duration::from_secs(5));
+    }
+
+    #[derive(Debug)]
+    struct AgainTool {
+        spec: crate::tools::traits::ToolSpec,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::tools::traits::ToolExecutor for AgainTool {
+        fn spec(&self) -> &crate::tools::traits::ToolSpec {
+            &self.spec
+        }
+
+        fn required_permissions(
+            &self,
+            _ctx: &crate::tools::traits::ToolContext,
+            _input: &serde_json::Value,
+        ) -> Result<Vec<crate::kernel::permissions::Permission>, crate::tools::traits::ToolError>
+        {
+            Ok(Vec::new())
+        }
+
+        async fn execute(
+            &self,
+            _ctx: &crate::tools::traits::ToolContext,
+            _input: serde_json::Value,
+        ) -> Result<crate::tools::traits::ToolOutput, crate::tools::traits::ToolError> {
+            Ok(serde_json::json!({"status": "call again"}))
+        }
+    }
+
+    #[tokio::test]
+    async fn turn_limit_returns_tool_free_summary() {
+        let mut script = vec![ScriptedReply::tool_call("again", serde_json::json!({})); 10];
+        script.push(ScriptedReply::text("partial summary"));
+        let provider = ScriptedProvider::start(script).await;
+
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(AgainTool {
+                spec: crate::tools::traits::ToolSpec {
+                    name: "again".to_string(),
+                    description: "Always asks to be called again.".to_string(),
+                    schema: serde_json::json!({ "type": "object" }),
+                    output_schema: None,
+                },
+            }))
+            .unwrap();
+        let registry = Arc::new(registry);
+        let kernel = Arc::new(Kernel::new(Arc::clone(&registry)));
+        let agent = provider
+            .builder()
+            .build_with_env(&registry, kernel, 2, |_| Some("test-key".to_string()))
+            .unwrap();
+
+        let reply = agent
+            .prompt_with_turns_retry_usage("keep going", 2, 0)
+            .await
+            .unwrap();
+
+        assert!(reply.turn_limit_reached);
+        assert_eq!(reply.output, "partial summary");
+        assert_eq!(reply.usage.total_tokens, 15);
+        assert!(reply.text_with_note().ends_with(TURN_LIMIT_NOTE));
+        let requests = provider.requests();
+        let summary_request = requests.last().unwrap();
+        assert!(summary_request["tools"].as_array().is_none_or(|tools| tools.is_empty()));
+        assert!(requests[..requests.len() - 1]
+            .iter()
+            .all(|request| request["tools"].as_array().is_some_and(|tools| !tools.is_empty())));
+    }
+}
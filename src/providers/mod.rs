@@ -1,2 +1,4 @@
 pub mod error;
 pub mod factory;
+#[cfg(test)]
+pub mod scripted;
@@ -0,0 +1,320 @@
+//! Scripted model endpoint for deterministic agent tests.
+//!
+//! [`ScriptedProvider`] serves a fixed sequence of replies over the OpenAI Responses API on
+//! a local port, so tests drive real [`ProviderAgent`](super::factory::ProviderAgent)s,
+//! tool wrappers, and the kernel without network access or API keys.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::providers::factory::{ProviderAgentBuilder, ProviderKind};
+
+/// One completion returned by a [`ScriptedProvider`].
+#[derive(Debug, Clone)]
+pub enum ScriptedReply {
+    Text(String),
+    ToolCall { name: String, arguments: Value },
+}
+
+impl ScriptedReply {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    pub fn tool_call(name: impl Into<String>, arguments: Value) -> Self {
+        Self::ToolCall {
+            name: name.into(),
+            arguments,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Script {
+    replies: VecDeque<ScriptedReply>,
+    requests: Vec<Value>,
+    delay: std::time::Duration,
+}
+
+/// Local model endpoint that answers requests from a script, in order.
+///
+/// A request that offers no tools skips ahead to the next [`ScriptedReply::Text`], since a
+/// model cannot call tools it was not given. Once the script is exhausted every request
+/// fails with HTTP 500. The server stops when the provider is dropped.
+pub struct ScriptedProvider {
+    addr: SocketAddr,
+    script: Arc<Mutex<Script>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl ScriptedProvider {
+    pub async fn start(replies: Vec<ScriptedReply>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind scripted provider");
+        let addr = listener.local_addr().expect("scripted provider address");
+        let script = Arc::new(Mutex::new(Script {
+            replies: replies.into(),
+            ..Script::default()
+        }));
+        let server = tokio::spawn(accept_loop(listener, Arc::clone(&script)));
+        Self {
+            addr,
+            script,
+            server,
+        }
+    }
+
+    /// Builder for an OpenAI-style agent pointed at this endpoint.
+    pub fn builder(&self) -> ProviderAgentBuilder {
+        ProviderAgentBuilder::from_parts(
+            ProviderKind::OpenAI,
+            "scripted".to_string(),
+            "You are a scripted test agent.".to_string(),
+            Some(self.base_url()),
+            Some("SCRIPTED_API_KEY".to_string()),
+        )
+    }
+
+    /// OpenAI-compatible base URL, for pointing configured `[[models]]` at this endpoint.
+    pub fn base_url(&self) -> String {
+        format!("http://{}/v1", self.addr)
+    }
+
+    /// Holds every later reply for `delay`, to simulate a slow model.
+    pub fn set_delay(&self, delay: std::time::Duration) {
+        self.lock().delay = delay;
+    }
+
+    /// Request bodies received so far, in arrival order.
+    pub fn requests(&self) -> Vec<Value> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Drop for ScriptedProvider {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn accept_loop(listener: TcpListener, script: Arc<Mutex<Script>>) {
+    while let Ok((socket, _)) = listener.accept().await {
+        tokio::spawn(serve_connection(socket, Arc::clone(&script)));
+    }
+}
+
+async fn serve_connection(mut socket: TcpStream, script: Arc<Mutex<Script>>) {
+    let mut buffer = Vec::new();
+    loop {
+        let header_end = loop {
+            if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if !read_more(&mut socket, &mut buffer).await {
+                return;
+            }
+        };
+        let headers = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+        let length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        while buffer.len() < header_end + length {
+            if !read_more(&mut socket, &mut buffer).await {
+                return;
+            }
+        }
+        let request: Value =
+            serde_json::from_slice(&buffer[header_end..header_end + length]).unwrap_or_default();
+        buffer.drain(..header_end + length);
+
+        let (reply, delay) = next_reply(&script, request);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        let (status, body) = match reply {
+            Some(reply) => ("200 OK", response_body(reply).to_string()),
+            None => (
+                "500 Internal Server Error",
+                json!({ "error": { "message": "script exhausted" } }).to_string(),
+            ),
+        };
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\n\
+             content-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        if socket.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn read_more(socket: &mut TcpStream, buffer: &mut Vec<u8>) -> bool {
+    let mut chunk = [0u8; 4096];
+    match socket.read(&mut chunk).await {
+        Ok(0) | Err(_) => false,
+        Ok(read) => {
+            buffer.extend_from_slice(&chunk[..read]);
+            true
+        }
+    }
+}
+
+fn next_reply(
+    script: &Mutex<Script>,
+    request: Value,
+) -> (Option<ScriptedReply>, std::time::Duration) {
+    let mut script = script.lock().unwrap_or_else(|err| err.into_inner());
+    let offers_tools = request["tools"]
+        .as_array()
+        .is_some_and(|tools| !tools.is_empty());
+    script.requests.push(request);
+    while let Some(reply) = script.replies.pop_front() {
+        if offers_tools || matches!(reply, ScriptedReply::Text(_)) {
+            return (Some(reply), script.delay);
+        }
+    }
+    (None, script.delay)
+}
+
+fn response_body(reply: ScriptedReply) -> Value {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let output = match reply {
+        ScriptedReply::Text(text) => json!({
+            "type": "message",
+            "id": format!("msg_{id}"),
+            "role": "assistant",
+            "status": "completed",
+            "content": [{ "type": "output_text", "text": text }]
+        }),
+        ScriptedReply::ToolCall { name, arguments } => json!({
+            "type": "function_call",
+            "id": format!("fc_{id}"),
+            "call_id": format!("call_{id}"),
+            "name": name,
+            "arguments": arguments.to_string(),
+            "status": "completed"
+        }),
+    };
+    json!({
+        "id": format!("resp_{id}"),
+        "object": "response",
+        "created_at": 0,
+        "status": "completed",
+        "error": null,
+        "incomplete_details": null,
+        "instructions": null,
+        "max_output_tokens": null,
+        "model": "scripted",
+        "usage": {
+            "input_tokens": 10,
+            "output_tokens": 5,
+            "output_tokens_details": { "reasoning_tokens": 0 },
+            "total_tokens": 15
+        },
+        "output": [output]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+    use serde_json::{Value, json};
+
+    use super::{ScriptedProvider, ScriptedReply};
+    use crate::kernel::core::Kernel;
+    use crate::kernel::permissions::Permission;
+    use crate::tools::registry::ToolRegistry;
+    use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+
+    #[derive(Debug)]
+    struct CountingTool {
+        spec: ToolSpec,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl CountingTool {
+        fn new(name: &str) -> Self {
+            Self {
+                spec: ToolSpec {
+                    name: name.to_string(),
+                    description: format!("Test tool {name}."),
+                    schema: json!({ "type": "object" }),
+                    output_schema: None,
+                },
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ToolExecutor for CountingTool {
+        fn spec(&self) -> &ToolSpec {
+            &self.spec
+        }
+
+        fn required_permissions(
+            &self,
+            _ctx: &ToolContext,
+            _input: &Value,
+        ) -> Result<Vec<Permission>, ToolError> {
+            Ok(Vec::new())
+        }
+
+        async fn execute(&self, _ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(json!({ "tool": self.spec.name, "input": input }))
+        }
+    }
+
+    #[tokio::test]
+    async fn scripted_two_tool_turn_runs_to_completion() {
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::tool_call("lookup", json!({ "key": "weather" })),
+            ScriptedReply::tool_call("record", json!({ "note": "sunny" })),
+            ScriptedReply::text("Recorded: sunny."),
+        ])
+        .await;
+        let lookup = Arc::new(CountingTool::new("lookup"));
+        let record = Arc::new(CountingTool::new("record"));
+        let mut registry = ToolRegistry::new();
+        registry.register(lookup.clone()).unwrap();
+        registry.register(record.clone()).unwrap();
+        let registry = Arc::new(registry);
+        let kernel = Arc::new(Kernel::new(Arc::clone(&registry)));
+        let agent = provider
+            .builder()
+            .build_with_env(&registry, kernel, 5, |_| Some("test-key".to_string()))
+            .unwrap();
+
+        let reply = agent
+            .prompt_with_turns_retry_usage("check the weather and note it", 5, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(reply.output, "Recorded: sunny.");
+        assert!(!reply.turn_limit_reached);
+        assert_eq!(lookup.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(record.calls.load(Ordering::SeqCst), 1);
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 3);
+        let final_input = requests[2]["input"].to_string();
+        assert!(final_input.contains("weather"), "{final_input}");
+        assert!(final_input.contains("sunny"), "{final_input}");
+    }
+}
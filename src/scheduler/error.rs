@@ -15,6 +15,8 @@ pub enum SchedulerError {
     InvalidSchedule(String),
     #[error("Concurrency limit reached")]
     ConcurrencyLimit,
+    #[error("Scheduler at capacity; retry in {retry_after_secs}s")]
+    AtCapacity { retry_after_secs: u64 },
     #[error("Quota exceeded: {0}")]
     QuotaExceeded(String),
     #[error("Execution failed: {0}")]
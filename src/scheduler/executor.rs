@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use dashmap::DashMap;
@@ -5,12 +6,13 @@ use tokio::time::Duration;
 use tokio_util::sync::CancellationToken;
 
 use crate::config::SchedulerConfig;
-use crate::kernel::core::Kernel;
+use crate::kernel::core::{Kernel, random_millis};
 use crate::notifications::service::NotificationService;
-use crate::providers::factory::{DEFAULT_PROVIDER_RETRIES, ModelRouter, ProviderAgentBuilder};
-use crate::scheduler::job::{ExecutionStatus, JobExecution, ScheduledJob};
+use crate::notifications::template::{NotificationFields, render_template};
+use crate::providers::factory::{ModelRouter, ProviderAgentBuilder};
+use crate::scheduler::job::{ExecutionStatus, JobAccount, JobExecution, ScheduledJob};
 use crate::scheduler::service::next_cron_occurrence;
-use crate::scheduler::store::ScheduleStore;
+use crate::scheduler::store::{ScheduleStore, execution_status_to_str};
 
 #[derive(Clone)]
 pub struct JobExecutor {
@@ -21,7 +23,9 @@ pub struct JobExecutor {
     agent_builder: ProviderAgentBuilder,
     router: Option<ModelRouter>,
     fallback_config: crate::config::Config,
-    notifications: Arc<tokio::sync::RwLock<Option<Arc<NotificationService>>>>,
+    accounts: Arc<Vec<JobAccount>>,
+    /// Notification services by account id.
+    notifications: Arc<tokio::sync::RwLock<HashMap<String, Arc<NotificationService>>>>,
 }
 
 impl JobExecutor {
@@ -41,13 +45,52 @@ impl JobExecutor {
             agent_builder,
             router,
             fallback_config,
-            notifications: Arc::new(tokio::sync::RwLock::new(None)),
+            accounts: Arc::new(Vec::new()),
+            notifications: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
         }
     }
 
-    pub async fn set_notifications(&self, service: Option<Arc<NotificationService>>) {
+    /// Channel accounts jobs can belong to; see [`JobAccount`]. The first one also notifies
+    /// for jobs that belong to none.
+    pub fn with_accounts(mut self, accounts: Vec<JobAccount>) -> Self {
+        self.accounts = Arc::new(accounts);
+        self
+    }
+
+    /// Sets the service that sends notifications for jobs of account `account_id`; `None`
+    /// removes it.
+    pub async fn set_notifications(
+        &self,
+        account_id: &str,
+        service: Option<Arc<NotificationService>>,
+    ) {
         let mut guard = self.notifications.write().await;
-        *guard = service;
+        match service {
+            Some(service) => guard.insert(account_id.to_string(), service),
+            None => guard.remove(account_id),
+        };
+    }
+
+    /// The account a job was created on. Senders may contain `:`, so the longest matching
+    /// session prefix wins.
+    fn job_account(&self, job: &ScheduledJob) -> Option<&JobAccount> {
+        self.accounts
+            .iter()
+            .filter(|account| account.owns(job))
+            .max_by_key(|account| account.session_prefix.len())
+    }
+
+    /// Jobs notify through their own account; jobs without one use the first account.
+    async fn notification_service(&self, job: &ScheduledJob) -> Option<Arc<NotificationService>> {
+        let account = self
+            .job_account(job)
+            .or_else(|| self.accounts.first())?;
+        self.notifications.read().await.get(&account.id).cloned()
+    }
+
+    /// Agent turn budget for a single job run.
+    pub fn max_turns(&self) -> usize {
+        self.config.job_max_turns(self.fallback_config.max_tool_rounds())
     }
 
     pub fn cancel_job(&self, job_id: &str) -> bool {
@@ -93,7 +136,7 @@ impl JobExecutor {
         let timeout = Duration::from_secs(self.config.job_timeout_secs());
         let outcome = tokio::select! {
             _ = token.cancelled() => ExecutionOutcome::Cancelled,
-            result = tokio::time::timeout(timeout, self.run_job(&job)) => {
+            result = tokio::time::timeout(timeout, self.run_with_progress(&job)) => {
                 match result {
                     Ok(value) => value,
                     Err(_) => ExecutionOutcome::Timeout,
@@ -147,26 +190,16 @@ impl JobExecutor {
                 execution.error = Some(error.clone());
                 job.consecutive_failures = job.consecutive_failures.saturating_add(1);
                 job.last_error = Some(error);
-                job.backoff_until = Some(
-                    finished_at
-                        + chrono::Duration::seconds(calculate_backoff_secs(
-                            job.consecutive_failures,
-                            &self.config,
-                        ) as i64),
-                );
+                job.backoff_until =
+                    Some(finished_at + calculate_backoff(job.consecutive_failures, &self.config));
             }
             ExecutionOutcome::Timeout => {
                 execution.status = ExecutionStatus::Timeout;
                 execution.error = Some("job timed out".to_string());
                 job.consecutive_failures = job.consecutive_failures.saturating_add(1);
                 job.last_error = Some("job timed out".to_string());
-                job.backoff_until = Some(
-                    finished_at
-                        + chrono::Duration::seconds(calculate_backoff_secs(
-                            job.consecutive_failures,
-                            &self.config,
-                        ) as i64),
-                );
+                job.backoff_until =
+                    Some(finished_at + calculate_backoff(job.consecutive_failures, &self.config));
             }
             ExecutionOutcome::Cancelled => {
                 execution.status = ExecutionStatus::Cancelled;
@@ -201,14 +234,63 @@ impl JobExecutor {
             && should_notify
             && !agent_notified
         {
-            let notification_text =
-                completion_message.unwrap_or_else(|| "Job completed".to_string());
-            self.enqueue_notification(&job.user_id, &channel_id, notification_text)
+            let result = completion_message.unwrap_or_else(|| "Job completed".to_string());
+            let notification_text = match self
+                .fallback_config
+                .notifications()
+                .template_for(&channel_id)
+            {
+                Some(template) => render_template(
+                    template,
+                    &NotificationFields {
+                        job_name: &job.name,
+                        result: &result,
+                        status: execution_status_to_str(execution.status),
+                        executed_at: &finished_at.to_rfc3339(),
+                    },
+                ),
+                None => result,
+            };
+            self.enqueue_notification(&job, &channel_id, notification_text)
                 .await;
         }
     }
 
-    async fn run_job(&self, job: &ScheduledJob) -> ExecutionOutcome {
+    /// Runs the job, sending one progress notification to its channel if it is still
+    /// running after `scheduler.progress_notify_after_secs`.
+    async fn run_with_progress(&self, job: &ScheduledJob) -> ExecutionOutcome {
+        let run = self.run_job(job);
+        let (Some(after), Some(channel_id)) = (
+            self.config.progress_notify_after(),
+            job.channel_id.as_deref(),
+        ) else {
+            return run.await;
+        };
+        tokio::pin!(run);
+        tokio::select! {
+            outcome = &mut run => return outcome,
+            _ = tokio::time::sleep(after) => {}
+        }
+        tracing::info!(
+            event = "scheduler_job_progress",
+            job_id = %job.id,
+            user_id = %job.user_id,
+            channel_id = %channel_id,
+            elapsed_secs = after.as_secs(),
+            "scheduler job still running, sending progress notification"
+        );
+        self.enqueue_notification(
+            job,
+            channel_id,
+            format!("Still working on \"{}\"...", job.name),
+        )
+        .await;
+        run.await
+    }
+
+    /// The kernel a job runs in: its owner, capabilities and channel, with the channel
+    /// profile and sandbox of the account it came from, if any.
+    fn job_kernel(&self, job: &ScheduledJob) -> Kernel {
         let scoped_kernel = self
             .kernel
             .clone_with_context(Some(job.user_id.clone()), job.session_id.clone())
@@ -219,16 +301,31 @@ impl JobExecutor {
             .channel_id
             .clone()
             .unwrap_or_else(|| "scheduler".to_string());
-        let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-        let profile = crate::channels::permissions::channel_profile(
-            &self.fallback_config.channels(),
+        let profile_name = self
+            .job_account(job)
+            .map_or(channel_id.as_str(), |account| account.profile.as_str());
+        let base_dir = self.fallback_config.base_dir();
+        let channels = self.fallback_config.channels();
+        let profile = crate::channels::permissions::named_channel_profile(
+            &channels,
+            profile_name,
             &channel_id,
             &base_dir,
         );
-        let scoped_kernel = scoped_kernel.with_prompt_profile(profile);
-        let notification_service = self.notifications.read().await.clone();
-        let scoped_kernel = scoped_kernel.with_notifications(notification_service.clone());
+        crate::channels::permissions::channel_sandbox(
+            scoped_kernel,
+            &channels,
+            profile_name,
+            &base_dir,
+        )
+        .with_prompt_profile(profile)
+    }
+
+    async fn run_job(&self, job: &ScheduledJob) -> ExecutionOutcome {
+        let notification_service = self.notification_service(job).await;
+        let scoped_kernel = self.job_kernel(job).with_notifications(notification_service);
 
+        let max_turns = self.max_turns();
         let agent = if let Some(router) = self.router.as_ref()
             && !router.is_empty()
         {
@@ -236,7 +333,7 @@ impl JobExecutor {
                 &self.fallback_config,
                 scoped_kernel.tool_registry(),
                 Arc::new(scoped_kernel.clone()),
-                8,
+                max_turns,
             ) {
                 Ok(agent) => Ok(agent),
                 Err(err) => {
@@ -244,7 +341,7 @@ impl JobExecutor {
                     self.agent_builder.clone().build_with_env(
                         scoped_kernel.tool_registry(),
                         Arc::new(scoped_kernel.clone()),
-                        8,
+                        max_turns,
                         |key| std::env::var(key).ok(),
                     )
                 }
@@ -253,7 +350,7 @@ impl JobExecutor {
             self.agent_builder.clone().build_with_env(
                 scoped_kernel.tool_registry(),
                 Arc::new(scoped_kernel.clone()),
-                8,
+                max_turns,
                 |key| std::env::var(key).ok(),
             )
         };
@@ -271,7 +368,11 @@ impl JobExecutor {
             job.task_prompt
         );
         let response = agent
-            .prompt_with_turns_retry_usage(prompt, 8, DEFAULT_PROVIDER_RETRIES)
+            .prompt_with_turns_retry_usage(
+                prompt,
+                max_turns,
+                self.fallback_config.provider_max_retries(),
+            )
             .await;
         let agent_notified = scoped_kernel
             .context()
@@ -279,7 +380,8 @@ impl JobExecutor {
             .load(std::sync::atomic::Ordering::Relaxed);
 
         match response {
-            Ok((text, usage)) => {
+            Ok(reply) => {
+                let usage = &reply.usage;
                 let usage_event = crate::session::types::UsageEvent {
                     session_id: job.session_id.clone(),
                     channel_id: job
@@ -298,7 +400,7 @@ impl JobExecutor {
                     tracing::warn!(error = %err, "failed to record usage");
                 }
                 ExecutionOutcome::Completed {
-                    response: Some(text),
+                    response: Some(reply.text_with_note()),
                     agent_notified,
                 }
             }
@@ -308,15 +410,15 @@ impl JobExecutor {
         }
     }
 
-    async fn enqueue_notification(&self, user_id: &str, channel_id: &str, message: String) {
-        let service = self.notifications.read().await.clone();
-        let Some(service) = service else {
+    async fn enqueue_notification(&self, job: &ScheduledJob, channel_id: &str, message: String) {
+        let Some(service) = self.notification_service(job).await else {
             return;
         };
         let request = crate::notifications::channel::NotificationRequest {
-            user_id: user_id.to_string(),
+            user_id: job.user_id.clone(),
             channel_id: channel_id.to_string(),
             message,
+            priority: Default::default(),
         };
         let _ = service.enqueue(request).await;
     }
@@ -362,10 +464,16 @@ fn should_disable(job: &ScheduledJob) -> bool {
     }
 }
 
-fn calculate_backoff_secs(failures: u32, config: &SchedulerConfig) -> u64 {
-    let base = 2u64.saturating_pow(failures.min(10));
-    let max = config.max_backoff_secs();
-    base.min(max)
+/// Exponential backoff capped at `max_backoff_secs`, with equal jitter: the delay is drawn
+/// from the upper half of the capped value so jobs that fail together retry spread out.
+fn calculate_backoff(failures: u32, config: &SchedulerConfig) -> chrono::Duration {
+    let capped_ms = 2u64
+        .saturating_pow(failures.min(10))
+        .min(config.max_backoff_secs())
+        .saturating_mul(1000);
+    let half = capped_ms / 2;
+    let jitter = random_millis(capped_ms - half);
+    chrono::Duration::milliseconds((half + jitter) as i64)
 }
 
 fn truncate(value: &str, max_len: usize) -> String {
@@ -376,3 +484,262 @@ fn truncate(value: &str, max_len: usize) -> String {
     out.push_str("...");
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::{JobExecutor, calculate_backoff};
+    use crate::config::{Config, SchedulerConfig};
+    use crate::kernel::core::Kernel;
+    use crate::kernel::permissions::{CapabilitySet, PathPattern, Permission};
+    use crate::notifications::channel::{NotificationChannel, NotificationRequest};
+    use crate::notifications::queue::{NotificationQueue, NotificationQueueConfig};
+    use crate::notifications::service::NotificationService;
+    use crate::providers::factory::{ProviderAgentBuilder, ProviderKind};
+    use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+    use crate::scheduler::job::{
+        CreateJobRequest, JobAccount, Principal, PrincipalType, ScheduleType,
+    };
+    use crate::scheduler::store::ScheduleStore;
+    use crate::session::db::SqliteStore;
+    use crate::tools::registry::ToolRegistry;
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for RecordingChannel {
+        fn channel_id(&self) -> &str {
+            "whatsapp"
+        }
+
+        async fn send(&self, request: NotificationRequest) -> Result<(), anyhow::Error> {
+            self.sent.lock().unwrap().push(request.message);
+            Ok(())
+        }
+    }
+
+    fn whatsapp_account(id: &str, session_prefix: &str, profile: &str) -> JobAccount {
+        JobAccount {
+            id: id.to_string(),
+            channel_id: "whatsapp".to_string(),
+            session_prefix: session_prefix.to_string(),
+            profile: profile.to_string(),
+        }
+    }
+
+    #[test]
+    fn simultaneous_failures_get_jittered_backoff_within_cap() {
+        let config = SchedulerConfig {
+            max_backoff_secs: Some(30),
+            ..Default::default()
+        };
+        let failed_at = chrono::Utc::now();
+        let deadlines = (0..50)
+            .map(|_| failed_at + calculate_backoff(6, &config))
+            .collect::<std::collections::HashSet<_>>();
+        for deadline in &deadlines {
+            assert!(*deadline >= failed_at + chrono::Duration::seconds(15), "{deadline}");
+            assert!(*deadline <= failed_at + chrono::Duration::seconds(30), "{deadline}");
+        }
+        // 50 draws from 15001 milliseconds are all equal with negligible probability.
+        assert!(deadlines.len() >= 2, "{deadlines:?}");
+        for _ in 0..100 {
+            let backoff = calculate_backoff(2, &config);
+            assert!(backoff >= chrono::Duration::seconds(2), "{backoff}");
+            assert!(backoff <= chrono::Duration::seconds(4), "{backoff}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_job_sends_progress_before_result() {
+        let provider = ScriptedProvider::start(vec![ScriptedReply::text("Report ready.")]).await;
+        provider.set_delay(Duration::from_millis(1500));
+        let temp_dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let sqlite = SqliteStore::new(temp_dir.join("picobot.db").to_string_lossy().to_string());
+        sqlite.touch().unwrap();
+        let store = ScheduleStore::new(sqlite);
+        let executor = JobExecutor::new(
+            Arc::new(Kernel::new(Arc::new(ToolRegistry::new()))),
+            store.clone(),
+            SchedulerConfig {
+                progress_notify_after_secs: Some(1),
+                ..Default::default()
+            },
+            ProviderAgentBuilder::from_parts(
+                ProviderKind::OpenAI,
+                "scripted".to_string(),
+                "test".to_string(),
+                Some(provider.base_url()),
+                Some("PATH".to_string()),
+            ),
+            None,
+            Config::default(),
+        )
+        .with_accounts(vec![whatsapp_account("default", "whatsapp", "whatsapp")]);
+        let channel = Arc::new(RecordingChannel::default());
+        let notifications = Arc::new(NotificationService::new(
+            NotificationQueue::new(NotificationQueueConfig::default()),
+            channel.clone(),
+        ));
+        executor
+            .set_notifications("default", Some(Arc::clone(&notifications)))
+            .await;
+        let worker = tokio::spawn(async move { notifications.worker_loop().await });
+        let job = store
+            .create_job(
+                CreateJobRequest {
+                    name: "weekly report".to_string(),
+                    schedule_type: ScheduleType::Once,
+                    schedule_expr: "in 1 minute".to_string(),
+                    task_prompt: "write the report".to_string(),
+                    session_id: None,
+                    user_id: "alice".to_string(),
+                    channel_id: Some("whatsapp".to_string()),
+                    capabilities: CapabilitySet::empty(),
+                    creator: Principal {
+                        principal_type: PrincipalType::User,
+                        id: "alice".to_string(),
+                    },
+                    enabled: true,
+                    max_executions: None,
+                    created_by_system: false,
+                    metadata: None,
+                },
+                chrono::Utc::now(),
+            )
+            .unwrap();
+
+        executor.execute(job).await;
+
+        for _ in 0..100 {
+            if channel.sent.lock().unwrap().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        worker.abort();
+        let sent = channel.sent.lock().unwrap().clone();
+        assert_eq!(
+            sent,
+            vec![
+                "Still working on \"weekly report\"...".to_string(),
+                "Report ready.".to_string(),
+            ]
+        );
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn whatsapp_job_runs_under_and_notifies_through_its_account() {
+        let provider =
+            ScriptedProvider::start(vec![ScriptedReply::text("Sales figures ready.")]).await;
+        let temp_dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let sqlite = SqliteStore::new(temp_dir.join("picobot.db").to_string_lossy().to_string());
+        sqlite.touch().unwrap();
+        let store = ScheduleStore::new(sqlite);
+        let config: Config = toml::from_str(
+            r#"
+            [channels.profiles.whatsapp]
+            pre_authorized = ["shell:*"]
+
+            [channels.profiles.whatsapp-sales]
+            pre_authorized = ["filesystem:read:/srv/sales/**"]
+            jail_root = "/srv/sales"
+            "#,
+        )
+        .unwrap();
+        let executor = JobExecutor::new(
+            Arc::new(Kernel::new(Arc::new(ToolRegistry::new()))),
+            store.clone(),
+            SchedulerConfig::default(),
+            ProviderAgentBuilder::from_parts(
+                ProviderKind::OpenAI,
+                "scripted".to_string(),
+                "test".to_string(),
+                Some(provider.base_url()),
+                Some("PATH".to_string()),
+            ),
+            None,
+            config,
+        )
+        .with_accounts(vec![
+            whatsapp_account("main", "whatsapp:main", "whatsapp"),
+            whatsapp_account("sales", "whatsapp:sales", "whatsapp-sales"),
+        ]);
+        let mut workers = Vec::new();
+        let mut channels = Vec::new();
+        for account_id in ["main", "sales"] {
+            let channel = Arc::new(RecordingChannel::default());
+            let notifications = Arc::new(NotificationService::new(
+                NotificationQueue::new(NotificationQueueConfig::default()),
+                channel.clone(),
+            ));
+            executor
+                .set_notifications(account_id, Some(Arc::clone(&notifications)))
+                .await;
+            workers.push(tokio::spawn(async move { notifications.worker_loop().await }));
+            channels.push(channel);
+        }
+        let job = store
+            .create_job(
+                CreateJobRequest {
+                    name: "sales report".to_string(),
+                    schedule_type: ScheduleType::Once,
+                    schedule_expr: "in 1 minute".to_string(),
+                    task_prompt: "summarize sales".to_string(),
+                    session_id: Some("whatsapp:sales:15550001111@s.whatsapp.net".to_string()),
+                    user_id: "alice".to_string(),
+                    channel_id: Some("whatsapp".to_string()),
+                    capabilities: CapabilitySet::empty(),
+                    creator: Principal {
+                        principal_type: PrincipalType::User,
+                        id: "alice".to_string(),
+                    },
+                    enabled: true,
+                    max_executions: None,
+                    created_by_system: false,
+                    metadata: None,
+                },
+                chrono::Utc::now(),
+            )
+            .unwrap();
+
+        let kernel = executor.job_kernel(&job);
+        assert_eq!(
+            kernel.context().jail_root,
+            Some(std::path::PathBuf::from("/srv/sales"))
+        );
+        let pre_authorized = &kernel.prompt_profile().pre_authorized;
+        assert!(pre_authorized.allows(&Permission::FileRead {
+            path: PathPattern("/srv/sales/**".to_string()),
+        }));
+        assert!(!pre_authorized.allows(&Permission::ShellExec {
+            allowed_commands: None,
+        }));
+
+        executor.execute(job).await;
+
+        for _ in 0..100 {
+            if !channels[1].sent.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        workers.iter().for_each(|worker| worker.abort());
+        assert!(channels[0].sent.lock().unwrap().is_empty());
+        assert_eq!(
+            channels[1].sent.lock().unwrap().clone(),
+            vec!["Sales figures ready.".to_string()]
+        );
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+}
@@ -2,6 +2,17 @@ use serde::{Deserialize, Serialize};
 
 use crate::kernel::permissions::CapabilitySet;
 
+/// Snapshot of how far the scheduler is behind its due jobs.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulerHealth {
+    /// Enabled, runnable jobs past `next_run_at` that no worker has claimed.
+    pub overdue_jobs: usize,
+    /// Seconds since the oldest overdue job was due; 0 when nothing is overdue.
+    pub max_lag_secs: i64,
+    pub running_jobs: usize,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ScheduleType {
@@ -101,3 +112,27 @@ impl ScheduledJob {
         }
     }
 }
+
+/// A channel account whose jobs run under its own channel profile and notify through it.
+/// Jobs belong to the account whose sessions they were created in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobAccount {
+    pub id: String,
+    pub channel_id: String,
+    /// The account's session ids are `<session_prefix>:<sender>`.
+    pub session_prefix: String,
+    /// Channel profile (`[channels.profiles.<name>]`) the account's jobs run under.
+    pub profile: String,
+}
+
+impl JobAccount {
+    /// Whether `job` was created on this account's channel, in one of its sessions.
+    pub fn owns(&self, job: &ScheduledJob) -> bool {
+        job.channel_id.as_deref() == Some(self.channel_id.as_str())
+            && job
+                .session_id
+                .as_deref()
+                .and_then(|session_id| session_id.strip_prefix(&self.session_prefix))
+                .is_some_and(|rest| rest.starts_with(':'))
+    }
+}
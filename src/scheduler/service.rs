@@ -8,7 +8,9 @@ use crate::kernel::permissions::Permission;
 use crate::notifications::service::NotificationService;
 use crate::scheduler::error::{SchedulerError, SchedulerResult};
 use crate::scheduler::executor::JobExecutor;
-use crate::scheduler::job::{CreateJobRequest, ScheduleType, ScheduledJob};
+use crate::scheduler::job::{
+    CreateJobRequest, PrincipalType, ScheduleType, ScheduledJob, SchedulerHealth,
+};
 use crate::scheduler::store::ScheduleStore;
 
 #[derive(Clone)]
@@ -36,8 +38,12 @@ impl SchedulerService {
         self.config.enabled()
     }
 
-    pub async fn set_notifications(&self, service: Option<Arc<NotificationService>>) {
-        self.executor.set_notifications(service).await;
+    pub async fn set_notifications(
+        &self,
+        account_id: &str,
+        service: Option<Arc<NotificationService>>,
+    ) {
+        self.executor.set_notifications(account_id, service).await;
     }
 
     pub async fn run_loop(&self) {
@@ -113,6 +119,42 @@ impl SchedulerService {
                 executor.execute(job).await;
             });
         }
+        self.warn_on_backlog();
+    }
+
+    /// Reports jobs the scheduler has fallen behind on.
+    pub fn health(&self) -> SchedulerResult<SchedulerHealth> {
+        let now = chrono::Utc::now();
+        let (overdue_jobs, oldest) = self.store.overdue_jobs(now)?;
+        let running_jobs = self
+            .config
+            .max_concurrent_jobs()
+            .saturating_sub(self.global_semaphore.available_permits());
+        Ok(SchedulerHealth {
+            overdue_jobs,
+            max_lag_secs: oldest.map_or(0, |oldest| (now - oldest).num_seconds().max(0)),
+            running_jobs,
+            checked_at: now,
+        })
+    }
+
+    fn warn_on_backlog(&self) {
+        let health = match self.health() {
+            Ok(health) => health,
+            Err(err) => {
+                tracing::error!(error = %err, "failed to check scheduler backlog");
+                return;
+            }
+        };
+        if health.overdue_jobs > 0 && health.max_lag_secs as u64 >= self.config.lag_warn_secs() {
+            tracing::warn!(
+                event = "scheduler_backlog",
+                overdue_jobs = health.overdue_jobs,
+                max_lag_secs = health.max_lag_secs,
+                running_jobs = health.running_jobs,
+                "scheduler is behind on due jobs"
+            );
+        }
     }
 
     pub fn create_job(&self, request: CreateJobRequest) -> SchedulerResult<ScheduledJob> {
@@ -121,6 +163,9 @@ impl SchedulerService {
         }
         self.ensure_schedule_permission(&request.capabilities)?;
         self.enforce_quotas(&request.user_id)?;
+        if request.creator.principal_type == PrincipalType::User && !request.created_by_system {
+            self.ensure_capacity()?;
+        }
         let next_run_at = compute_initial_run(&request)?;
         self.store.create_job(request, next_run_at)
     }
@@ -220,6 +265,29 @@ impl SchedulerService {
         }
         Ok(())
     }
+
+    /// Refuses new user jobs while a full batch (`max_concurrent_jobs`) of due jobs has been
+    /// waiting for at least a tick; new work would only add to the backlog. The suggested
+    /// retry is one tick per batch of backlog.
+    fn ensure_capacity(&self) -> SchedulerResult<()> {
+        let health = self.health()?;
+        let batch = self.config.max_concurrent_jobs().max(1);
+        let tick_secs = self.config.tick_interval_secs().max(1);
+        if health.overdue_jobs >= batch && health.max_lag_secs as u64 >= tick_secs {
+            tracing::warn!(
+                event = "scheduler_at_capacity",
+                overdue_jobs = health.overdue_jobs,
+                max_lag_secs = health.max_lag_secs,
+                running_jobs = health.running_jobs,
+                "rejecting new job while the scheduler is behind"
+            );
+            let batches = health.overdue_jobs.div_ceil(batch) as u64;
+            return Err(SchedulerError::AtCapacity {
+                retry_after_secs: tick_secs.saturating_mul(batches),
+            });
+        }
+        Ok(())
+    }
 }
 
 fn compute_initial_run(
@@ -234,7 +302,8 @@ pub fn compute_next_run_for(
 ) -> SchedulerResult<chrono::DateTime<chrono::Utc>> {
     match schedule_type {
         ScheduleType::Interval => {
-            let secs = schedule_expr.parse::<u64>().map_err(|_| {
+            let secs = normalize_interval_expr(schedule_expr)?;
+            let secs = secs.parse::<u64>().map_err(|_| {
                 SchedulerError::InvalidSchedule(
                     "interval schedule_expr must be seconds".to_string(),
                 )
@@ -256,6 +325,87 @@ pub fn compute_next_run_for(
     }
 }
 
+/// Normalizes an interval `schedule_expr` to whole seconds before storage.
+///
+/// Accepts plain seconds (`"300"`) or `@every` with humantime-style units, e.g. `@every 30s`,
+/// `@every 5m`, `@every 1h30m`. `@every` durations must be positive.
+pub fn normalize_interval_expr(expr: &str) -> SchedulerResult<String> {
+    let trimmed = expr.trim();
+    let Some(duration) = trimmed.strip_prefix("@every") else {
+        return Ok(trimmed.to_string());
+    };
+    let secs = parse_every_duration(duration).ok_or_else(|| {
+        SchedulerError::InvalidSchedule(format!(
+            "invalid @every duration '{}': use units like 30s, 5m, 2h or 1d",
+            duration.trim()
+        ))
+    })?;
+    if secs == 0 {
+        return Err(SchedulerError::InvalidSchedule(
+            "@every duration must be greater than zero".to_string(),
+        ));
+    }
+    Ok(secs.to_string())
+}
+
+/// Normalizes a cron `schedule_expr` before storage: pads 5-field expressions with a seconds
+/// field, and prefixes `default_tz` when the expression has no `tz|` prefix of its own.
+pub fn normalize_cron_expr(expr: &str, default_tz: Option<&str>) -> SchedulerResult<String> {
+    let trimmed = expr.trim();
+    let (tz, raw) = if let Some((prefix, rest)) = trimmed.split_once('|') {
+        let tz = prefix.trim();
+        if tz.is_empty() {
+            return Err(SchedulerError::InvalidSchedule(
+                "cron timezone missing".to_string(),
+            ));
+        }
+        (Some(tz), rest.trim())
+    } else {
+        (default_tz, trimmed)
+    };
+    let normalized = match raw.split_whitespace().count() {
+        5 => format!("0 {raw}"),
+        6 | 7 => raw.to_string(),
+        _ => {
+            return Err(SchedulerError::InvalidSchedule(
+                "cron expression must have 5 or 6 fields".to_string(),
+            ));
+        }
+    };
+    Ok(match tz {
+        Some(tz) => format!("{tz}|{normalized}"),
+        None => normalized,
+    })
+}
+
+fn parse_every_duration(value: &str) -> Option<u64> {
+    let compact: String = value
+        .to_ascii_lowercase()
+        .split_whitespace()
+        .collect();
+    if compact.is_empty() {
+        return None;
+    }
+    let mut total = 0u64;
+    let mut rest = compact.as_str();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let amount = rest[..digits].parse::<u64>().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+            "d" | "day" | "days" => 60 * 60 * 24,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total = total.checked_add(amount.checked_mul(scale)?)?;
+    }
+    Some(total)
+}
+
 fn parse_relative_duration(value: &str) -> Option<u64> {
     let trimmed = value.trim().to_ascii_lowercase();
     let trimmed = trimmed.strip_prefix("in ").unwrap_or(&trimmed);
@@ -385,9 +535,38 @@ fn parse_cron_with_timezone(expr: &str) -> SchedulerResult<(chrono_tz::Tz, &str)
 
 #[cfg(test)]
 mod tests {
-    use super::{compute_initial_run, next_cron_occurrence};
+    use super::{
+        compute_initial_run, next_cron_occurrence, normalize_cron_expr, normalize_interval_expr,
+    };
     use crate::scheduler::job::{CreateJobRequest, Principal, PrincipalType, ScheduleType};
 
+    #[test]
+    fn every_durations_normalize_to_seconds() {
+        assert_eq!(normalize_interval_expr("@every 90s").unwrap(), "90");
+        assert_eq!(normalize_interval_expr("@every 5m").unwrap(), "300");
+        assert_eq!(normalize_interval_expr("@every 1h30m").unwrap(), "5400");
+        assert_eq!(normalize_interval_expr(" 45 ").unwrap(), "45");
+        assert!(normalize_interval_expr("@every foo").is_err());
+        assert!(normalize_interval_expr("@every 0s").is_err());
+        assert!(normalize_interval_expr("@every -5m").is_err());
+        assert!(normalize_interval_expr("@every").is_err());
+    }
+
+    #[test]
+    fn cron_defaults_apply_only_without_a_prefix() {
+        assert_eq!(normalize_cron_expr("0 9 * * *", None).unwrap(), "0 0 9 * * *");
+        assert_eq!(
+            normalize_cron_expr("0 9 * * *", Some("Asia/Kolkata")).unwrap(),
+            "Asia/Kolkata|0 0 9 * * *"
+        );
+        assert_eq!(
+            normalize_cron_expr("UTC|0 9 * * *", Some("Asia/Kolkata")).unwrap(),
+            "UTC|0 0 9 * * *"
+        );
+        assert!(normalize_cron_expr("|0 9 * * *", None).is_err());
+        assert!(normalize_cron_expr("9 * *", None).is_err());
+    }
+
     #[test]
     fn compute_initial_run_interval() {
         let request = CreateJobRequest {
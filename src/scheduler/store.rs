@@ -172,6 +172,34 @@ impl ScheduleStore {
             .map_err(|err| SchedulerError::Store(err.to_string()))
     }
 
+    /// Counts runnable jobs that are due but unclaimed at `now`, with the oldest due time.
+    pub fn overdue_jobs(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> SchedulerResult<(usize, Option<chrono::DateTime<chrono::Utc>>)> {
+        let now_value = now.to_rfc3339();
+        self.store
+            .with_connection(|conn| {
+                let (count, oldest): (i64, Option<String>) = conn
+                    .query_row(
+                        "SELECT COUNT(*), MIN(next_run_at) FROM schedules
+                         WHERE enabled = 1
+                           AND next_run_at <= ?1
+                           AND (backoff_until IS NULL OR backoff_until <= ?1)
+                           AND (claim_expires_at IS NULL OR claim_expires_at <= ?1)
+                           AND (max_executions IS NULL OR execution_count < max_executions)",
+                        params![now_value],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+                let oldest = oldest
+                    .and_then(|value| chrono::DateTime::parse_from_rfc3339(&value).ok())
+                    .map(|value| value.with_timezone(&chrono::Utc));
+                Ok((count as usize, oldest))
+            })
+            .map_err(|err| SchedulerError::Store(err.to_string()))
+    }
+
     pub fn claim_due_jobs(
         &self,
         now: chrono::DateTime<chrono::Utc>,
@@ -183,50 +211,37 @@ impl ScheduleStore {
         let expires_at = (now + chrono::Duration::seconds(lease_secs as i64)).to_rfc3339();
         self.store
             .with_connection(|conn| {
-                conn.execute("BEGIN IMMEDIATE", [])
-                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+                // One statement selects and leases the due rows, so concurrent claimers
+                // serialize on the write lock and never receive the same job.
                 let mut stmt = conn
-                    .prepare(
-                        "SELECT id FROM schedules
-                         WHERE enabled = 1
-                           AND next_run_at <= ?1
-                           AND (backoff_until IS NULL OR backoff_until <= ?1)
-                           AND (claim_expires_at IS NULL OR claim_expires_at <= ?1)
-                           AND (max_executions IS NULL OR execution_count < max_executions)
-                         ORDER BY next_run_at ASC
-                         LIMIT ?2",
-                    )
-                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-                let ids = stmt
-                    .query_map(params![now_value, limit as i64], |row| row.get::<_, String>(0))
-                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-                let mut claimed_ids = Vec::new();
-                for id in ids {
-                    let id = id.map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-                    let updated = conn
-                        .execute(
-                            "UPDATE schedules
-                             SET claimed_at = ?1, claim_id = ?2, claim_expires_at = ?3, updated_at = ?4
-                             WHERE id = ?5
-                               AND (claim_expires_at IS NULL OR claim_expires_at <= ?1)
+                    .prepare(&format!(
+                        "UPDATE schedules
+                         SET claimed_at = ?1, claim_id = ?2, claim_expires_at = ?3, updated_at = ?1
+                         WHERE id IN (
+                             SELECT id FROM schedules
+                             WHERE enabled = 1
+                               AND next_run_at <= ?1
                                AND (backoff_until IS NULL OR backoff_until <= ?1)
+                               AND (claim_expires_at IS NULL OR claim_expires_at <= ?1)
                                AND (max_executions IS NULL OR execution_count < max_executions)
-                               AND enabled = 1",
-                            params![now_value, claim_id, expires_at, now_value, id],
-                        )
-                        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-                    if updated == 1 {
-                        claimed_ids.push(id);
-                    }
-                }
-                conn.execute("COMMIT", [])
+                             ORDER BY next_run_at ASC
+                             LIMIT ?4
+                         )
+                         RETURNING {JOB_COLUMNS}"
+                    ))
+                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+                let mut rows = stmt
+                    .query(params![now_value, claim_id, expires_at, limit as i64])
                     .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
                 let mut jobs = Vec::new();
-                for id in claimed_ids {
-                    if let Some(job) = load_job(conn, &id)? {
-                        jobs.push(job);
-                    }
+                while let Some(row) = rows
+                    .next()
+                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?
+                {
+                    jobs.push(job_from_row(row)?);
                 }
+                // RETURNING does not follow the subquery's ORDER BY.
+                jobs.sort_by_key(|job| job.next_run_at);
                 Ok(jobs)
             })
             .map_err(|err| SchedulerError::Store(err.to_string()))
@@ -337,20 +352,11 @@ fn load_jobs_by_user(
     conn: &Connection,
     user_id: &str,
 ) -> Result<Vec<ScheduledJob>, SessionDbError> {
-    let mut stmt = conn
-        .prepare("SELECT id FROM schedules WHERE user_id = ?1 ORDER BY created_at DESC")
-        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    let rows = stmt
-        .query_map([user_id], |row| row.get::<_, String>(0))
-        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    let mut jobs = Vec::new();
-    for row in rows {
-        let id = row.map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-        if let Some(job) = load_job(conn, &id)? {
-            jobs.push(job);
-        }
-    }
-    Ok(jobs)
+    query_jobs(
+        conn,
+        "WHERE user_id = ?1 ORDER BY created_at DESC",
+        params![user_id],
+    )
 }
 
 fn load_jobs_by_user_with_session(
@@ -358,62 +364,61 @@ fn load_jobs_by_user_with_session(
     user_id: &str,
     session_id: &str,
 ) -> Result<Vec<ScheduledJob>, SessionDbError> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id FROM schedules WHERE user_id = ?1 AND session_id = ?2 ORDER BY created_at DESC",
-        )
-        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    let rows = stmt
-        .query_map(params![user_id, session_id], |row| row.get::<_, String>(0))
-        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    let mut jobs = Vec::new();
-    for row in rows {
-        let id = row.map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-        if let Some(job) = load_job(conn, &id)? {
-            jobs.push(job);
-        }
-    }
-    Ok(jobs)
+    query_jobs(
+        conn,
+        "WHERE user_id = ?1 AND session_id = ?2 ORDER BY created_at DESC",
+        params![user_id, session_id],
+    )
 }
 
 #[allow(dead_code)]
 fn load_jobs(conn: &Connection) -> Result<Vec<ScheduledJob>, SessionDbError> {
+    query_jobs(conn, "ORDER BY created_at DESC", [])
+}
+
+fn query_jobs<P: rusqlite::Params>(
+    conn: &Connection,
+    clause: &str,
+    params: P,
+) -> Result<Vec<ScheduledJob>, SessionDbError> {
     let mut stmt = conn
-        .prepare("SELECT id FROM schedules ORDER BY created_at DESC")
+        .prepare(&format!("SELECT {JOB_COLUMNS} FROM schedules {clause}"))
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    let rows = stmt
-        .query_map([], |row| row.get::<_, String>(0))
+    let mut rows = stmt
+        .query(params)
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
     let mut jobs = Vec::new();
-    for row in rows {
-        let id = row.map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-        if let Some(job) = load_job(conn, &id)? {
-            jobs.push(job);
-        }
+    while let Some(row) = rows
+        .next()
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?
+    {
+        jobs.push(job_from_row(row)?);
     }
     Ok(jobs)
 }
 
+const JOB_COLUMNS: &str = "id, name, schedule_type, schedule_expr, task_prompt, session_id, user_id, channel_id,
+    capabilities_json, creator_principal, enabled, max_executions, execution_count,
+    claimed_at, claim_id, claim_expires_at, last_run_at, next_run_at, created_at, updated_at,
+    consecutive_failures, last_error, backoff_until, metadata_json, created_by_system";
+
 fn load_job(conn: &Connection, id: &str) -> Result<Option<ScheduledJob>, SessionDbError> {
     let mut stmt = conn
-        .prepare(
-            "SELECT id, name, schedule_type, schedule_expr, task_prompt, session_id, user_id, channel_id,
-                    capabilities_json, creator_principal, enabled, max_executions, execution_count,
-                    claimed_at, claim_id, claim_expires_at, last_run_at, next_run_at, created_at, updated_at,
-                    consecutive_failures, last_error, backoff_until, metadata_json, created_by_system
-             FROM schedules WHERE id = ?1",
-        )
+        .prepare(&format!("SELECT {JOB_COLUMNS} FROM schedules WHERE id = ?1"))
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
     let mut rows = stmt
         .query(params![id])
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    let row = match rows
+    match rows
         .next()
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?
     {
-        Some(row) => row,
-        None => return Ok(None),
-    };
+        Some(row) => job_from_row(row).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn job_from_row(row: &rusqlite::Row<'_>) -> Result<ScheduledJob, SessionDbError> {
     let schedule_type: String = row
         .get(2)
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
@@ -438,7 +443,7 @@ fn load_job(conn: &Connection, id: &str) -> Result<Option<ScheduledJob>, Session
         .map(|value| serde_json::from_str(&value))
         .transpose()
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
-    Ok(Some(ScheduledJob {
+    Ok(ScheduledJob {
         id: row
             .get(0)
             .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?,
@@ -518,7 +523,7 @@ fn load_job(conn: &Connection, id: &str) -> Result<Option<ScheduledJob>, Session
         ),
         metadata,
         created_by_system: created_by_system != 0,
-    }))
+    })
 }
 
 fn insert_execution(conn: &Connection, execution: &JobExecution) -> Result<(), SessionDbError> {
@@ -650,7 +655,7 @@ fn schedule_type_to_str(value: ScheduleType) -> &'static str {
     }
 }
 
-fn execution_status_to_str(value: ExecutionStatus) -> &'static str {
+pub(crate) fn execution_status_to_str(value: ExecutionStatus) -> &'static str {
     match value {
         ExecutionStatus::Running => "running",
         ExecutionStatus::Completed => "completed",
@@ -735,4 +740,109 @@ mod tests {
         assert_eq!(claimed.len(), 1);
         std::fs::remove_dir_all(&dir).ok();
     }
+
+    #[test]
+    fn claim_due_jobs_leases_large_batches_in_due_order() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("picobot.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let schedule_store = ScheduleStore::new(store.clone());
+
+        let now = chrono::Utc::now();
+        for index in 0..200 {
+            let request = crate::scheduler::job::CreateJobRequest {
+                name: format!("job-{index}"),
+                schedule_type: crate::scheduler::job::ScheduleType::Interval,
+                schedule_expr: "60".to_string(),
+                task_prompt: "ping".to_string(),
+                session_id: None,
+                user_id: "user".to_string(),
+                channel_id: None,
+                capabilities: crate::kernel::permissions::CapabilitySet::empty(),
+                creator: crate::scheduler::job::Principal {
+                    principal_type: crate::scheduler::job::PrincipalType::User,
+                    id: "user".to_string(),
+                },
+                enabled: true,
+                max_executions: None,
+                created_by_system: false,
+                metadata: None,
+            };
+            let next_run_at = now - chrono::Duration::seconds(200 - index);
+            schedule_store.create_job(request, next_run_at).unwrap();
+        }
+
+        let claim_id = uuid::Uuid::new_v4().to_string();
+        let claimed = schedule_store
+            .claim_due_jobs(now, 500, &claim_id, 30)
+            .unwrap();
+        assert_eq!(claimed.len(), 200);
+        assert!(
+            claimed
+                .iter()
+                .all(|job| job.claim_id.as_deref() == Some(claim_id.as_str())
+                    && job.claim_expires_at.is_some())
+        );
+        assert!(
+            claimed
+                .windows(2)
+                .all(|pair| pair[0].next_run_at <= pair[1].next_run_at)
+        );
+        assert_eq!(claimed[0].name, "job-0");
+
+        let again = schedule_store
+            .claim_due_jobs(now, 500, &uuid::Uuid::new_v4().to_string(), 30)
+            .unwrap();
+        assert!(again.is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn overdue_jobs_reports_unclaimed_backlog_and_lag() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("picobot.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let schedule_store = ScheduleStore::new(store.clone());
+
+        let now = chrono::Utc::now();
+        for (index, offset_secs) in [-300, -120, -30, 600].into_iter().enumerate() {
+            let request = crate::scheduler::job::CreateJobRequest {
+                name: format!("job-{index}"),
+                schedule_type: crate::scheduler::job::ScheduleType::Interval,
+                schedule_expr: "60".to_string(),
+                task_prompt: "ping".to_string(),
+                session_id: None,
+                user_id: "user".to_string(),
+                channel_id: None,
+                capabilities: crate::kernel::permissions::CapabilitySet::empty(),
+                creator: crate::scheduler::job::Principal {
+                    principal_type: crate::scheduler::job::PrincipalType::User,
+                    id: "user".to_string(),
+                },
+                enabled: true,
+                max_executions: None,
+                created_by_system: false,
+                metadata: None,
+            };
+            schedule_store
+                .create_job(request, now + chrono::Duration::seconds(offset_secs))
+                .unwrap();
+        }
+
+        let (overdue, oldest) = schedule_store.overdue_jobs(now).unwrap();
+        assert_eq!(overdue, 3);
+        assert_eq!((now - oldest.unwrap()).num_seconds(), 300);
+
+        // Claimed jobs are being worked on, so they no longer count as backlog.
+        let claimed = schedule_store
+            .claim_due_jobs(now, 1, &uuid::Uuid::new_v4().to_string(), 30)
+            .unwrap();
+        assert_eq!(claimed[0].name, "job-0");
+        let (overdue, oldest) = schedule_store.overdue_jobs(now).unwrap();
+        assert_eq!(overdue, 2);
+        assert_eq!((now - oldest.unwrap()).num_seconds(), 120);
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -1,23 +1,176 @@
 use std::fs;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use rusqlite::{Connection, OpenFlags, params};
+use rusqlite::{Connection, OpenFlags, Transaction, TransactionBehavior, params};
 
 use crate::session::error::{SessionDbError, SessionDbResult};
 
+/// Stored in `PRAGMA user_version` once `migrate` has run; bump it when the schema changes.
+const SCHEMA_VERSION: i64 = 1;
+const MAX_POOL_CONNECTIONS: u32 = 8;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const JOURNAL_MODES: &[&str] = &["WAL", "DELETE", "TRUNCATE", "PERSIST", "MEMORY"];
+const SYNCHRONOUS_MODES: &[&str] = &["NORMAL", "FULL", "EXTRA", "OFF"];
+/// Extra attempts for work that still hits a lock after `BUSY_TIMEOUT`, e.g. a read
+/// transaction that cannot upgrade to a write because its WAL snapshot went stale.
+const BUSY_RETRIES: u32 = 3;
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(25);
+
 #[derive(Debug, Clone)]
 pub struct SqliteStore {
     path: Arc<String>,
+    pragmas: SqlitePragmas,
+    pool: r2d2::Pool<SqliteConnectionManager>,
+    /// Set once this store (or a clone) has seen the schema at `SCHEMA_VERSION`.
+    migrated: Arc<Mutex<bool>>,
+}
+
+/// Journaling, sync, and lock-wait settings applied to every connection a store opens.
+/// The default is WAL with `synchronous = NORMAL` and a 5 second busy timeout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlitePragmas {
+    journal_mode: &'static str,
+    synchronous: &'static str,
+    busy_timeout: Duration,
+}
+
+impl Default for SqlitePragmas {
+    fn default() -> Self {
+        Self {
+            journal_mode: "WAL",
+            synchronous: "NORMAL",
+            busy_timeout: BUSY_TIMEOUT,
+        }
+    }
+}
+
+impl SqlitePragmas {
+    /// Validates configured values (case-insensitive); unset ones keep the default.
+    pub fn new(
+        journal_mode: Option<&str>,
+        synchronous: Option<&str>,
+        busy_timeout_ms: Option<u64>,
+    ) -> Result<Self, String> {
+        let defaults = Self::default();
+        let pick = |value: Option<&str>, allowed: &[&'static str], default, name: &str| {
+            let Some(value) = value else {
+                return Ok(default);
+            };
+            allowed
+                .iter()
+                .find(|mode| mode.eq_ignore_ascii_case(value.trim()))
+                .copied()
+                .ok_or_else(|| {
+                    format!("invalid {name} '{value}' (expected one of {})", allowed.join(", "))
+                })
+        };
+        Ok(Self {
+            journal_mode: pick(journal_mode, JOURNAL_MODES, defaults.journal_mode, "journal_mode")?,
+            synchronous: pick(synchronous, SYNCHRONOUS_MODES, defaults.synchronous, "synchronous")?,
+            busy_timeout: busy_timeout_ms.map_or(defaults.busy_timeout, Duration::from_millis),
+        })
+    }
+
+    pub fn journal_mode(&self) -> &'static str {
+        self.journal_mode
+    }
+
+    pub fn synchronous(&self) -> &'static str {
+        self.synchronous
+    }
+
+    pub fn busy_timeout(&self) -> Duration {
+        self.busy_timeout
+    }
+
+    fn apply(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.busy_timeout(self.busy_timeout)?;
+        // journal_mode answers with the resulting mode, so it has to be read as a query.
+        conn.query_row(&format!("PRAGMA journal_mode = {}", self.journal_mode), [], |_| Ok(()))?;
+        conn.execute_batch(&format!("PRAGMA synchronous = {}", self.synchronous))
+    }
+}
+
+/// Opens pooled connections. With the default WAL journal, pooled readers run in parallel
+/// while writers serialize on SQLite's lock, waiting up to the busy timeout.
+#[derive(Debug)]
+struct SqliteConnectionManager {
+    path: Arc<String>,
+    pragmas: SqlitePragmas,
+}
+
+impl r2d2::ManageConnection for SqliteConnectionManager {
+    type Connection = Connection;
+    type Error = rusqlite::Error;
+
+    fn connect(&self) -> Result<Connection, rusqlite::Error> {
+        let conn = open_connection(self.path.as_str())?;
+        self.pragmas.apply(&conn)?;
+        Ok(conn)
+    }
+
+    fn is_valid(&self, conn: &mut Connection) -> Result<(), rusqlite::Error> {
+        conn.execute_batch("")
+    }
+
+    // A connection handed back mid-transaction (an early return between BEGIN and
+    // COMMIT) is discarded, which rolls the transaction back on close.
+    fn has_broken(&self, conn: &mut Connection) -> bool {
+        !conn.is_autocommit()
+    }
+}
+
+fn open_connection(path: &str) -> Result<Connection, rusqlite::Error> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_CREATE
+            | OpenFlags::SQLITE_OPEN_FULL_MUTEX,
+    )
+}
+
+fn build_pool(path: &Arc<String>, pragmas: &SqlitePragmas) -> r2d2::Pool<SqliteConnectionManager> {
+    r2d2::Pool::builder()
+        .max_size(MAX_POOL_CONNECTIONS)
+        .min_idle(Some(0))
+        .connection_timeout(BUSY_TIMEOUT)
+        .build_unchecked(SqliteConnectionManager {
+            path: Arc::clone(path),
+            pragmas: pragmas.clone(),
+        })
 }
 
 impl SqliteStore {
     pub fn new(path: String) -> Self {
+        let path = Arc::new(path);
+        let pragmas = SqlitePragmas::default();
+        let pool = build_pool(&path, &pragmas);
         Self {
-            path: Arc::new(path),
+            path,
+            pragmas,
+            pool,
+            migrated: Arc::new(Mutex::new(false)),
         }
     }
 
+    /// Opens the database at `path` with `pragmas` and brings its schema up to date.
+    /// `main` opens each database once and hands clones around, so components share
+    /// one connection pool.
+    pub fn open_migrated(path: &Path, pragmas: SqlitePragmas) -> SessionDbResult<Self> {
+        let store = Self::new(path.to_string_lossy().to_string()).with_pragmas(pragmas);
+        store.touch()?;
+        Ok(store)
+    }
+
+    /// Opens connections with `pragmas` instead of the defaults.
+    pub fn with_pragmas(mut self, pragmas: SqlitePragmas) -> Self {
+        self.pool = build_pool(&self.path, &pragmas);
+        self.pragmas = pragmas;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn path(&self) -> &str {
         self.path.as_str()
@@ -33,19 +186,17 @@ impl SqliteStore {
 
     pub fn open(&self) -> SessionDbResult<Connection> {
         self.ensure_parent_dir()?;
-        Connection::open_with_flags(
-            self.path.as_str(),
-            OpenFlags::SQLITE_OPEN_READ_WRITE
-                | OpenFlags::SQLITE_OPEN_CREATE
-                | OpenFlags::SQLITE_OPEN_FULL_MUTEX,
-        )
-        .map_err(|err| SessionDbError::OpenFailed(err.to_string()))
+        let conn = open_connection(self.path.as_str())
+            .map_err(|err| SessionDbError::OpenFailed(err.to_string()))?;
+        self.pragmas
+            .apply(&conn)
+            .map_err(|err| SessionDbError::OpenFailed(err.to_string()))?;
+        Ok(conn)
     }
 
     pub fn migrate(&self, conn: &Connection) -> SessionDbResult<()> {
         conn.execute_batch(
-            "PRAGMA journal_mode = WAL;
-            PRAGMA foreign_keys = ON;
+            "PRAGMA foreign_keys = ON;
             CREATE TABLE IF NOT EXISTS sessions (
                 id TEXT PRIMARY KEY,
                 channel_type TEXT NOT NULL,
@@ -55,7 +206,9 @@ impl SqliteStore {
                 created_at TEXT NOT NULL,
                 last_active TEXT NOT NULL,
                 state_json TEXT NOT NULL,
-                summary TEXT
+                summary TEXT,
+                title TEXT,
+                metadata_json TEXT
             );
             CREATE TABLE IF NOT EXISTS messages (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -153,6 +306,24 @@ impl SqliteStore {
         ) {
             return Err(SessionDbError::MigrationFailed(err.to_string()));
         }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS notification_deliveries (
+                id TEXT PRIMARY KEY,
+                queue TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                channel_id TEXT NOT NULL,
+                message TEXT NOT NULL,
+                priority TEXT NOT NULL,
+                status TEXT NOT NULL CHECK(status IN ('pending', 'sending', 'failed')),
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_notification_deliveries_queue
+                ON notification_deliveries(queue, status, created_at);",
+        )
+        .map_err(|err| SessionDbError::MigrationFailed(err.to_string()))?;
         if let Err(err) = conn.execute(
             "ALTER TABLE schedules ADD COLUMN created_by_system INTEGER NOT NULL DEFAULT 0",
             [],
@@ -160,21 +331,94 @@ impl SqliteStore {
         {
             return Err(SessionDbError::MigrationFailed(err.to_string()));
         }
+        for statement in [
+            "ALTER TABLE sessions ADD COLUMN title TEXT",
+            "ALTER TABLE sessions ADD COLUMN metadata_json TEXT",
+        ] {
+            if let Err(err) = conn.execute(statement, [])
+                && !err.to_string().contains("duplicate column")
+            {
+                return Err(SessionDbError::MigrationFailed(err.to_string()));
+            }
+        }
         Ok(())
     }
 
     pub fn touch(&self) -> SessionDbResult<()> {
-        let conn = self.open()?;
-        self.migrate(&conn)?;
-        Ok(())
+        self.ensure_migrated().map(|_| ())
+    }
+
+    /// Runs migrations unless the database is already at `SCHEMA_VERSION`; returns whether
+    /// they ran. The version is checked and written in one immediate transaction, so
+    /// stores opened separately on the same file (or other processes) wait instead of racing.
+    pub(crate) fn ensure_migrated(&self) -> SessionDbResult<bool> {
+        let mut migrated = self.migrated.lock().unwrap_or_else(|err| err.into_inner());
+        if *migrated {
+            return Ok(false);
+        }
+        let failed = |err: rusqlite::Error| SessionDbError::MigrationFailed(err.to_string());
+        let mut conn = self.open()?;
+        let tx = Transaction::new(&mut conn, TransactionBehavior::Immediate).map_err(failed)?;
+        let version: i64 = tx
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(failed)?;
+        let ran = version < SCHEMA_VERSION;
+        if ran {
+            self.migrate(&tx)?;
+            tx.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION}"))
+                .map_err(failed)?;
+        }
+        tx.commit().map_err(failed)?;
+        *migrated = true;
+        Ok(ran)
     }
 
-    pub fn with_connection<F, T>(&self, f: F) -> SessionDbResult<T>
+    /// Runs `f` on a pooled connection. When it fails because the database is busy or
+    /// locked, `f` is run again on a fresh connection, up to `BUSY_RETRIES` times with
+    /// backoff, so it must be safe to repeat (wrap multi-statement writes in a transaction).
+    /// Other errors are returned as they are. Retries sleep on the calling thread, so on a
+    /// multi-threaded tokio runtime they run under `block_in_place` to let the worker's
+    /// other tasks move elsewhere meanwhile.
+    pub fn with_connection<F, T>(&self, mut f: F) -> SessionDbResult<T>
     where
-        F: FnOnce(&Connection) -> SessionDbResult<T>,
+        F: FnMut(&Connection) -> SessionDbResult<T>,
     {
-        let conn = self.open()?;
-        f(&conn)
+        self.ensure_parent_dir()?;
+        let result = f(&*self.pooled()?);
+        match result {
+            Err(err) if is_busy(&err) => off_runtime_worker(|| self.retry_busy(err, f)),
+            result => result,
+        }
+    }
+
+    fn retry_busy<F, T>(&self, mut err: SessionDbError, mut f: F) -> SessionDbResult<T>
+    where
+        F: FnMut(&Connection) -> SessionDbResult<T>,
+    {
+        for attempt in 0..BUSY_RETRIES {
+            let delay = BUSY_RETRY_BASE_DELAY * 2u32.pow(attempt);
+            tracing::warn!(
+                event = "sqlite_busy_retry",
+                path = %self.path,
+                attempt = attempt + 1,
+                delay_ms = delay.as_millis() as u64,
+                error = %err,
+                "database busy, retrying"
+            );
+            std::thread::sleep(delay);
+            let result = f(&*self.pooled()?);
+            match result {
+                Err(next) if is_busy(&next) => err = next,
+                result => return result,
+            }
+        }
+        Err(err)
+    }
+
+    fn pooled(&self) -> SessionDbResult<r2d2::PooledConnection<SqliteConnectionManager>> {
+        self.pool
+            .get()
+            .map_err(|err| SessionDbError::OpenFailed(err.to_string()))
     }
 
     #[allow(dead_code)]
@@ -201,11 +445,40 @@ impl SqliteStore {
     }
 }
 
+/// Runs blocking `op`, handing this worker's other tasks to another thread first when
+/// called from a multi-threaded tokio runtime. Elsewhere `op` just runs in place.
+fn off_runtime_worker<T>(op: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(op)
+        }
+        _ => op(),
+    }
+}
+
+/// Whether `err` came from SQLite reporting `SQLITE_BUSY` or `SQLITE_LOCKED`. Query errors
+/// are stored as text, so this matches SQLite's messages for those codes.
+fn is_busy(err: &SessionDbError) -> bool {
+    match err {
+        SessionDbError::Busy => true,
+        SessionDbError::QueryFailed(message) => {
+            message.contains("database is locked")
+                || message.contains("database table is locked")
+                || message.contains("database is busy")
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
 
-    use super::SqliteStore;
+    use super::{SqlitePragmas, SqliteStore};
+    use crate::session::error::SessionDbError;
     use uuid::Uuid;
 
     #[test]
@@ -219,4 +492,227 @@ mod tests {
         store.insert_probe(&conn).unwrap();
         fs::remove_dir_all(&dir).ok();
     }
+
+    #[test]
+    fn configured_pragmas_are_set_on_fresh_connections() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let pragmas = |store: &SqliteStore| {
+            store
+                .with_connection(|conn| {
+                    let read = |pragma: &str| {
+                        conn.query_row(&format!("PRAGMA {pragma}"), [], |row| {
+                            row.get::<_, rusqlite::types::Value>(0)
+                        })
+                        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+                    };
+                    Ok((read("journal_mode")?, read("synchronous")?, read("busy_timeout")?))
+                })
+                .unwrap()
+        };
+        use rusqlite::types::Value::{Integer, Text};
+
+        let store = SqliteStore::new(dir.join("default.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        assert_eq!(pragmas(&store), (Text("wal".to_string()), Integer(1), Integer(5000)));
+
+        let configured = SqlitePragmas::new(Some("delete"), Some("FULL"), Some(1234)).unwrap();
+        let store = SqliteStore::new(dir.join("durable.db").to_string_lossy().to_string())
+            .with_pragmas(configured);
+        store.touch().unwrap();
+        assert_eq!(pragmas(&store), (Text("delete".to_string()), Integer(2), Integer(1234)));
+
+        assert!(SqlitePragmas::new(Some("wall"), None, None).is_err());
+        assert!(SqlitePragmas::new(None, Some("sometimes"), None).is_err());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn concurrent_touch_migrates_once() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("picobot.db").to_string_lossy().to_string();
+
+        let handles = (0..8)
+            .map(|_| {
+                let store = SqliteStore::new(path.clone());
+                std::thread::spawn(move || store.ensure_migrated().unwrap())
+            })
+            .collect::<Vec<_>>();
+        let ran = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|ran| *ran)
+            .count();
+        assert_eq!(ran, 1);
+
+        let store = SqliteStore::new(path.clone());
+        assert!(!store.ensure_migrated().unwrap());
+        let conn = store.open().unwrap();
+        store.insert_probe(&conn).unwrap();
+        drop(conn);
+
+        // A new file at the same path is a new database and gets the schema again.
+        fs::remove_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(path);
+        assert!(store.ensure_migrated().unwrap());
+        assert!(!store.ensure_migrated().unwrap());
+        let conn = store.open().unwrap();
+        store.insert_probe(&conn).unwrap();
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn pooled_reads_and_writes_do_not_hit_locked_errors() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("picobot.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let started = std::time::Instant::now();
+
+        let mut handles = (0..6)
+            .map(|_| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        store.with_connection(|conn| {
+                            conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| {
+                                row.get::<_, i64>(0)
+                            })
+                            .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+                        })?;
+                    }
+                    Ok::<_, SessionDbError>(())
+                })
+            })
+            .collect::<Vec<_>>();
+        let writer = store.clone();
+        handles.push(std::thread::spawn(move || {
+            for index in 0..50 {
+                writer.with_connection(|conn| {
+                    conn.execute(
+                        "INSERT INTO sessions (id, channel_type, channel_id, user_id, permissions_json, created_at, last_active, state_json)
+                         VALUES (?1, 'repl', 'repl', 'user', '{}', '', '', '{}')",
+                        rusqlite::params![format!("session-{index}")],
+                    )
+                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+                })?;
+            }
+            Ok(())
+        }));
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        let count = store
+            .with_connection(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+            })
+            .unwrap();
+        assert_eq!(count, 50);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn busy_errors_are_retried_but_query_errors_are_not() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("picobot.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+
+        // Another writer holds the lock for the first two attempts, as when a deferred
+        // transaction cannot upgrade to a write.
+        let mut attempts = 0;
+        let count = store
+            .with_connection(|conn| {
+                attempts += 1;
+                if attempts <= 2 {
+                    return Err(SessionDbError::QueryFailed("database is locked".to_string()));
+                }
+                conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+            })
+            .unwrap();
+        assert_eq!((count, attempts), (0, 3));
+
+        let mut attempts = 0;
+        let result: Result<i64, _> = store.with_connection(|conn| {
+            attempts += 1;
+            conn.query_row("SELECT COUNT(*) FROM missing_table", [], |row| row.get(0))
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+        });
+        assert!(result.unwrap_err().to_string().contains("no such table"));
+        assert_eq!(attempts, 1);
+
+        let mut attempts = 0;
+        let result: Result<(), _> = store.with_connection(|_| {
+            attempts += 1;
+            Err(SessionDbError::Busy)
+        });
+        assert!(matches!(result, Err(SessionDbError::Busy)));
+        assert_eq!(attempts, 4);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn busy_retries_let_other_tasks_run_on_the_worker() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("picobot.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+        // Both tasks share the single worker, so the ticker only advances while the
+        // retries sleep if the store gives the worker up.
+        let seen = tokio::spawn(async move {
+            let mut seen = Vec::new();
+            let result: Result<(), _> = store.with_connection(|_| {
+                seen.push(ticks.load(Ordering::SeqCst));
+                Err(SessionDbError::Busy)
+            });
+            assert!(matches!(result, Err(SessionDbError::Busy)));
+            seen
+        })
+        .await
+        .unwrap();
+        ticker.abort();
+        assert_eq!(seen.len(), 4);
+        assert!(seen[3] > seen[0], "{seen:?}");
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn unfinished_transactions_are_not_returned_to_the_pool() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("picobot.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+
+        let result: Result<(), SessionDbError> = store.with_connection(|conn| {
+            conn.execute("BEGIN IMMEDIATE", [])
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Err(SessionDbError::QueryFailed("aborted".to_string()))
+        });
+        assert!(result.is_err());
+        let autocommit = store
+            .with_connection(|conn| Ok(conn.is_autocommit()))
+            .unwrap();
+        assert!(autocommit);
+        fs::remove_dir_all(&dir).ok();
+    }
 }
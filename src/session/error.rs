@@ -6,6 +6,8 @@ pub enum SessionDbError {
     MigrationFailed(String),
     #[error("Database query failed: {0}")]
     QueryFailed(String),
+    #[error("seq_order {0} falls inside compacted history")]
+    CompactedHistory(i64),
     #[error("Database busy")]
     #[allow(dead_code)]
     Busy,
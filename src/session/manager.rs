@@ -1,18 +1,43 @@
-use rusqlite::{params, Connection};
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior, params};
 
 use crate::kernel::permissions::CapabilitySet;
 use crate::session::db::SqliteStore;
 use crate::session::error::{SessionDbError, SessionDbResult};
-use crate::session::types::{MessageType, Session, SessionState, StoredMessage, UsageEvent};
+use crate::session::types::{
+    MessageType, NewMessage, Session, SessionListing, SessionState, StoredMessage, UsageEvent,
+};
+
+/// Longest session summary kept by compaction; the oldest text is dropped first.
+const MAX_SUMMARY_CHARS: usize = 4000;
+/// Per-message excerpt length in compaction summaries.
+const SUMMARY_EXCERPT_CHARS: usize = 200;
+
+/// Rows removed by [`SessionManager::purge_older_than`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeReport {
+    pub messages: usize,
+    pub sessions: usize,
+}
 
 #[derive(Debug, Clone)]
 pub struct SessionManager {
     store: SqliteStore,
+    max_stored_messages: Option<usize>,
 }
 
 impl SessionManager {
     pub fn new(store: SqliteStore) -> Self {
-        Self { store }
+        Self {
+            store,
+            max_stored_messages: None,
+        }
+    }
+
+    /// Caps stored messages per session. When an append pushes a session past `max`, the
+    /// oldest messages are folded into its summary so only the newest half remain.
+    pub fn with_max_stored_messages(mut self, max: Option<usize>) -> Self {
+        self.max_stored_messages = max.filter(|max| *max > 0);
+        self
     }
 
     #[allow(dead_code)]
@@ -38,6 +63,8 @@ impl SessionManager {
             created_at: now,
             last_active: now,
             state: SessionState::Active,
+            title: None,
+            metadata: None,
         };
         self.store.with_connection(|conn| {
             insert_session(conn, &session)?;
@@ -50,6 +77,12 @@ impl SessionManager {
         self.store.with_connection(|conn| load_session(conn, id))
     }
 
+    /// Sessions owned by `user_id`, most recently active first.
+    pub fn list_sessions_for_user(&self, user_id: &str) -> SessionDbResult<Vec<SessionListing>> {
+        self.store
+            .with_connection(|conn| load_sessions_for_user(conn, user_id))
+    }
+
     pub fn touch(&self, id: &str) -> SessionDbResult<()> {
         let now = chrono::Utc::now().to_rfc3339();
         self.store.with_connection(|conn| {
@@ -62,9 +95,154 @@ impl SessionManager {
         })
     }
 
-    pub fn append_message(&self, session_id: &str, message: &StoredMessage) -> SessionDbResult<()> {
-        self.store
-            .with_connection(|conn| insert_message(conn, session_id, message))
+    /// Appends a message and returns the `seq_order` assigned to it.
+    ///
+    /// The order is allocated inside an immediate transaction, so concurrent
+    /// appends to one session get distinct, gapless values.
+    pub fn append_message(&self, session_id: &str, message: &NewMessage) -> SessionDbResult<i64> {
+        self.store.with_connection(|conn| {
+            let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            let seq_order = insert_message(&tx, session_id, message)?;
+            if let Some(max) = self.max_stored_messages {
+                compact_messages(&tx, session_id, max)?;
+            }
+            if message.message_type == MessageType::User
+                && let Some(title) = derive_session_title(&message.content)
+            {
+                tx.execute(
+                    "UPDATE sessions SET title = ?1 WHERE id = ?2 AND title IS NULL",
+                    params![title, session_id],
+                )
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            }
+            tx.commit()
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Ok(seq_order)
+        })
+    }
+
+    /// Copies session `source_id` into a new session `new_id` with the same owner,
+    /// channel, title, metadata and summary, plus its messages up to and including
+    /// `up_to_seq` (all of them when `None`), and its compaction summary. Returns how many
+    /// messages were copied, or `None` when the source does not exist; fails if `new_id`
+    /// is taken, or with [`SessionDbError::CompactedHistory`] if `up_to_seq` falls inside
+    /// history that only survives in the summary.
+    pub fn fork_session(
+        &self,
+        source_id: &str,
+        new_id: &str,
+        up_to_seq: Option<i64>,
+    ) -> SessionDbResult<Option<usize>> {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.store.with_connection(|conn| {
+            let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            let inserted = tx
+                .execute(
+                    "INSERT INTO sessions
+                     (id, channel_type, channel_id, user_id, permissions_json, created_at,
+                      last_active, state_json, summary, title, metadata_json)
+                     SELECT ?1, channel_type, channel_id, user_id, permissions_json, ?3, ?3,
+                            state_json, summary, title, metadata_json
+                     FROM sessions WHERE id = ?2",
+                    params![new_id, source_id, now],
+                )
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            if inserted == 0 {
+                return Ok(None);
+            }
+            if let Some(seq) = up_to_seq {
+                let compacted: bool = tx
+                    .query_row(
+                        "SELECT EXISTS(SELECT 1 FROM session_summaries WHERE session_id = ?1)
+                         AND ?2 < (SELECT MIN(seq_order) FROM messages WHERE session_id = ?1)",
+                        params![source_id, seq],
+                        |row| row.get(0),
+                    )
+                    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+                if compacted {
+                    return Err(SessionDbError::CompactedHistory(seq));
+                }
+            }
+            let copied = tx
+                .execute(
+                    "INSERT INTO messages
+                     (session_id, message_type, content, tool_call_id, created_at, seq_order,
+                      token_estimate)
+                     SELECT ?1, message_type, content, tool_call_id, created_at, seq_order,
+                            token_estimate
+                     FROM messages WHERE session_id = ?2 AND (?3 IS NULL OR seq_order <= ?3)
+                     ORDER BY seq_order",
+                    params![new_id, source_id, up_to_seq],
+                )
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            // Compacted messages survive only in the summary, so the fork needs it too.
+            tx.execute(
+                "INSERT INTO session_summaries
+                 (session_id, summary, message_count, created_at, updated_at)
+                 SELECT ?1, summary, message_count, created_at, ?3
+                 FROM session_summaries WHERE session_id = ?2",
+                params![new_id, source_id, now],
+            )
+            .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            tx.commit()
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Ok(Some(copied))
+        })
+    }
+
+    /// `None` clears the title for good: it is stored empty, so the next user message
+    /// does not derive a new one.
+    pub fn set_title(&self, id: &str, title: Option<&str>) -> SessionDbResult<()> {
+        self.store.with_connection(|conn| {
+            conn.execute(
+                "UPDATE sessions SET title = ?1 WHERE id = ?2",
+                params![title.unwrap_or_default(), id],
+            )
+            .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Ok(())
+        })
+    }
+
+    /// Applies `patch` to the stored metadata as a JSON merge patch (RFC 7386): keys in
+    /// the patch replace stored ones, `null` removes a key and other keys are kept.
+    pub fn merge_metadata(&self, id: &str, patch: &serde_json::Value) -> SessionDbResult<()> {
+        self.store.with_connection(|conn| {
+            let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            let stored: Option<String> = tx
+                .query_row(
+                    "SELECT metadata_json FROM sessions WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?
+                .flatten();
+            let mut metadata = stored
+                .map(|value| serde_json::from_str(&value))
+                .transpose()
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?
+                .unwrap_or(serde_json::Value::Null);
+            merge_patch(&mut metadata, patch);
+            let metadata_json = match &metadata {
+                serde_json::Value::Object(map) if map.is_empty() => None,
+                serde_json::Value::Null => None,
+                metadata => Some(
+                    serde_json::to_string(metadata)
+                        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?,
+                ),
+            };
+            tx.execute(
+                "UPDATE sessions SET metadata_json = ?1 WHERE id = ?2",
+                params![metadata_json, id],
+            )
+            .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            tx.commit()
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Ok(())
+        })
     }
 
     pub fn get_messages(
@@ -76,6 +254,38 @@ impl SessionManager {
             .with_connection(|conn| load_messages(conn, session_id, limit))
     }
 
+    /// Deletes messages created before `cutoff`, then sessions inactive since `cutoff` that
+    /// have no messages left. `user_id` limits both to that user's sessions.
+    pub fn purge_older_than(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+        user_id: Option<&str>,
+    ) -> SessionDbResult<PurgeReport> {
+        let cutoff = cutoff.to_rfc3339();
+        self.store.with_connection(|conn| {
+            let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            let messages = tx
+                .execute(
+                    "DELETE FROM messages WHERE created_at < ?1
+                     AND (?2 IS NULL OR session_id IN (SELECT id FROM sessions WHERE user_id = ?2))",
+                    params![cutoff, user_id],
+                )
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            let sessions = tx
+                .execute(
+                    "DELETE FROM sessions WHERE last_active < ?1
+                     AND (?2 IS NULL OR user_id = ?2)
+                     AND NOT EXISTS (SELECT 1 FROM messages WHERE messages.session_id = sessions.id)",
+                    params![cutoff, user_id],
+                )
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            tx.commit()
+                .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+            Ok(PurgeReport { messages, sessions })
+        })
+    }
+
     pub fn record_usage(&self, event: &UsageEvent) -> SessionDbResult<()> {
         self.store
             .with_connection(|conn| insert_usage_event(conn, event))
@@ -113,7 +323,8 @@ fn insert_session(conn: &Connection, session: &Session) -> SessionDbResult<()> {
 fn load_session(conn: &Connection, id: &str) -> SessionDbResult<Option<Session>> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, channel_type, channel_id, user_id, permissions_json, created_at, last_active, state_json
+            "SELECT id, channel_type, channel_id, user_id, permissions_json, created_at, last_active, state_json,
+                    title, metadata_json
              FROM sessions WHERE id = ?1",
         )
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
@@ -146,6 +357,17 @@ fn load_session(conn: &Connection, id: &str) -> SessionDbResult<Option<Session>>
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
     let state: SessionState = serde_json::from_str(&state_json)
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let title: Option<String> = row
+        .get::<_, Option<String>>(8)
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?
+        .filter(|title| !title.is_empty());
+    let metadata_json: Option<String> = row
+        .get(9)
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let metadata = metadata_json
+        .map(|value| serde_json::from_str(&value))
+        .transpose()
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
 
     Ok(Some(Session {
         id: row
@@ -164,30 +386,167 @@ fn load_session(conn: &Connection, id: &str) -> SessionDbResult<Option<Session>>
         created_at,
         last_active,
         state,
+        title,
+        metadata,
     }))
 }
 
+fn load_sessions_for_user(conn: &Connection, user_id: &str) -> SessionDbResult<Vec<SessionListing>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.channel_type, s.channel_id, s.title, s.created_at,
+                    MAX(s.last_active, COALESCE(MAX(m.created_at), s.last_active)) AS last_activity,
+                    COUNT(m.id)
+             FROM sessions s
+             LEFT JOIN messages m ON m.session_id = s.id
+             WHERE s.user_id = ?1
+             GROUP BY s.id
+             ORDER BY last_activity DESC, s.id ASC",
+        )
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let rows = stmt
+        .query_map(params![user_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, i64>(6)?,
+            ))
+        })
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let mut sessions = Vec::new();
+    for row in rows {
+        let (id, channel_type, channel_id, title, created_at, last_activity, message_count) =
+            row.map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+        sessions.push(SessionListing {
+            id,
+            channel_type,
+            channel_id,
+            title: title.filter(|title| !title.is_empty()),
+            created_at: parse_datetime(created_at)?,
+            last_activity: parse_datetime(last_activity)?,
+            message_count: message_count.max(0) as u64,
+        });
+    }
+    Ok(sessions)
+}
+
 fn insert_message(
     conn: &Connection,
     session_id: &str,
-    message: &StoredMessage,
-) -> SessionDbResult<()> {
+    message: &NewMessage,
+) -> SessionDbResult<i64> {
     let now = chrono::Utc::now().to_rfc3339();
-    conn.execute(
+    conn.query_row(
         "INSERT INTO messages
          (session_id, message_type, content, tool_call_id, created_at, seq_order, token_estimate)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+         SELECT ?1, ?2, ?3, ?4, ?5, COALESCE(MAX(seq_order) + 1, 0), ?6
+         FROM messages WHERE session_id = ?1
+         RETURNING seq_order",
         params![
             session_id,
             message.message_type.as_str(),
             message.content,
             message.tool_call_id,
             now,
-            message.seq_order,
             message.token_estimate,
         ],
+        |row| row.get(0),
+    )
+    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
+}
+
+/// Folds the oldest messages of an over-cap session into `session_summaries`.
+///
+/// The summary is extractive (role-tagged excerpts appended to the previous summary), so
+/// compaction needs no model call and can run inside the append transaction.
+fn compact_messages(conn: &Connection, session_id: &str, max: usize) -> SessionDbResult<()> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM messages WHERE session_id = ?1",
+            [session_id],
+            |row| row.get(0),
+        )
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let count = count as usize;
+    if count <= max {
+        return Ok(());
+    }
+    let compacted = count - (max / 2).max(1);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT message_type, content, seq_order FROM messages
+             WHERE session_id = ?1 ORDER BY seq_order ASC LIMIT ?2",
+        )
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let rows = stmt
+        .query_map(params![session_id, compacted as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    let mut lines = Vec::new();
+    let mut last_seq_order = 0;
+    for row in rows {
+        let (message_type, content, seq_order) =
+            row.map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+        last_seq_order = seq_order;
+        // Tool traffic is bulky and rarely useful once the answer built on it is kept.
+        if message_type == "tool" || message_type == "assistant_tool_calls" {
+            continue;
+        }
+        let excerpt = content.split_whitespace().collect::<Vec<_>>().join(" ");
+        let excerpt = match excerpt.char_indices().nth(SUMMARY_EXCERPT_CHARS) {
+            Some((end, _)) => format!("{}...", &excerpt[..end]),
+            None => excerpt,
+        };
+        lines.push(format!("[{message_type}] {excerpt}"));
+    }
+
+    let previous = conn
+        .query_row(
+            "SELECT summary FROM session_summaries WHERE session_id = ?1",
+            [session_id],
+            |row| row.get::<_, String>(0),
+        )
+        .ok();
+    let mut summary = previous.into_iter().chain(lines).collect::<Vec<_>>().join("\n");
+    let total_chars = summary.chars().count();
+    if total_chars > MAX_SUMMARY_CHARS
+        && let Some((start, _)) = summary.char_indices().nth(total_chars - MAX_SUMMARY_CHARS)
+    {
+        summary = summary[start..].to_string();
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO session_summaries (session_id, summary, message_count, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(session_id) DO UPDATE SET
+             summary = excluded.summary,
+             message_count = message_count + excluded.message_count,
+             updated_at = excluded.updated_at",
+        params![session_id, summary, compacted as i64, now],
     )
     .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    conn.execute(
+        "DELETE FROM messages WHERE session_id = ?1 AND seq_order <= ?2",
+        params![session_id, last_seq_order],
+    )
+    .map_err(|err| SessionDbError::QueryFailed(err.to_string()))?;
+    tracing::info!(
+        event = "session_compacted",
+        session_id = %session_id,
+        compacted,
+        max_stored_messages = max,
+        "compacted old session messages into summary"
+    );
     Ok(())
 }
 
@@ -254,8 +613,364 @@ fn insert_usage_event(conn: &Connection, event: &UsageEvent) -> SessionDbResult<
     Ok(())
 }
 
+const MAX_DERIVED_TITLE_CHARS: usize = 60;
+
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    if let serde_json::Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(
+                    target.entry(key.clone()).or_insert(serde_json::Value::Null),
+                    value,
+                );
+            }
+        }
+    }
+}
+
+/// First line of a user message, whitespace-collapsed and shortened for use as a title.
+fn derive_session_title(content: &str) -> Option<String> {
+    let line = content.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= MAX_DERIVED_TITLE_CHARS {
+        return Some(collapsed);
+    }
+    let mut title = collapsed
+        .chars()
+        .take(MAX_DERIVED_TITLE_CHARS)
+        .collect::<String>()
+        .trim_end()
+        .to_string();
+    title.push_str("...");
+    Some(title)
+}
+
 fn parse_datetime(value: String) -> SessionDbResult<chrono::DateTime<chrono::Utc>> {
     chrono::DateTime::parse_from_rfc3339(&value)
         .map(|dt| dt.with_timezone(&chrono::Utc))
         .map_err(|err| SessionDbError::QueryFailed(err.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SessionManager;
+    use crate::kernel::permissions::CapabilitySet;
+    use crate::session::db::SqliteStore;
+    use crate::session::error::SessionDbError;
+    use crate::session::types::{MessageType, NewMessage};
+
+    fn temp_manager() -> (SessionManager, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        (SessionManager::new(store), dir)
+    }
+
+    fn user_message(index: i64) -> NewMessage {
+        NewMessage::new(MessageType::User, format!("message {index}"))
+    }
+
+    #[test]
+    fn list_sessions_for_user_orders_by_activity_with_counts() {
+        let (manager, dir) = temp_manager();
+        for (id, channel) in [
+            ("api:alice", "api"),
+            ("whatsapp:alice", "whatsapp"),
+            ("repl:alice", "repl"),
+        ] {
+            manager
+                .create_session(
+                    id.to_string(),
+                    channel.to_string(),
+                    channel.to_string(),
+                    "alice".to_string(),
+                    CapabilitySet::empty(),
+                )
+                .unwrap();
+        }
+        manager
+            .create_session(
+                "api:bob".to_string(),
+                "api".to_string(),
+                "api".to_string(),
+                "bob".to_string(),
+                CapabilitySet::empty(),
+            )
+            .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        manager.append_message("api:alice", &user_message(0)).unwrap();
+        manager.append_message("api:alice", &user_message(1)).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        manager
+            .append_message("whatsapp:alice", &user_message(0))
+            .unwrap();
+        manager.append_message("api:bob", &user_message(0)).unwrap();
+
+        let sessions = manager.list_sessions_for_user("alice").unwrap();
+        let ids = sessions
+            .iter()
+            .map(|session| session.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["whatsapp:alice", "api:alice", "repl:alice"]);
+        let counts = sessions
+            .iter()
+            .map(|session| session.message_count)
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![1, 2, 0]);
+        assert_eq!(sessions[0].channel_type, "whatsapp");
+        assert!(sessions[0].last_activity >= sessions[0].created_at);
+        assert_eq!(manager.list_sessions_for_user("bob").unwrap().len(), 1);
+        assert!(manager.list_sessions_for_user("carol").unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn concurrent_appends_get_distinct_gapless_seq_orders() {
+        let (manager, dir) = temp_manager();
+        manager
+            .create_session(
+                "api:alice".to_string(),
+                "api".to_string(),
+                "api".to_string(),
+                "alice".to_string(),
+                CapabilitySet::empty(),
+            )
+            .unwrap();
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+        let handles = (0..2)
+            .map(|_| {
+                let manager = manager.clone();
+                let barrier = std::sync::Arc::clone(&barrier);
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    (0..20)
+                        .map(|_| manager.append_message("api:alice", &user_message(0)).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut assigned = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+        assigned.sort_unstable();
+        assert_eq!(assigned, (0..40).collect::<Vec<i64>>());
+        let stored = manager
+            .get_messages("api:alice", 100)
+            .unwrap()
+            .into_iter()
+            .map(|message| message.seq_order)
+            .collect::<Vec<_>>();
+        assert_eq!(stored, (0..40).collect::<Vec<i64>>());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn session_title_is_derived_then_overridable() {
+        let (manager, dir) = temp_manager();
+        manager
+            .create_session(
+                "api:alice".to_string(),
+                "api".to_string(),
+                "api".to_string(),
+                "alice".to_string(),
+                CapabilitySet::empty(),
+            )
+            .unwrap();
+        let mut first = user_message(0);
+        first.content = "  Plan the   quarterly review\nwith details".to_string();
+        manager.append_message("api:alice", &first).unwrap();
+        manager.append_message("api:alice", &user_message(1)).unwrap();
+        let session = manager.get_session("api:alice").unwrap().unwrap();
+        assert_eq!(session.title.as_deref(), Some("Plan the quarterly review"));
+
+        manager.set_title("api:alice", Some("Q3 review")).unwrap();
+        let metadata = serde_json::json!({"tags": ["work"], "model": "fast"});
+        manager.merge_metadata("api:alice", &metadata).unwrap();
+        let session = manager.get_session("api:alice").unwrap().unwrap();
+        assert_eq!(session.title.as_deref(), Some("Q3 review"));
+        assert_eq!(session.metadata, Some(metadata));
+        let listing = manager.list_sessions_for_user("alice").unwrap();
+        assert_eq!(listing[0].title.as_deref(), Some("Q3 review"));
+
+        let patch = serde_json::json!({"tags": ["home"], "pinned": true, "model": null});
+        manager.merge_metadata("api:alice", &patch).unwrap();
+        let session = manager.get_session("api:alice").unwrap().unwrap();
+        assert_eq!(
+            session.metadata,
+            Some(serde_json::json!({"tags": ["home"], "pinned": true}))
+        );
+
+        // A cleared title stays cleared instead of being derived from the next message.
+        manager.set_title("api:alice", None).unwrap();
+        manager.append_message("api:alice", &first).unwrap();
+        let session = manager.get_session("api:alice").unwrap().unwrap();
+        assert_eq!(session.title, None);
+        let listing = manager.list_sessions_for_user("alice").unwrap();
+        assert_eq!(listing[0].title, None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn forking_at_a_midpoint_copies_only_earlier_messages() {
+        let (manager, dir) = temp_manager();
+        manager
+            .create_session(
+                "api:alice".to_string(),
+                "api".to_string(),
+                "api".to_string(),
+                "alice".to_string(),
+                CapabilitySet::empty(),
+            )
+            .unwrap();
+        manager.set_title("api:alice", Some("Trip plans")).unwrap();
+        for seq in 0..4 {
+            manager.append_message("api:alice", &user_message(seq)).unwrap();
+        }
+
+        let copied = manager
+            .fork_session("api:alice", "api:alice:fork-1", Some(1))
+            .unwrap()
+            .expect("source exists");
+        assert_eq!(copied, 2);
+        let fork = manager.get_session("api:alice:fork-1").unwrap().unwrap();
+        assert_eq!(fork.user_id, "alice");
+        assert_eq!(fork.title.as_deref(), Some("Trip plans"));
+        let contents = |id: &str| {
+            manager
+                .get_messages(id, 10)
+                .unwrap()
+                .into_iter()
+                .map(|message| (message.seq_order, message.content))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            contents("api:alice:fork-1"),
+            vec![(0, "message 0".to_string()), (1, "message 1".to_string())]
+        );
+
+        // The branches diverge from here.
+        let next = manager
+            .append_message("api:alice:fork-1", &user_message(0))
+            .unwrap();
+        assert_eq!(next, 2);
+        assert_eq!(contents("api:alice").len(), 4);
+
+        assert!(manager.fork_session("api:nobody", "api:x", None).unwrap().is_none());
+        assert!(manager.fork_session("api:alice", "api:alice:fork-1", None).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn forking_a_compacted_session_keeps_its_summary() {
+        let (manager, dir) = temp_manager();
+        let manager = manager.with_max_stored_messages(Some(4));
+        manager
+            .create_session(
+                "api:alice".to_string(),
+                "api".to_string(),
+                "api".to_string(),
+                "alice".to_string(),
+                CapabilitySet::empty(),
+            )
+            .unwrap();
+        for seq in 0..5 {
+            manager.append_message("api:alice", &user_message(seq)).unwrap();
+        }
+        let retriever = crate::session::memory::MemoryRetriever::new(
+            crate::config::MemoryConfig::default(),
+            manager.store().clone(),
+        );
+        let context = |id: &str| {
+            let messages = manager.get_messages(id, 10).unwrap();
+            retriever
+                .build_context(None, Some(id), &messages)
+                .into_iter()
+                .map(|message| message.content)
+                .collect::<Vec<_>>()
+        };
+
+        for (fork_id, up_to_seq) in [("api:alice:all", None), ("api:alice:at-3", Some(3))] {
+            manager
+                .fork_session("api:alice", fork_id, up_to_seq)
+                .unwrap()
+                .expect("source exists");
+            assert_eq!(context(fork_id)[0], context("api:alice")[0]);
+            assert!(context(fork_id)[0].starts_with("Session summary:\n[user] message 0"));
+        }
+        assert_eq!(context("api:alice:at-3").len(), 2);
+
+        // Forking inside the compacted history would claim messages past the fork point.
+        let err = manager
+            .fork_session("api:alice", "api:alice:at-1", Some(1))
+            .unwrap_err();
+        assert!(matches!(err, SessionDbError::CompactedHistory(1)));
+        assert!(manager.get_session("api:alice:at-1").unwrap().is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn exceeding_max_stored_messages_compacts_into_summary() {
+        let (manager, dir) = temp_manager();
+        let manager = manager.with_max_stored_messages(Some(4));
+        manager
+            .create_session(
+                "whatsapp:alice".to_string(),
+                "whatsapp".to_string(),
+                "whatsapp".to_string(),
+                "alice".to_string(),
+                CapabilitySet::empty(),
+            )
+            .unwrap();
+        for seq in 0..4 {
+            manager
+                .append_message("whatsapp:alice", &user_message(seq))
+                .unwrap();
+        }
+        assert_eq!(manager.get_messages("whatsapp:alice", 10).unwrap().len(), 4);
+
+        // The fifth append crosses the cap: the oldest three fold into the summary.
+        manager
+            .append_message("whatsapp:alice", &user_message(4))
+            .unwrap();
+        let remaining = manager.get_messages("whatsapp:alice", 10).unwrap();
+        let contents = remaining
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, vec!["message 3", "message 4"]);
+        let appended = manager
+            .append_message("whatsapp:alice", &user_message(5))
+            .unwrap();
+        assert_eq!(appended, 5);
+
+        let retriever = crate::session::memory::MemoryRetriever::new(
+            crate::config::MemoryConfig::default(),
+            manager.store().clone(),
+        );
+        let remaining = manager.get_messages("whatsapp:alice", 10).unwrap();
+        let context = retriever.build_context(None, Some("whatsapp:alice"), &remaining);
+        assert_eq!(context[0].message_type, MessageType::System);
+        assert_eq!(
+            context[0].content,
+            "Session summary:\n[user] message 0\n[user] message 1\n[user] message 2"
+        );
+        assert_eq!(context.len(), 4);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -5,15 +5,39 @@ use crate::session::db::SqliteStore;
 use crate::session::error::SessionDbError;
 use crate::session::types::{MessageType, StoredMessage};
 
+/// Most tokens of context assembled for a prompt when the model's window is unknown.
+pub const MAX_CONTEXT_BUDGET_TOKENS: usize = 128_000;
+
+/// Largest usable `context_budget_tokens`: three quarters of a known context window, so the
+/// system prompt, tool schemas and the reply still fit, else [`MAX_CONTEXT_BUDGET_TOKENS`].
+pub fn context_budget_cap(context_window_tokens: Option<u32>) -> usize {
+    match context_window_tokens {
+        Some(window) => (window as usize).saturating_mul(3) / 4,
+        None => MAX_CONTEXT_BUDGET_TOKENS,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MemoryRetriever {
     pub config: MemoryConfig,
     store: SqliteStore,
+    context_window_tokens: Option<u32>,
 }
 
 impl MemoryRetriever {
     pub fn new(config: MemoryConfig, store: SqliteStore) -> Self {
-        Self { config, store }
+        Self {
+            config,
+            store,
+            context_window_tokens: None,
+        }
+    }
+
+    /// Caps the context budget relative to the model's context window (see
+    /// [`context_budget_cap`]).
+    pub fn with_context_window(mut self, context_window_tokens: Option<u32>) -> Self {
+        self.context_window_tokens = context_window_tokens;
+        self
     }
 
     pub fn build_context(
@@ -23,10 +47,10 @@ impl MemoryRetriever {
         session_messages: &[StoredMessage],
     ) -> Vec<StoredMessage> {
         let mut output = Vec::new();
-        let include_summary = self.config.include_summary_on_truncation.unwrap_or(true);
-        if self.config.enable_user_memories.unwrap_or(true)
+        let include_summary = self.config.include_summary_on_truncation();
+        if self.config.enable_user_memories()
             && let Some(user_id) = user_id
-            && let Ok(memories) = load_user_memories(&self.store, user_id, self.max_user_memories())
+            && let Ok(memories) = load_user_memories(&self.store, user_id, self.config.max_user_memories())
             && !memories.is_empty()
         {
             let mut lines = Vec::new();
@@ -43,11 +67,12 @@ impl MemoryRetriever {
             });
         }
 
-        let max_messages = self.config.max_session_messages.unwrap_or(20);
+        let max_messages = self.config.max_session_messages();
         let count = session_messages.len();
         let start = count.saturating_sub(max_messages);
+        // Summaries are written when old messages are compacted away, so they only ever
+        // cover history that is no longer in `session_messages`.
         if include_summary
-            && start > 0
             && let Some(summary) = load_session_summary(&self.store, session_id)
         {
             output.push(StoredMessage {
@@ -63,15 +88,13 @@ impl MemoryRetriever {
         self.apply_budget(output)
     }
 
-    fn max_user_memories(&self) -> usize {
-        self.config.max_user_memories.unwrap_or(50)
-    }
-
     fn apply_budget(&self, mut messages: Vec<StoredMessage>) -> Vec<StoredMessage> {
-        let budget = self.config.context_budget_tokens.unwrap_or(4000) as usize;
-        if budget == 0 {
-            return messages;
-        }
+        // A budget of 0 turns off the configured limit but never the safety cap.
+        let cap = context_budget_cap(self.context_window_tokens);
+        let budget = match self.config.context_budget_tokens() as usize {
+            0 => cap,
+            budget => budget.min(cap),
+        };
         while estimate_tokens(&messages) > budget {
             if messages.len() <= 1 {
                 break;
@@ -180,3 +203,54 @@ fn load_session_summary(store: &SqliteStore, session_id: Option<&str>) -> Option
         .ok()
         .flatten()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryRetriever, estimate_tokens};
+    use crate::config::MemoryConfig;
+    use crate::session::db::SqliteStore;
+    use crate::session::types::{MessageType, StoredMessage};
+
+    #[test]
+    fn oversized_context_budgets_are_clamped() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let config = MemoryConfig {
+            context_budget_tokens: Some(1_000_000),
+            ..Default::default()
+        };
+        // Twenty messages of 10k tokens each, 200k in total.
+        let messages = (0..20)
+            .map(|seq| StoredMessage {
+                message_type: MessageType::User,
+                content: "x".repeat(40_000),
+                tool_call_id: None,
+                seq_order: seq,
+                token_estimate: None,
+            })
+            .collect::<Vec<_>>();
+
+        let retriever = MemoryRetriever::new(config, store);
+        let context = retriever.build_context(None, None, &messages);
+        assert_eq!(context.len(), 12);
+        assert!(estimate_tokens(&context) <= 128_000);
+
+        let retriever = retriever.with_context_window(Some(32_000));
+        let context = retriever.build_context(None, None, &messages);
+        assert_eq!(context.len(), 2);
+        assert!(estimate_tokens(&context) <= 24_000);
+
+        let unlimited = MemoryRetriever::new(
+            MemoryConfig {
+                context_budget_tokens: Some(0),
+                ..Default::default()
+            },
+            retriever.store.clone(),
+        );
+        let context = unlimited.build_context(None, None, &messages);
+        assert_eq!(context.len(), 12);
+        assert!(estimate_tokens(&context) <= 128_000);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -13,6 +13,20 @@ pub struct Session {
     pub created_at: DateTime<Utc>,
     pub last_active: DateTime<Utc>,
     pub state: SessionState,
+    pub title: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Lightweight view of a session for listings, without loading permissions or state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionListing {
+    pub id: String,
+    pub channel_type: String,
+    pub channel_id: String,
+    pub title: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub message_count: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +45,26 @@ pub struct StoredMessage {
     pub token_estimate: Option<i64>,
 }
 
+/// A message to append to a session; the store assigns its `seq_order`.
+#[derive(Debug, Clone)]
+pub struct NewMessage {
+    pub message_type: MessageType,
+    pub content: String,
+    pub tool_call_id: Option<String>,
+    pub token_estimate: Option<i64>,
+}
+
+impl NewMessage {
+    pub fn new(message_type: MessageType, content: impl Into<String>) -> Self {
+        Self {
+            message_type,
+            content: content.into(),
+            tool_call_id: None,
+            token_estimate: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UsageEvent {
     pub session_id: Option<String>,
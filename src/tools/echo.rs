@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::kernel::permissions::Permission;
+use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+
+/// Debug tool that returns its input unchanged, for checking provider tool-call round trips
+/// without side effects. Registered only when `tools.debug_enabled` is set.
+#[derive(Debug, Default)]
+pub struct EchoTool {
+    spec: ToolSpec,
+}
+
+impl EchoTool {
+    pub fn new() -> Self {
+        Self {
+            spec: ToolSpec {
+                name: "echo".to_string(),
+                description: "Return the input unchanged. Only for testing that tool calls work; it has no other effect.".to_string(),
+                schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "message": { "type": "string" }
+                    }
+                }),
+                output_schema: None,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for EchoTool {
+    fn spec(&self) -> &ToolSpec {
+        &self.spec
+    }
+
+    fn required_permissions(
+        &self,
+        _ctx: &ToolContext,
+        _input: &Value,
+    ) -> Result<Vec<Permission>, ToolError> {
+        Ok(Vec::new())
+    }
+
+    async fn execute(&self, _ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::EchoTool;
+    use crate::kernel::core::Kernel;
+    use crate::tools::registry::ToolRegistry;
+
+    #[tokio::test]
+    async fn echo_returns_input_through_kernel() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool::new())).unwrap();
+        let kernel = Kernel::new(Arc::new(registry));
+        let payload = json!({ "message": "ping", "nested": { "values": [1, 2.5, null, true] } });
+
+        let output = kernel
+            .invoke_tool_with_prompt_by_name("echo", payload.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(output, payload);
+    }
+}
@@ -1,9 +1,14 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
 use async_trait::async_trait;
 use serde_json::{Value, json};
 
 use crate::kernel::permissions::{PathPattern, Permission};
 use crate::tools::path_utils::resolve_path;
-use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+use crate::tools::traits::{
+    ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
 
 #[derive(Debug, Default)]
 pub struct FilesystemTool {
@@ -15,7 +20,7 @@ impl FilesystemTool {
         Self {
             spec: ToolSpec {
                 name: "filesystem".to_string(),
-                description: "Read or write files within the allowed directory. path is relative to the working directory; jail escapes are rejected. write requires content. Returns {content} on read, {status} on write."
+                description: "Read or write files within the allowed directory. path is relative to the working directory; jail escapes are rejected. write requires content and replaces the file atomically; backup=true first keeps the previous content in <path>.bak. Returns {content} on read, {status} on write."
                     .to_string(),
                 schema: json!({
                     "type": "object",
@@ -31,10 +36,26 @@ impl FilesystemTool {
                         },
                         "content": {
                             "type": "string"
+                        },
+                        "backup": {
+                            "type": "boolean"
                         }
                     },
                     "additionalProperties": false
                 }),
+                output_schema: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "content": { "type": "string" },
+                        "status": { "const": "ok" },
+                        "backup": { "type": "string" }
+                    },
+                    "oneOf": [
+                        { "required": ["content"] },
+                        { "required": ["status"] }
+                    ],
+                    "additionalProperties": false
+                })),
             },
         }
     }
@@ -62,12 +83,28 @@ impl ToolExecutor for FilesystemTool {
 
         let resolved = resolve_path(&ctx.working_dir, ctx.jail_root.as_deref(), path)?;
         let pattern = PathPattern(resolved.canonical.to_string_lossy().to_string());
-        let permission = match operation {
-            "read" => Permission::FileRead { path: pattern },
-            "write" => Permission::FileWrite { path: pattern },
-            _ => return Err(ToolError::new("invalid operation".to_string())),
-        };
-        Ok(vec![permission])
+        match operation {
+            "read" => Ok(vec![Permission::FileRead { path: pattern }]),
+            "write" if wants_backup(input) => Ok(vec![
+                Permission::FileWrite { path: pattern },
+                Permission::FileWrite {
+                    path: PathPattern(
+                        resolve_backup(ctx, &resolved.canonical)?
+                            .to_string_lossy()
+                            .to_string(),
+                    ),
+                },
+            ]),
+            "write" => Ok(vec![Permission::FileWrite { path: pattern }]),
+            _ => Err(ToolError::new("invalid operation".to_string())),
+        }
+    }
+
+    fn permission_hints(&self) -> Vec<String> {
+        vec![
+            "filesystem:read:<path>".to_string(),
+            "filesystem:write:<path>".to_string(),
+        ]
     }
 
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
@@ -92,6 +129,19 @@ impl ToolExecutor for FilesystemTool {
                     .get("content")
                     .and_then(Value::as_str)
                     .ok_or_else(|| ToolError::new("missing content".to_string()))?;
+                if ctx.dry_run {
+                    return Ok(dry_run_output(
+                        ctx,
+                        &self.spec.name,
+                        "write",
+                        json!({
+                            "path": resolved.canonical.to_string_lossy(),
+                            "bytes": content.len(),
+                            "exists": resolved.canonical.exists(),
+                            "backup": wants_backup(&input),
+                        }),
+                    ));
+                }
                 if let Some(parent) = resolved.canonical.parent() {
                     std::fs::create_dir_all(parent)
                         .map_err(|err| ToolError::new(err.to_string()))?;
@@ -102,18 +152,124 @@ impl ToolExecutor for FilesystemTool {
                         "path changed after directory creation".to_string(),
                     ));
                 }
-                std::fs::write(&re_resolved.canonical, content)
-                    .map_err(|err| ToolError::new(err.to_string()))?;
-                Ok(json!({"status": "ok"}))
+                let backup = if wants_backup(&input) {
+                    Some(resolve_backup(ctx, &re_resolved.canonical)?)
+                } else {
+                    None
+                };
+                let backup = write_atomically(&re_resolved.canonical, backup.as_deref(), |file| {
+                    file.write_all(content.as_bytes())
+                })
+                .map_err(|err| ToolError::new(err.to_string()))?;
+                match backup {
+                    Some(backup) => Ok(json!({
+                        "status": "ok",
+                        "backup": backup.to_string_lossy(),
+                    })),
+                    None => Ok(json!({"status": "ok"})),
+                }
             }
             _ => Err(ToolError::new("invalid operation".to_string())),
         }
     }
 }
 
+fn wants_backup(input: &Value) -> bool {
+    input.get("backup").and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// `<path>.bak`, resolved against the jail like any other target. A symlink there is
+/// refused outright rather than followed.
+fn resolve_backup(ctx: &ToolContext, canonical: &Path) -> Result<PathBuf, ToolError> {
+    let backup = backup_path(canonical);
+    if backup
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+    {
+        return Err(ToolError::new(format!(
+            "backup path is a symlink: {}",
+            backup.display()
+        )));
+    }
+    let resolved = resolve_path(
+        &ctx.working_dir,
+        ctx.jail_root.as_deref(),
+        &backup.to_string_lossy(),
+    )?;
+    Ok(resolved.canonical)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    path.with_file_name(temp_name)
+}
+
+/// Writes through a temp file in the target's directory and renames it over `path`, so a
+/// crash or failed write leaves the previous content in place. With `backup`, an existing
+/// file is first copied there the same way, and the backup path is returned.
+fn write_atomically(
+    path: &Path,
+    backup: Option<&Path>,
+    write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> std::io::Result<Option<PathBuf>> {
+    let temp = temp_path(path);
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        write(&mut file)?;
+        file.sync_all()?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        let backup = match backup {
+            Some(backup) if path.is_file() => {
+                copy_atomically(path, backup)?;
+                Some(backup.to_path_buf())
+            }
+            _ => None,
+        };
+        std::fs::rename(&temp, path)?;
+        Ok(backup)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Copies `from` to a fresh temp file beside `to` and renames it into place, so the
+/// rename replaces whatever sits at `to` instead of writing through it.
+fn copy_atomically(from: &Path, to: &Path) -> std::io::Result<()> {
+    let temp = temp_path(to);
+    let result = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        std::io::copy(&mut std::fs::File::open(from)?, &mut file)?;
+        file.sync_all()?;
+        file.set_permissions(std::fs::metadata(from)?.permissions())?;
+        std::fs::rename(&temp, to)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
-    use super::FilesystemTool;
+    use super::{FilesystemTool, write_atomically};
     use crate::kernel::permissions::{CapabilitySet, Permission};
     use crate::tools::path_utils::{normalize_path, resolve_path};
     use crate::tools::traits::{ExecutionMode, ToolContext, ToolExecutor};
@@ -175,11 +331,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
 
         let read = tool
@@ -192,4 +352,214 @@ mod tests {
             .unwrap();
         assert!(matches!(write[0], Permission::FileWrite { .. }));
     }
+
+    #[tokio::test]
+    async fn dry_run_write_reports_plan_without_touching_filesystem() {
+        let dir = std::env::temp_dir().join(format!("picobot-dry-run-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tool = FilesystemTool::new();
+        let ctx = ToolContext {
+            working_dir: dir.clone(),
+            capabilities: std::sync::Arc::new(CapabilitySet::empty()),
+            user_id: None,
+            session_id: None,
+            channel_id: None,
+            jail_root: None,
+            scheduler: None,
+            notifications: None,
+            notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
+            execution_mode: ExecutionMode::User,
+            timezone_offset: "+00:00".to_string(),
+            timezone_name: "UTC".to_string(),
+            timezone_requested: false,
+            max_response_bytes: None,
+            max_response_chars: None,
+            dry_run: true,
+        };
+
+        let output = tool
+            .execute(
+                &ctx,
+                json!({"operation": "write", "path": "nested/out.txt", "content": "hello"}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output["status"], "dry_run");
+        assert_eq!(output["action"], "write");
+        assert_eq!(output["details"]["bytes"], 5);
+        assert!(
+            output["details"]["path"]
+                .as_str()
+                .unwrap()
+                .ends_with("nested/out.txt")
+        );
+        assert!(!dir.join("nested").exists());
+
+        std::fs::write(dir.join("existing.txt"), "data").unwrap();
+        let read = tool
+            .execute(&ctx, json!({"operation": "read", "path": "existing.txt"}))
+            .await
+            .unwrap();
+        assert_eq!(read["content"], "data");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn writes_replace_files_atomically_and_keep_a_backup() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        std::fs::write(dir.join("status.md"), "v1").unwrap();
+        let tool = FilesystemTool::new();
+        let ctx = ToolContext {
+            working_dir: dir.clone(),
+            capabilities: std::sync::Arc::new(CapabilitySet::empty()),
+            user_id: None,
+            session_id: None,
+            channel_id: None,
+            jail_root: Some(dir.clone()),
+            scheduler: None,
+            notifications: None,
+            notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
+            execution_mode: ExecutionMode::User,
+            timezone_offset: "+00:00".to_string(),
+            timezone_name: "UTC".to_string(),
+            timezone_requested: false,
+            max_response_bytes: None,
+            max_response_chars: None,
+            dry_run: false,
+        };
+        let write = |backup: bool| {
+            json!({"operation": "write", "path": "status.md", "content": "v2", "backup": backup})
+        };
+        let mut registry = crate::tools::registry::ToolRegistry::new();
+        registry
+            .register(std::sync::Arc::new(FilesystemTool::new()))
+            .unwrap();
+
+        let required = tool.required_permissions(&ctx, &write(true)).unwrap();
+        assert_eq!(required.len(), 2);
+        assert!(matches!(
+            &required[1],
+            Permission::FileWrite { path } if path.0.ends_with("status.md.bak")
+        ));
+        let output = tool.execute(&ctx, write(true)).await.unwrap();
+        assert_eq!(output["status"], "ok");
+        assert!(output["backup"].as_str().unwrap().ends_with("status.md.bak"));
+        registry.validate_output(&tool, &output).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("status.md")).unwrap(), "v2");
+        assert_eq!(std::fs::read_to_string(dir.join("status.md.bak")).unwrap(), "v1");
+
+        let output = tool.execute(&ctx, write(false)).await.unwrap();
+        assert!(output.get("backup").is_none());
+        registry.validate_output(&tool, &output).unwrap();
+        let read = json!({"operation": "read", "path": "status.md"});
+        let output = tool.execute(&ctx, read).await.unwrap();
+        registry.validate_output(&tool, &output).unwrap();
+        assert!(registry.validate_output(&tool, &json!({"status": "ok", "content": ""})).is_err());
+        let mut names = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["status.md", "status.md.bak"]);
+
+        // A write that dies part-way leaves the original and no temp file behind.
+        let backup = dir.join("status.md.bak");
+        let result = write_atomically(&dir.join("status.md"), Some(&backup), |file| {
+            use std::io::Write;
+            file.write_all(b"partial")?;
+            Err(std::io::Error::other("disk full"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(dir.join("status.md")).unwrap(), "v2");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn symlinks_out_of_jail_are_rejected() {
+        let base = std::env::temp_dir().join(format!("picobot-jail-{}", uuid::Uuid::new_v4()));
+        let jail = base.join("jail");
+        let outside = base.join("outside");
+        std::fs::create_dir_all(&jail).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        let jail = jail.canonicalize().unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        std::fs::write(jail.join("notes.txt"), "notes").unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), jail.join("secret-link")).unwrap();
+        std::os::unix::fs::symlink(&outside, jail.join("outside-dir")).unwrap();
+        std::os::unix::fs::symlink(outside.join("new.txt"), jail.join("dangling")).unwrap();
+        std::fs::write(outside.join("backup.txt"), "old backup").unwrap();
+        std::os::unix::fs::symlink(outside.join("backup.txt"), jail.join("notes.txt.bak"))
+            .unwrap();
+
+        let tool = FilesystemTool::new();
+        let ctx = ToolContext {
+            working_dir: jail.clone(),
+            capabilities: std::sync::Arc::new(CapabilitySet::empty()),
+            user_id: None,
+            session_id: None,
+            channel_id: None,
+            jail_root: Some(jail.clone()),
+            scheduler: None,
+            notifications: None,
+            notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
+            execution_mode: ExecutionMode::User,
+            timezone_offset: "+00:00".to_string(),
+            timezone_name: "UTC".to_string(),
+            timezone_requested: false,
+            max_response_bytes: None,
+            max_response_chars: None,
+            dry_run: false,
+        };
+
+        let read = tool
+            .execute(&ctx, json!({"operation": "read", "path": "notes.txt"}))
+            .await
+            .unwrap();
+        assert_eq!(read["content"], "notes");
+
+        for path in ["secret-link", "outside-dir/secret.txt"] {
+            let result = tool
+                .execute(&ctx, json!({"operation": "read", "path": path}))
+                .await;
+            assert!(result.is_err(), "{path} should escape the jail");
+        }
+        for path in ["dangling", "outside-dir/new.txt"] {
+            let result = tool
+                .execute(
+                    &ctx,
+                    json!({"operation": "write", "path": path, "content": "x"}),
+                )
+                .await;
+            assert!(result.is_err(), "{path} should escape the jail");
+        }
+        assert!(!outside.join("new.txt").exists());
+
+        let backup = json!({
+            "operation": "write",
+            "path": "notes.txt",
+            "content": "x",
+            "backup": true
+        });
+        assert!(tool.required_permissions(&ctx, &backup).is_err());
+        assert!(tool.execute(&ctx, backup).await.is_err());
+        assert_eq!(
+            std::fs::read_to_string(outside.join("backup.txt")).unwrap(),
+            "old backup"
+        );
+        assert_eq!(std::fs::read_to_string(jail.join("notes.txt")).unwrap(), "notes");
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
 }
@@ -8,7 +8,9 @@ use serde_json::{Map, Value, json};
 
 use crate::kernel::permissions::{DomainPattern, Permission};
 use crate::tools::net_utils::{ensure_allowed_url, parse_host, read_response_bytes};
-use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+use crate::tools::traits::{
+    ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
 
 const DEFAULT_MAX_RESPONSE_BYTES: u64 = 5 * 1024 * 1024;
 const DEFAULT_MAX_RESPONSE_CHARS: usize = 50_000;
@@ -106,6 +108,7 @@ impl HttpTool {
                     },
                     "additionalProperties": false
                 }),
+                output_schema: None,
             },
             client,
         })
@@ -133,6 +136,10 @@ impl ToolExecutor for HttpTool {
         }])
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        vec!["net:<host>".to_string()]
+    }
+
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
         let url = input
             .get("url")
@@ -151,6 +158,19 @@ impl ToolExecutor for HttpTool {
         let host = parse_host(url)?;
         ensure_allowed_url(url, &host, Some(ctx)).await?;
 
+        if ctx.dry_run && method != "GET" {
+            return Ok(dry_run_output(
+                ctx,
+                &self.spec.name,
+                "request",
+                json!({
+                    "method": method,
+                    "url": url,
+                    "body_bytes": body.map(str::len).unwrap_or(0),
+                }),
+            ));
+        }
+
         let mut request = match method {
             "GET" => self.client.get(url),
             "POST" => self.client.post(url),
@@ -169,10 +189,13 @@ impl ToolExecutor for HttpTool {
             request = request.body(body.to_string());
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|err| ToolError::new(err.to_string()))?;
+        let response = request.send().await.map_err(|err| {
+            if err.is_timeout() || err.is_connect() {
+                ToolError::transient(err.to_string())
+            } else {
+                ToolError::new(err.to_string())
+            }
+        })?;
         if response.status().is_redirection() {
             return Err(ToolError::new("redirects are not allowed".to_string()));
         }
@@ -4,7 +4,12 @@ use serde_json::{Value, json};
 use crate::kernel::permissions::{MemoryScope, Permission};
 use crate::session::db::SqliteStore;
 use crate::session::error::SessionDbError;
-use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+use crate::tools::traits::{
+    ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
+
+/// Owner id for shared memories saved with `scope: "global"`.
+const GLOBAL_MEMORY_OWNER: &str = "__global__";
 
 #[derive(Debug, Clone)]
 pub struct MemoryTool {
@@ -17,17 +22,19 @@ impl MemoryTool {
         Self {
             spec: ToolSpec {
                 name: "memory".to_string(),
-                description: "Persist, list, or delete user-scoped memories. save requires key and content. list returns all memories. delete requires key. Keys must be lowercase alphanumeric with underscores (max 64 chars).".to_string(),
+                description: "Persist, list, or delete user-scoped memories. save requires key and content. list returns all memories. delete requires key. Keys must be lowercase alphanumeric with underscores (max 64 chars). Set scope \"global\" for shared organization memories, which need explicit access.".to_string(),
                 schema: json!({
                     "type": "object",
                     "required": ["action"],
                     "properties": {
                         "action": { "type": "string", "enum": ["save", "list", "delete"] },
                         "key": { "type": "string", "minLength": 1, "maxLength": 64, "pattern": "^[a-z][a-z0-9_]*$" },
-                        "content": { "type": "string", "minLength": 1 }
+                        "content": { "type": "string", "minLength": 1 },
+                        "scope": { "type": "string", "enum": ["user", "global"] }
                     },
                     "additionalProperties": false
                 }),
+                output_schema: None,
             },
             store,
         }
@@ -49,26 +56,35 @@ impl ToolExecutor for MemoryTool {
             .get("action")
             .and_then(Value::as_str)
             .ok_or_else(|| ToolError::new("missing action".to_string()))?;
+        let scope = memory_scope(input);
         match action {
-            "list" => Ok(vec![Permission::MemoryRead {
-                scope: MemoryScope::User,
-            }]),
-            "save" | "delete" => Ok(vec![Permission::MemoryWrite {
-                scope: MemoryScope::User,
-            }]),
+            "list" => Ok(vec![Permission::MemoryRead { scope }]),
+            "save" | "delete" => Ok(vec![Permission::MemoryWrite { scope }]),
             _ => Err(ToolError::new("invalid action".to_string())),
         }
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        vec![
+            "memory:read:user".to_string(),
+            "memory:write:user".to_string(),
+            "memory:read:global".to_string(),
+            "memory:write:global".to_string(),
+        ]
+    }
+
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
         let action = input
             .get("action")
             .and_then(Value::as_str)
             .ok_or_else(|| ToolError::new("missing action".to_string()))?;
-        let user_id = ctx
-            .user_id
-            .as_ref()
-            .ok_or_else(|| ToolError::new("missing user_id".to_string()))?;
+        let user_id = match memory_scope(&input) {
+            MemoryScope::Global => GLOBAL_MEMORY_OWNER,
+            _ => ctx
+                .user_id
+                .as_deref()
+                .ok_or_else(|| ToolError::new("missing user_id".to_string()))?,
+        };
         match action {
             "list" => list_memories(&self.store, user_id),
             "save" => {
@@ -81,6 +97,14 @@ impl ToolExecutor for MemoryTool {
                     .and_then(Value::as_str)
                     .ok_or_else(|| ToolError::new("missing content".to_string()))?;
                 validate_key(key)?;
+                if ctx.dry_run {
+                    return Ok(dry_run_output(
+                        ctx,
+                        &self.spec.name,
+                        "save",
+                        json!({"key": key, "content_chars": content.chars().count()}),
+                    ));
+                }
                 save_memory(&self.store, ctx, user_id, key, content)
             }
             "delete" => {
@@ -89,6 +113,9 @@ impl ToolExecutor for MemoryTool {
                     .and_then(Value::as_str)
                     .ok_or_else(|| ToolError::new("missing key".to_string()))?;
                 validate_key(key)?;
+                if ctx.dry_run {
+                    return Ok(dry_run_output(ctx, &self.spec.name, "delete", json!({"key": key})));
+                }
                 delete_memory(&self.store, user_id, key)
             }
             _ => Err(ToolError::new("invalid action".to_string())),
@@ -96,6 +123,13 @@ impl ToolExecutor for MemoryTool {
     }
 }
 
+fn memory_scope(input: &Value) -> MemoryScope {
+    match input.get("scope").and_then(Value::as_str) {
+        Some("global") => MemoryScope::Global,
+        _ => MemoryScope::User,
+    }
+}
+
 fn validate_key(key: &str) -> Result<(), ToolError> {
     if key.len() > 64 {
         return Err(ToolError::new("key too long".to_string()));
@@ -187,7 +221,74 @@ fn delete_memory(store: &SqliteStore, user_id: &str, key: &str) -> Result<ToolOu
 
 #[cfg(test)]
 mod tests {
-    use super::validate_key;
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::{MemoryTool, validate_key};
+    use crate::kernel::core::Kernel;
+    use crate::kernel::permissions::GlobalMemoryGrant;
+    use crate::session::db::SqliteStore;
+    use crate::tools::registry::ToolRegistry;
+
+    #[tokio::test]
+    async fn global_memory_requires_explicit_grant() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(MemoryTool::new(store))).unwrap();
+        let base = Kernel::new(Arc::new(registry)).with_channel_id(Some("api".to_string()));
+        let as_user = |kernel: &Kernel, user: &str| {
+            kernel.clone_with_context(Some(user.to_string()), Some(format!("session:{user}")))
+        };
+        let list_global = json!({ "action": "list", "scope": "global" });
+
+        // Denied by default, even though user-scoped memories are auto-granted.
+        let alice = as_user(&base, "api:alice");
+        let err = alice
+            .invoke_tool_with_prompt_by_name("memory", list_global.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("memory:read:global"), "{err}");
+        alice
+            .invoke_tool_with_prompt_by_name("memory", json!({ "action": "list" }))
+            .await
+            .unwrap();
+
+        let granted = base.with_global_memory_grants(vec![GlobalMemoryGrant {
+            channel: "api".to_string(),
+            users: vec!["api:alice".to_string()],
+            write: true,
+        }]);
+        let alice = as_user(&granted, "api:alice");
+        alice
+            .invoke_tool_with_prompt_by_name(
+                "memory",
+                json!({ "action": "save", "scope": "global", "key": "office", "content": "Berlin" }),
+            )
+            .await
+            .unwrap();
+        let output = alice
+            .invoke_tool_with_prompt_by_name("memory", list_global.clone())
+            .await
+            .unwrap();
+        assert_eq!(output["memories"][0]["content"], "Berlin");
+        let own = alice
+            .invoke_tool_with_prompt_by_name("memory", json!({ "action": "list" }))
+            .await
+            .unwrap();
+        assert_eq!(own["memories"], json!([]));
+
+        let bob = as_user(&granted, "api:bob");
+        assert!(
+            bob.invoke_tool_with_prompt_by_name("memory", list_global)
+                .await
+                .is_err()
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
 
     #[test]
     fn validate_key_rejects_prefixes() {
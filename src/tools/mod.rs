@@ -1,3 +1,4 @@
+pub mod echo;
 pub mod filesystem;
 pub mod http;
 pub mod memory;
@@ -5,8 +6,10 @@ pub mod multimodal_looker;
 pub mod net_utils;
 pub mod notify;
 pub mod path_utils;
+pub mod process;
 pub mod registry;
 pub mod rig_wrapper;
+pub mod say;
 pub mod schedule;
 pub mod search;
 pub mod shell;
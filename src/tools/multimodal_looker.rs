@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::path::Path;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use base64::Engine;
@@ -35,6 +37,9 @@ pub struct MultimodalLookerTool {
     client: Client,
     max_media_size_bytes: u64,
     max_image_size_bytes: u64,
+    max_calls_per_minute: u32,
+    /// Recent model calls per user, for `max_calls_per_minute`.
+    calls: Mutex<HashMap<String, Vec<Instant>>>,
     session_manager: SessionManager,
 }
 
@@ -67,14 +72,53 @@ impl MultimodalLookerTool {
                     },
                     "additionalProperties": false
                 }),
+                output_schema: None,
             },
             agent,
             client,
             max_media_size_bytes,
             max_image_size_bytes,
+            max_calls_per_minute: 0,
+            calls: Mutex::new(HashMap::new()),
             session_manager,
         }
     }
+
+    /// Limits each user to `limit` model calls per minute; 0 leaves calls unlimited.
+    pub fn with_max_calls_per_minute(mut self, limit: u32) -> Self {
+        self.max_calls_per_minute = limit;
+        self
+    }
+
+    /// Records a model call for `user` unless it would exceed `max_calls_per_minute`.
+    /// Users with no call in the last minute are dropped from the map on the way.
+    fn try_acquire_call(&self, user: &str) -> Result<(), ToolError> {
+        if self.max_calls_per_minute == 0 {
+            return Ok(());
+        }
+        let mut calls = self.calls.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+        let window = Duration::from_secs(60);
+        calls.retain(|_, entries| {
+            entries.retain(|instant| now.duration_since(*instant) < window);
+            !entries.is_empty()
+        });
+        let entries = calls.entry(user.to_string()).or_default();
+        if entries.len() >= self.max_calls_per_minute as usize {
+            tracing::warn!(
+                event = "multimodal_rate_limited",
+                user_id = %user,
+                limit = self.max_calls_per_minute,
+                "multimodal call rejected"
+            );
+            return Err(ToolError::new(format!(
+                "multimodal_looker rate limit reached ({} calls per minute); try again shortly",
+                self.max_calls_per_minute
+            )));
+        }
+        entries.push(now);
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -104,6 +148,13 @@ impl ToolExecutor for MultimodalLookerTool {
         }
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        vec![
+            "filesystem:read:<path>".to_string(),
+            "net:<host>".to_string(),
+        ]
+    }
+
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
         let source = input
             .get("source")
@@ -178,10 +229,12 @@ impl ToolExecutor for MultimodalLookerTool {
 
         if matches!(media_kind, MediaKind::Image) && size > self.max_image_size_bytes {
             return Err(ToolError::new(format!(
-                "image is too large: {} bytes (limit {})",
+                "image is too large: {} bytes (limit {}); ask the user for a smaller or \
+                 compressed image",
                 size, self.max_image_size_bytes
             )));
         }
+        self.try_acquire_call(ctx.user_id.as_deref().unwrap_or("anonymous"))?;
 
         let encoded = BASE64_STANDARD.encode(bytes);
         let user_content = build_user_content(media_kind, &mime_type, encoded, detail)?;
@@ -189,11 +242,13 @@ impl ToolExecutor for MultimodalLookerTool {
             .map_err(|_| ToolError::new("failed to build multimodal prompt".to_string()))?;
         let message = Message::User { content };
 
-        let (response, usage) = self
+        let reply = self
             .agent
+            .for_context(ctx)
             .prompt_message_with_retry_usage(message, 4, DEFAULT_PROVIDER_RETRIES)
             .await
             .map_err(|err| ToolError::new(err.to_string()))?;
+        let (response, usage) = (reply.output, reply.usage);
         let usage_event = UsageEvent {
             session_id: ctx.session_id.clone(),
             channel_id: ctx.channel_id.clone(),
@@ -377,3 +432,99 @@ async fn download_url(
     let mime = content_type.ok_or_else(|| ToolError::new("missing content-type".to_string()))?;
     Ok((bytes.to_vec(), mime, url.to_string(), size_hint))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::MultimodalLookerTool;
+    use crate::kernel::core::Kernel;
+    use crate::providers::scripted::{ScriptedProvider, ScriptedReply};
+    use crate::session::db::SqliteStore;
+    use crate::session::manager::SessionManager;
+    use crate::tools::registry::ToolRegistry;
+    use crate::tools::traits::ToolExecutor;
+
+    fn looker(provider: &ScriptedProvider, dir: &std::path::Path) -> MultimodalLookerTool {
+        let agent = provider
+            .builder()
+            .build_without_tools_with_env(|_| Some("test-key".to_string()))
+            .unwrap();
+        let store = SqliteStore::new(dir.join("sessions.db").to_string_lossy().to_string());
+        store.touch().unwrap();
+        MultimodalLookerTool::new(agent, 1024, 16, SessionManager::new(store))
+    }
+
+    fn write_png(dir: &std::path::Path, name: &str, len: usize) -> PathBuf {
+        let path = dir.join(name);
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        bytes.resize(len, 0);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn oversized_images_are_rejected_before_reaching_the_model() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let provider = ScriptedProvider::start(vec![ScriptedReply::text("a red square")]).await;
+        let tool = looker(&provider, &dir);
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()))
+            .clone_with_context(Some("alice".to_string()), None);
+
+        let large = write_png(&dir, "large.png", 64);
+        let err = tool
+            .execute(kernel.context(), json!({ "source": large.to_string_lossy() }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("image is too large: 64 bytes (limit 16)"), "{err}");
+        assert!(provider.requests().is_empty());
+
+        let small = write_png(&dir, "small.png", 16);
+        let output = tool
+            .execute(kernel.context(), json!({ "source": small.to_string_lossy() }))
+            .await
+            .unwrap();
+        assert_eq!(output["description"], "a red square");
+        assert_eq!(output["media_type"], "image");
+        assert_eq!(provider.requests().len(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn model_calls_are_rate_limited_per_user() {
+        let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let provider = ScriptedProvider::start(vec![
+            ScriptedReply::text("first"),
+            ScriptedReply::text("second"),
+        ])
+        .await;
+        let tool = looker(&provider, &dir).with_max_calls_per_minute(1);
+        let kernel = Kernel::new(Arc::new(ToolRegistry::new()));
+        let alice = kernel.clone_with_context(Some("alice".to_string()), None);
+        let bob = kernel.clone_with_context(Some("bob".to_string()), None);
+        let image = json!({ "source": write_png(&dir, "image.png", 16).to_string_lossy() });
+
+        tool.execute(alice.context(), image.clone()).await.unwrap();
+        let err = tool
+            .execute(alice.context(), image.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("rate limit reached"), "{err}");
+        tool.execute(bob.context(), image).await.unwrap();
+        assert_eq!(provider.requests().len(), 2);
+
+        // Users whose calls have all left the window are pruned.
+        let stale = std::time::Instant::now()
+            .checked_sub(std::time::Duration::from_secs(61))
+            .expect("uptime over a minute");
+        tool.calls.lock().unwrap().insert("carol".to_string(), vec![stale]);
+        tool.try_acquire_call("dave").unwrap();
+        assert!(!tool.calls.lock().unwrap().contains_key("carol"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -3,8 +3,10 @@ use serde_json::{Value, json};
 use std::sync::atomic::Ordering;
 
 use crate::kernel::permissions::Permission;
-use crate::notifications::channel::NotificationRequest;
-use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+use crate::notifications::channel::{NotificationPriority, NotificationRequest};
+use crate::tools::traits::{
+    ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
 
 #[derive(Debug, Default)]
 pub struct NotifyTool {
@@ -16,15 +18,26 @@ impl NotifyTool {
         Self {
             spec: ToolSpec {
                 name: "notify".to_string(),
-                description: "Send a notification message to the current user on the current channel. Only requires message. Use for reminders, alerts, and delivering results from scheduled jobs.".to_string(),
+                description: "Send a notification message to the current user on the current channel. Only requires message; set priority \"high\" for urgent alerts. Use for reminders, alerts, and delivering results from scheduled jobs.".to_string(),
                 schema: json!({
                     "type": "object",
                     "required": ["message"],
                     "properties": {
-                        "message": { "type": "string", "minLength": 1 }
+                        "message": { "type": "string", "minLength": 1 },
+                        "priority": { "type": "string", "enum": ["low", "normal", "high"] }
                     },
                     "additionalProperties": false
                 }),
+                output_schema: Some(json!({
+                    "type": "object",
+                    "required": ["status"],
+                    "properties": {
+                        "status": { "enum": ["queued", "skipped"] },
+                        "id": { "type": "string" },
+                        "reason": { "type": "string" }
+                    },
+                    "additionalProperties": false
+                })),
             },
         }
     }
@@ -41,27 +54,25 @@ impl ToolExecutor for NotifyTool {
         ctx: &ToolContext,
         input: &Value,
     ) -> Result<Vec<Permission>, ToolError> {
-        let channel = input
-            .get("channel_id")
-            .and_then(Value::as_str)
-            .map(|value| value.to_string())
-            .or_else(|| ctx.channel_id.clone())
-            .or_else(|| {
-                ctx.session_id.as_deref().and_then(|value| {
-                    value
-                        .split_once(':')
-                        .map(|(channel, _)| channel.to_string())
-                })
-            })
-            .unwrap_or_else(|| "*".to_string());
+        let channel = target_channel(ctx, input)
+            .ok_or_else(|| ToolError::new("missing channel_id".to_string()))?;
         Ok(vec![Permission::Notify { channel }])
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        vec!["notify:<channel>".to_string()]
+    }
+
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
         let message = input
             .get("message")
             .and_then(Value::as_str)
             .ok_or_else(|| ToolError::new("missing message".to_string()))?;
+        let priority = match input.get("priority").and_then(Value::as_str) {
+            Some(value) => NotificationPriority::parse(value)
+                .ok_or_else(|| ToolError::new(format!("invalid priority '{value}'")))?,
+            None => NotificationPriority::default(),
+        };
         let input_user = input
             .get("user_id")
             .and_then(Value::as_str)
@@ -116,16 +127,20 @@ impl ToolExecutor for NotifyTool {
                 "channel_id does not match context".to_string(),
             ));
         }
-        let channel_id = input_channel
-            .or_else(|| ctx.channel_id.clone())
-            .or_else(|| {
-                ctx.session_id.as_deref().and_then(|value| {
-                    value
-                        .split_once(':')
-                        .map(|(channel, _)| channel.to_string())
-                })
-            })
+        let channel_id = target_channel(ctx, &input)
             .ok_or_else(|| ToolError::new("missing channel_id".to_string()))?;
+        if ctx.dry_run {
+            return Ok(dry_run_output(
+                ctx,
+                &self.spec.name,
+                "notify",
+                json!({
+                    "user_id": user_id,
+                    "channel_id": channel_id,
+                    "message_chars": message.chars().count(),
+                }),
+            ));
+        }
         let service = ctx.notifications.as_ref().ok_or_else(|| {
             tracing::warn!(
                 event = "notification_restricted",
@@ -138,13 +153,50 @@ impl ToolExecutor for NotifyTool {
             );
             ToolError::new("notifications not available".to_string())
         })?;
+        // One notification per scheduled run: claim the flag atomically so a
+        // second (or concurrent) notify in the same execution is skipped.
+        if ctx.execution_mode.is_scheduled_job()
+            && ctx.notify_tool_used.swap(true, Ordering::AcqRel)
+        {
+            tracing::info!(
+                event = "tool_skipped",
+                tool = "notify",
+                user_id = ?ctx.user_id,
+                session_id = ?ctx.session_id,
+                channel_id = ?ctx.channel_id,
+                reason = "scheduled_job_already_notified",
+                "scheduled job already notified; skipping notify"
+            );
+            return Ok(json!({
+                "status": "skipped",
+                "reason": "scheduled job already notified"
+            }));
+        }
         let request = NotificationRequest {
             user_id: user_id.clone(),
             channel_id: channel_id.clone(),
             message: message.to_string(),
+            priority,
         };
         let id = service.enqueue(request).await;
         ctx.notify_tool_used.store(true, Ordering::Relaxed);
         Ok(json!({"status": "queued", "id": id}))
     }
 }
+
+/// Channel a notify call delivers to. `required_permissions` and `execute` both
+/// use this so the permission check always covers the actual target.
+fn target_channel(ctx: &ToolContext, input: &Value) -> Option<String> {
+    input
+        .get("channel_id")
+        .and_then(Value::as_str)
+        .map(|value| value.to_string())
+        .or_else(|| ctx.channel_id.clone())
+        .or_else(|| {
+            ctx.session_id.as_deref().and_then(|value| {
+                value
+                    .split_once(':')
+                    .map(|(channel, _)| channel.to_string())
+            })
+        })
+}
@@ -74,6 +74,13 @@ fn canonicalize_with_fallback(path: &Path) -> Result<PathBuf, ToolError> {
     let mut current = path;
     let mut remainder: Vec<std::ffi::OsString> = Vec::new();
     while !current.exists() {
+        // A dangling symlink would be followed on write, landing wherever it points.
+        if current.symlink_metadata().is_ok() {
+            return Err(ToolError::new(format!(
+                "path contains a dangling symlink: {}",
+                current.display()
+            )));
+        }
         if let Some(name) = current.file_name() {
             remainder.push(name.to_os_string());
         } else {
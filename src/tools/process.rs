@@ -0,0 +1,231 @@
+use std::path::Path;
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::config::ExternalToolConfig;
+use crate::kernel::permissions::{Permission, parse_permission_with_base};
+use crate::tools::traits::{
+    Artifact, ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
+
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+const STDERR_BYTES: usize = 16 * 1024;
+
+/// Tool backed by an external command speaking JSON over stdio.
+///
+/// The tool input is written to the child's stdin as a single JSON document and
+/// stdin is closed; the child must print one JSON value to stdout and exit 0. A top-level
+/// `artifacts` array in that value is taken out of the output and attached for the
+/// channel to deliver.
+/// The child is killed if the kernel's tool timeout drops the execution.
+#[derive(Debug)]
+pub struct ProcessTool {
+    spec: ToolSpec,
+    command: String,
+    args: Vec<String>,
+    permissions: Vec<Permission>,
+    max_output_bytes: usize,
+}
+
+impl ProcessTool {
+    pub fn new(
+        spec: ToolSpec,
+        command: String,
+        args: Vec<String>,
+        permissions: Vec<Permission>,
+    ) -> Self {
+        Self {
+            spec,
+            command,
+            args,
+            permissions,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+
+    pub fn from_config(config: &ExternalToolConfig, base_dir: &Path) -> Result<Self, ToolError> {
+        let name = config.name.trim();
+        if name.is_empty() {
+            return Err(ToolError::new("external tool name cannot be empty".to_string()));
+        }
+        if config.command.trim().is_empty() {
+            return Err(ToolError::new(format!(
+                "external tool '{name}' command cannot be empty"
+            )));
+        }
+        let permissions = config
+            .permissions
+            .iter()
+            .flatten()
+            .map(|value| {
+                parse_permission_with_base(value, base_dir).map_err(|err| {
+                    ToolError::new(format!(
+                        "external tool '{name}' has invalid permission '{value}': {err}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // The kernel only gates a call on what the tool requires, so no permissions
+        // would let any session run the command.
+        if permissions.is_empty() {
+            return Err(ToolError::new(format!(
+                "external tool '{name}' must declare at least one permission"
+            )));
+        }
+        let spec = ToolSpec {
+            name: name.to_string(),
+            description: config
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("External tool '{name}'.")),
+            schema: config
+                .schema
+                .clone()
+                .unwrap_or_else(|| json!({ "type": "object" })),
+            output_schema: config.output_schema.clone(),
+        };
+        Ok(Self::new(
+            spec,
+            config.command.clone(),
+            config.args.clone().unwrap_or_default(),
+            permissions,
+        ))
+    }
+
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ProcessTool {
+    fn spec(&self) -> &ToolSpec {
+        &self.spec
+    }
+
+    fn required_permissions(
+        &self,
+        _ctx: &ToolContext,
+        _input: &Value,
+    ) -> Result<Vec<Permission>, ToolError> {
+        Ok(self.permissions.clone())
+    }
+
+    fn permission_hints(&self) -> Vec<String> {
+        self.permissions
+            .iter()
+            .map(|permission| permission.to_string())
+            .collect()
+    }
+
+    async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
+        if ctx.dry_run {
+            return Ok(dry_run_output(
+                ctx,
+                &self.spec.name,
+                "execute",
+                json!({ "command": self.command, "args": self.args }),
+            ));
+        }
+
+        let name = &self.spec.name;
+        let mut child = tokio::process::Command::new(&self.command)
+            .args(&self.args)
+            .current_dir(&ctx.working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| ToolError::new(format!("failed to start external tool '{name}': {err}")))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .map(|stdout| tokio::spawn(read_limited(stdout, self.max_output_bytes)));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|stderr| tokio::spawn(read_limited(stderr, STDERR_BYTES)));
+        if let Some(mut stdin) = child.stdin.take() {
+            let payload =
+                serde_json::to_vec(&input).map_err(|err| ToolError::new(err.to_string()))?;
+            // A child that ignores its input may exit before reading it; that is not an error.
+            let _ = stdin.write_all(&payload).await;
+            let _ = stdin.shutdown().await;
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|err| ToolError::new(err.to_string()))?;
+        let (stdout, stdout_truncated) = join_output(stdout).await?;
+        let (stderr, _) = join_output(stderr).await?;
+
+        if !status.success() {
+            let code = status
+                .code()
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "signal".to_string());
+            return Err(ToolError::new(format!(
+                "external tool '{name}' exited with status {code}: {}",
+                String::from_utf8_lossy(&stderr).trim()
+            )));
+        }
+        if stdout_truncated {
+            return Err(ToolError::new(format!(
+                "external tool '{name}' output exceeded {} bytes",
+                self.max_output_bytes
+            )));
+        }
+        let mut output: Value = serde_json::from_slice(&stdout).map_err(|err| {
+            ToolError::new(format!(
+                "external tool '{name}' returned invalid JSON: {err}"
+            ))
+        })?;
+        if let Some(artifacts) = output
+            .as_object_mut()
+            .and_then(|object| object.remove("artifacts"))
+        {
+            let artifacts: Vec<Artifact> = serde_json::from_value(artifacts).map_err(|err| {
+                ToolError::new(format!(
+                    "external tool '{name}' returned invalid artifacts: {err}"
+                ))
+            })?;
+            for artifact in artifacts {
+                ctx.artifacts.attach(artifact);
+            }
+        }
+        Ok(output)
+    }
+}
+
+async fn read_limited<R>(reader: R, limit: usize) -> std::io::Result<(Vec<u8>, bool)>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buffer = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut buffer)
+        .await?;
+    let truncated = buffer.len() > limit;
+    buffer.truncate(limit);
+    Ok((buffer, truncated))
+}
+
+type OutputHandle = tokio::task::JoinHandle<std::io::Result<(Vec<u8>, bool)>>;
+
+async fn join_output(handle: Option<OutputHandle>) -> Result<(Vec<u8>, bool), ToolError> {
+    match handle {
+        Some(handle) => handle
+            .await
+            .map_err(|err| ToolError::new(err.to_string()))?
+            .map_err(|err| ToolError::new(err.to_string())),
+        None => Ok((Vec::new(), false)),
+    }
+}
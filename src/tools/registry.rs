@@ -11,6 +11,7 @@ use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolSpec};
 pub struct ToolRegistry {
     tools: Vec<Arc<dyn ToolExecutor>>,
     schemas: HashMap<String, Validator>,
+    output_schemas: HashMap<String, Validator>,
 }
 
 impl ToolRegistry {
@@ -18,6 +19,7 @@ impl ToolRegistry {
         Self {
             tools: Vec::new(),
             schemas: HashMap::new(),
+            output_schemas: HashMap::new(),
         }
     }
 
@@ -29,8 +31,19 @@ impl ToolRegistry {
             )));
         }
         let schema = tool.spec().schema.clone();
-        let validator = jsonschema::validator_for(&schema)
+        let validator = jsonschema::options()
+            .should_validate_formats(true)
+            .build(&schema)
             .map_err(|err| ToolError::new(format!("invalid schema for '{name}': {err}")))?;
+        if let Some(output_schema) = &tool.spec().output_schema {
+            let output_validator = jsonschema::options()
+                .should_validate_formats(true)
+                .build(output_schema)
+                .map_err(|err| {
+                    ToolError::new(format!("invalid output schema for '{name}': {err}"))
+                })?;
+            self.output_schemas.insert(name.clone(), output_validator);
+        }
         self.schemas.insert(name.clone(), validator);
         self.tools.push(tool);
         Ok(())
@@ -43,6 +56,10 @@ impl ToolRegistry {
             .cloned()
     }
 
+    pub fn tools(&self) -> &[Arc<dyn ToolExecutor>] {
+        &self.tools
+    }
+
     pub fn specs(&self) -> Vec<ToolSpec> {
         self.tools.iter().map(|tool| tool.spec().clone()).collect()
     }
@@ -56,17 +73,32 @@ impl ToolRegistry {
         if validator.is_valid(input) {
             Ok(())
         } else {
-            let errors = validator
-                .iter_errors(input)
-                .map(|err| err.to_string())
-                .collect::<Vec<_>>()
-                .join("; ");
+            let errors = schema_errors(validator, input);
             Err(ToolError::new(format!(
                 "invalid input for '{name}': {errors}"
             )))
         }
     }
 
+    /// Checks a successful output against the tool's `output_schema`; tools without one
+    /// always pass.
+    pub fn validate_output(
+        &self,
+        tool: &dyn ToolExecutor,
+        output: &Value,
+    ) -> Result<(), ToolError> {
+        let name = &tool.spec().name;
+        match self.output_schemas.get(name) {
+            Some(validator) if !validator.is_valid(output) => {
+                let errors = schema_errors(validator, output);
+                Err(ToolError::new(format!(
+                    "output of '{name}' does not match its output schema: {errors}"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn required_permissions(
         &self,
         tool: &dyn ToolExecutor,
@@ -76,3 +108,114 @@ impl ToolRegistry {
         tool.required_permissions(ctx, input)
     }
 }
+
+/// Each failure with its JSON pointer, so the exact field can be fixed.
+fn schema_errors(validator: &Validator, value: &Value) -> String {
+    validator
+        .iter_errors(value)
+        .map(|err| {
+            let path = err.instance_path().as_str();
+            let path = if path.is_empty() { "/" } else { path };
+            format!("at '{path}': {err}")
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use serde_json::{Value, json};
+
+    use super::ToolRegistry;
+    use crate::kernel::permissions::Permission;
+    use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+
+    struct SchemaTool {
+        spec: ToolSpec,
+    }
+
+    #[async_trait]
+    impl ToolExecutor for SchemaTool {
+        fn spec(&self) -> &ToolSpec {
+            &self.spec
+        }
+
+        fn required_permissions(
+            &self,
+            _ctx: &ToolContext,
+            _input: &Value,
+        ) -> Result<Vec<Permission>, ToolError> {
+            Ok(Vec::new())
+        }
+
+        async fn execute(&self, _ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
+            Ok(input)
+        }
+    }
+
+    fn registry_with_tool() -> (ToolRegistry, Arc<dyn ToolExecutor>) {
+        let tool: Arc<dyn ToolExecutor> = Arc::new(SchemaTool {
+            spec: ToolSpec {
+                name: "schema_tool".to_string(),
+                description: "test".to_string(),
+                schema: json!({
+                    "type": "object",
+                    "required": ["path"],
+                    "properties": {
+                        "path": { "type": "string" },
+                        "options": {
+                            "type": "object",
+                            "properties": { "limit": { "type": "integer" } }
+                        }
+                    },
+                    "additionalProperties": false
+                }),
+                output_schema: None,
+            },
+        });
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::clone(&tool)).unwrap();
+        (registry, tool)
+    }
+
+    #[test]
+    fn validate_input_reports_missing_required_field() {
+        let (registry, tool) = registry_with_tool();
+        let err = registry
+            .validate_input(tool.as_ref(), &json!({"options": {}}))
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("at '/'"), "{message}");
+        assert!(message.contains("\"path\" is a required property"), "{message}");
+    }
+
+    #[test]
+    fn validate_input_reports_path_of_wrong_type() {
+        let (registry, tool) = registry_with_tool();
+        let err = registry
+            .validate_input(
+                tool.as_ref(),
+                &json!({"path": "a.txt", "options": {"limit": "ten"}}),
+            )
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("at '/options/limit'"), "{message}");
+        assert!(message.contains("integer"), "{message}");
+    }
+
+    #[test]
+    fn validate_input_accepts_valid_input() {
+        let (registry, tool) = registry_with_tool();
+        assert!(
+            registry
+                .validate_input(
+                    tool.as_ref(),
+                    &json!({"path": "a.txt", "options": {"limit": 10}}),
+                )
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crate::kernel::permissions::Permission;
+use crate::tools::traits::{
+    ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
+
+/// Channel-side delivery for `say`: shows a message to the user of the current turn
+/// right away, without waiting for the final response.
+#[async_trait]
+pub trait MessageSink: Send + Sync {
+    async fn send(&self, message: &str) -> Result<(), anyhow::Error>;
+}
+
+#[derive(Debug, Default)]
+pub struct SayTool {
+    spec: ToolSpec,
+}
+
+impl SayTool {
+    pub fn new() -> Self {
+        Self {
+            spec: ToolSpec {
+                name: "say".to_string(),
+                description: "Send the user a short progress update right now, without ending the turn. Use it during long multi-step tasks; the final answer is still sent as usual.".to_string(),
+                schema: json!({
+                    "type": "object",
+                    "required": ["message"],
+                    "properties": {
+                        "message": { "type": "string", "minLength": 1 }
+                    },
+                    "additionalProperties": false
+                }),
+                output_schema: Some(json!({
+                    "type": "object",
+                    "required": ["status"],
+                    "properties": {
+                        "status": { "const": "sent" }
+                    },
+                    "additionalProperties": false
+                })),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SayTool {
+    fn spec(&self) -> &ToolSpec {
+        &self.spec
+    }
+
+    /// The same `notify:<channel>` grant as `notify`, which channels pre-authorize for
+    /// their own users by default.
+    fn required_permissions(
+        &self,
+        ctx: &ToolContext,
+        _input: &Value,
+    ) -> Result<Vec<Permission>, ToolError> {
+        let channel = ctx
+            .channel_id
+            .clone()
+            .ok_or_else(|| ToolError::new("missing channel_id".to_string()))?;
+        Ok(vec![Permission::Notify { channel }])
+    }
+
+    fn permission_hints(&self) -> Vec<String> {
+        vec!["notify:<channel>".to_string()]
+    }
+
+    async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
+        let message = input
+            .get("message")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::new("missing message".to_string()))?;
+        if ctx.dry_run {
+            return Ok(dry_run_output(
+                ctx,
+                &self.spec.name,
+                "say",
+                json!({ "message_chars": message.chars().count() }),
+            ));
+        }
+        let sink = ctx.message_sink.as_ref().ok_or_else(|| {
+            ToolError::new("this channel cannot send messages mid-turn".to_string())
+        })?;
+        sink.send(message).await.map_err(|err| {
+            tracing::warn!(
+                event = "say_failed",
+                user_id = ?ctx.user_id,
+                session_id = ?ctx.session_id,
+                channel_id = ?ctx.channel_id,
+                error = %err,
+                "failed to send interim message"
+            );
+            ToolError::new(format!("failed to send message: {err}"))
+        })?;
+        Ok(json!({ "status": "sent" }))
+    }
+}
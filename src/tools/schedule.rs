@@ -2,8 +2,14 @@ use async_trait::async_trait;
 use serde_json::{Value, json};
 
 use crate::kernel::permissions::{CapabilitySet, Permission};
-use crate::scheduler::job::{CreateJobRequest, Principal, PrincipalType, ScheduleType};
-use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec};
+use crate::scheduler::error::SchedulerResult;
+use crate::scheduler::job::{
+    CreateJobRequest, Principal, PrincipalType, ScheduleType, ScheduledJob,
+};
+use crate::scheduler::service::{normalize_cron_expr, normalize_interval_expr};
+use crate::tools::traits::{
+    ToolContext, ToolError, ToolExecutor, ToolOutput, ToolSpec, dry_run_output,
+};
 
 #[derive(Debug, Default)]
 pub struct ScheduleTool {
@@ -15,7 +21,7 @@ impl ScheduleTool {
         Self {
             spec: ToolSpec {
                 name: "schedule".to_string(),
-                description: "Manage scheduled jobs. create requires schedule_type, schedule_expr, task_prompt. list returns your jobs. cancel requires job_id. Only your own jobs are visible/cancellable."
+                description: "Manage scheduled jobs. create requires schedule_type, schedule_expr, task_prompt. list returns your jobs. cancel requires job_id or name; an ambiguous name returns candidates instead of cancelling. Only your own jobs are visible/cancellable."
                     .to_string(),
                 schema: json!({
                     "type": "object",
@@ -24,7 +30,7 @@ impl ScheduleTool {
                         "action": { "type": "string", "enum": ["create", "list", "cancel"] },
                         "name": { "type": "string", "minLength": 1, "maxLength": 100 },
                         "schedule_type": { "type": "string", "enum": ["interval", "once", "cron"] },
-                        "schedule_expr": { "type": "string", "minLength": 1, "description": "For interval: seconds or relative duration (e.g. '2 minutes'). For once: relative duration (e.g. '2 minutes') or RFC3339 datetime. For cron: cron expression." },
+                        "schedule_expr": { "type": "string", "minLength": 1, "description": "For interval: seconds, relative duration (e.g. '2 minutes') or '@every 5m'. For once: relative duration (e.g. '2 minutes') or RFC3339 datetime. For cron: cron expression." },
                         "task_prompt": { "type": "string", "minLength": 1, "description": "User-facing message to send when the job runs." },
                         "enabled": { "type": "boolean" },
                         "max_executions": { "type": "integer", "minimum": 1, "maximum": 10000 },
@@ -33,6 +39,7 @@ impl ScheduleTool {
                     },
                     "additionalProperties": false
                 }),
+                output_schema: None,
             },
         }
     }
@@ -67,6 +74,13 @@ impl ToolExecutor for ScheduleTool {
         ])
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        ["create", "list", "cancel"]
+            .iter()
+            .map(|action| format!("schedule:{action}"))
+            .collect()
+    }
+
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
         if ctx.execution_mode.is_scheduled_job() {
             return Err(ToolError::new(
@@ -107,7 +121,8 @@ fn create_job(
         .ok_or_else(|| ToolError::new("missing schedule_expr".to_string()))?;
     let mut schedule_expr = schedule_expr.to_string();
     if matches!(schedule_type, ScheduleType::Cron) {
-        schedule_expr = normalize_cron_expr(&schedule_expr)?;
+        schedule_expr = normalize_cron_expr(&schedule_expr, ctx.cron_default_timezone())
+            .map_err(|err| ToolError::new(err.to_string()))?;
     }
     let task_prompt = input
         .get("task_prompt")
@@ -237,6 +252,9 @@ fn create_job(
             }
             ScheduleType::Cron => {}
         }
+    } else if matches!(schedule_type, ScheduleType::Interval) {
+        schedule_expr = normalize_interval_expr(&schedule_expr)
+            .map_err(|err| ToolError::new(err.to_string()))?;
     } else if matches!(schedule_type, ScheduleType::Once) {
         schedule_expr = normalize_once_expr(&schedule_expr, ctx.timezone_offset.as_str())?;
     }
@@ -254,6 +272,21 @@ fn create_job(
             "next_run_at": duplicate.next_run_at,
         }));
     }
+    if ctx.dry_run {
+        return Ok(dry_run_output(
+            ctx,
+            "schedule",
+            "create",
+            json!({
+                "name": name,
+                "schedule_type": schedule_type,
+                "schedule_expr": schedule_expr,
+                "user_id": user_id,
+                "channel_id": channel_id,
+                "max_executions": max_executions,
+            }),
+        ));
+    }
     let metadata = input.get("metadata").cloned();
     let requested = input
         .get("capabilities")
@@ -334,20 +367,14 @@ fn list_jobs(
         .user_id
         .as_ref()
         .ok_or_else(|| ToolError::new("missing user_id".to_string()))?;
-    let jobs = if let Some(session_id) = ctx.session_id.as_deref() {
-        scheduler
-            .store()
-            .list_jobs_by_user_with_session(user_id, session_id)
-    } else {
-        scheduler.list_jobs_by_user(user_id)
-    };
-    jobs.map(|jobs| {
+    visible_jobs(scheduler, ctx, user_id).map(|jobs| {
         let items = jobs
             .into_iter()
             .map(|job| {
                 json!({
                     "id": job.id,
                     "name": job.name,
+                    "task_prompt": job.task_prompt,
                     "schedule_type": job.schedule_type,
                     "schedule_expr": job.schedule_expr,
                     "enabled": job.enabled,
@@ -363,19 +390,64 @@ fn list_jobs(
     .map_err(|err| ToolError::new(err.to_string()))
 }
 
+/// The user's jobs as `list` shows them: only the current session's, when there is one.
+fn visible_jobs(
+    scheduler: &crate::scheduler::service::SchedulerService,
+    ctx: &ToolContext,
+    user_id: &str,
+) -> SchedulerResult<Vec<ScheduledJob>> {
+    if let Some(session_id) = ctx.session_id.as_deref() {
+        scheduler
+            .store()
+            .list_jobs_by_user_with_session(user_id, session_id)
+    } else {
+        scheduler.list_jobs_by_user(user_id)
+    }
+}
+
 fn cancel_job(
     scheduler: &crate::scheduler::service::SchedulerService,
     ctx: &ToolContext,
     input: &Value,
 ) -> Result<ToolOutput, ToolError> {
-    let job_id = input
-        .get("job_id")
-        .and_then(Value::as_str)
-        .ok_or_else(|| ToolError::new("missing job_id".to_string()))?;
     let user_id = ctx
         .user_id
         .as_ref()
         .ok_or_else(|| ToolError::new("missing user_id".to_string()))?;
+    let job_id = match input.get("job_id").and_then(Value::as_str) {
+        Some(job_id) => job_id.to_string(),
+        None => {
+            let name = input
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| ToolError::new("missing job_id or name".to_string()))?;
+            let mut matches = visible_jobs(scheduler, ctx, user_id)
+                .map_err(|err| ToolError::new(err.to_string()))?
+                .into_iter()
+                .filter(|job| job.enabled && job.name.trim().eq_ignore_ascii_case(name.trim()))
+                .collect::<Vec<_>>();
+            match matches.len() {
+                0 => return Err(ToolError::new(format!("no active job named '{name}'"))),
+                1 => matches.remove(0).id,
+                _ => {
+                    let candidates = matches
+                        .into_iter()
+                        .map(|job| {
+                            json!({
+                                "id": job.id,
+                                "name": job.name,
+                                "task_prompt": job.task_prompt,
+                                "schedule_expr": job.schedule_expr,
+                                "next_run_at": job.next_run_at,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    return Ok(json!({"status": "ambiguous", "candidates": candidates}));
+                }
+            }
+        }
+    };
+    let job_id = job_id.as_str();
     let job = scheduler
         .store()
         .get_job(job_id)
@@ -393,6 +465,14 @@ fn cancel_job(
         );
         return Err(ToolError::new("job not owned by user".to_string()));
     }
+    if ctx.dry_run {
+        return Ok(dry_run_output(
+            ctx,
+            "schedule",
+            "cancel",
+            json!({"job_id": job_id, "name": job.name}),
+        ));
+    }
     let cancelled = scheduler
         .cancel_job_and_disable(job_id)
         .map_err(|err| ToolError::new(err.to_string()))?;
@@ -444,33 +524,6 @@ fn parse_relative_duration(value: &str) -> Option<u64> {
     }
 }
 
-fn normalize_cron_expr(value: &str) -> Result<String, ToolError> {
-    let trimmed = value.trim();
-    let (tz, raw) = if let Some((prefix, rest)) = trimmed.split_once('|') {
-        let tz = prefix.trim();
-        if tz.is_empty() {
-            return Err(ToolError::new("cron timezone missing".to_string()));
-        }
-        (Some(tz), rest.trim())
-    } else {
-        (None, trimmed)
-    };
-    let field_count = raw.split_whitespace().count();
-    let normalized = match field_count {
-        5 => format!("0 {raw}"),
-        6 | 7 => raw.to_string(),
-        _ => {
-            return Err(ToolError::new(
-                "cron expression must have 5 or 6 fields".to_string(),
-            ));
-        }
-    };
-    Ok(match tz {
-        Some(tz) => format!("{tz}|{normalized}"),
-        None => normalized,
-    })
-}
-
 fn normalize_once_expr(value: &str, tz_offset: &str) -> Result<String, ToolError> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
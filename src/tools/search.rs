@@ -1,7 +1,9 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use reqwest::Client;
+use serde::Serialize;
 use serde_json::{Value, json};
 
 use crate::config::SearchConfig;
@@ -11,29 +13,74 @@ use crate::tools::traits::{ToolContext, ToolError, ToolExecutor, ToolOutput, Too
 
 const DEFAULT_MAX_RESULTS: usize = 5;
 const DEFAULT_MAX_SNIPPET_CHARS: usize = 2000;
+const DEFAULT_MAX_TOTAL_CHARS: usize = 8000;
 const DEFAULT_MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
 const ERROR_BODY_BYTES: u64 = 16 * 1024;
 const GOOGLE_SEARCH_BASE_URL: &str = "https://www.googleapis.com/customsearch/v1";
+const BRAVE_SEARCH_BASE_URL: &str = "https://api.search.brave.com/res/v1/web/search";
 const SEARXNG_SEARCH_PATH: &str = "/search";
 
+/// A single normalized search hit returned by a [`SearchBackend`].
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub display_url: Option<String>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchRequest<'a> {
+    pub query: &'a str,
+    pub count: usize,
+    pub freshness: Option<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResponse {
+    /// Base URL that served the results (after any fallbacks).
+    pub base_url: String,
+    pub results: Vec<SearchResult>,
+}
+
+/// Pluggable search engine behind the `web_search` tool.
+///
+/// The tool requires `NetAccess` on the host of every URL in `base_urls`,
+/// so backends must only contact those hosts.
+#[async_trait]
+pub trait SearchBackend: Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &str;
+
+    fn base_urls(&self) -> &[String];
+
+    async fn search(
+        &self,
+        ctx: &ToolContext,
+        request: &SearchRequest<'_>,
+    ) -> Result<SearchResponse, ToolError>;
+}
+
 #[derive(Debug)]
 pub struct SearchTool {
     spec: ToolSpec,
-    client: Client,
-    provider: SearchProvider,
+    backend: Arc<dyn SearchBackend>,
     max_results: usize,
     max_snippet_chars: usize,
+    max_total_chars: usize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SearchProviderKind {
     Google,
     Searxng,
+    Brave,
 }
 
+/// HTTP backend for the built-in providers (Google CSE, SearxNG, Brave).
 #[derive(Debug)]
-struct SearchProvider {
+pub struct SearchProvider {
     kind: SearchProviderKind,
+    client: Client,
     api_key: Option<String>,
     engine_id: Option<String>,
     base_urls: Vec<String>,
@@ -45,19 +92,62 @@ struct SearchProvider {
 
 impl SearchTool {
     pub fn new(config: &SearchConfig) -> Result<Self, ToolError> {
+        let provider = SearchProvider::from_config(config)?;
+        Ok(Self::with_backend(Arc::new(provider), config))
+    }
+
+    /// Builds the tool around a custom backend; limits still come from `config`.
+    pub fn with_backend(backend: Arc<dyn SearchBackend>, config: &SearchConfig) -> Self {
+        let max_results = config
+            .max_results
+            .unwrap_or(DEFAULT_MAX_RESULTS)
+            .clamp(1, 10);
+        let max_snippet_chars = config
+            .max_snippet_chars
+            .unwrap_or(DEFAULT_MAX_SNIPPET_CHARS);
+        let max_total_chars = config.max_total_chars.unwrap_or(DEFAULT_MAX_TOTAL_CHARS);
+
+        Self {
+            spec: ToolSpec {
+                name: "web_search".to_string(),
+                description: "Search the web via a configured search provider. Returns a short list of results with title, url, and snippet. Use http_fetch to retrieve full pages when needed."
+                    .to_string(),
+                schema: json!({
+                    "type": "object",
+                    "required": ["query"],
+                    "properties": {
+                        "query": { "type": "string", "minLength": 1, "maxLength": 400 },
+                        "count": { "type": "integer", "minimum": 1, "maximum": 10, "default": 5 },
+                        "freshness": { "type": "string", "enum": ["day", "week", "month", "year"] }
+                    },
+                    "additionalProperties": false
+                }),
+                output_schema: None,
+            },
+            backend,
+            max_results,
+            max_snippet_chars,
+            max_total_chars,
+        }
+    }
+}
+
+impl SearchProvider {
+    pub fn from_config(config: &SearchConfig) -> Result<Self, ToolError> {
         let provider = config.provider.as_deref().unwrap_or("google");
         let kind = match provider.trim().to_ascii_lowercase().as_str() {
             "google" => SearchProviderKind::Google,
             "searxng" => SearchProviderKind::Searxng,
+            "brave" => SearchProviderKind::Brave,
             _ => return Err(ToolError::new("unsupported search provider".to_string())),
         };
 
         let api_key = match kind {
-            SearchProviderKind::Google => {
+            SearchProviderKind::Google | SearchProviderKind::Brave => {
                 let api_key_env = config
                     .api_key_env
                     .as_deref()
-                    .unwrap_or("GOOGLE_CSE_API_KEY");
+                    .unwrap_or(default_api_key_env(kind));
                 Some(
                     std::env::var(api_key_env)
                         .map_err(|_| ToolError::new(format!("missing API key in env '{api_key_env}'")))?,
@@ -74,7 +164,7 @@ impl SearchTool {
                     .ok_or_else(|| ToolError::new("search.engine_id is required".to_string()))?
                     .to_string(),
             ),
-            SearchProviderKind::Searxng => None,
+            SearchProviderKind::Searxng | SearchProviderKind::Brave => None,
         };
         let mut base_urls = Vec::new();
         if let Some(base_url) = config.base_url.as_deref()
@@ -89,15 +179,16 @@ impl SearchTool {
             }
         }
         if base_urls.is_empty() {
-            base_urls.push(GOOGLE_SEARCH_BASE_URL.to_string());
+            match kind {
+                SearchProviderKind::Google => base_urls.push(GOOGLE_SEARCH_BASE_URL.to_string()),
+                SearchProviderKind::Brave => base_urls.push(BRAVE_SEARCH_BASE_URL.to_string()),
+                SearchProviderKind::Searxng => {
+                    return Err(ToolError::new(
+                        "search.base_url or search.base_urls is required for searxng".to_string(),
+                    ));
+                }
+            }
         }
-        let max_results = config
-            .max_results
-            .unwrap_or(DEFAULT_MAX_RESULTS)
-            .clamp(1, 10);
-        let max_snippet_chars = config
-            .max_snippet_chars
-            .unwrap_or(DEFAULT_MAX_SNIPPET_CHARS);
 
         let client = Client::builder()
             .timeout(Duration::from_secs(20))
@@ -106,38 +197,35 @@ impl SearchTool {
             .map_err(|err| ToolError::new(err.to_string()))?;
 
         Ok(Self {
-            spec: ToolSpec {
-                name: "web_search".to_string(),
-                description: "Search the web via a configured search provider (Google Custom Search). Returns a short list of results with title, url, and snippet. Use http_fetch to retrieve full pages when needed."
-                    .to_string(),
-                schema: json!({
-                    "type": "object",
-                    "required": ["query"],
-                    "properties": {
-                        "query": { "type": "string", "minLength": 1, "maxLength": 400 },
-                        "count": { "type": "integer", "minimum": 1, "maximum": 10, "default": 5 },
-                        "freshness": { "type": "string", "enum": ["day", "week", "month", "year"] }
-                    },
-                    "additionalProperties": false
-                }),
-            },
+            kind,
             client,
-            provider: SearchProvider {
-                kind,
-                api_key,
-                engine_id,
-                base_urls: base_urls.iter().map(|value| value.trim_end_matches('/').to_string()).collect(),
-                allow_private_base_urls: config.allow_private_base_urls.unwrap_or(false),
-                searxng_engines: config.searxng_engines.clone(),
-                searxng_categories: config.searxng_categories.clone(),
-                searxng_safesearch: config.searxng_safesearch,
-            },
-            max_results,
-            max_snippet_chars,
+            api_key,
+            engine_id,
+            base_urls: base_urls.iter().map(|value| value.trim_end_matches('/').to_string()).collect(),
+            allow_private_base_urls: config.allow_private_base_urls.unwrap_or(false),
+            searxng_engines: config.searxng_engines.clone(),
+            searxng_categories: config.searxng_categories.clone(),
+            searxng_safesearch: config.searxng_safesearch,
         })
     }
 }
 
+/// Env var consulted for the provider API key when `search.api_key_env` is unset.
+pub fn default_search_api_key_env(provider: &str) -> Option<&'static str> {
+    match provider.trim().to_ascii_lowercase().as_str() {
+        "google" => Some(default_api_key_env(SearchProviderKind::Google)),
+        "brave" => Some(default_api_key_env(SearchProviderKind::Brave)),
+        _ => None,
+    }
+}
+
+fn default_api_key_env(kind: SearchProviderKind) -> &'static str {
+    match kind {
+        SearchProviderKind::Brave => "BRAVE_SEARCH_API_KEY",
+        SearchProviderKind::Google | SearchProviderKind::Searxng => "GOOGLE_CSE_API_KEY",
+    }
+}
+
 #[async_trait]
 impl ToolExecutor for SearchTool {
     fn spec(&self) -> &ToolSpec {
@@ -150,7 +238,7 @@ impl ToolExecutor for SearchTool {
         _input: &Value,
     ) -> Result<Vec<Permission>, ToolError> {
         let mut permissions = Vec::new();
-        for base_url in &self.provider.base_urls {
+        for base_url in self.backend.base_urls() {
             let host = parse_host(base_url)?;
             permissions.push(Permission::NetAccess {
                 domain: DomainPattern(host),
@@ -159,6 +247,15 @@ impl ToolExecutor for SearchTool {
         Ok(permissions)
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        self.backend
+            .base_urls()
+            .iter()
+            .filter_map(|base_url| parse_host(base_url).ok())
+            .map(|host| format!("net:{host}"))
+            .collect()
+    }
+
     async fn execute(&self, ctx: &ToolContext, input: Value) -> Result<ToolOutput, ToolError> {
         let query = input
             .get("query")
@@ -172,88 +269,132 @@ impl ToolExecutor for SearchTool {
             .clamp(1, 10);
         let freshness = input.get("freshness").and_then(Value::as_str);
 
-        let (base_url, payload) = self.fetch_with_fallbacks(ctx, query, count, freshness).await?;
+        let request = SearchRequest {
+            query,
+            count,
+            freshness,
+        };
+        let response = self.backend.search(ctx, &request).await?;
+
         let mut results = Vec::new();
-        match self.provider.kind {
-            SearchProviderKind::Google => {
-                let items = payload.get("items").and_then(Value::as_array);
-                if let Some(items) = items {
-                    for (idx, item) in items.iter().take(count).enumerate() {
-                        let title = item.get("title").and_then(Value::as_str).unwrap_or("");
-                        let url = item.get("link").and_then(Value::as_str).unwrap_or("");
-                        let raw_snippet = item
-                            .get("snippet")
-                            .and_then(Value::as_str)
-                            .or_else(|| item.get("htmlSnippet").and_then(Value::as_str));
-                        let snippet = raw_snippet
-                            .map(strip_google_snippet)
-                            .map(|value| truncate_chars(&value, self.max_snippet_chars))
-                            .unwrap_or_default();
-                        let display_url = item.get("displayLink").and_then(Value::as_str);
-                        results.push(json!({
-                            "index": idx + 1,
-                            "title": title,
-                            "url": url,
-                            "display_url": display_url,
-                            "snippet": snippet
-                        }));
-                    }
-                }
+        let mut remaining = self.max_total_chars;
+        let mut truncated = false;
+        for mut result in response.results.into_iter().take(count) {
+            result.snippet = truncate_chars(&result.snippet, self.max_snippet_chars);
+            let fixed = result.title.chars().count() + result.url.chars().count();
+            if fixed >= remaining {
+                truncated = true;
+                break;
             }
-            SearchProviderKind::Searxng => {
-                let items = payload.get("results").and_then(Value::as_array);
-                if let Some(items) = items {
-                    for (idx, item) in items.iter().take(count).enumerate() {
-                        let title = item.get("title").and_then(Value::as_str).unwrap_or("");
-                        let url = item.get("url").and_then(Value::as_str).unwrap_or("");
-                        let raw_snippet = item
-                            .get("content")
-                            .and_then(Value::as_str)
-                            .or_else(|| item.get("snippet").and_then(Value::as_str));
-                        let snippet = raw_snippet
-                            .map(strip_google_snippet)
-                            .map(|value| truncate_chars(&value, self.max_snippet_chars))
-                            .unwrap_or_default();
-                        let display_url = item.get("pretty_url").and_then(Value::as_str);
-                        results.push(json!({
-                            "index": idx + 1,
-                            "title": title,
-                            "url": url,
-                            "display_url": display_url,
-                            "snippet": snippet
-                        }));
-                    }
-                }
+            remaining -= fixed;
+            let snippet_chars = result.snippet.chars().count();
+            if snippet_chars > remaining {
+                result.snippet = truncate_chars(&result.snippet, remaining);
+                truncated = true;
+            }
+            remaining = remaining.saturating_sub(snippet_chars);
+            results.push(json!({
+                "index": results.len() + 1,
+                "title": result.title,
+                "url": result.url,
+                "display_url": result.display_url,
+                "snippet": result.snippet
+            }));
+            if truncated {
+                break;
             }
         }
 
         Ok(json!({
             "query": query,
-            "provider": match self.provider.kind {
-                SearchProviderKind::Google => "google",
-                SearchProviderKind::Searxng => "searxng",
-            },
-            "base_url": base_url,
+            "provider": self.backend.name(),
+            "base_url": response.base_url,
             "result_count": results.len(),
+            "truncated": truncated,
             "results": results
         }))
     }
 }
 
-impl SearchTool {
+#[async_trait]
+impl SearchBackend for SearchProvider {
+    fn name(&self) -> &str {
+        match self.kind {
+            SearchProviderKind::Google => "google",
+            SearchProviderKind::Searxng => "searxng",
+            SearchProviderKind::Brave => "brave",
+        }
+    }
+
+    fn base_urls(&self) -> &[String] {
+        &self.base_urls
+    }
+
+    async fn search(
+        &self,
+        ctx: &ToolContext,
+        request: &SearchRequest<'_>,
+    ) -> Result<SearchResponse, ToolError> {
+        let (base_url, payload) = self.fetch_with_fallbacks(ctx, request).await?;
+        let results = match self.kind {
+            SearchProviderKind::Google => parse_results(
+                payload.get("items"),
+                ("title", "link", "/displayLink"),
+                &["snippet", "htmlSnippet"],
+            ),
+            SearchProviderKind::Searxng => parse_results(
+                payload.get("results"),
+                ("title", "url", "/pretty_url"),
+                &["content", "snippet"],
+            ),
+            SearchProviderKind::Brave => parse_results(
+                payload.get("web").and_then(|web| web.get("results")),
+                ("title", "url", "/meta_url/hostname"),
+                &["description"],
+            ),
+        };
+        Ok(SearchResponse { base_url, results })
+    }
+}
+
+fn parse_results(
+    items: Option<&Value>,
+    (title_key, url_key, display_pointer): (&str, &str, &str),
+    snippet_keys: &[&str],
+) -> Vec<SearchResult> {
+    let Some(items) = items.and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .map(|item| {
+            let text = |key: &str| item.get(key).and_then(Value::as_str);
+            SearchResult {
+                title: strip_google_snippet(text(title_key).unwrap_or("")),
+                url: text(url_key).unwrap_or("").to_string(),
+                display_url: item
+                    .pointer(display_pointer)
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                snippet: snippet_keys
+                    .iter()
+                    .find_map(|key| text(key))
+                    .map(strip_google_snippet)
+                    .unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+impl SearchProvider {
     async fn fetch_with_fallbacks(
         &self,
         ctx: &ToolContext,
-        query: &str,
-        count: usize,
-        freshness: Option<&str>,
+        request: &SearchRequest<'_>,
     ) -> Result<(String, Value), ToolError> {
         let mut last_error = None;
-        for base_url in &self.provider.base_urls {
-            match self
-                .fetch_from_base_url(ctx, base_url, query, count, freshness)
-                .await
-            {
+        for base_url in &self.base_urls {
+            match self.fetch_from_base_url(ctx, base_url, request).await {
                 Ok(payload) => return Ok((base_url.clone(), payload)),
                 Err(err) => last_error = Some(err),
             }
@@ -265,33 +406,43 @@ impl SearchTool {
         &self,
         ctx: &ToolContext,
         base_url: &str,
-        query: &str,
-        count: usize,
-        freshness: Option<&str>,
+        request: &SearchRequest<'_>,
     ) -> Result<Value, ToolError> {
         let host = parse_host(base_url)?;
-        if !self.provider.allow_private_base_urls {
+        if !self.allow_private_base_urls {
             ensure_allowed_url(base_url, &host, Some(ctx)).await?;
         }
 
-        let request = match self.provider.kind {
+        let SearchRequest {
+            query,
+            count,
+            freshness,
+        } = *request;
+        let request = match self.kind {
             SearchProviderKind::Google => self.build_google_request(base_url, query, count, freshness)?,
             SearchProviderKind::Searxng => self.build_searxng_request(base_url, query, count, freshness)?,
+            SearchProviderKind::Brave => self.build_brave_request(base_url, query, count, freshness)?,
         };
 
-        let response = request
-            .send()
-            .await
-            .map_err(|err| ToolError::new(err.to_string()))?;
+        let response = request.send().await.map_err(|err| {
+            if err.is_timeout() || err.is_connect() {
+                ToolError::transient(err.to_string())
+            } else {
+                ToolError::new(err.to_string())
+            }
+        })?;
         if !response.status().is_success() {
             let status = response.status();
             let body_bytes = read_response_bytes(response, ERROR_BODY_BYTES, "search error")
                 .await
                 .unwrap_or_default();
             let body = String::from_utf8_lossy(&body_bytes).to_string();
-            return Err(ToolError::new(format!(
-                "search request failed with status {status}: {body}"
-            )));
+            let message = format!("search request failed with status {status}: {body}");
+            return Err(if status.is_server_error() || status.as_u16() == 429 {
+                ToolError::transient(message)
+            } else {
+                ToolError::new(message)
+            });
         }
 
         let max_bytes = ctx.max_response_bytes.unwrap_or(DEFAULT_MAX_RESPONSE_BYTES);
@@ -309,12 +460,10 @@ impl SearchTool {
         freshness: Option<&str>,
     ) -> Result<reqwest::RequestBuilder, ToolError> {
         let api_key = self
-            .provider
             .api_key
             .as_ref()
             .ok_or_else(|| ToolError::new("missing search API key".to_string()))?;
         let engine_id = self
-            .provider
             .engine_id
             .as_ref()
             .ok_or_else(|| ToolError::new("search.engine_id is required".to_string()))?;
@@ -355,13 +504,13 @@ impl SearchTool {
             ("pageno".to_string(), "1".to_string()),
             ("count".to_string(), count.to_string()),
         ];
-        if let Some(engines) = self.provider.searxng_engines.as_deref() {
+        if let Some(engines) = self.searxng_engines.as_deref() {
             params.push(("engines".to_string(), engines.to_string()));
         }
-        if let Some(categories) = self.provider.searxng_categories.as_deref() {
+        if let Some(categories) = self.searxng_categories.as_deref() {
             params.push(("categories".to_string(), categories.to_string()));
         }
-        if let Some(safesearch) = self.provider.searxng_safesearch {
+        if let Some(safesearch) = self.searxng_safesearch {
             params.push(("safesearch".to_string(), safesearch.to_string()));
         }
         if let Some(freshness) = freshness {
@@ -378,6 +527,41 @@ impl SearchTool {
         }
         Ok(self.client.get(&url).query(&params))
     }
+
+    fn build_brave_request(
+        &self,
+        base_url: &str,
+        query: &str,
+        count: usize,
+        freshness: Option<&str>,
+    ) -> Result<reqwest::RequestBuilder, ToolError> {
+        let api_key = self
+            .api_key
+            .as_ref()
+            .ok_or_else(|| ToolError::new("missing search API key".to_string()))?;
+        let mut params = vec![
+            ("q".to_string(), query.to_string()),
+            ("count".to_string(), count.to_string()),
+        ];
+        if let Some(freshness) = freshness {
+            let value = match freshness {
+                "day" => "pd",
+                "week" => "pw",
+                "month" => "pm",
+                "year" => "py",
+                _ => "",
+            };
+            if !value.is_empty() {
+                params.push(("freshness".to_string(), value.to_string()));
+            }
+        }
+        Ok(self
+            .client
+            .get(base_url)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", api_key)
+            .query(&params))
+    }
 }
 
 fn truncate_chars(value: &str, max_chars: usize) -> String {
@@ -426,7 +610,7 @@ fn strip_google_snippet(value: &str) -> String {
 mod tests {
     use serde_json::json;
 
-    use super::{SearchTool, truncate_chars};
+    use super::{SearchProvider, SearchTool, truncate_chars};
     use crate::config::SearchConfig;
     use crate::kernel::permissions::{CapabilitySet, Permission};
     use crate::tools::traits::{ExecutionMode, ToolContext, ToolExecutor};
@@ -452,6 +636,7 @@ mod tests {
             searxng_engines: None,
             searxng_categories: None,
             searxng_safesearch: None,
+            max_total_chars: None,
         };
         let tool = SearchTool::new(&config).unwrap();
         let ctx = ToolContext {
@@ -464,11 +649,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
         let required = tool.required_permissions(&ctx, &json!({"query": "test"})).unwrap();
         assert!(matches!(
@@ -497,8 +686,9 @@ mod tests {
             searxng_engines: None,
             searxng_categories: None,
             searxng_safesearch: None,
+            max_total_chars: None,
         };
-        let tool = SearchTool::new(&config).unwrap();
-        assert!(tool.provider.allow_private_base_urls);
+        let provider = SearchProvider::from_config(&config).unwrap();
+        assert!(provider.allow_private_base_urls);
     }
 }
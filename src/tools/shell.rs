@@ -13,6 +13,7 @@ use crate::tools::traits::{
     ToolExecutor,
     ToolOutput,
     ToolSpec,
+    dry_run_output,
 };
 
 #[derive(Debug)]
@@ -40,6 +41,7 @@ impl ShellTool {
                     },
                     "additionalProperties": false
                 }),
+                output_schema: None,
             },
             policy: ShellPolicy::default(),
             runner: std::sync::Arc::new(HostRunner),
@@ -97,6 +99,10 @@ impl ToolExecutor for ShellTool {
         }])
     }
 
+    fn permission_hints(&self) -> Vec<String> {
+        vec!["shell:<command>".to_string()]
+    }
+
     fn pre_execution_policy(
         &self,
         _ctx: &ToolContext,
@@ -149,6 +155,19 @@ impl ToolExecutor for ShellTool {
             ctx.working_dir.clone()
         };
 
+        if ctx.dry_run {
+            return Ok(dry_run_output(
+                ctx,
+                &self.spec.name,
+                "exec",
+                json!({
+                    "command": command,
+                    "args": args,
+                    "working_dir": effective_dir.to_string_lossy(),
+                }),
+            ));
+        }
+
         let output = self
             .runner
             .run(command, &args, &effective_dir, &self.limits)
@@ -193,11 +212,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
         let required = tool
             .required_permissions(&ctx, &json!({"command": "ls"}))
@@ -229,11 +252,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
 
         let result = tool
@@ -266,11 +293,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
         let decision = tool
             .pre_execution_policy(&ctx, &json!({"command": "rm", "args": ["/tmp"]}))
@@ -295,11 +326,15 @@ mod tests {
             scheduler: None,
             notifications: None,
             notify_tool_used: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            message_sink: None,
+            artifacts: Default::default(),
             execution_mode: ExecutionMode::User,
             timezone_offset: "+00:00".to_string(),
             timezone_name: "UTC".to_string(),
+            timezone_requested: false,
             max_response_bytes: None,
             max_response_chars: None,
+            dry_run: false,
         };
         let decision = tool
             .pre_execution_policy(&ctx, &json!({"command": "sh", "args": ["-c", "echo hi"]}))
@@ -2,17 +2,21 @@ use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::kernel::permissions::{CapabilitySet, Permission};
 use crate::notifications::service::NotificationService;
 use crate::scheduler::service::SchedulerService;
+use crate::tools::say::MessageSink;
 
 #[derive(Debug, Default, Clone)]
 pub struct ToolSpec {
     pub name: String,
     pub description: String,
     pub schema: Value,
+    /// JSON Schema the tool's successful output should match; checked after each call.
+    pub output_schema: Option<Value>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -46,11 +50,25 @@ pub struct ToolContext {
     pub scheduler: Option<Arc<SchedulerService>>,
     pub notifications: Option<Arc<NotificationService>>,
     pub notify_tool_used: Arc<AtomicBool>,
+    /// Where `say` sends mid-turn messages; `None` on channels that cannot.
+    pub message_sink: Option<Arc<dyn MessageSink>>,
+    /// Where the running call attaches files for the channel; see [`ArtifactSink`].
+    pub artifacts: ArtifactSink,
     pub execution_mode: ExecutionMode,
     pub timezone_offset: String,
     pub timezone_name: String,
+    /// Whether the request named `timezone_name` itself rather than inheriting the default.
+    pub timezone_requested: bool,
     pub max_response_bytes: Option<u64>,
     pub max_response_chars: Option<usize>,
+    pub dry_run: bool,
+}
+
+impl ToolContext {
+    /// Timezone for crons without a `tz|` prefix: the request's, when it named one.
+    pub fn cron_default_timezone(&self) -> Option<&str> {
+        self.timezone_requested.then_some(self.timezone_name.as_str())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -64,6 +82,8 @@ pub struct ToolError {
 pub enum ToolErrorKind {
     General,
     Timeout,
+    /// Failures worth retrying unchanged, such as dropped connections or upstream 5xx/429.
+    Transient,
 }
 
 impl ToolError {
@@ -91,10 +111,22 @@ impl ToolError {
         }
     }
 
+    pub fn transient(message: String) -> Self {
+        Self {
+            message,
+            required: None,
+            kind: ToolErrorKind::Transient,
+        }
+    }
+
     pub fn is_timeout(&self) -> bool {
         self.kind == ToolErrorKind::Timeout
     }
 
+    pub fn is_transient(&self) -> bool {
+        self.kind == ToolErrorKind::Transient
+    }
+
     pub fn required_permissions(&self) -> Option<&[Permission]> {
         self.required.as_deref()
     }
@@ -110,6 +142,64 @@ impl std::error::Error for ToolError {}
 
 pub type ToolOutput = Value;
 
+/// What an [`Artifact`] contains, so channels can choose how to deliver it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Image,
+    Audio,
+    Video,
+    File,
+}
+
+/// A file a tool produced alongside its text output, such as a rendered chart.
+///
+/// Tools attach artifacts through [`ToolContext::artifacts`]; the kernel keeps only those
+/// the call could have read itself, so channels can deliver them (WhatsApp as media, the
+/// API in its reply).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub path: std::path::PathBuf,
+    pub mime: String,
+    pub kind: ArtifactKind,
+}
+
+/// Artifacts attached during one tool call, drained by the kernel when the call returns.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactSink(Arc<std::sync::Mutex<Vec<Artifact>>>);
+
+impl ArtifactSink {
+    pub fn attach(&self, artifact: Artifact) {
+        self.0
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(artifact);
+    }
+
+    pub(crate) fn take(&self) -> Vec<Artifact> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+}
+
+pub fn dry_run_output(ctx: &ToolContext, tool: &str, action: &str, details: Value) -> ToolOutput {
+    tracing::info!(
+        event = "tool_dry_run",
+        tool = %tool,
+        action = %action,
+        user_id = ?ctx.user_id,
+        session_id = ?ctx.session_id,
+        channel_id = ?ctx.channel_id,
+        details = %details,
+        "dry run: tool action skipped"
+    );
+    serde_json::json!({
+        "status": "dry_run",
+        "tool": tool,
+        "action": action,
+        "details": details,
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PreExecutionDecision {
     Allow,
@@ -132,6 +222,11 @@ pub trait ToolExecutor: Send + Sync {
         ctx: &ToolContext,
         input: &Value,
     ) -> Result<Vec<Permission>, ToolError>;
+    /// Permission patterns this tool can require, with input-dependent parts as
+    /// placeholders (e.g. `net:<host>`). Used for discovery only, never enforcement.
+    fn permission_hints(&self) -> Vec<String> {
+        Vec::new()
+    }
     fn pre_execution_policy(
         &self,
         _ctx: &ToolContext,
@@ -11,7 +11,10 @@ use picobot::scheduler::executor::JobExecutor;
 use picobot::scheduler::service::SchedulerService;
 use picobot::scheduler::store::ScheduleStore;
 use picobot::session::db::SqliteStore;
+use picobot::tools::filesystem::FilesystemTool;
 use picobot::tools::registry::ToolRegistry;
+use picobot::tools::schedule::ScheduleTool;
+use picobot::tools::shell::ShellTool;
 
 fn build_test_config() -> Config {
     let mut config = Config::default();
@@ -25,6 +28,12 @@ fn build_test_config() -> Config {
         }),
         rate_limit: None,
         max_body_bytes: Some(1_048_576),
+        body_limits: None,
+        allow_insecure_external: None,
+        allowed_ips: None,
+        admin_identities: None,
+        observer_identities: None,
+        idempotency_ttl_secs: None,
     });
     config.provider = Some("openai".to_string());
     config.model = Some("gpt-4o-mini".to_string());
@@ -32,12 +41,25 @@ fn build_test_config() -> Config {
     config
 }
 
+fn test_router(
+    config: Config,
+    kernel: Kernel,
+    agent_builder: ProviderAgentBuilder,
+) -> anyhow::Result<(String, axum::Router)> {
+    let store = SqliteStore::open_migrated(&config.sessions_db_path(), config.sqlite_pragmas())?;
+    api::router(config, kernel, agent_builder, store)
+}
+
 fn build_kernel() -> Kernel {
     let registry = ToolRegistry::new();
     Kernel::new(std::sync::Arc::new(registry))
 }
 
 fn build_kernel_with_scheduler(config: &Config) -> Kernel {
+    build_kernel_with_schedule_store(config).0
+}
+
+fn build_kernel_with_schedule_store(config: &Config) -> (Kernel, ScheduleStore) {
     let registry = ToolRegistry::new();
     let base_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
     let capabilities = CapabilitySet::from_config_with_base(&config.permissions(), &base_dir);
@@ -57,8 +79,19 @@ fn build_kernel_with_scheduler(config: &Config) -> Kernel {
         None,
         config.clone(),
     );
-    let scheduler = SchedulerService::new(schedule_store, executor, scheduler_config);
-    kernel.with_scheduler(Some(std::sync::Arc::new(scheduler)))
+    let scheduler = SchedulerService::new(schedule_store.clone(), executor, scheduler_config);
+    let kernel = kernel.with_scheduler(Some(std::sync::Arc::new(scheduler)));
+    (kernel, schedule_store)
+}
+
+fn schedule_create_request(payload: &serde_json::Value) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/schedules")
+        .header("content-type", "application/json")
+        .header("x-api-key", "test-key")
+        .body(Body::from(payload.to_string()))
+        .unwrap()
 }
 
 fn temp_path(prefix: &str) -> std::path::PathBuf {
@@ -70,7 +103,7 @@ async fn prompt_requires_api_key() {
     let config = build_test_config();
     let kernel = build_kernel();
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
 
     let payload = serde_json::json!({
         "prompt": "hello"
@@ -90,7 +123,7 @@ async fn schedule_cancel_without_scheduler_returns_unavailable() {
     let config = build_test_config();
     let kernel = build_kernel();
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let request = Request::builder()
         .method("POST")
         .uri("/v1/schedules/abc123/cancel")
@@ -109,7 +142,7 @@ async fn schedule_list_requires_schedule_permission() {
     config.scheduler = Some(scheduler_config);
     let kernel = build_kernel_with_scheduler(&config);
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let request = Request::builder()
         .method("GET")
         .uri("/v1/schedules")
@@ -165,7 +198,7 @@ async fn schedule_cancel_rejects_non_owner() {
     let job = scheduler.create_job(request).unwrap();
 
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, base_kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, base_kernel, agent_builder).unwrap();
     let request = Request::builder()
         .method("POST")
         .uri(format!("/v1/schedules/{}/cancel", job.id))
@@ -181,7 +214,7 @@ async fn chat_requires_api_key() {
     let config = build_test_config();
     let kernel = build_kernel();
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let payload = serde_json::json!({
         "message": "hello"
     });
@@ -200,7 +233,7 @@ async fn chat_rejects_mismatched_session_id() {
     let config = build_test_config();
     let kernel = build_kernel();
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let payload = serde_json::json!({
         "message": "hello",
         "session_id": "api:other"
@@ -216,6 +249,27 @@ async fn chat_rejects_mismatched_session_id() {
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
 
+#[tokio::test]
+async fn chat_rejects_grants_outside_channel_max_allowed() {
+    let config = build_test_config();
+    let kernel = build_kernel();
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+    let payload = serde_json::json!({
+        "message": "fetch the page",
+        "grant_permissions": ["net:example.com"]
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/chat")
+        .header("content-type", "application/json")
+        .header("x-api-key", "test-key")
+        .body(Body::from(payload.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
 #[tokio::test]
 async fn rate_limit_returns_429() {
     let mut config = build_test_config();
@@ -225,10 +279,16 @@ async fn rate_limit_returns_429() {
             requests_per_minute: Some(2),
         }),
         max_body_bytes: Some(1_048_576),
+        body_limits: None,
+        allow_insecure_external: None,
+        allowed_ips: None,
+        admin_identities: None,
+        observer_identities: None,
+        idempotency_ttl_secs: None,
     });
     let kernel = build_kernel();
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let payload = serde_json::json!({
         "prompt": "hello"
     });
@@ -257,7 +317,7 @@ async fn schedule_create_requires_permission() {
     config.scheduler = Some(scheduler_config);
     let kernel = build_kernel_with_scheduler(&config);
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let payload = serde_json::json!({
         "schedule_type": "interval",
         "schedule_expr": "60",
@@ -288,7 +348,7 @@ async fn schedule_create_succeeds_with_permission() {
     });
     let kernel = build_kernel_with_scheduler(&config);
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let payload = serde_json::json!({
         "schedule_type": "interval",
         "schedule_expr": "60",
@@ -305,12 +365,162 @@ async fn schedule_create_succeeds_with_permission() {
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn schedule_create_without_scheduler_reports_disabled() {
+    let mut config = build_test_config();
+    config.permissions = Some(picobot::config::PermissionsConfig {
+        schedule: Some(picobot::config::SchedulePermissions {
+            allowed_actions: vec!["create".to_string()],
+        }),
+        ..Default::default()
+    });
+    let base_dir = std::env::current_dir().unwrap();
+    let capabilities = CapabilitySet::from_config_with_base(&config.permissions(), &base_dir);
+    let kernel = build_kernel().with_capabilities(capabilities);
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+    let payload = serde_json::json!({
+        "schedule_type": "interval",
+        "schedule_expr": "60",
+        "task_prompt": "ping"
+    });
+    let response = app.oneshot(schedule_create_request(&payload)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_IMPLEMENTED);
+    assert!(response.headers().get("retry-after").is_none());
+}
+
+#[tokio::test]
+async fn schedule_create_at_capacity_asks_clients_to_retry_later() {
+    let mut config = build_test_config();
+    config.scheduler = Some(picobot::config::SchedulerConfig {
+        enabled: Some(true),
+        max_concurrent_jobs: Some(2),
+        tick_interval_secs: Some(5),
+        ..Default::default()
+    });
+    config.permissions = Some(picobot::config::PermissionsConfig {
+        schedule: Some(picobot::config::SchedulePermissions {
+            allowed_actions: vec!["create".to_string()],
+        }),
+        ..Default::default()
+    });
+    let (kernel, store) = build_kernel_with_schedule_store(&config);
+    // Simulate a stalled scheduler: three jobs overdue by a minute, none claimed.
+    for index in 0..3 {
+        let request = picobot::scheduler::job::CreateJobRequest {
+            name: format!("stalled-{index}"),
+            schedule_type: picobot::scheduler::job::ScheduleType::Interval,
+            schedule_expr: "60".to_string(),
+            task_prompt: "ping".to_string(),
+            session_id: None,
+            user_id: "someone-else".to_string(),
+            channel_id: None,
+            capabilities: CapabilitySet::empty(),
+            creator: picobot::scheduler::job::Principal {
+                principal_type: picobot::scheduler::job::PrincipalType::User,
+                id: "someone-else".to_string(),
+            },
+            enabled: true,
+            max_executions: None,
+            created_by_system: false,
+            metadata: None,
+        };
+        let overdue = chrono::Utc::now() - chrono::Duration::seconds(60);
+        store.create_job(request, overdue).unwrap();
+    }
+    let scheduler = kernel.context().scheduler.clone().unwrap();
+    let capabilities = kernel.context().capabilities.as_ref().clone();
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+    let payload = serde_json::json!({
+        "schedule_type": "interval",
+        "schedule_expr": "60",
+        "task_prompt": "ping"
+    });
+    let response = app.oneshot(schedule_create_request(&payload)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    // Three overdue jobs in batches of two take two 5s ticks to clear.
+    assert_eq!(response.headers()["retry-after"], "10");
+
+    // Jobs the system creates for itself are not turned away by the backlog.
+    let request = picobot::scheduler::job::CreateJobRequest {
+        name: "maintenance".to_string(),
+        schedule_type: picobot::scheduler::job::ScheduleType::Interval,
+        schedule_expr: "60".to_string(),
+        task_prompt: "compact sessions".to_string(),
+        session_id: None,
+        user_id: "api:test".to_string(),
+        channel_id: None,
+        capabilities,
+        creator: picobot::scheduler::job::Principal {
+            principal_type: picobot::scheduler::job::PrincipalType::System,
+            id: "api:test".to_string(),
+        },
+        enabled: true,
+        max_executions: None,
+        created_by_system: false,
+        metadata: None,
+    };
+    assert!(scheduler.create_job(request).is_ok());
+}
+
+#[tokio::test]
+async fn schedule_create_runs_cron_in_the_request_timezone() {
+    let mut config = build_test_config();
+    config.scheduler = Some(picobot::config::SchedulerConfig {
+        enabled: Some(true),
+        ..Default::default()
+    });
+    config.permissions = Some(picobot::config::PermissionsConfig {
+        schedule: Some(picobot::config::SchedulePermissions {
+            allowed_actions: vec!["create".to_string()],
+        }),
+        ..Default::default()
+    });
+    let (kernel, store) = build_kernel_with_schedule_store(&config);
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+
+    let invalid = serde_json::json!({
+        "schedule_type": "cron",
+        "schedule_expr": "0 9 * * *",
+        "task_prompt": "morning briefing",
+        "timezone": "Mars/Olympus_Mons"
+    });
+    let response = app
+        .clone()
+        .oneshot(schedule_create_request(&invalid))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let payload = serde_json::json!({
+        "schedule_type": "cron",
+        "schedule_expr": "0 9 * * *",
+        "task_prompt": "morning briefing",
+        "timezone": "America/New_York"
+    });
+    let response = app.oneshot(schedule_create_request(&payload)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let job = store
+        .get_job(body["job_id"].as_str().unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.schedule_expr, "America/New_York|0 0 9 * * *");
+    let local = job.next_run_at.with_timezone(&chrono_tz::America::New_York);
+    assert_eq!(local.format("%H:%M:%S").to_string(), "09:00:00");
+}
+
 #[tokio::test]
 async fn auth_via_bearer_token() {
     let config = build_test_config();
     let kernel = build_kernel();
     let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
-    let (_addr, app) = api::router(config, kernel, agent_builder).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
     let payload = serde_json::json!({
         "prompt": "hello"
     });
@@ -324,3 +534,219 @@ async fn auth_via_bearer_token() {
     let response = app.oneshot(request).await.unwrap();
     assert_ne!(response.status(), StatusCode::UNAUTHORIZED);
 }
+
+#[tokio::test]
+async fn tool_list_returns_registered_tools_with_schemas() {
+    let config = build_test_config();
+    let mut registry = ToolRegistry::new();
+    registry
+        .register(std::sync::Arc::new(FilesystemTool::new()))
+        .unwrap();
+    registry.register(std::sync::Arc::new(ShellTool::new())).unwrap();
+    registry.register(std::sync::Arc::new(ScheduleTool::new())).unwrap();
+    let kernel = Kernel::new(std::sync::Arc::new(registry));
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+
+    let unauthenticated = Request::builder()
+        .method("GET")
+        .uri("/v1/tools")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(unauthenticated).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let request = Request::builder()
+        .method("GET")
+        .uri("/v1/tools")
+        .header("x-api-key", "user1")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let tools = payload["tools"].as_array().unwrap();
+    let names = tools
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["filesystem", "shell", "schedule"]);
+    let filesystem = &tools[0];
+    assert_eq!(filesystem["schema"]["type"], "object");
+    assert!(filesystem["schema"]["properties"]["path"].is_object());
+    assert!(!filesystem["description"].as_str().unwrap().is_empty());
+    assert_eq!(
+        filesystem["permissions"],
+        serde_json::json!(["filesystem:read:<path>", "filesystem:write:<path>"])
+    );
+    assert_eq!(tools[1]["permissions"], serde_json::json!(["shell:<command>"]));
+}
+
+#[tokio::test]
+async fn external_bind_requires_auth_or_explicit_override() {
+    let build = |config: Config| {
+        let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+        test_router(config, build_kernel(), agent_builder).map(|_| ())
+    };
+    let mut config = build_test_config();
+    config.bind = Some("0.0.0.0:8080".to_string());
+    assert!(build(config.clone()).is_ok());
+
+    config.api.as_mut().unwrap().auth = None;
+    let err = build(config.clone()).unwrap_err();
+    assert!(err.to_string().contains("api.allow_insecure_external"), "{err}");
+
+    config.bind = Some("localhost:8080".to_string());
+    assert!(build(config.clone()).is_ok());
+
+    config.bind = Some("0.0.0.0:8080".to_string());
+    config.api.as_mut().unwrap().allow_insecure_external = Some(true);
+    assert!(build(config).is_ok());
+}
+
+#[tokio::test]
+async fn ip_allowlist_rejects_sources_outside_ranges() {
+    let mut config = build_test_config();
+    config.api.as_mut().unwrap().allowed_ips =
+        Some(vec!["10.0.0.0/8".to_string(), "2001:db8::/32".to_string()]);
+    let kernel = build_kernel();
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+
+    let status_from = |peer: &str| {
+        let mut request = Request::builder()
+            .method("GET")
+            .uri("/v1/tools")
+            .body(Body::empty())
+            .unwrap();
+        let peer: std::net::SocketAddr = peer.parse().unwrap();
+        request.extensions_mut().insert(axum::extract::ConnectInfo(peer));
+        let app = app.clone();
+        async move { app.oneshot(request).await.unwrap().status() }
+    };
+
+    // Allowed sources reach the handler, which still enforces API keys.
+    assert_eq!(status_from("10.1.2.3:5000").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status_from("[2001:db8::1]:5000").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status_from("[::ffff:10.0.0.1]:5000").await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status_from("192.168.1.1:5000").await, StatusCode::FORBIDDEN);
+    assert_eq!(status_from("[2001:db9::1]:5000").await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn scheduler_health_is_admin_only_and_reports_backlog() {
+    let mut config = build_test_config();
+    config.scheduler = Some(picobot::config::SchedulerConfig {
+        enabled: Some(true),
+        ..Default::default()
+    });
+    config.api.as_mut().unwrap().admin_identities = Some(vec!["api:user1".to_string()]);
+    let kernel = build_kernel_with_scheduler(&config);
+    let scheduler = kernel.context().scheduler.clone().unwrap();
+    let now = chrono::Utc::now();
+    for offset_secs in [-90, -10] {
+        let request = picobot::scheduler::job::CreateJobRequest {
+            name: format!("overdue{offset_secs}"),
+            schedule_type: picobot::scheduler::job::ScheduleType::Interval,
+            schedule_expr: "60".to_string(),
+            task_prompt: "ping".to_string(),
+            session_id: None,
+            user_id: "api:user2".to_string(),
+            channel_id: None,
+            capabilities: CapabilitySet::empty(),
+            creator: picobot::scheduler::job::Principal {
+                principal_type: picobot::scheduler::job::PrincipalType::User,
+                id: "api:user2".to_string(),
+            },
+            enabled: true,
+            max_executions: None,
+            created_by_system: false,
+            metadata: None,
+        };
+        scheduler
+            .store()
+            .create_job(request, now + chrono::Duration::seconds(offset_secs))
+            .unwrap();
+    }
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+    let request = |key: &str| {
+        Request::builder()
+            .method("GET")
+            .uri("/v1/admin/scheduler/health")
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(request("user2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app.oneshot(request("user1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(health["overdue_jobs"], 2);
+    assert!(health["max_lag_secs"].as_i64().unwrap() >= 90, "{health}");
+    assert_eq!(health["running_jobs"], 0);
+}
+
+#[tokio::test]
+async fn schedule_create_replays_idempotency_key() {
+    let mut config = build_test_config();
+    config.scheduler = Some(picobot::config::SchedulerConfig {
+        enabled: Some(true),
+        ..Default::default()
+    });
+    config.permissions = Some(picobot::config::PermissionsConfig {
+        schedule: Some(picobot::config::SchedulePermissions {
+            allowed_actions: vec!["create".to_string()],
+        }),
+        ..Default::default()
+    });
+    let kernel = build_kernel_with_scheduler(&config);
+    let scheduler = kernel.context().scheduler.clone().unwrap();
+    let agent_builder = ProviderAgentBuilder::new(&config).unwrap();
+    let (_addr, app) = test_router(config, kernel, agent_builder).unwrap();
+    let create = |key: &str, api_key: &str| {
+        let payload = serde_json::json!({
+            "schedule_type": "interval",
+            "schedule_expr": "@every 5m",
+            "task_prompt": "ping"
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/v1/schedules")
+            .header("content-type", "application/json")
+            .header("x-api-key", api_key)
+            .header("idempotency-key", key)
+            .body(Body::from(payload.to_string()))
+            .unwrap()
+    };
+    let job_id = |response: axum::response::Response| async move {
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        body["job_id"].as_str().unwrap().to_string()
+    };
+
+    let first = job_id(app.clone().oneshot(create("retry-1", "user1")).await.unwrap()).await;
+    let replay = job_id(app.clone().oneshot(create("retry-1", "user1")).await.unwrap()).await;
+    assert_eq!(first, replay);
+    assert_eq!(scheduler.list_jobs_by_user("api:user1").unwrap().len(), 1);
+
+    let other = job_id(app.clone().oneshot(create("retry-2", "user1")).await.unwrap()).await;
+    assert_ne!(other, first);
+    // Keys are scoped per identity, so another caller reusing the key gets its own job.
+    let user2 = job_id(app.oneshot(create("retry-1", "user2")).await.unwrap()).await;
+    assert_ne!(user2, first);
+    assert_eq!(scheduler.list_jobs_by_user("api:user1").unwrap().len(), 2);
+    assert_eq!(scheduler.list_jobs_by_user("api:user2").unwrap().len(), 1);
+}
@@ -25,6 +25,7 @@ impl StaticTool {
                 name: name.to_string(),
                 description: "static tool".to_string(),
                 schema: json!({"type": "object"}),
+                output_schema: None,
             },
             required,
         }
@@ -5,7 +5,8 @@ use serde_json::json;
 use async_trait::async_trait;
 
 use picobot::kernel::core::Kernel;
-use picobot::kernel::permissions::{CapabilitySet, PathPattern, Permission};
+use picobot::config::{ExternalToolConfig, SearchConfig};
+use picobot::kernel::permissions::{CapabilitySet, DomainPattern, PathPattern, Permission};
 use picobot::notifications::channel::{NotificationChannel, NotificationRequest};
 use picobot::notifications::queue::{NotificationQueue, NotificationQueueConfig};
 use picobot::notifications::service::NotificationService;
@@ -16,8 +17,14 @@ use picobot::scheduler::store::ScheduleStore;
 use picobot::tools::filesystem::FilesystemTool;
 use picobot::tools::http::HttpTool;
 use picobot::tools::notify::NotifyTool;
+use picobot::tools::process::ProcessTool;
 use picobot::tools::registry::ToolRegistry;
 use picobot::tools::schedule::ScheduleTool;
+use picobot::tools::search::{
+    SearchBackend, SearchRequest, SearchResponse, SearchResult, SearchTool,
+};
+use picobot::tools::shell::ShellTool;
+use picobot::tools::traits::{ExecutionMode, ToolContext, ToolError};
 
 #[tokio::test]
 async fn filesystem_read_allowed_via_kernel() {
@@ -73,6 +80,298 @@ async fn filesystem_read_denied_via_kernel() {
     std::fs::remove_dir_all(&dir).ok();
 }
 
+#[tokio::test]
+async fn oversized_filesystem_read_is_truncated() {
+    let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("large.txt");
+    std::fs::write(&file, "x".repeat(10_000)).unwrap();
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(FilesystemTool::new())).unwrap();
+    let registry = Arc::new(registry);
+
+    let canonical_dir = dir.canonicalize().unwrap();
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::FileRead {
+        path: PathPattern(format!("{}/**", canonical_dir.to_string_lossy())),
+    });
+    let kernel = Kernel::new(Arc::clone(&registry))
+        .with_capabilities(capabilities)
+        .with_max_response_chars(Some(500));
+
+    let tool = kernel.tool_registry().get("filesystem").unwrap();
+    let output = kernel
+        .invoke_tool(
+            tool.as_ref(),
+            json!({"operation": "read", "path": file.to_string_lossy()}),
+        )
+        .await
+        .unwrap();
+    assert!(output.to_string().chars().count() <= 500);
+    let content = output["content"].as_str().unwrap();
+    assert!(content.ends_with("[truncated]"));
+    assert!(content.starts_with("xxxx"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn oversized_shell_output_is_truncated() {
+    let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(ShellTool::new())).unwrap();
+    let registry = Arc::new(registry);
+
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::ShellExec {
+        allowed_commands: Some(vec!["echo".to_string()]),
+    });
+    let kernel = Kernel::new(Arc::clone(&registry))
+        .with_capabilities(capabilities)
+        .with_working_dir(dir.clone())
+        .with_max_response_chars(Some(300));
+
+    let tool = kernel.tool_registry().get("shell").unwrap();
+    let output = kernel
+        .invoke_tool(
+            tool.as_ref(),
+            json!({"command": "echo", "args": ["y".repeat(5_000)]}),
+        )
+        .await
+        .unwrap();
+    assert!(output.to_string().chars().count() <= 300);
+    assert_eq!(output["status"], "ok");
+    assert!(output["stdout"].as_str().unwrap().ends_with("[truncated]"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[derive(Debug)]
+struct FixtureSearchBackend {
+    base_urls: Vec<String>,
+}
+
+#[async_trait]
+impl SearchBackend for FixtureSearchBackend {
+    fn name(&self) -> &str {
+        "fixture"
+    }
+
+    fn base_urls(&self) -> &[String] {
+        &self.base_urls
+    }
+
+    async fn search(
+        &self,
+        _ctx: &ToolContext,
+        request: &SearchRequest<'_>,
+    ) -> Result<SearchResponse, ToolError> {
+        let results = (1..=8)
+            .map(|idx| SearchResult {
+                title: format!("Result {idx} for {}", request.query),
+                url: format!("https://example.com/{idx}"),
+                display_url: Some("example.com".to_string()),
+                snippet: "x".repeat(100),
+            })
+            .collect();
+        Ok(SearchResponse {
+            base_url: self.base_urls[0].clone(),
+            results,
+        })
+    }
+}
+
+#[tokio::test]
+async fn web_search_requires_domain_permission_and_caps_results() {
+    let backend = Arc::new(FixtureSearchBackend {
+        base_urls: vec!["https://search.fixture.test".to_string()],
+    });
+    let config = SearchConfig {
+        max_results: Some(3),
+        max_snippet_chars: Some(50),
+        max_total_chars: Some(200),
+        ..SearchConfig::default()
+    };
+    let mut registry = ToolRegistry::new();
+    registry
+        .register(Arc::new(SearchTool::with_backend(backend, &config)))
+        .unwrap();
+    let registry = Arc::new(registry);
+
+    let denied = Kernel::new(Arc::clone(&registry));
+    let tool = denied.tool_registry().get("web_search").unwrap();
+    let result = denied.invoke_tool(tool.as_ref(), json!({"query": "rust"})).await;
+    assert!(result.is_err());
+
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::NetAccess {
+        domain: DomainPattern("search.fixture.test".to_string()),
+    });
+    let kernel = Kernel::new(Arc::clone(&registry)).with_capabilities(capabilities);
+    let output = kernel
+        .invoke_tool(tool.as_ref(), json!({"query": "rust"}))
+        .await
+        .unwrap();
+    assert_eq!(output["provider"], "fixture");
+    assert_eq!(output["truncated"], true);
+    let results = output["results"].as_array().unwrap();
+    assert!(!results.is_empty() && results.len() <= 3);
+    let total_chars: usize = results
+        .iter()
+        .map(|result| {
+            ["title", "url", "snippet"]
+                .iter()
+                .map(|key| result[*key].as_str().unwrap().chars().count())
+                .sum::<usize>()
+        })
+        .sum();
+    assert!(total_chars <= 200 + "\n\n[truncated]".len() * results.len());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn external_process_tool_round_trips_json_via_kernel() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("echo_tool.sh");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\ninput=$(cat)\nprintf '{\"status\":\"ok\",\"echo\":%s}' \"$input\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let slow = dir.join("slow_tool.sh");
+    std::fs::write(&slow, "#!/bin/sh\nsleep 5\necho '{}'\n").unwrap();
+    std::fs::set_permissions(&slow, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let permission = format!("filesystem:read:{}/**", dir.to_string_lossy());
+    let echo = ExternalToolConfig {
+        name: "echo_tool".to_string(),
+        command: script.to_string_lossy().to_string(),
+        schema: Some(json!({
+            "type": "object",
+            "required": ["text"],
+            "properties": { "text": { "type": "string" } }
+        })),
+        output_schema: Some(json!({
+            "type": "object",
+            "required": ["status", "echo"],
+            "properties": { "status": { "const": "ok" } }
+        })),
+        permissions: Some(vec![permission.clone()]),
+        ..ExternalToolConfig::default()
+    };
+    let slow = ExternalToolConfig {
+        name: "slow_tool".to_string(),
+        command: slow.to_string_lossy().to_string(),
+        permissions: Some(vec![permission]),
+        ..ExternalToolConfig::default()
+    };
+    let ungated = ExternalToolConfig {
+        name: "ungated_tool".to_string(),
+        command: script.to_string_lossy().to_string(),
+        ..ExternalToolConfig::default()
+    };
+    assert!(ProcessTool::from_config(&ungated, &dir).is_err());
+    let mut registry = ToolRegistry::new();
+    for config in [echo, slow] {
+        registry
+            .register(Arc::new(ProcessTool::from_config(&config, &dir).unwrap()))
+            .unwrap();
+    }
+    let registry = Arc::new(registry);
+
+    let denied = Kernel::new(Arc::clone(&registry)).with_working_dir(dir.clone());
+    let tool = denied.tool_registry().get("echo_tool").unwrap();
+    let result = denied.invoke_tool(tool.as_ref(), json!({"text": "hi"})).await;
+    assert!(result.is_err());
+
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::FileRead {
+        path: PathPattern(format!("{}/**", dir.to_string_lossy())),
+    });
+    let mut timeouts = std::collections::HashMap::new();
+    timeouts.insert("slow_tool".to_string(), std::time::Duration::from_millis(300));
+    let kernel = Kernel::new(Arc::clone(&registry))
+        .with_capabilities(capabilities)
+        .with_working_dir(dir.clone())
+        .with_tool_timeouts(std::time::Duration::from_secs(10), timeouts);
+    let output = kernel
+        .invoke_tool(tool.as_ref(), json!({"text": "hi"}))
+        .await
+        .unwrap();
+    assert_eq!(output["status"], "ok");
+    assert_eq!(output["echo"]["text"], "hi");
+    assert!(
+        kernel
+            .tool_registry()
+            .validate_output(tool.as_ref(), &json!({"status": "failed"}))
+            .is_err()
+    );
+
+    let slow_tool = kernel.tool_registry().get("slow_tool").unwrap();
+    let started = std::time::Instant::now();
+    let err = kernel
+        .invoke_tool(slow_tool.as_ref(), json!({}))
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+    assert!(started.elapsed() < std::time::Duration::from_secs(3));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn external_process_tool_attaches_listed_artifacts() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("chart.png"), b"\x89PNG").unwrap();
+    let script = dir.join("chart_tool.sh");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\ncat >/dev/null\nprintf '{\"status\":\"ok\",\"artifacts\":\
+         [{\"path\":\"chart.png\",\"mime\":\"image/png\",\"kind\":\"image\"}]}'\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let permission = format!("filesystem:read:{}/**", dir.to_string_lossy());
+    let config = ExternalToolConfig {
+        name: "chart_tool".to_string(),
+        command: script.to_string_lossy().to_string(),
+        permissions: Some(vec![permission]),
+        ..ExternalToolConfig::default()
+    };
+    let mut registry = ToolRegistry::new();
+    registry
+        .register(Arc::new(ProcessTool::from_config(&config, &dir).unwrap()))
+        .unwrap();
+
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::FileRead {
+        path: PathPattern(format!("{}/**", dir.to_string_lossy())),
+    });
+    let kernel = Kernel::new(Arc::new(registry))
+        .with_capabilities(capabilities)
+        .with_working_dir(dir.clone());
+    let tool = kernel.tool_registry().get("chart_tool").unwrap();
+    let output = kernel.invoke_tool(tool.as_ref(), json!({})).await.unwrap();
+    assert_eq!(output, json!({ "status": "ok" }));
+    let artifacts = kernel.artifacts();
+    assert_eq!(artifacts.len(), 1);
+    assert!(artifacts[0].path.ends_with("chart.png"));
+    assert_eq!(artifacts[0].mime, "image/png");
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
 #[test]
 fn duplicate_tool_registration_rejected() {
     let mut registry = ToolRegistry::new();
@@ -204,6 +503,57 @@ fn build_scheduler(temp_dir: &std::path::Path) -> SchedulerService {
     SchedulerService::new(schedule_store, executor, scheduler_config)
 }
 
+#[test]
+fn scheduler_job_turn_budget_comes_from_config() {
+    let temp_dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let store = picobot::session::db::SqliteStore::new(
+        temp_dir.join("picobot.db").to_string_lossy().to_string(),
+    );
+    store.touch().unwrap();
+    let build_executor = |config: picobot::config::Config| {
+        JobExecutor::new(
+            Arc::new(Kernel::new(Arc::new(ToolRegistry::new()))),
+            ScheduleStore::new(store.clone()),
+            config.scheduler(),
+            ProviderAgentBuilder::from_parts(
+                ProviderKind::OpenAI,
+                "gpt-4o-mini".to_string(),
+                "test".to_string(),
+                None,
+                None,
+            ),
+            None,
+            config,
+        )
+    };
+
+    let default_executor = build_executor(picobot::config::Config::default());
+    assert_eq!(default_executor.max_turns(), 5);
+    // The legacy top-level max_turns still applies until agent.max_tool_rounds is set.
+    let legacy = picobot::config::Config {
+        max_turns: Some(7),
+        ..Default::default()
+    };
+    assert_eq!(build_executor(legacy.clone()).max_turns(), 7);
+    let configured = picobot::config::Config {
+        agent: Some(picobot::config::AgentConfig {
+            max_tool_rounds: Some(12),
+        }),
+        ..legacy
+    };
+    assert_eq!(build_executor(configured.clone()).max_turns(), 12);
+    let job_specific = picobot::config::Config {
+        scheduler: Some(picobot::config::SchedulerConfig {
+            job_max_turns: Some(3),
+            ..Default::default()
+        }),
+        ..configured
+    };
+    assert_eq!(build_executor(job_specific).max_turns(), 3);
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
 async fn spawn_http_server(body: Vec<u8>, include_length: bool) -> String {
     use tokio::io::AsyncWriteExt;
     use tokio::net::TcpListener;
@@ -308,6 +658,159 @@ async fn schedule_rejects_cross_user_override() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn schedule_list_returns_only_callers_jobs() {
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(ScheduleTool::new())).unwrap();
+    let registry = Arc::new(registry);
+    let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let scheduler = Arc::new(build_scheduler(&dir));
+    let mut capabilities = CapabilitySet::empty();
+    for action in ["create", "list"] {
+        capabilities.insert(Permission::Schedule {
+            action: action.to_string(),
+        });
+    }
+    let kernel_for = |user: &str| {
+        Kernel::new(Arc::clone(&registry))
+            .with_capabilities(capabilities.clone())
+            .with_scheduler(Some(Arc::clone(&scheduler)))
+            .with_channel_id(Some("repl".to_string()))
+            .clone_with_context(Some(user.to_string()), Some(format!("repl:{user}")))
+    };
+    let alice = kernel_for("alice");
+    let bob = kernel_for("bob");
+    let tool = alice.tool_registry().get("schedule").unwrap();
+    for (kernel, task) in [(&alice, "water the plants"), (&bob, "call mom")] {
+        kernel
+            .invoke_tool(
+                tool.as_ref(),
+                json!({
+                    "action": "create",
+                    "schedule_type": "interval",
+                    "schedule_expr": "3600",
+                    "task_prompt": task
+                }),
+            )
+            .await
+            .unwrap();
+    }
+
+    let listed = alice
+        .invoke_tool(tool.as_ref(), json!({"action": "list"}))
+        .await
+        .unwrap();
+    let schedules = listed["schedules"].as_array().unwrap();
+    assert_eq!(schedules.len(), 1, "{listed}");
+    assert_eq!(schedules[0]["task_prompt"], "water the plants");
+
+    let no_list = Kernel::new(Arc::clone(&registry))
+        .with_scheduler(Some(Arc::clone(&scheduler)))
+        .clone_with_context(Some("alice".to_string()), Some("repl:alice".to_string()));
+    let denied = no_list
+        .invoke_tool(tool.as_ref(), json!({"action": "list"}))
+        .await;
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(denied.is_err());
+}
+
+#[tokio::test]
+async fn schedule_cancel_by_id_or_name() {
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(ScheduleTool::new())).unwrap();
+    let registry = Arc::new(registry);
+    let dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let scheduler = Arc::new(build_scheduler(&dir));
+    let mut capabilities = CapabilitySet::empty();
+    for action in ["create", "cancel"] {
+        capabilities.insert(Permission::Schedule {
+            action: action.to_string(),
+        });
+    }
+    let kernel = Kernel::new(Arc::clone(&registry))
+        .with_capabilities(capabilities)
+        .with_scheduler(Some(Arc::clone(&scheduler)))
+        .with_channel_id(Some("repl".to_string()))
+        .clone_with_context(Some("alice".to_string()), Some("repl:alice".to_string()));
+    let tool = kernel.tool_registry().get("schedule").unwrap();
+    let mut ids = Vec::new();
+    let jobs = [
+        ("standup", "600"),
+        ("morning", "3600"),
+        ("morning", "7200"),
+        ("tea", "900"),
+    ];
+    for (name, expr) in jobs {
+        let created = kernel
+            .invoke_tool(
+                tool.as_ref(),
+                json!({
+                    "action": "create",
+                    "name": name,
+                    "schedule_type": "interval",
+                    "schedule_expr": expr,
+                    "task_prompt": format!("{name} every {expr}s")
+                }),
+            )
+            .await
+            .unwrap();
+        ids.push(created["job_id"].as_str().unwrap().to_string());
+    }
+    let is_enabled = |id: &str| scheduler.store().get_job(id).unwrap().unwrap().enabled;
+
+    let by_id = kernel
+        .invoke_tool(tool.as_ref(), json!({"action": "cancel", "job_id": ids[3]}))
+        .await
+        .unwrap();
+    assert_eq!(by_id["status"], "cancelled");
+    assert!(!is_enabled(&ids[3]));
+
+    let by_name = kernel
+        .invoke_tool(tool.as_ref(), json!({"action": "cancel", "name": "Standup"}))
+        .await
+        .unwrap();
+    assert_eq!(by_name["status"], "cancelled");
+    assert!(!is_enabled(&ids[0]));
+
+    let ambiguous = kernel
+        .invoke_tool(tool.as_ref(), json!({"action": "cancel", "name": "morning"}))
+        .await
+        .unwrap();
+    assert_eq!(ambiguous["status"], "ambiguous");
+    assert_eq!(ambiguous["candidates"].as_array().unwrap().len(), 2);
+    assert!(is_enabled(&ids[1]) && is_enabled(&ids[2]));
+
+    let missing = kernel
+        .invoke_tool(tool.as_ref(), json!({"action": "cancel", "name": "standup"}))
+        .await;
+    assert!(missing.is_err());
+
+    let other_session = kernel
+        .clone_with_context(Some("alice".to_string()), Some("repl:other".to_string()));
+    let created = other_session
+        .invoke_tool(
+            tool.as_ref(),
+            json!({
+                "action": "create",
+                "name": "walk",
+                "schedule_type": "interval",
+                "schedule_expr": "1800",
+                "task_prompt": "walk every 1800s"
+            }),
+        )
+        .await
+        .unwrap();
+    let walk_id = created["job_id"].as_str().unwrap().to_string();
+    let elsewhere = kernel
+        .invoke_tool(tool.as_ref(), json!({"action": "cancel", "name": "walk"}))
+        .await;
+    assert!(elsewhere.is_err());
+    assert!(is_enabled(&walk_id));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
 #[tokio::test]
 async fn notify_requires_permission() {
     let mut registry = ToolRegistry::new();
@@ -324,6 +827,113 @@ async fn notify_requires_permission() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn job_notify_permission_is_gated_per_channel() {
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(NotifyTool::new())).unwrap();
+    let registry = Arc::new(registry);
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::Notify {
+        channel: "whatsapp".to_string(),
+    });
+    let job_kernel = |channel: &str| {
+        Kernel::new(Arc::clone(&registry))
+            .with_capabilities(capabilities.clone())
+            .with_notifications(Some(Arc::new(build_notifications())))
+            .with_execution_mode(ExecutionMode::ScheduledJob)
+            .with_channel_id(Some(channel.to_string()))
+            .clone_with_context(
+                Some("alice".to_string()),
+                Some(format!("{channel}:alice")),
+            )
+    };
+
+    let whatsapp = job_kernel("whatsapp");
+    let tool = whatsapp.tool_registry().get("notify").unwrap();
+    let output = whatsapp
+        .invoke_tool(tool.as_ref(), json!({"message": "reminder"}))
+        .await
+        .unwrap();
+    assert_eq!(output["status"], "queued");
+
+    let email = job_kernel("email");
+    let err = email
+        .invoke_tool(tool.as_ref(), json!({"message": "reminder"}))
+        .await
+        .unwrap_err();
+    assert!(
+        err.required_permissions()
+            .is_some_and(|required| required.contains(&Permission::Notify {
+                channel: "email".to_string(),
+            })),
+        "{err}"
+    );
+}
+
+#[derive(Default)]
+struct CountingNotificationChannel {
+    sent: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait]
+impl NotificationChannel for CountingNotificationChannel {
+    fn channel_id(&self) -> &str {
+        "test"
+    }
+
+    async fn send(&self, _request: NotificationRequest) -> Result<(), anyhow::Error> {
+        self.sent.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn scheduled_job_notifies_only_once_per_run() {
+    let channel = Arc::new(CountingNotificationChannel::default());
+    let notifications = Arc::new(NotificationService::new(
+        NotificationQueue::new(NotificationQueueConfig::default()),
+        channel.clone(),
+    ));
+    let worker = {
+        let notifications = Arc::clone(&notifications);
+        tokio::spawn(async move { notifications.worker_loop().await })
+    };
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(NotifyTool::new())).unwrap();
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::Notify {
+        channel: "whatsapp".to_string(),
+    });
+    let kernel = Kernel::new(Arc::new(registry))
+        .with_capabilities(capabilities)
+        .with_notifications(Some(notifications))
+        .with_execution_mode(ExecutionMode::ScheduledJob)
+        .with_channel_id(Some("whatsapp".to_string()))
+        .clone_with_context(Some("alice".to_string()), Some("whatsapp:alice".to_string()));
+    let tool = kernel.tool_registry().get("notify").unwrap();
+
+    let first = kernel
+        .invoke_tool(tool.as_ref(), json!({"message": "first"}))
+        .await
+        .unwrap();
+    assert_eq!(first["status"], "queued");
+    let second = kernel
+        .invoke_tool(tool.as_ref(), json!({"message": "second"}))
+        .await
+        .unwrap();
+    assert_eq!(second["status"], "skipped");
+
+    for _ in 0..50 {
+        if channel.sent.load(std::sync::atomic::Ordering::SeqCst) > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert_eq!(channel.sent.load(std::sync::atomic::Ordering::SeqCst), 1);
+    worker.abort();
+}
+
 #[tokio::test]
 async fn whatsapp_media_permissions_are_user_scoped() {
     let base_dir =
@@ -379,3 +989,95 @@ async fn whatsapp_media_permissions_are_user_scoped() {
 
     std::fs::remove_dir_all(&base_dir).ok();
 }
+
+#[tokio::test]
+async fn auto_grant_within_max_allowed_skips_prompting() {
+    use picobot::channels::permissions::channel_profile;
+    use picobot::config::{ChannelConfig, ChannelsConfig};
+
+    let base_dir = std::env::temp_dir().join(format!("picobot-auto-grant-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&base_dir).unwrap();
+    let base_dir = base_dir.canonicalize().unwrap();
+    let file = base_dir.join("notes.txt");
+    std::fs::write(&file, "hello").unwrap();
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(FilesystemTool::new())).unwrap();
+    let registry = Arc::new(registry);
+
+    for auto_grant in [true, false] {
+        let channel = ChannelConfig {
+            max_allowed: Some(vec![format!("filesystem:read:{}/**", base_dir.display())]),
+            allow_user_prompts: Some(false),
+            auto_grant_within_max_allowed: Some(auto_grant),
+            ..Default::default()
+        };
+        let config = ChannelsConfig {
+            profiles: [("whatsapp".to_string(), channel)].into_iter().collect(),
+        };
+        let profile = channel_profile(&config, "whatsapp", &base_dir);
+        let kernel = Kernel::new(Arc::clone(&registry))
+            .with_channel_id(Some("whatsapp".to_string()))
+            .with_prompt_profile(profile)
+            .clone_with_context(Some("user".to_string()), Some("whatsapp:user".to_string()));
+        let tool = kernel.tool_registry().get("filesystem").unwrap();
+        let result = kernel
+            .invoke_tool(
+                tool.as_ref(),
+                json!({"operation": "read", "path": file.to_string_lossy()}),
+            )
+            .await;
+        assert_eq!(result.is_ok(), auto_grant, "auto_grant={auto_grant}: {result:?}");
+    }
+
+    std::fs::remove_dir_all(&base_dir).ok();
+}
+
+#[tokio::test]
+async fn channel_jail_root_overrides_global_jail() {
+    use picobot::channels::permissions::channel_sandbox;
+    use picobot::config::{ChannelConfig, ChannelsConfig};
+
+    let base_dir = std::env::temp_dir().join(format!("picobot-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(base_dir.join("api")).unwrap();
+    let base_dir = base_dir.canonicalize().unwrap();
+    let shared = base_dir.join("shared.txt");
+    std::fs::write(&shared, "shared").unwrap();
+    std::fs::write(base_dir.join("api").join("notes.txt"), "api notes").unwrap();
+
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(FilesystemTool::new())).unwrap();
+    let registry = Arc::new(registry);
+
+    let mut capabilities = CapabilitySet::empty();
+    capabilities.insert(Permission::FileRead {
+        path: PathPattern(format!("{}/**", base_dir.display())),
+    });
+    let global = Kernel::new(Arc::clone(&registry))
+        .with_capabilities(capabilities)
+        .with_jail_root(Some(base_dir.clone()))
+        .with_working_dir(base_dir.clone());
+
+    let channel = ChannelConfig {
+        jail_root: Some("api".to_string()),
+        working_dir: Some("api".to_string()),
+        ..Default::default()
+    };
+    let config = ChannelsConfig {
+        profiles: [("api".to_string(), channel)].into_iter().collect(),
+    };
+    let scoped = channel_sandbox(global.clone(), &config, "api", &base_dir);
+    let unscoped = channel_sandbox(global.clone(), &config, "repl", &base_dir);
+
+    let tool = global.tool_registry().get("filesystem").unwrap();
+    let read_shared = json!({"operation": "read", "path": shared.to_string_lossy()});
+    assert!(global.invoke_tool(tool.as_ref(), read_shared.clone()).await.is_ok());
+    assert!(unscoped.invoke_tool(tool.as_ref(), read_shared.clone()).await.is_ok());
+    assert!(scoped.invoke_tool(tool.as_ref(), read_shared).await.is_err());
+
+    let read_relative = json!({"operation": "read", "path": "notes.txt"});
+    let result = scoped.invoke_tool(tool.as_ref(), read_relative).await.unwrap();
+    assert!(result.to_string().contains("api notes"), "{result}");
+
+    std::fs::remove_dir_all(&base_dir).ok();
+}